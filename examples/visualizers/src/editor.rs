@@ -71,10 +71,17 @@ pub(crate) fn create(
                         ValueScaling::Decibels,
                     )
                     .color(Color::rgba(255, 92, 92, 128));
-                    Histogram::new(cx, bus.clone(), 250.0, (-32.0, 8.0), ValueScaling::Decibels)
-                        .width(Pixels(64.0))
-                        .color(Color::rgba(64, 128, 255, 64))
-                        .background_color(Color::rgba(64, 128, 255, 32));
+                    Histogram::new(
+                        cx,
+                        bus.clone(),
+                        250.0,
+                        (-32.0, 8.0),
+                        ValueScaling::Decibels,
+                        Orientation::Vertical,
+                    )
+                    .width(Pixels(64.0))
+                    .color(Color::rgba(64, 128, 255, 64))
+                    .background_color(Color::rgba(64, 128, 255, 32));
                     UnitRuler::new(
                         cx,
                         (-32.0, 8.0),
@@ -189,14 +196,7 @@ pub(crate) fn create(
                 .with_slope(4.5)
                 .color(Color::rgba(255, 255, 255, 60))
                 .background_color(Color::rgba(255, 255, 255, 30));
-                Element::new(cx)
-                    .background_gradient(
-                        LinearGradientBuilder::with_direction("to bottom")
-                            .add_stop(Color::transparent())
-                            .add_stop(Color::rgb(16, 16, 16)),
-                    )
-                    .height(Pixels(48.))
-                    .top(Stretch(1.));
+                FadeEdge::new(cx, FadeSide::Bottom, 48., Color::rgb(16, 16, 16));
                 UnitRuler::new(
                     cx,
                     (10., 21_000.),
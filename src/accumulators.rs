@@ -1,5 +1,11 @@
 //! Represent metrics that can be gathered from incoming audio.
 
+use nih_plug::util::window::hann;
+use realfft::num_complex::Complex32;
+use realfft::{RealFftPlanner, RealToComplex};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
 use crate::utils::RingBuffer;
 
 pub trait Accumulator: Sync + Send {
@@ -8,6 +14,15 @@ pub trait Accumulator: Sync + Send {
     fn set_sample_rate(&mut self, sample_rate: f32);
     fn set_size(&mut self, size: usize);
     fn set_duration(&mut self, duration: f32);
+
+    /// The last `size` values at the current size and duration, oldest first, if
+    /// the accumulator keeps a history of its own.
+    ///
+    /// Graphs use this to rebuild their history when they are resized, or their
+    /// duration changes. Values the history doesn't reach back to are `None`.
+    fn history(&self, _size: usize) -> Option<Vec<Option<f32>>> {
+        None
+    }
 }
 
 #[inline]
@@ -109,12 +124,146 @@ impl Accumulator for PeakAccumulator {
     }
 }
 
+/// The taps of each phase of the oversampling filter.
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// The 48-tap, 4x oversampling filter given in ITU-R BS.1770, split into its
+/// four phases.
+///
+/// The taps are kept exactly as they are given in the standard, which takes more
+/// precision than an `f32` has.
+const TRUE_PEAK_PHASES: [[f64; TRUE_PEAK_TAPS]; 4] = [
+    [
+        0.0017089843750,
+        0.0109863281250,
+        -0.0196533203125,
+        0.0332031250000,
+        -0.0594482421875,
+        0.1373291015625,
+        0.9721679687500,
+        -0.1022949218750,
+        0.0476074218750,
+        -0.0266113281250,
+        0.0148925781250,
+        -0.0083007812500,
+    ],
+    [
+        -0.0291748046875,
+        0.0292968750000,
+        -0.0517578125000,
+        0.0891113281250,
+        -0.1665039062500,
+        0.4650878906250,
+        0.7797851562500,
+        -0.2003173828125,
+        0.1015625000000,
+        -0.0582275390625,
+        0.0330810546875,
+        -0.0189208984375,
+    ],
+    [
+        -0.0189208984375,
+        0.0330810546875,
+        -0.0582275390625,
+        0.1015625000000,
+        -0.2003173828125,
+        0.7797851562500,
+        0.4650878906250,
+        -0.1665039062500,
+        0.0891113281250,
+        -0.0517578125000,
+        0.0292968750000,
+        -0.0291748046875,
+    ],
+    [
+        -0.0083007812500,
+        0.0148925781250,
+        -0.0266113281250,
+        0.0476074218750,
+        -0.1022949218750,
+        0.9721679687500,
+        0.1373291015625,
+        -0.0594482421875,
+        0.0332031250000,
+        -0.0196533203125,
+        0.0109863281250,
+        0.0017089843750,
+    ],
+];
+
+/// Stores the latest true peak.
+///
+/// The signal is oversampled 4x using the polyphase FIR filter described in ITU-R
+/// BS.1770, so that peaks in between samples are caught as well. These
+/// inter-sample peaks can exceed the level of the samples themselves, and end up
+/// clipping after D/A or sample rate conversion, even though a regular
+/// [`PeakAccumulator`] never reads above 0 dBFS.
+pub struct TruePeakAccumulator {
+    peak: PeakAccumulator,
+    /// The most recent samples, newest first
+    history: [f32; TRUE_PEAK_TAPS],
+}
+
+impl TruePeakAccumulator {
+    pub fn new(duration: f32, decay: f32) -> Self {
+        Self {
+            peak: PeakAccumulator::new(duration, decay),
+            history: [0.0; TRUE_PEAK_TAPS],
+        }
+    }
+}
+
+impl Accumulator for TruePeakAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        self.history.copy_within(..TRUE_PEAK_TAPS - 1, 1);
+        self.history[0] = sample;
+
+        let true_peak = TRUE_PEAK_PHASES
+            .iter()
+            .map(|phase| {
+                phase
+                    .iter()
+                    .zip(self.history.iter())
+                    .map(|(tap, sample)| *tap as f32 * sample)
+                    .sum::<f32>()
+                    .abs()
+            })
+            .fold(0.0, f32::max);
+
+        self.peak.accumulate(true_peak)
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.peak.prev()
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.peak.set_sample_rate(sample_rate);
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.peak.set_size(size);
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.peak.set_duration(duration);
+    }
+}
+
 /// Stores the latest minimum.
+///
+/// Until the first minimum has been found, its value is 1 - e.g. unity gain, for
+/// a gain reduction meter.
 pub struct MinimumAccumulator {
-    /// Maximum accumulator
+    /// Minimum accumulator
     min_acc: f32,
-    /// Previous accumulator value
-    prev: f32,
+    /// Previous accumulator value, once there is one
+    prev: Option<f32>,
     size: usize,
     duration: f32,
     decay: f32,
@@ -131,8 +280,8 @@ impl MinimumAccumulator {
         Self {
             duration,
             decay,
-            min_acc: 0.0,
-            prev: 0.0,
+            min_acc: f32::MAX,
+            prev: None,
             size: 1,
             sample_delta: 1.0,
             sample_rate: 1.0,
@@ -158,15 +307,16 @@ impl Accumulator for MinimumAccumulator {
             let minimum = self.min_acc;
 
             self.t -= self.sample_delta;
-            self.min_acc = 0.;
+            self.min_acc = f32::MAX;
 
-            let next = if minimum >= self.prev {
-                minimum
-            } else {
-                self.prev * self.decay_weight + minimum * (1.0 - self.decay_weight)
+            let next = match self.prev {
+                Some(prev) if minimum > prev => {
+                    prev * self.decay_weight + minimum * (1.0 - self.decay_weight)
+                }
+                _ => minimum,
             };
 
-            self.prev = next;
+            self.prev = Some(next);
 
             Some(next)
         } else {
@@ -176,7 +326,7 @@ impl Accumulator for MinimumAccumulator {
 
     #[inline]
     fn prev(&self) -> f32 {
-        self.prev
+        self.prev.unwrap_or(1.0)
     }
 
     #[inline]
@@ -285,3 +435,1061 @@ impl Accumulator for RMSAccumulator {
         self.update();
     }
 }
+
+/// Describes how a level meter responds to changes in the signal.
+///
+/// Used by the [`BallisticsAccumulator`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ballistics {
+    /// Follows the signal's peak level without any smoothing.
+    Instant,
+    /// Rises instantly, and then takes `decay` ms to fall by -12 dB.
+    Peak { decay: f32 },
+    /// Classic VU ballistics, reaching 99% of a steady level within 300 ms.
+    ///
+    /// Like the needle of an analog VU meter, the level briefly overshoots by about
+    /// 1% before settling.
+    VU,
+    /// Type I PPM (DIN 45406), with a 5 ms integration time and a return time of
+    /// 20 dB per 1.5 seconds.
+    PpmTypeI,
+    /// Type II PPM (EBU / BBC), with a 10 ms integration time and a return time of
+    /// 24 dB per 2.8 seconds.
+    PpmTypeII,
+    /// Custom attack and release times, in ms.
+    ///
+    /// The attack time is how long it takes to cover 75% of the distance to a
+    /// higher level, and the release time is how long it takes to fall by -12 dB.
+    Custom { attack: f32, release: f32 },
+}
+
+impl Ballistics {
+    /// The attack and release times of these ballistics, in ms.
+    ///
+    /// See [`Ballistics::Custom`] for what these times mean.
+    pub fn times(&self) -> (f32, f32) {
+        // Time it takes to reach 99% is log_4(100) times the time it takes to
+        // reach 75%
+        const VU_TIME: f32 = 300.0 / std::f32::consts::LOG2_10;
+
+        match self {
+            Ballistics::Instant => (0.0, 0.0),
+            Ballistics::Peak { decay } => (0.0, *decay),
+            Ballistics::VU => (VU_TIME, VU_TIME),
+            Ballistics::PpmTypeI => (5.0, 1500.0 * 12.0 / 20.0),
+            Ballistics::PpmTypeII => (10.0, 2800.0 * 12.0 / 24.0),
+            Ballistics::Custom { attack, release } => (*attack, *release),
+        }
+    }
+}
+
+/// The damping ratio of a VU meter's needle, which makes it overshoot by 1%.
+const VU_DAMPING: f32 = 0.826;
+/// The natural frequency of a VU meter's needle (in rad/s), which makes it reach
+/// 99% of a steady level within 300 ms.
+const VU_NATURAL_FREQUENCY: f32 = 13.97;
+
+/// Follows the level of a signal with some [`Ballistics`].
+///
+/// [`Ballistics::VU`] are modelled as a damped second-order system, like the
+/// needle of an analog meter. All other ballistics use separate one-pole
+/// smoothing for rising and falling levels.
+pub struct BallisticsAccumulator {
+    ballistics: Ballistics,
+    /// The current envelope level
+    envelope: f32,
+    /// The rate at which the envelope currently changes, per second (VU only)
+    velocity: f32,
+    /// Maximum envelope level within the current interval
+    max_acc: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+    attack_weight: f32,
+    release_weight: f32,
+    /// The duration of a sample, in seconds (VU only)
+    time_step: f32,
+}
+
+impl BallisticsAccumulator {
+    pub fn new(duration: f32, ballistics: Ballistics) -> Self {
+        Self {
+            ballistics,
+            envelope: 0.0,
+            velocity: 0.0,
+            max_acc: 0.0,
+            prev: 0.0,
+            size: 1,
+            duration,
+            sample_rate: 1.0,
+            t: 0.0,
+            sample_delta: 1.0,
+            attack_weight: 0.0,
+            release_weight: 0.0,
+            time_step: 0.0,
+        }
+    }
+
+    /// Changes the ballistics of this accumulator.
+    pub fn set_ballistics(&mut self, ballistics: Ballistics) {
+        self.ballistics = ballistics;
+        self.velocity = 0.0;
+        self.update();
+    }
+
+    fn update(self: &mut Self) {
+        let (attack, release) = self.ballistics.times();
+        self.attack_weight = Self::weight(attack, self.sample_rate);
+        self.release_weight = Self::weight(release, self.sample_rate);
+        // The needle stays put until the sample rate is known
+        self.time_step = if self.sample_rate > 0.0 {
+            self.sample_rate.recip()
+        } else {
+            0.0
+        };
+        self.sample_delta = sample_delta(self.size, self.sample_rate, self.duration);
+        self.t = 0.0;
+    }
+
+    /// The per-sample weight of the previous envelope level, such that the
+    /// envelope covers 75% of the distance to a new level within `time` ms.
+    ///
+    /// This is also used by views that smooth their readings on their own, like
+    /// the [`BalanceMeter`](crate::visualizers::BalanceMeter).
+    pub(crate) fn weight(time: f32, sample_rate: f32) -> f32 {
+        let weight = if time <= 0.0 {
+            0.0
+        } else {
+            0.25f64.powf(((time / 1000.0) as f64 * sample_rate as f64).recip()) as f32
+        };
+
+        // The weight is undefined for an unknown sample rate
+        if weight.is_finite() {
+            weight
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Accumulator for BallisticsAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        let rectified = sample.abs();
+
+        if self.ballistics == Ballistics::VU {
+            // The needle is pulled towards the level, and slowed down by damping
+            let dt = self.time_step;
+            self.velocity += (VU_NATURAL_FREQUENCY.powi(2) * (rectified - self.envelope)
+                - 2.0 * VU_DAMPING * VU_NATURAL_FREQUENCY * self.velocity)
+                * dt;
+            self.envelope += self.velocity * dt;
+        } else {
+            let weight = if rectified > self.envelope {
+                self.attack_weight
+            } else {
+                self.release_weight
+            };
+            self.envelope = rectified + (self.envelope - rectified) * weight;
+        }
+
+        self.max_acc = self.max_acc.max(self.envelope);
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            let next = self.max_acc;
+
+            self.t -= self.sample_delta;
+            self.max_acc = 0.;
+            self.prev = next;
+
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+}
+
+/// Stores the rate at which the level of a signal changes, in dB per second.
+///
+/// Positive values mean the level is rising, negative values mean it is falling.
+/// Useful for visualizing the pumping of a compressor, or the behavior of an
+/// auto-gain.
+pub struct SlewAccumulator {
+    /// The time (in ms) over which the level and its rate of change are smoothed
+    window: f32,
+    /// Smoothed mean square of the signal
+    mean_square: f32,
+    /// Level of the previous sample, in dB
+    level: f32,
+    /// Smoothed rate of change of the level, in dB/s
+    slew: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+    weight: f32,
+}
+
+impl SlewAccumulator {
+    /// The level of silence, in dB.
+    const FLOOR: f32 = -100.0;
+
+    pub fn new(duration: f32, window: f32) -> Self {
+        Self {
+            window,
+            mean_square: 0.0,
+            level: Self::FLOOR,
+            slew: 0.0,
+            prev: 0.0,
+            size: 1,
+            duration,
+            sample_rate: 1.0,
+            t: 0.0,
+            sample_delta: 1.0,
+            weight: 0.0,
+        }
+    }
+
+    fn update(self: &mut Self) {
+        self.weight = BallisticsAccumulator::weight(self.window, self.sample_rate);
+        self.sample_delta = sample_delta(self.size, self.sample_rate, self.duration);
+        self.t = 0.0;
+    }
+}
+
+impl Accumulator for SlewAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        self.mean_square = self.mean_square * self.weight + sample * sample * (1.0 - self.weight);
+
+        let level = (10.0 * self.mean_square.log10()).max(Self::FLOOR);
+        let delta = (level - self.level) * self.sample_rate;
+        self.level = level;
+
+        self.slew = self.slew * self.weight + delta * (1.0 - self.weight);
+
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            self.t -= self.sample_delta;
+            self.prev = self.slew;
+
+            Some(self.slew)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+}
+
+/// Stores the crest factor of a signal - the ratio of its peak to its RMS level -
+/// in dB.
+///
+/// Peak and RMS level are both measured over the same sliding window. A sine wave
+/// has a crest factor of about 3 dB, dynamic material usually reads well above
+/// that, and the more a signal gets compressed or limited, the lower its crest
+/// factor gets. Silence has a crest factor of 0 dB.
+pub struct CrestFactorAccumulator {
+    /// The length (in ms) of the window over which peak and RMS are measured
+    window: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+    squared_buffer: RingBuffer<f32>,
+    sum_acc: f32,
+    /// Candidates for the window's peak, as (sample index, absolute value), with
+    /// decreasing values from front to back
+    peaks: VecDeque<(u64, f32)>,
+    /// The index of the next sample
+    index: u64,
+}
+
+impl CrestFactorAccumulator {
+    pub fn new(duration: f32, window: f32) -> Self {
+        Self {
+            window,
+            prev: 0.0,
+            size: 1,
+            duration,
+            sample_rate: 1.0,
+            t: 0.0,
+            sample_delta: 1.0,
+            squared_buffer: RingBuffer::<f32>::new(1),
+            sum_acc: 0.0,
+            peaks: VecDeque::new(),
+            index: 0,
+        }
+    }
+
+    fn update(self: &mut Self) {
+        self.sample_delta = sample_delta(self.size, self.sample_rate, self.duration);
+
+        let window_size = (self.sample_rate as f64 * (self.window as f64 / 1000.0)) as usize;
+        self.squared_buffer.resize(window_size.max(1));
+        self.squared_buffer.clear();
+        self.sum_acc = 0.0;
+        self.peaks.clear();
+        self.t = 0.0;
+    }
+
+    /// The crest factor of the current window, in dB.
+    fn value(&self) -> f32 {
+        let peak = self.peaks.front().map_or(0.0, |(_, peak)| *peak);
+        let mean_square = self.sum_acc.max(0.0) / self.squared_buffer.len() as f32;
+
+        if mean_square > f32::EPSILON {
+            20.0 * (peak / mean_square.sqrt()).log10()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Accumulator for CrestFactorAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        let squared_value = sample * sample;
+
+        self.sum_acc -= self.squared_buffer.tail();
+        self.squared_buffer.enqueue(squared_value);
+        self.sum_acc += squared_value;
+
+        // Samples smaller than the new one can never be the window's peak again
+        let value = sample.abs();
+        while self.peaks.back().is_some_and(|(_, peak)| *peak <= value) {
+            self.peaks.pop_back();
+        }
+        self.peaks.push_back((self.index, value));
+
+        let window_size = self.squared_buffer.len() as u64;
+        while self
+            .peaks
+            .front()
+            .is_some_and(|(index, _)| index + window_size <= self.index)
+        {
+            self.peaks.pop_front();
+        }
+
+        self.index += 1;
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            self.t -= self.sample_delta;
+            self.prev = self.value();
+
+            Some(self.prev)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+}
+
+/// Stores the spectral centroid of a signal - the magnitude-weighted mean of its
+/// frequencies - in Hz.
+///
+/// The centroid is a measure of how bright a signal sounds: the more energy there
+/// is in the highs, the higher the centroid. It is computed using a small FFT over
+/// a sliding window, four times per window length, so it reacts to tonal changes
+/// that level meters can't show. Silence has a centroid of 0 Hz.
+pub struct SpectralCentroidAccumulator {
+    /// The length (in ms) of the window the FFT runs over
+    window: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+    buffer: RingBuffer<f32>,
+    /// Samples left until the centroid is computed again
+    countdown: usize,
+    centroid: f32,
+    fft: Arc<dyn RealToComplex<f32>>,
+    window_function: Vec<f32>,
+    frame: Vec<f32>,
+    spectrum: Vec<Complex32>,
+}
+
+impl SpectralCentroidAccumulator {
+    pub fn new(duration: f32, window: f32) -> Self {
+        let mut accumulator = Self {
+            window,
+            prev: 0.0,
+            size: 1,
+            duration,
+            sample_rate: 1.0,
+            t: 0.0,
+            sample_delta: 1.0,
+            buffer: RingBuffer::<f32>::new(1),
+            countdown: 1,
+            centroid: 0.0,
+            fft: RealFftPlanner::new().plan_fft_forward(2),
+            window_function: vec![],
+            frame: vec![],
+            spectrum: vec![],
+        };
+        accumulator.update();
+        accumulator
+    }
+
+    fn update(self: &mut Self) {
+        self.sample_delta = sample_delta(self.size, self.sample_rate, self.duration);
+
+        // The FFT is most efficient for powers of two
+        let window_size = ((self.sample_rate as f64 * (self.window as f64 / 1000.0)) as usize)
+            .max(64)
+            .next_power_of_two();
+
+        if window_size != self.frame.len() {
+            self.fft = RealFftPlanner::new().plan_fft_forward(window_size);
+            self.window_function = hann(window_size);
+            self.frame = self.fft.make_input_vec();
+            self.spectrum = self.fft.make_output_vec();
+        }
+
+        self.buffer = RingBuffer::new(window_size);
+        self.countdown = self.hop();
+        self.centroid = 0.0;
+        self.t = 0.0;
+    }
+
+    fn hop(&self) -> usize {
+        self.buffer.len() / 4
+    }
+
+    /// Computes the centroid of the current window, in Hz.
+    fn compute(&mut self) -> f32 {
+        for (i, (x, w)) in self
+            .frame
+            .iter_mut()
+            .zip(self.window_function.iter())
+            .enumerate()
+        {
+            *x = self.buffer[i] * w;
+        }
+
+        if self
+            .fft
+            .process(&mut self.frame, &mut self.spectrum)
+            .is_err()
+        {
+            return self.centroid;
+        }
+
+        let bin_width = self.sample_rate / self.buffer.len() as f32;
+        let (weighted, total) = self.spectrum.iter().enumerate().fold(
+            (0.0, 0.0),
+            |(weighted, total), (bin_idx, bin)| {
+                let magnitude = bin.norm();
+                (
+                    weighted + magnitude * bin_idx as f32 * bin_width,
+                    total + magnitude,
+                )
+            },
+        );
+
+        if total > f32::EPSILON {
+            weighted / total
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Accumulator for SpectralCentroidAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        self.buffer.enqueue(sample);
+
+        self.countdown -= 1;
+        if self.countdown == 0 {
+            self.countdown = self.hop();
+            self.centroid = self.compute();
+        }
+
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            self.t -= self.sample_delta;
+            self.prev = self.centroid;
+
+            Some(self.prev)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+}
+
+/// The factor by which each tier of a [`DecimatingAccumulator`] is coarser than
+/// the one before it.
+const TIER_FACTOR: usize = 8;
+/// The number of values each tier of a [`DecimatingAccumulator`] keeps.
+const TIER_SIZE: usize = 512;
+/// The number of tiers of a [`DecimatingAccumulator`].
+const TIERS: usize = 6;
+
+/// One tier of a [`DecimatingAccumulator`]'s history.
+struct Tier {
+    /// The most recent values, each the maximum of [`TIER_FACTOR`] values of the
+    /// tier below
+    values: RingBuffer<f32>,
+    /// The number of values this tier has received
+    count: u64,
+    /// The maximum of the values collected for the next tier
+    max_acc: f32,
+    /// The number of values collected for the next tier
+    collected: usize,
+}
+
+impl Tier {
+    fn new() -> Self {
+        Self {
+            values: RingBuffer::filled(TIER_SIZE, f32::NEG_INFINITY),
+            count: 0,
+            max_acc: f32::NEG_INFINITY,
+            collected: 0,
+        }
+    }
+}
+
+/// Decimates another accumulator, so that it can cover very long durations.
+///
+/// For long histories, a graph's columns each span seconds or even minutes, and
+/// sampling the wrapped accumulator once per column would miss everything that
+/// happened in between. Instead, the wrapped accumulator is run at a fixed
+/// `resolution` (in seconds), and the values it produces are reduced to their
+/// maximum for each column.
+///
+/// Along the way, the values are kept in tiers of decreasing resolution: the
+/// finest tier holds the latest 512 values, and each following tier holds 512
+/// maxima of 8 values of the tier before it - 6 tiers in total. At a resolution of
+/// 100 ms, this reaches back more than two weeks. Whenever the graph is resized or
+/// its duration changes, its history is rebuilt from the finest tiers that still
+/// reach back far enough. This way, both memory and drawing cost stay the same,
+/// no matter the duration.
+///
+/// If a column spans less than the resolution, the wrapped accumulator is simply
+/// run at the graph's own rate, and no tiers are kept.
+pub struct DecimatingAccumulator<A: Accumulator> {
+    inner: A,
+    /// The time (in seconds) each value of the wrapped accumulator covers
+    resolution: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    /// The number of inner values per column, if they are decimated
+    values_per_column: Option<f32>,
+    /// The inner values left until the current column is complete
+    t: f32,
+    max_acc: f32,
+    /// The history at decreasing resolutions, the finest tier first
+    tiers: Vec<Tier>,
+}
+
+impl<A: Accumulator> DecimatingAccumulator<A> {
+    pub fn new(inner: A, duration: f32, resolution: f32) -> Self {
+        let mut accumulator = Self {
+            inner,
+            resolution,
+            prev: 0.0,
+            size: 1,
+            duration,
+            values_per_column: None,
+            t: 0.0,
+            max_acc: f32::NEG_INFINITY,
+            tiers: (0..TIERS).map(|_| Tier::new()).collect(),
+        };
+        accumulator.update();
+        accumulator
+    }
+
+    fn update(self: &mut Self) {
+        let column = self.duration / self.size as f32;
+
+        if column > self.resolution {
+            self.inner.set_size(1);
+            self.inner.set_duration(self.resolution);
+            self.values_per_column = Some(column / self.resolution);
+        } else {
+            self.inner.set_size(self.size);
+            self.inner.set_duration(self.duration);
+            self.values_per_column = None;
+
+            // The inner values no longer match the resolution of the tiers
+            self.tiers = (0..TIERS).map(|_| Tier::new()).collect();
+        }
+
+        self.t = self.values_per_column.unwrap_or(1.0);
+        self.max_acc = f32::NEG_INFINITY;
+    }
+
+    /// Adds an inner value to the finest tier, passing the maximum of every few
+    /// values on to the next tier.
+    fn push_to_tiers(&mut self, mut value: f32) {
+        for tier in self.tiers.iter_mut() {
+            tier.values.enqueue(value);
+            tier.count += 1;
+
+            tier.max_acc = tier.max_acc.max(value);
+            tier.collected += 1;
+            if tier.collected < TIER_FACTOR {
+                break;
+            }
+
+            value = tier.max_acc;
+            tier.max_acc = f32::NEG_INFINITY;
+            tier.collected = 0;
+        }
+    }
+
+    /// The maximum of the inner values from index `start` up to `end`, counting
+    /// from the first value the tiers received - or `None` if the tiers don't
+    /// reach back that far.
+    ///
+    /// Each part of the range is read from the finest tier that still holds it.
+    /// Coarser values only partly within the range count towards it as well.
+    fn max_between(&self, start: u64, end: u64) -> Option<f32> {
+        let mut max = None::<f32>;
+        // The part of the range which finer tiers have already covered
+        let mut covered = end;
+        let mut span = 1;
+
+        for tier in &self.tiers {
+            let held = tier.count.min(TIER_SIZE as u64);
+            let first = (tier.count - held) * span;
+            let from = start.max(first);
+
+            if from < covered {
+                for i in from / span..covered.div_ceil(span) {
+                    let value = tier.values[TIER_SIZE - (tier.count - i) as usize];
+                    max = Some(max.map_or(value, |max| max.max(value)));
+                }
+                covered = from;
+            }
+
+            if covered <= start {
+                break;
+            }
+            span *= TIER_FACTOR as u64;
+        }
+
+        max
+    }
+}
+
+impl<A: Accumulator> Accumulator for DecimatingAccumulator<A> {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        let value = self.inner.accumulate(sample)?;
+
+        let Some(values_per_column) = self.values_per_column else {
+            self.prev = value;
+            return Some(value);
+        };
+
+        self.push_to_tiers(value);
+
+        self.max_acc = self.max_acc.max(value);
+        self.t -= 1.0;
+
+        if self.t <= 0.0 {
+            self.t += values_per_column;
+            self.prev = self.max_acc;
+            self.max_acc = f32::NEG_INFINITY;
+
+            Some(self.prev)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.inner.set_sample_rate(sample_rate);
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+
+    /// Rebuilds the last `size` columns from the tiers, as long as they reach
+    /// back far enough.
+    fn history(&self, size: usize) -> Option<Vec<Option<f32>>> {
+        let values_per_column = self.values_per_column? as f64;
+        let now = self.tiers[0].count as f64;
+
+        let history = (0..size)
+            .map(|column| {
+                let end = now - (size - 1 - column) as f64 * values_per_column;
+                let start = end - values_per_column;
+
+                if end.round() <= start.round().max(0.0) {
+                    return None;
+                }
+                self.max_between(start.round().max(0.0) as u64, end.round() as u64)
+            })
+            .collect();
+
+        Some(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accumulator(ballistics: Ballistics) -> BallisticsAccumulator {
+        let mut acc = BallisticsAccumulator::new(1.0, ballistics);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(1000);
+        acc
+    }
+
+    #[test]
+    fn true_peak_catches_inter_sample_peaks() {
+        let sample_rate = 48000.0;
+        let mut peak = PeakAccumulator::new(1.0, 0.0);
+        let mut true_peak = TruePeakAccumulator::new(1.0, 0.0);
+
+        for acc in [&mut peak as &mut dyn Accumulator, &mut true_peak] {
+            acc.set_sample_rate(sample_rate);
+            acc.set_size(100);
+        }
+
+        // A full-scale sine at a quarter of the sample rate, sampled 45° off its
+        // peaks, never has a sample above -3 dBFS
+        for i in 0..4800 {
+            let phase = std::f32::consts::TAU * i as f32 / 4. + std::f32::consts::FRAC_PI_4;
+            peak.accumulate(phase.sin());
+            true_peak.accumulate(phase.sin());
+        }
+
+        assert!((peak.prev() - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-3);
+        assert!((true_peak.prev() - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn minimum_snaps_down_and_decays_up() {
+        let mut acc = MinimumAccumulator::new(1.0, 100.0);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(1000);
+
+        // An idle accumulator sits at unity, rather than at some huge sentinel
+        assert_eq!(acc.prev(), 1.0);
+
+        for _ in 0..20 {
+            acc.accumulate(0.5);
+        }
+        assert_eq!(acc.prev(), 0.5);
+
+        acc.accumulate(0.1);
+        acc.accumulate(0.1);
+        assert_eq!(acc.prev(), 0.1);
+
+        for _ in 0..20 {
+            acc.accumulate(1.0);
+        }
+        assert!(acc.prev() > 0.1);
+        assert!(acc.prev() < 1.0);
+    }
+
+    #[test]
+    fn instant_ballistics_follow_peaks() {
+        let mut acc = accumulator(Ballistics::Instant);
+
+        for _ in 0..20 {
+            acc.accumulate(-0.5);
+        }
+        assert_eq!(acc.prev(), 0.5);
+
+        for _ in 0..20 {
+            acc.accumulate(0.25);
+        }
+        assert_eq!(acc.prev(), 0.25);
+    }
+
+    #[test]
+    fn peak_ballistics_decay() {
+        let mut acc = accumulator(Ballistics::Peak { decay: 100.0 });
+
+        for _ in 0..20 {
+            acc.accumulate(1.0);
+        }
+        assert_eq!(acc.prev(), 1.0);
+
+        // After 100 ms of silence, the level should have dropped by 12 dB
+        for _ in 0..100 {
+            acc.accumulate(0.0);
+        }
+        assert!((acc.prev() - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn vu_ballistics_integrate() {
+        let mut acc = accumulator(Ballistics::VU);
+
+        // After 300 ms of a steady signal, the level should be at 99% of it
+        for _ in 0..300 {
+            acc.accumulate(1.0);
+        }
+        assert!(acc.prev() < 1.0);
+        assert!(acc.prev() > 0.985);
+    }
+
+    #[test]
+    fn vu_ballistics_overshoot() {
+        let mut acc = accumulator(Ballistics::VU);
+
+        let mut max: f32 = 0.0;
+        for _ in 0..1500 {
+            acc.accumulate(1.0);
+            max = max.max(acc.prev());
+        }
+
+        // The level overshoots by about 1%, and then settles
+        assert!(max > 1.005);
+        assert!(max < 1.015);
+        assert!((acc.prev() - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn slew_follows_level_changes() {
+        let mut acc = SlewAccumulator::new(1.0, 50.0);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(1000);
+
+        // A level rising by 20 dB per second
+        for i in 0..2000 {
+            acc.accumulate(0.001 * 10f32.powf(i as f32 / 1000.0));
+        }
+        assert!((acc.prev() - 20.0).abs() < 0.5);
+
+        // A steady level
+        for _ in 0..500 {
+            acc.accumulate(0.5);
+        }
+        assert!(acc.prev().abs() < 0.5);
+    }
+
+    #[test]
+    fn crest_factor_of_sine_and_square() {
+        let mut acc = CrestFactorAccumulator::new(1.0, 100.0);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(1000);
+
+        // A sine at a quarter of the sample rate, phased so that samples land on
+        // its peaks
+        for i in 0..500 {
+            acc.accumulate(
+                (i as f32 * std::f32::consts::FRAC_PI_2 + std::f32::consts::FRAC_PI_2).sin(),
+            );
+        }
+        assert!((acc.prev() - 3.01).abs() < 0.01);
+
+        // A square wave's peak equals its RMS level
+        for i in 0..500 {
+            acc.accumulate(if i % 10 < 5 { 0.5 } else { -0.5 });
+        }
+        assert!(acc.prev().abs() < 0.01);
+    }
+
+    #[test]
+    fn spectral_centroid_follows_the_brightness() {
+        let sample_rate = 48000.0;
+        let mut acc = SpectralCentroidAccumulator::new(1.0, 20.0);
+        acc.set_sample_rate(sample_rate);
+        acc.set_size(100);
+
+        let sine = |frequency: f32, i: usize| {
+            (std::f32::consts::TAU * frequency * i as f32 / sample_rate).sin()
+        };
+
+        for i in 0..9600 {
+            acc.accumulate(sine(1000.0, i));
+        }
+        assert!((acc.prev() - 1000.0).abs() < 50.0);
+
+        // Adding a louder sine higher up brightens the signal
+        for i in 0..9600 {
+            acc.accumulate(0.5 * sine(1000.0, i) + sine(5000.0, i));
+        }
+        assert!(acc.prev() > 3000.0);
+        assert!(acc.prev() < 5000.0);
+
+        for _ in 0..9600 {
+            acc.accumulate(0.0);
+        }
+        assert_eq!(acc.prev(), 0.0);
+    }
+
+    #[test]
+    fn decimation_keeps_short_bursts() {
+        let mut acc = DecimatingAccumulator::new(RMSAccumulator::new(100.0, 10.0), 100.0, 0.005);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(10);
+
+        // A 20 ms burst within a 10 second column
+        let mut columns = vec![];
+        for i in 0..20_000 {
+            let sample = if (3000..3020).contains(&i) { 1.0 } else { 0.0 };
+            columns.extend(acc.accumulate(sample));
+        }
+
+        assert_eq!(columns.len(), 2);
+        assert!((columns[0] - 1.0).abs() < 0.01);
+        assert_eq!(columns[1], 0.0);
+    }
+
+    #[test]
+    fn tiers_rebuild_the_history() {
+        let mut acc = DecimatingAccumulator::new(PeakAccumulator::new(1.0, 0.0), 60.0, 0.01);
+        acc.set_sample_rate(1000.0);
+        acc.set_size(6);
+
+        // A peak after 15 seconds, which only the coarser tiers still reach back to
+        for i in 0..60_000 {
+            acc.accumulate(if i == 15_000 { 1.0 } else { 0.0 });
+        }
+
+        assert_eq!(
+            acc.history(6),
+            Some(vec![
+                Some(0.0),
+                Some(1.0),
+                Some(0.0),
+                Some(0.0),
+                Some(0.0),
+                Some(0.0)
+            ])
+        );
+
+        // Twice the duration reaches back further than the signal goes
+        acc.set_duration(120.0);
+        let history = acc.history(6).unwrap();
+        assert_eq!(history[..3], [None, None, None]);
+        assert_eq!(history[3], Some(1.0));
+    }
+}
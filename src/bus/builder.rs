@@ -0,0 +1,109 @@
+use std::fmt;
+
+use super::*;
+
+/// An error that occurs when building a bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusError {
+    /// The bus would not be able to hold any samples.
+    ZeroCapacity,
+}
+
+impl fmt::Display for BusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BusError::ZeroCapacity => write!(f, "a bus needs a capacity of at least one sample"),
+        }
+    }
+}
+
+impl std::error::Error for BusError {}
+
+/// Builds a [`MonoBus`] or [`MultiChannelBus`] with a specific capacity.
+///
+/// The capacity is the number of samples the bus can hold until its next
+/// [`update`](Bus::update). Any samples sent to a full bus are dropped. The default
+/// capacity of 4096 samples suffices for common sample rates and buffer sizes, but
+/// plug-ins running at high sample rates, or with large host buffers, should size
+/// their buses using [`fit`](Self::fit).
+///
+/// # Example
+///
+/// ```
+/// let bus = MonoBus::builder()
+///     .fit(192_000.0, 8192)
+///     .build()
+///     .unwrap();
+/// ```
+pub struct BusBuilder<B> {
+    capacity: usize,
+    bus: PhantomData<B>,
+}
+
+impl<B> BusBuilder<B> {
+    pub(crate) fn new() -> Self {
+        Self {
+            capacity: 4096,
+            bus: PhantomData,
+        }
+    }
+
+    /// Sets the capacity of the bus, in samples.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sizes the bus so that it can hold all samples that arrive between two
+    /// updates, given the sample rate and the host's maximum buffer size.
+    ///
+    /// This assumes the bus is updated via [`subscribe`](Bus::subscribe), and
+    /// leaves some headroom in case an update is late.
+    pub fn fit(self, sample_rate: f32, max_buffer_size: usize) -> Self {
+        let per_update = (sample_rate * UPDATE_INTERVAL.as_secs_f32()).ceil() as usize;
+        self.capacity((per_update + max_buffer_size) * 2)
+    }
+
+    fn validate(&self) -> Result<usize, BusError> {
+        if self.capacity == 0 {
+            Err(BusError::ZeroCapacity)
+        } else {
+            Ok(self.capacity)
+        }
+    }
+}
+
+impl BusBuilder<MonoBus> {
+    /// Builds the [`MonoBus`].
+    pub fn build(self) -> Result<MonoBus, BusError> {
+        self.validate().map(MonoBus::new)
+    }
+}
+
+impl<const C: usize> BusBuilder<MultiChannelBus<C>> {
+    /// Builds the [`MultiChannelBus`].
+    pub fn build(self) -> Result<MultiChannelBus<C>, BusError> {
+        self.validate().map(MultiChannelBus::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_capacity_is_rejected() {
+        assert_eq!(
+            MonoBus::builder().capacity(0).build().err(),
+            Some(BusError::ZeroCapacity)
+        );
+    }
+
+    #[test]
+    fn fit_holds_samples_between_updates() {
+        let bus = StereoBus::builder().fit(192_000.0, 8192).build().unwrap();
+
+        assert!(bus.capacity() >= 192_000 * 15 / 1000 + 8192);
+        assert!(bus.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use core::slice;
+use nih_plug::util::db_to_gain;
+use std::sync::Arc;
+
+use super::*;
+
+/// Thinly wraps around a bus and applies a fixed gain to everything its
+/// dispatchers receive.
+///
+/// Use it to calibrate views without touching the signal that is sent to the bus,
+/// or what other views connected to it display - e.g. to compensate for a known
+/// pad before the point where your plug-in taps the signal, or to align a meter to
+/// a reference level. The gain is applied on the GUI thread, when the bus is
+/// updated.
+///
+/// # Example
+///
+/// A meter that makes up for a -6 dB pad before the tap.
+///
+/// ```
+/// let calibrated = CalibratedBus::new(&bus, 6.0);
+///
+/// Meter::peak(
+///     cx,
+///     calibrated,
+///     50.0,
+///     (-32.0, 8.0),
+///     ValueScaling::Decibels,
+///     Orientation::Vertical,
+/// );
+/// ```
+#[derive(Clone)]
+pub struct CalibratedBus<B: Bus<f32>> {
+    pub(crate) bus: B,
+    pub(crate) gain: f32,
+}
+
+impl<B: Bus<f32>> CalibratedBus<B> {
+    /// Creates a bus that offsets everything it receives from `bus` by
+    /// `offset_db` decibels.
+    pub fn new(bus: &Arc<B>, offset_db: f32) -> Arc<Self> {
+        Self::with_gain(bus, db_to_gain(offset_db))
+    }
+
+    /// Creates a bus that scales everything it receives from `bus` by a linear
+    /// `gain`.
+    pub fn with_gain(bus: &Arc<B>, gain: f32) -> Arc<Self> {
+        Self {
+            bus: bus.as_ref().clone(),
+            gain,
+        }
+        .into()
+    }
+
+    /// The linear gain that is applied to each sample.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+}
+
+impl<B: Bus<f32>> Bus<f32> for CalibratedBus<B> {
+    type I<'a> = slice::Iter<'a, f32>;
+    type O<'a> = B::O<'a>;
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::O<'a>) + Sync + Send> {
+        let gain = self.gain;
+        self.bus.register_dispatcher(move |samples| {
+            let calibrated = samples.map(|x| x * gain).collect::<Vec<f32>>();
+            dispatcher(calibrated.iter());
+        })
+    }
+
+    fn update(&self) {
+        self.bus.update()
+    }
+
+    #[inline]
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.bus.set_sample_rate(sample_rate)
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> f32 {
+        self.bus.sample_rate()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.bus.register_sample_rate_listener(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn only_calibrated_dispatchers_are_scaled() {
+        let bus = Arc::new(MonoBus::new(16));
+        let calibrated = CalibratedBus::new(&bus, 6.0);
+
+        let raw: Arc<Mutex<Vec<f32>>> = Default::default();
+        let raw_c = raw.clone();
+        let _raw_handle = bus.register_dispatcher(move |samples| {
+            raw_c.lock().unwrap().extend(samples);
+        });
+
+        let scaled: Arc<Mutex<Vec<f32>>> = Default::default();
+        let scaled_c = scaled.clone();
+        let _scaled_handle = calibrated.register_dispatcher(move |samples| {
+            scaled_c.lock().unwrap().extend(samples);
+        });
+
+        bus.send(0.25);
+        bus.send(-0.5);
+        calibrated.update();
+
+        assert_eq!(*raw.lock().unwrap(), vec![0.25, -0.5]);
+
+        let gain = calibrated.gain();
+        assert!((gain - 1.9953).abs() < 1e-3);
+        assert_eq!(*scaled.lock().unwrap(), vec![0.25 * gain, -0.5 * gain]);
+    }
+}
@@ -0,0 +1,129 @@
+use core::slice;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::Instant;
+
+use super::*;
+
+/// A discrete event, along with the point in time at which it occurred.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEvent<E> {
+    pub event: E,
+    pub time: Instant,
+}
+
+/// A bus for discrete events.
+///
+/// Instead of audio samples, this bus carries discrete occurrences inside your
+/// plug-in, like clipping, a gate opening or a limiter engaging. The payload is
+/// usually some enum of your own. Each event is stamped with the time at which it
+/// was sent, so that views like marker overlays, clip lights and counters can
+/// consume them.
+///
+/// # Example
+///
+/// ```
+/// #[derive(Clone, Copy)]
+/// enum LimiterEvent {
+///     Engaged,
+///     Released,
+/// }
+///
+/// let bus: Arc<EventBus<LimiterEvent>> = Default::default();
+///
+/// // Inside your plug-in's process() function
+/// bus.send(LimiterEvent::Engaged);
+///
+/// // Inside your editor, forward events to a graph's markers
+/// let handle = bus.register_dispatcher(move |events| {
+///     for event in events {
+///         if let LimiterEvent::Engaged = event.event {
+///             marker_input.send(MarkerStyle::Flag);
+///         }
+///     }
+/// });
+/// ```
+#[derive(Clone)]
+pub struct EventBus<E: Copy + Send + Sync + 'static> {
+    dispatchers: Arc<RwLock<Vec<Weak<dyn Fn(slice::Iter<'_, TimedEvent<E>>) + Sync + Send>>>>,
+    channel: (Sender<TimedEvent<E>>, Receiver<TimedEvent<E>>),
+    sample_rate: SampleRate,
+}
+
+impl<E: Copy + Send + Sync + 'static> EventBus<E> {
+    pub fn new(size: usize) -> Self {
+        let channel = bounded(size);
+        Self {
+            dispatchers: RwLock::new(vec![]).into(),
+            channel,
+            sample_rate: SampleRate::default(),
+        }
+    }
+
+    /// Sends an event, stamped with the current time.
+    ///
+    /// This operation will silently fail if the Bus is congested.
+    #[inline]
+    pub fn send(&self, event: E) {
+        let _ = self.channel.0.try_send(TimedEvent {
+            event,
+            time: Instant::now(),
+        });
+    }
+}
+
+impl<E: Copy + Send + Sync + 'static> Default for EventBus<E> {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl<E: Copy + Send + Sync + 'static> Bus<TimedEvent<E>> for EventBus<E> {
+    type I<'a> = slice::Iter<'a, TimedEvent<E>>;
+    type O<'a> = Self::I<'a>;
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> {
+        let dispatcher: Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> = Arc::new(dispatcher);
+
+        let downgraded = Arc::downgrade(&dispatcher);
+
+        let mut dispatchers = self.dispatchers.write().unwrap();
+
+        if let Some(pos) = dispatchers.iter().position(|d| d.upgrade().is_none()) {
+            dispatchers[pos] = downgraded;
+            dispatchers.retain(|d| d.upgrade().is_some());
+        } else {
+            dispatchers.push(downgraded);
+        }
+
+        dispatcher
+    }
+
+    fn update(&self) {
+        let events = self.channel.1.try_iter().collect::<Vec<_>>();
+
+        if events.is_empty() {
+            return;
+        }
+
+        dispatch(&self.dispatchers, |d| d(events.iter()));
+    }
+
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.set(sample_rate);
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate.get()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.sample_rate.register(listener)
+    }
+}
@@ -1,3 +1,4 @@
+use core::slice;
 use std::{iter::Map, sync::Arc};
 
 use super::*;
@@ -46,4 +47,61 @@ where
     fn sample_rate(&self) -> f32 {
         self.bus.sample_rate()
     }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.bus.register_sample_rate_listener(listener)
+    }
+}
+
+/// Thinly wraps around a [`MultiChannelBus`] and acts like a mono bus, mixing
+/// down its channels with a gain for each channel.
+///
+/// Unlike the [`IntoMonoBus`], the downmix is computed, so it can blend channels
+/// together - e.g. using standard surround downmix coefficients.
+#[derive(Clone)]
+pub struct WeightedMonoBus<const C: usize> {
+    pub(crate) bus: MultiChannelBus<C>,
+    pub(crate) gains: [f32; C],
+}
+
+impl<const C: usize> Bus<f32> for WeightedMonoBus<C> {
+    type I<'a> = slice::Iter<'a, f32>;
+    type O<'a> = <MultiChannelBus<C> as Bus<[f32; C]>>::I<'a>;
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::O<'a>) + Sync + Send> {
+        let gains = self.gains;
+        self.bus.register_dispatcher(move |samples| {
+            let mono_samples = samples
+                .map(|sample| sample.iter().zip(gains).map(|(x, gain)| x * gain).sum())
+                .collect::<Vec<f32>>();
+            dispatcher(mono_samples.iter());
+        })
+    }
+
+    fn update(&self) {
+        self.bus.update()
+    }
+
+    #[inline]
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.bus.set_sample_rate(sample_rate)
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> f32 {
+        self.bus.sample_rate()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.bus.register_sample_rate_listener(listener)
+    }
 }
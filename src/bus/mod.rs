@@ -1,15 +1,41 @@
 //! The main means of inter-process communication in Cyma.
 
-use std::{any::Any, hint::spin_loop, marker::PhantomData, sync::Arc, thread, time::Duration};
+use std::{
+    any::Any,
+    hint::spin_loop,
+    marker::PhantomData,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{Arc, RwLock, Weak},
+    thread,
+    time::Duration,
+};
 
+mod builder;
+mod calibrated;
+mod event;
 mod into_bus;
 mod mono;
 mod multichannel;
+mod select;
+mod switch;
+mod value;
 
+pub use builder::*;
+pub use calibrated::*;
+pub use event::*;
 pub use into_bus::*;
 pub use mono::*;
 pub use multichannel::*;
+use nih_plug::nih_log;
+use nih_plug::prelude::AtomicF32;
 use nih_plug_vizia::vizia::prelude::*;
+pub use select::*;
+use std::sync::atomic::Ordering;
+pub use switch::*;
+pub use value::*;
+
+/// How often buses are updated after calling [`Bus::subscribe`].
+pub(crate) const UPDATE_INTERVAL: Duration = Duration::from_millis(15);
 
 /// A bus for stereo data.
 pub type StereoBus = MultiChannelBus<2>;
@@ -35,6 +61,23 @@ where
     /// The current sample rate.
     fn sample_rate(&self) -> f32;
 
+    /// Registers a listener that gets called whenever the sample rate changes, and
+    /// returns a handle to it.
+    ///
+    /// Views use this to recompute anything that depends on the sample rate, such
+    /// as the coefficients of their accumulators, if the host changes the sample
+    /// rate mid-session. Like with dispatchers, the listener will not be called
+    /// anymore once the handle goes out of scope.
+    ///
+    /// By default, the listener is never called, as if the sample rate never
+    /// changed. Buses whose sample rate can change should notify their listeners.
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        Arc::new(listener)
+    }
+
     /// Calls all registered dispatchers and provides them with the latest
     /// audio data, if any is available.
     fn update(&self);
@@ -54,7 +97,106 @@ where
         let bus = self.clone();
         cx.spawn(move |cx| loop {
             bus.update();
-            thread::sleep(Duration::from_millis(15));
+            thread::sleep(UPDATE_INTERVAL);
         });
     }
 }
+
+/// A sample rate that is shared between a bus and its clones.
+///
+/// Notifies its listeners whenever it changes.
+#[derive(Clone)]
+pub(crate) struct SampleRate {
+    value: Arc<AtomicF32>,
+    listeners: Arc<RwLock<Vec<Weak<dyn Fn(f32) + Sync + Send>>>>,
+}
+
+impl Default for SampleRate {
+    fn default() -> Self {
+        Self {
+            value: Arc::new(f32::NAN.into()),
+            listeners: Default::default(),
+        }
+    }
+}
+
+impl SampleRate {
+    pub fn get(&self) -> f32 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, sample_rate: f32) {
+        if self.value.swap(sample_rate, Ordering::Relaxed) == sample_rate {
+            return;
+        }
+
+        // Listeners are called just like dispatchers, so a panicking one is dropped
+        dispatch(&self.listeners, |listener| listener(sample_rate));
+    }
+
+    pub fn register<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        let listener: Arc<dyn Fn(f32) + Sync + Send> = Arc::new(listener);
+
+        let mut listeners = self.listeners.write().unwrap();
+        listeners.retain(|l| l.upgrade().is_some());
+        listeners.push(Arc::downgrade(&listener));
+
+        listener
+    }
+}
+
+/// A function that gets called with the message of a panicking dispatcher.
+type DispatcherPanicHook = Box<dyn Fn(&str) + Send + Sync>;
+
+static DISPATCHER_PANIC_HOOK: RwLock<Option<DispatcherPanicHook>> = RwLock::new(None);
+
+/// Sets a hook that gets called whenever a dispatcher panics.
+///
+/// Buses catch panics inside their dispatchers, so that a single misbehaving
+/// visualizer can't take down the update loop and every other visualizer with
+/// it. The panicking dispatcher is then dropped from the bus, and the hook gets
+/// called with the panic message. By default, the message is logged.
+pub fn set_dispatcher_panic_hook(hook: impl Fn(&str) + Send + Sync + 'static) {
+    if let Ok(mut current) = DISPATCHER_PANIC_HOOK.write() {
+        *current = Some(Box::new(hook));
+    }
+}
+
+/// Calls all dispatchers that are still alive, dropping the ones that panic.
+pub(crate) fn dispatch<D: ?Sized>(dispatchers: &RwLock<Vec<Weak<D>>>, call: impl Fn(&D)) {
+    let mut panicked = vec![];
+
+    for dispatcher in dispatchers.read().unwrap().iter() {
+        let Some(d) = dispatcher.upgrade() else {
+            continue;
+        };
+
+        if let Err(payload) = catch_unwind(AssertUnwindSafe(|| call(&d))) {
+            report_panic(payload.as_ref());
+            panicked.push(dispatcher.clone());
+        }
+    }
+
+    if !panicked.is_empty() {
+        dispatchers
+            .write()
+            .unwrap()
+            .retain(|d| !panicked.iter().any(|p| p.ptr_eq(d)));
+    }
+}
+
+fn report_panic(payload: &(dyn Any + Send)) {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+
+    match DISPATCHER_PANIC_HOOK.read().as_deref() {
+        Ok(Some(hook)) => hook(message),
+        _ => nih_log!("A dispatcher panicked and was dropped: {}", message),
+    }
+}
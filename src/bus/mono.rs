@@ -2,9 +2,7 @@ use core::slice;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use nih_plug::buffer::Buffer;
 use nih_plug::nih_dbg;
-use nih_plug::prelude::AtomicF32;
-use std::sync::atomic::Ordering;
-use std::sync::{atomic, Arc, RwLock, Weak};
+use std::sync::{Arc, RwLock, Weak};
 
 use super::*;
 
@@ -13,7 +11,7 @@ use super::*;
 pub struct MonoBus {
     dispatchers: Arc<RwLock<Vec<Weak<dyn Fn(slice::Iter<'_, f32>) + Sync + Send>>>>,
     channel: (Sender<f32>, Receiver<f32>),
-    sample_rate: Arc<AtomicF32>,
+    sample_rate: SampleRate,
 }
 
 impl MonoBus {
@@ -22,9 +20,29 @@ impl MonoBus {
         Self {
             dispatchers: RwLock::new(vec![]).into(),
             channel,
-            sample_rate: Arc::new(f32::NAN.into()),
+            sample_rate: SampleRate::default(),
         }
     }
+
+    /// Creates a [`BusBuilder`] for configuring the bus' capacity.
+    pub fn builder() -> BusBuilder<Self> {
+        BusBuilder::new()
+    }
+
+    /// The number of samples this bus can hold until its next update.
+    pub fn capacity(&self) -> usize {
+        self.channel.0.capacity().unwrap_or(usize::MAX)
+    }
+
+    /// The number of samples currently waiting for the next update.
+    pub fn len(&self) -> usize {
+        self.channel.1.len()
+    }
+
+    /// Whether there are no samples waiting for the next update.
+    pub fn is_empty(&self) -> bool {
+        self.channel.1.is_empty()
+    }
 }
 
 impl Default for MonoBus {
@@ -67,12 +85,18 @@ impl Bus<f32> for MonoBus {
     type O<'a> = Self::I<'a>;
 
     fn set_sample_rate(&self, sample_rate: f32) {
-        self.sample_rate
-            .store(sample_rate, atomic::Ordering::Relaxed);
+        self.sample_rate.set(sample_rate);
     }
 
     fn sample_rate(&self) -> f32 {
-        self.sample_rate.load(Ordering::Relaxed)
+        self.sample_rate.get()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.sample_rate.register(listener)
     }
 
     fn update(&self) {
@@ -82,12 +106,7 @@ impl Bus<f32> for MonoBus {
 
         let samples = self.channel.1.try_iter().collect::<Vec<_>>();
 
-        self.dispatchers
-            .read()
-            .unwrap()
-            .iter()
-            .filter_map(|d| d.upgrade())
-            .for_each(|d| d(samples.iter()));
+        dispatch(&self.dispatchers, |d| d(samples.iter()));
     }
 
     fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
@@ -2,9 +2,7 @@ use core::slice;
 use crossbeam_channel::{bounded, Receiver, Sender};
 use nih_plug::buffer::Buffer;
 use nih_plug::nih_dbg;
-use nih_plug::prelude::AtomicF32;
-use std::sync::atomic::Ordering;
-use std::sync::{atomic, Arc, RwLock, Weak};
+use std::sync::{Arc, RwLock, Weak};
 
 use super::*;
 
@@ -13,7 +11,7 @@ use super::*;
 pub struct MultiChannelBus<const C: usize> {
     dispatchers: Arc<RwLock<Vec<Weak<dyn Fn(slice::Iter<'_, [f32; C]>) + Sync + Send>>>>,
     channel: (Sender<[f32; C]>, Receiver<[f32; C]>),
-    sample_rate: Arc<AtomicF32>,
+    sample_rate: SampleRate,
 }
 
 impl<const C: usize> MultiChannelBus<C> {
@@ -22,9 +20,29 @@ impl<const C: usize> MultiChannelBus<C> {
         Self {
             dispatchers: RwLock::new(vec![]).into(),
             channel,
-            sample_rate: Arc::new(f32::NAN.into()),
+            sample_rate: SampleRate::default(),
         }
     }
+
+    /// Creates a [`BusBuilder`] for configuring the bus' capacity.
+    pub fn builder() -> BusBuilder<Self> {
+        BusBuilder::new()
+    }
+
+    /// The number of samples this bus can hold until its next update.
+    pub fn capacity(&self) -> usize {
+        self.channel.0.capacity().unwrap_or(usize::MAX)
+    }
+
+    /// The number of samples currently waiting for the next update.
+    pub fn len(&self) -> usize {
+        self.channel.1.len()
+    }
+
+    /// Whether there are no samples waiting for the next update.
+    pub fn is_empty(&self) -> bool {
+        self.channel.1.is_empty()
+    }
 }
 
 impl<const C: usize> Default for MultiChannelBus<C> {
@@ -76,6 +94,37 @@ impl<const C: usize> MultiChannelBus<C> {
         .into()
     }
 
+    /// Creates a mono bus, by mixing down the channels with the given gains.
+    ///
+    /// See [`WeightedMonoBus`].
+    ///
+    /// # Example
+    ///
+    /// Standard 5.1 to mono downmix, leaving out the LFE channel.
+    ///
+    /// ```
+    /// let mono_bus = surround_bus.into_mono_weighted([0.707, 0.707, 1.0, 0.0, 0.5, 0.5]);
+    /// ```
+    pub fn into_mono_weighted(&self, gains: [f32; C]) -> Arc<WeightedMonoBus<C>> {
+        WeightedMonoBus {
+            bus: self.clone(),
+            gains,
+        }
+        .into()
+    }
+
+    /// Creates a mono bus passing on a single channel, or the average of all of
+    /// them, which can be changed at runtime.
+    ///
+    /// See [`SelectableMonoBus`].
+    pub fn into_mono_selectable(&self, selection: ChannelSelection) -> Arc<SelectableMonoBus<C>> {
+        SelectableMonoBus {
+            bus: self.clone(),
+            selection: Arc::new(selection.encode().into()),
+        }
+        .into()
+    }
+
     // /// Creates a mono bus, by summing samples.
     // ///
     // /// See [`IntoMonoBus`].
@@ -104,6 +153,34 @@ impl<const C: usize> MultiChannelBus<C> {
     }
 }
 
+impl MultiChannelBus<2> {
+    /// Creates a mono bus carrying the mid signal, (L+R)/2.
+    ///
+    /// See [`WeightedMonoBus`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Oscilloscope::new(
+    ///     cx,
+    ///     stereo_bus.into_mid(),
+    ///     4.0,
+    ///     (-1.0, 1.0),
+    ///     ValueScaling::Linear,
+    /// );
+    /// ```
+    pub fn into_mid(&self) -> Arc<WeightedMonoBus<2>> {
+        self.into_mono_weighted([0.5, 0.5])
+    }
+
+    /// Creates a mono bus carrying the side signal, (L-R)/2.
+    ///
+    /// See [`WeightedMonoBus`].
+    pub fn into_side(&self) -> Arc<WeightedMonoBus<2>> {
+        self.into_mono_weighted([0.5, -0.5])
+    }
+}
+
 impl<const C: usize> Bus<[f32; C]> for MultiChannelBus<C> {
     type I<'a> = slice::Iter<'a, [f32; C]>;
     type O<'a> = Self::I<'a>;
@@ -135,20 +212,57 @@ impl<const C: usize> Bus<[f32; C]> for MultiChannelBus<C> {
             return;
         }
 
-        self.dispatchers
-            .read()
-            .unwrap()
-            .iter()
-            .filter_map(|d| d.upgrade())
-            .for_each(|d| d(samples.iter()));
+        dispatch(&self.dispatchers, |d| d(samples.iter()));
     }
 
     fn set_sample_rate(&self, sample_rate: f32) {
-        self.sample_rate
-            .store(sample_rate, atomic::Ordering::Relaxed);
+        self.sample_rate.set(sample_rate);
     }
 
     fn sample_rate(&self) -> f32 {
-        self.sample_rate.load(Ordering::Relaxed)
+        self.sample_rate.get()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.sample_rate.register(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn mid_and_side_are_split() {
+        let bus = StereoBus::new(16);
+        let (mid, side) = (bus.into_mid(), bus.into_side());
+
+        let received: Arc<Mutex<Vec<(char, f32)>>> = Default::default();
+
+        let received_c = received.clone();
+        let _mid_handle = mid.register_dispatcher(move |samples| {
+            received_c
+                .lock()
+                .unwrap()
+                .extend(samples.map(|x| ('m', *x)));
+        });
+        let received_c = received.clone();
+        let _side_handle = side.register_dispatcher(move |samples| {
+            received_c
+                .lock()
+                .unwrap()
+                .extend(samples.map(|x| ('s', *x)));
+        });
+
+        bus.send([1.0, 0.5]);
+        bus.update();
+
+        let received = received.lock().unwrap();
+        assert!(received.contains(&('m', 0.75)));
+        assert!(received.contains(&('s', 0.25)));
     }
 }
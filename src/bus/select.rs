@@ -0,0 +1,157 @@
+use core::slice;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+
+use super::*;
+
+/// Which channel of a [`MultiChannelBus`] a [`SelectableMonoBus`] passes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Data)]
+pub enum ChannelSelection {
+    /// The average of all channels.
+    Mix,
+    /// Only the channel with the given index.
+    Solo(usize),
+}
+
+impl ChannelSelection {
+    pub(crate) fn encode(self) -> usize {
+        match self {
+            ChannelSelection::Mix => usize::MAX,
+            ChannelSelection::Solo(channel) => channel,
+        }
+    }
+
+    fn decode(value: usize) -> Self {
+        match value {
+            usize::MAX => ChannelSelection::Mix,
+            channel => ChannelSelection::Solo(channel),
+        }
+    }
+}
+
+/// Thinly wraps around a [`MultiChannelBus`] and acts like a mono bus, passing on
+/// a single channel that can be changed at runtime.
+///
+/// This lets users solo a channel in any mono visualizer, such as an
+/// [`Oscilloscope`](crate::visualizers::Oscilloscope), without your plug-in having
+/// to send each channel to a bus of its own. The selection can be changed directly
+/// using [`select`](Self::select), or bound to your editor's model using
+/// [`bind_selection`](Self::bind_selection). Selecting a channel that the bus
+/// doesn't have results in silence.
+///
+/// # Example
+///
+/// ```
+/// let selectable = stereo_bus.into_mono_selectable(ChannelSelection::Mix);
+///
+/// // Inside your editor, where Data::channel is a ChannelSelection
+/// selectable.bind_selection(cx, Data::channel);
+///
+/// Oscilloscope::new(cx, selectable.clone(), 4.0, (-1.0, 1.0), ValueScaling::Linear);
+/// ```
+#[derive(Clone)]
+pub struct SelectableMonoBus<const C: usize> {
+    pub(crate) bus: MultiChannelBus<C>,
+    pub(crate) selection: Arc<AtomicUsize>,
+}
+
+impl<const C: usize> SelectableMonoBus<C> {
+    /// The channel that is currently passed on.
+    pub fn selection(&self) -> ChannelSelection {
+        ChannelSelection::decode(self.selection.load(Ordering::Relaxed))
+    }
+
+    /// Changes the channel that is passed on, starting with the next update.
+    pub fn select(&self, selection: ChannelSelection) {
+        self.selection.store(selection.encode(), Ordering::Relaxed);
+    }
+
+    /// Keeps the selection in sync with the given lens, or sets it once if a plain
+    /// value is given.
+    pub fn bind_selection(
+        self: &Arc<Self>,
+        cx: &mut Context,
+        selection: impl Res<ChannelSelection>,
+    ) {
+        let bus = self.clone();
+        let entity = cx.current();
+
+        selection.set_or_bind(cx, entity, move |_, selection| bus.select(selection));
+    }
+}
+
+impl<const C: usize> Bus<f32> for SelectableMonoBus<C> {
+    type I<'a> = slice::Iter<'a, f32>;
+    type O<'a> = <MultiChannelBus<C> as Bus<[f32; C]>>::I<'a>;
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::O<'a>) + Sync + Send> {
+        let selection = self.selection.clone();
+        self.bus.register_dispatcher(move |samples| {
+            let mono_samples = match ChannelSelection::decode(selection.load(Ordering::Relaxed)) {
+                ChannelSelection::Mix => samples
+                    .map(|sample| sample.iter().sum::<f32>() / C as f32)
+                    .collect::<Vec<f32>>(),
+                ChannelSelection::Solo(channel) => samples
+                    .map(|sample| sample.get(channel).copied().unwrap_or_default())
+                    .collect::<Vec<f32>>(),
+            };
+            dispatcher(mono_samples.iter());
+        })
+    }
+
+    fn update(&self) {
+        self.bus.update()
+    }
+
+    #[inline]
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.bus.set_sample_rate(sample_rate)
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> f32 {
+        self.bus.sample_rate()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.bus.register_sample_rate_listener(listener)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn selection_changes_at_runtime() {
+        let bus = MultiChannelBus::<3>::new(16);
+        let selectable = bus.into_mono_selectable(ChannelSelection::Solo(1));
+
+        let received: Arc<Mutex<Vec<f32>>> = Default::default();
+        let received_c = received.clone();
+        let _handle = selectable.register_dispatcher(move |samples| {
+            received_c.lock().unwrap().extend(samples);
+        });
+
+        bus.send([0.25, 0.5, 0.75]);
+        bus.update();
+
+        selectable.select(ChannelSelection::Mix);
+        bus.send([0.25, 0.5, 0.75]);
+        bus.update();
+
+        selectable.select(ChannelSelection::Solo(5));
+        bus.send([0.25, 0.5, 0.75]);
+        bus.update();
+
+        assert_eq!(*received.lock().unwrap(), vec![0.5, 0.5, 0.0]);
+        assert_eq!(selectable.selection(), ChannelSelection::Solo(5));
+    }
+}
@@ -0,0 +1,209 @@
+use core::slice;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, RwLock, Weak};
+
+use super::*;
+
+type Dispatchers<T> = Arc<RwLock<Vec<Weak<dyn for<'a> Fn(slice::Iter<'a, T>) + Sync + Send>>>>;
+
+/// A bus that passes on the samples of one of several buses, which can be
+/// switched at runtime.
+///
+/// This lets users pick the source of a visualizer - such as the input, output or
+/// sidechain of your plug-in - without rebuilding the editor. Visualizers register
+/// their dispatchers on the [`SwitchableBus`] once, and only receive samples from
+/// the selected source from then on. The selection can be changed directly using
+/// [`select`](Self::select), bound to your editor's model using
+/// [`bind_selection`](Self::bind_selection), or left to an
+/// [`InputSelector`](crate::visualizers::InputSelector). Selecting a source that
+/// doesn't exist results in silence.
+///
+/// Updating a [`SwitchableBus`] updates all of its sources, so that switching to
+/// another one doesn't replay stale samples.
+///
+/// # Example
+///
+/// ```
+/// let analyzer_bus = SwitchableBus::new(vec![input_bus.clone(), output_bus.clone()]);
+///
+/// // Inside your editor
+/// analyzer_bus.subscribe(cx);
+/// InputSelector::new(cx, analyzer_bus.clone(), ["In", "Out"]);
+/// Oscilloscope::new(cx, analyzer_bus.clone(), 4.0, (-1.0, 1.0), ValueScaling::Linear);
+/// ```
+#[derive(Clone)]
+pub struct SwitchableBus<T: Clone + Copy + Sized + 'static, B: Bus<T>> {
+    sources: Vec<Arc<B>>,
+    selection: Arc<AtomicUsize>,
+    dispatchers: Dispatchers<T>,
+    sample_rate: SampleRate,
+    /// Keeps the dispatchers and listeners registered on the sources alive
+    source_handles: Arc<Vec<Box<dyn Any + Send + Sync>>>,
+}
+
+impl<T: Clone + Copy + Sized + 'static, B: Bus<T>> SwitchableBus<T, B> {
+    /// Creates a new [`SwitchableBus`] passing on the first of the given sources.
+    pub fn new(sources: Vec<Arc<B>>) -> Arc<Self> {
+        let selection = Arc::new(AtomicUsize::new(0));
+        let dispatchers: Dispatchers<T> = Default::default();
+        let sample_rate = SampleRate::default();
+
+        let mut source_handles: Vec<Box<dyn Any + Send + Sync>> = vec![];
+
+        for (index, source) in sources.iter().enumerate() {
+            let selection_c = selection.clone();
+            let dispatchers_c = dispatchers.clone();
+            source_handles.push(Box::new(source.register_dispatcher(move |samples| {
+                if selection_c.load(Ordering::Relaxed) != index {
+                    return;
+                }
+
+                let samples = samples.copied().collect::<Vec<T>>();
+                dispatch(&dispatchers_c, |d| d(samples.iter()));
+            })));
+
+            let selection_c = selection.clone();
+            let sample_rate_c = sample_rate.clone();
+            source_handles.push(Box::new(source.register_sample_rate_listener(
+                move |rate| {
+                    if selection_c.load(Ordering::Relaxed) == index {
+                        sample_rate_c.set(rate);
+                    }
+                },
+            )));
+        }
+
+        let bus = Self {
+            sources,
+            selection,
+            dispatchers,
+            sample_rate,
+            source_handles: Arc::new(source_handles),
+        };
+        bus.select(0);
+
+        bus.into()
+    }
+
+    /// The index of the source that is currently passed on.
+    pub fn selection(&self) -> usize {
+        self.selection.load(Ordering::Relaxed)
+    }
+
+    /// The number of sources to choose from.
+    pub fn num_sources(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Switches to the source with the given index, starting with the next update.
+    ///
+    /// If the new source runs at a different sample rate, the bus' sample rate
+    /// listeners are informed.
+    pub fn select(&self, selection: usize) {
+        self.selection.store(selection, Ordering::Relaxed);
+
+        if let Some(source) = self.sources.get(selection) {
+            let sample_rate = source.sample_rate();
+            if !sample_rate.is_nan() {
+                self.sample_rate.set(sample_rate);
+            }
+        }
+    }
+
+    /// Keeps the selection in sync with the given lens, or sets it once if a plain
+    /// value is given.
+    pub fn bind_selection(self: &Arc<Self>, cx: &mut Context, selection: impl Res<usize>) {
+        let bus = self.clone();
+        let entity = cx.current();
+
+        selection.set_or_bind(cx, entity, move |_, selection| bus.select(selection));
+    }
+}
+
+impl<T: Clone + Copy + Sized + 'static, B: Bus<T>> Bus<T> for SwitchableBus<T, B> {
+    type I<'a> = slice::Iter<'a, T>;
+    type O<'a> = Self::I<'a>;
+
+    /// Sets the sample rate of all sources.
+    fn set_sample_rate(&self, sample_rate: f32) {
+        for source in &self.sources {
+            source.set_sample_rate(sample_rate);
+        }
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate.get()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.sample_rate.register(listener)
+    }
+
+    fn update(&self) {
+        for source in &self.sources {
+            source.update();
+        }
+    }
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> {
+        let dispatcher: Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> = Arc::new(dispatcher);
+        let downgraded = Arc::downgrade(&dispatcher);
+
+        let mut dispatchers = self.dispatchers.write().unwrap();
+
+        if let Some(pos) = dispatchers.iter().position(|d| d.upgrade().is_none()) {
+            dispatchers[pos] = downgraded;
+            dispatchers.retain(|d| d.upgrade().is_some());
+        } else {
+            dispatchers.push(downgraded);
+        }
+
+        dispatcher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn only_the_selected_source_is_passed_on() {
+        let input = Arc::new(MonoBus::new(16));
+        let output = Arc::new(MonoBus::new(16));
+        input.set_sample_rate(44_100.);
+        output.set_sample_rate(48_000.);
+
+        let switchable = SwitchableBus::new(vec![input.clone(), output.clone()]);
+
+        let received: Arc<Mutex<Vec<f32>>> = Default::default();
+        let received_c = received.clone();
+        let _handle = switchable.register_dispatcher(move |samples| {
+            received_c.lock().unwrap().extend(samples);
+        });
+
+        input.send(1.);
+        output.send(2.);
+        switchable.update();
+        assert_eq!(switchable.sample_rate(), 44_100.);
+
+        switchable.select(1);
+        input.send(1.);
+        output.send(2.);
+        switchable.update();
+        assert_eq!(switchable.sample_rate(), 48_000.);
+
+        switchable.select(2);
+        input.send(1.);
+        output.send(2.);
+        switchable.update();
+
+        assert_eq!(*received.lock().unwrap(), vec![1., 2.]);
+    }
+}
@@ -0,0 +1,203 @@
+use core::slice;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+use super::*;
+use crate::utils::lock_or_recover;
+
+/// The most samples a [`ValueBus`] expands its values into before dispatching
+/// them.
+const EXPANSION_SIZE: usize = 4096;
+
+/// A bus for scalar values that change at their own rate, like the gain
+/// reduction of a compressor.
+///
+/// Unlike a [`MonoBus`], it isn't meant to carry audio. Instead, each value is
+/// sent along with the number of samples it holds for - e.g. once per buffer, for
+/// the whole buffer. Dispatchers then receive every value repeated for that many
+/// samples, so that accumulators see them at the bus' sample rate and views
+/// display them on the same time axis as audio. This way, there's no need to send
+/// the same value for every sample, or to fake a sample rate. Values that hold
+/// for many samples are dispatched in chunks of up to 4096 samples, so the bus
+/// never allocates more than that.
+///
+/// Like any other bus, set its sample rate to the one of your plug-in.
+///
+/// # Example
+///
+/// ```
+/// let gain_reduction_bus: Arc<ValueBus> = Default::default();
+///
+/// // Inside your plug-in's process() function
+/// gain_reduction_bus.send_held(self.compressor.gain(), buffer.samples());
+///
+/// // Inside your editor
+/// Graph::gain_reduction(cx, gain_reduction_bus.clone(), 10.0, (-24.0, 0.0));
+/// ```
+#[derive(Clone)]
+pub struct ValueBus {
+    dispatchers: Arc<RwLock<Vec<Weak<dyn Fn(slice::Iter<'_, f32>) + Sync + Send>>>>,
+    /// Each value, along with the number of samples it holds for
+    channel: (Sender<(f32, u32)>, Receiver<(f32, u32)>),
+    sample_rate: SampleRate,
+    /// The values of the last update, repeated for each sample they hold for, up
+    /// to [`EXPANSION_SIZE`] samples at a time
+    expanded: Arc<Mutex<Vec<f32>>>,
+}
+
+impl ValueBus {
+    /// Creates a new [`ValueBus`] which can hold `size` values until its next
+    /// update.
+    pub fn new(size: usize) -> Self {
+        let channel = bounded(size);
+        Self {
+            dispatchers: RwLock::new(vec![]).into(),
+            channel,
+            sample_rate: SampleRate::default(),
+            expanded: Mutex::new(Vec::with_capacity(EXPANSION_SIZE)).into(),
+        }
+    }
+
+    /// The number of values this bus can hold until its next update.
+    pub fn capacity(&self) -> usize {
+        self.channel.0.capacity().unwrap_or(usize::MAX)
+    }
+
+    /// Sends a value that holds for a single sample.
+    ///
+    /// This operation will silently fail if the Bus is congested.
+    #[inline]
+    pub fn send(&self, value: f32) {
+        self.send_held(value, 1);
+    }
+
+    /// Sends a value that holds for the given number of samples.
+    ///
+    /// This operation will silently fail if the Bus is congested.
+    #[inline]
+    pub fn send_held(&self, value: f32, samples: usize) {
+        if samples > 0 {
+            let _ = self
+                .channel
+                .0
+                .try_send((value, samples.min(u32::MAX as usize) as u32));
+        }
+    }
+}
+
+impl Default for ValueBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl Bus<f32> for ValueBus {
+    type I<'a> = slice::Iter<'a, f32>;
+    type O<'a> = Self::I<'a>;
+
+    fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.set(sample_rate);
+    }
+
+    fn sample_rate(&self) -> f32 {
+        self.sample_rate.get()
+    }
+
+    fn register_sample_rate_listener<F: Fn(f32) + Sync + Send + 'static>(
+        &self,
+        listener: F,
+    ) -> Arc<dyn Fn(f32) + Sync + Send> {
+        self.sample_rate.register(listener)
+    }
+
+    fn update(&self) {
+        if self.channel.1.is_empty() {
+            return;
+        }
+
+        let mut expanded = lock_or_recover(&self.expanded);
+        expanded.clear();
+
+        for (value, samples) in self.channel.1.try_iter() {
+            let mut remaining = samples as usize;
+
+            while remaining > 0 {
+                let taken = remaining.min(EXPANSION_SIZE - expanded.len());
+                expanded.extend(std::iter::repeat(value).take(taken));
+                remaining -= taken;
+
+                if expanded.len() == EXPANSION_SIZE {
+                    dispatch(&self.dispatchers, |d| d(expanded.iter()));
+                    expanded.clear();
+                }
+            }
+        }
+
+        if !expanded.is_empty() {
+            dispatch(&self.dispatchers, |d| d(expanded.iter()));
+        }
+    }
+
+    fn register_dispatcher<F: for<'a> Fn(Self::I<'a>) + Sync + Send + 'static>(
+        &self,
+        dispatcher: F,
+    ) -> Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> {
+        let dispatcher: Arc<dyn for<'a> Fn(Self::I<'a>) + Sync + Send> = Arc::new(dispatcher);
+        let downgraded = Arc::downgrade(&dispatcher);
+
+        let mut dispatchers = self.dispatchers.write().unwrap();
+
+        if let Some(pos) = dispatchers.iter().position(|d| d.upgrade().is_none()) {
+            dispatchers[pos] = downgraded;
+            dispatchers.retain(|d| d.upgrade().is_some());
+        } else {
+            dispatchers.push(downgraded);
+        }
+
+        dispatcher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_values_are_repeated() {
+        let bus = ValueBus::new(16);
+
+        let received: Arc<Mutex<Vec<f32>>> = Default::default();
+        let received_c = received.clone();
+        let _handle = bus.register_dispatcher(move |values| {
+            received_c.lock().unwrap().extend(values);
+        });
+
+        bus.send_held(0.5, 3);
+        bus.send_held(0.25, 0);
+        bus.send(1.0);
+        bus.update();
+
+        assert_eq!(*received.lock().unwrap(), vec![0.5, 0.5, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn long_holds_are_dispatched_in_chunks() {
+        let bus = ValueBus::new(16);
+
+        let chunks: Arc<Mutex<Vec<usize>>> = Default::default();
+        let chunks_c = chunks.clone();
+        let _handle = bus.register_dispatcher(move |values| {
+            chunks_c.lock().unwrap().push(values.len());
+        });
+
+        bus.send_held(0.5, EXPANSION_SIZE * 2 + 10);
+        bus.send(1.0);
+        bus.update();
+
+        assert_eq!(
+            *chunks.lock().unwrap(),
+            vec![EXPANSION_SIZE, EXPANSION_SIZE, 11]
+        );
+        assert!(bus.expanded.lock().unwrap().capacity() <= EXPANSION_SIZE);
+    }
+}
@@ -0,0 +1,57 @@
+//! Shared clocks that keep the time axes of multiple views aligned.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+
+/// A clock that aligns the time axes of multiple views.
+///
+/// Views that are fed by different buses receive their samples at slightly
+/// different points in time, depending on when each bus is updated. Because of
+/// this, graphs showing e.g. the input level, gain reduction and output level of a
+/// compressor will drift against each other by a few pixels.
+///
+/// Each view registered to a clock counts the samples it has received since it
+/// was created. The clock's position is the lowest count among all registered
+/// views. Views then only draw their history up to that position, so that all of
+/// their time axes line up exactly.
+///
+/// # Example
+///
+/// ```
+/// let clock = GraphClock::new();
+///
+/// Graph::peak(cx, input_bus.clone(), 10.0, 50.0, (-32.0, 8.0), ValueScaling::Decibels)
+///     .with_clock(clock.clone());
+/// Graph::peak(cx, output_bus.clone(), 10.0, 50.0, (-32.0, 8.0), ValueScaling::Decibels)
+///     .with_clock(clock.clone());
+/// ```
+#[derive(Clone, Default)]
+pub struct GraphClock {
+    positions: Arc<RwLock<Vec<Weak<AtomicU64>>>>,
+}
+
+impl GraphClock {
+    /// Creates a new [`GraphClock`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a view's sample counter with this clock.
+    pub(crate) fn register(&self, position: &Arc<AtomicU64>) {
+        let mut positions = self.positions.write().unwrap();
+        positions.retain(|p| p.upgrade().is_some());
+        positions.push(Arc::downgrade(position));
+    }
+
+    /// The number of samples that all registered views have received.
+    pub fn position(&self) -> u64 {
+        self.positions
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|p| p.upgrade())
+            .map(|p| p.load(Ordering::Relaxed))
+            .min()
+            .unwrap_or_default()
+    }
+}
@@ -4,10 +4,27 @@
 
 pub mod accumulators;
 pub mod bus;
+pub mod clock;
+pub mod loudness;
+pub mod markers;
+pub mod presets;
+pub mod recording;
 pub mod spectrum;
+pub mod testing;
 pub mod utils;
 pub mod visualizers;
 
 pub mod prelude {
-    pub use crate::{accumulators::*, bus::*, spectrum::*, utils::ValueScaling, visualizers::*};
+    pub use crate::{
+        accumulators::*,
+        bus::*,
+        clock::*,
+        loudness::*,
+        markers::*,
+        presets::{self, MeterScale},
+        recording::*,
+        spectrum::*,
+        utils::{ColorMap, ValueScaling},
+        visualizers::*,
+    };
 }
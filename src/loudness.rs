@@ -0,0 +1,621 @@
+//! Loudness metering according to ITU-R BS.1770 / EBU R128.
+//!
+//! The [`LoudnessAccumulator`] measures the loudness of a signal in LUFS, using
+//! the K-weighting filter and gating described in BS.1770. It can be used with
+//! any [`Meter`](crate::visualizers::Meter) or [`Graph`](crate::visualizers::Graph),
+//! and there are constructors for the most common use cases, such as
+//! [`Meter::lufs_momentary`](crate::visualizers::Meter::lufs_momentary) and
+//! [`Graph::lufs_short_term`](crate::visualizers::Graph::lufs_short_term).
+//!
+//! Like all accumulators, it measures the mono signal its bus provides. For a
+//! stereo signal, you can feed it the sum of both channels, e.g. via
+//! [`into_mono_weighted([1.0, 1.0])`](crate::bus::MultiChannelBus::into_mono_weighted).
+//! This matches BS.1770 for uncorrelated channels, but reads up to 3 LU high for
+//! content that is identical on both channels.
+//!
+//! The [`LoudnessRange`] measures how much the loudness varies over time, as
+//! described in EBU Tech 3342.
+
+use std::collections::VecDeque;
+
+use crate::accumulators::{sample_delta, Accumulator};
+
+/// The loudness reported for silence, in LUFS.
+const LOUDNESS_FLOOR: f32 = -100.0;
+/// Blocks quieter than this are ignored by the integrated loudness, in LUFS.
+const ABSOLUTE_GATE: f32 = -70.0;
+/// Blocks this far below the ungated loudness are ignored by the integrated
+/// loudness, in LU.
+const RELATIVE_GATE: f32 = -10.0;
+/// Short-term values this far below their loudness are ignored by the loudness
+/// range, in LU.
+const RANGE_RELATIVE_GATE: f32 = -20.0;
+/// The percentiles of the short-term loudness distribution whose difference is the
+/// loudness range.
+const RANGE_PERCENTILES: (f32, f32) = (0.10, 0.95);
+/// The length of a block, in seconds. Loudness values are updated once per block.
+const BLOCK_LENGTH: f32 = 0.1;
+/// The width of each bin of a [`GatingHistogram`], in LU.
+const HISTOGRAM_RESOLUTION: f32 = 0.1;
+/// The upper end of a [`GatingHistogram`], in LUFS.
+const HISTOGRAM_CEILING: f32 = 5.0;
+
+/// Converts a mean square of K-weighted samples to LUFS.
+fn loudness(power: f64) -> f32 {
+    if power > 0.0 {
+        ((-0.691 + 10.0 * power.log10()) as f32).max(LOUDNESS_FLOOR)
+    } else {
+        LOUDNESS_FLOOR
+    }
+}
+
+/// The mean of some mean squares, or 0 if there are none.
+fn mean(powers: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = powers.fold((0.0, 0), |(sum, count), p| (sum + p, count + 1));
+    if count > 0 {
+        sum / count as f64
+    } else {
+        0.0
+    }
+}
+
+/// A second order IIR filter, in transposed direct form II.
+#[derive(Debug, Clone, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// The K-weighting filter described in BS.1770.
+///
+/// It consists of a high shelf modelling the acoustic effect of the head, followed
+/// by a high pass roughly modelling the ear's insensitivity to low frequencies.
+#[derive(Debug, Clone)]
+pub struct KWeighting {
+    shelf: Biquad,
+    high_pass: Biquad,
+}
+
+impl KWeighting {
+    /// Creates a new K-weighting filter for the given sample rate.
+    ///
+    /// The coefficients are derived from the analog prototype, so that they match
+    /// the ones given in BS.1770 at 48 kHz, and are correct at any other sample
+    /// rate as well.
+    pub fn new(sample_rate: f32) -> Self {
+        let sample_rate = sample_rate as f64;
+
+        let shelf = {
+            let f0 = 1681.974450955533;
+            let gain = 3.999843853973347;
+            let q = 0.7071752369554196;
+
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let vh = 10f64.powf(gain / 20.0);
+            let vb = vh.powf(0.4996667741545416);
+            let a0 = 1.0 + k / q + k * k;
+
+            Biquad {
+                b: [
+                    (vh + vb * k / q + k * k) / a0,
+                    2.0 * (k * k - vh) / a0,
+                    (vh - vb * k / q + k * k) / a0,
+                ],
+                a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+                z: [0.0; 2],
+            }
+        };
+
+        let high_pass = {
+            let f0 = 38.13547087602444;
+            let q = 0.5003270373238773;
+
+            let k = (std::f64::consts::PI * f0 / sample_rate).tan();
+            let a0 = 1.0 + k / q + k * k;
+
+            Biquad {
+                b: [1.0, -2.0, 1.0],
+                a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+                z: [0.0; 2],
+            }
+        };
+
+        Self { shelf, high_pass }
+    }
+
+    /// Filters a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        self.high_pass.process(self.shelf.process(sample as f64)) as f32
+    }
+
+    /// Clears the filter's state.
+    pub fn reset(&mut self) {
+        self.shelf.z = [0.0; 2];
+        self.high_pass.z = [0.0; 2];
+    }
+}
+
+/// Splits a signal into 100 ms blocks and computes the mean square of each
+/// K-weighted block.
+#[derive(Debug, Clone)]
+struct BlockMeter {
+    filter: KWeighting,
+    /// Sum of squared K-weighted samples in the current block
+    sum: f64,
+    /// Number of samples in the current block so far
+    pos: usize,
+    len: usize,
+}
+
+impl BlockMeter {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            filter: KWeighting::new(sample_rate),
+            sum: 0.0,
+            pos: 0,
+            len: (sample_rate * BLOCK_LENGTH) as usize,
+        }
+    }
+
+    /// Processes a single sample, returning the mean square of the block it
+    /// completes, if any.
+    #[inline]
+    fn process(&mut self, sample: f32) -> Option<f64> {
+        let weighted = self.filter.process(sample) as f64;
+        self.sum += weighted * weighted;
+        self.pos += 1;
+
+        if self.pos >= self.len {
+            let power = self.sum / self.len.max(1) as f64;
+            self.sum = 0.0;
+            self.pos = 0;
+            Some(power)
+        } else {
+            None
+        }
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+        self.sum = 0.0;
+        self.pos = 0;
+    }
+}
+
+/// The 400 ms windows above the absolute gate, counted in bins of 0.1 LU from -70
+/// to +5 LUFS, like libebur128 does.
+///
+/// Each bin also sums up the mean squares of its windows, so the means used for
+/// gating are exact, and only the relative gate is rounded to a bin. This way,
+/// the integrated loudness takes the same memory and time no matter how long it
+/// has been measured for.
+#[derive(Debug, Clone)]
+struct GatingHistogram {
+    /// The number of windows in each bin, and the sum of their mean squares
+    bins: Vec<(u64, f64)>,
+}
+
+impl GatingHistogram {
+    fn new() -> Self {
+        let len = ((HISTOGRAM_CEILING - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION).round() as usize;
+
+        Self {
+            bins: vec![(0, 0.0); len],
+        }
+    }
+
+    /// The bin a loudness (in LUFS) falls into. Anything louder than the histogram
+    /// reaches shares its top bin.
+    fn bin(&self, loudness: f32) -> usize {
+        let bin = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION).max(0.0) as usize;
+        bin.min(self.bins.len() - 1)
+    }
+
+    /// Adds the mean square of a window, unless it is below the absolute gate.
+    fn push(&mut self, power: f64) {
+        let window_loudness = loudness(power);
+
+        if window_loudness > ABSOLUTE_GATE {
+            let bin = self.bin(window_loudness);
+            self.bins[bin].0 += 1;
+            self.bins[bin].1 += power;
+        }
+    }
+
+    /// The mean square of all windows from the given bin upwards, or 0 if there
+    /// are none.
+    fn mean_from(&self, bin: usize) -> f64 {
+        let (count, sum) = self.bins[bin..]
+            .iter()
+            .fold((0, 0.0), |(count, sum), (c, s)| (count + c, sum + s));
+
+        if count > 0 {
+            sum / count as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// The loudness of the windows above the relative gate, in LUFS.
+    fn integrated(&self) -> f32 {
+        let threshold = loudness(self.mean_from(0)) + RELATIVE_GATE;
+        loudness(self.mean_from(self.bin(threshold)))
+    }
+
+    fn clear(&mut self) {
+        self.bins.iter_mut().for_each(|bin| *bin = (0, 0.0));
+    }
+}
+
+/// The time window over which a [`LoudnessAccumulator`] measures loudness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoudnessWindow {
+    /// The loudness of the last 400 ms.
+    Momentary,
+    /// The loudness of the last 3 seconds.
+    ShortTerm,
+    /// The gated loudness since the accumulator was created or last reset.
+    Integrated,
+}
+
+impl LoudnessWindow {
+    /// The number of blocks this window spans.
+    fn blocks(&self) -> usize {
+        match self {
+            LoudnessWindow::Momentary | LoudnessWindow::Integrated => 4,
+            LoudnessWindow::ShortTerm => 30,
+        }
+    }
+}
+
+/// Measures the loudness of a signal in LUFS.
+///
+/// The signal is K-weighted, and its mean square is computed in 100 ms blocks. The
+/// momentary and short-term loudness are computed over the last 400 ms and 3
+/// seconds worth of blocks, respectively. The integrated loudness is computed over
+/// all overlapping 400 ms windows since the accumulator was created, ignoring those
+/// quieter than -70 LUFS, and then those more than 10 LU below the loudness of the
+/// remaining ones. The windows are kept in a histogram with a resolution of 0.1 LU,
+/// so even an integrated loudness measured over hours takes constant memory and
+/// time to update.
+pub struct LoudnessAccumulator {
+    window: LoudnessWindow,
+    meter: BlockMeter,
+    /// Mean squares of the most recent blocks
+    blocks: VecDeque<f64>,
+    /// Mean squares of all 400 ms windows above the absolute gate
+    gated: GatingHistogram,
+    /// The latest loudness, in LUFS
+    loudness: f32,
+    /// Previous accumulator value
+    prev: f32,
+    size: usize,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+}
+
+impl LoudnessAccumulator {
+    pub fn new(duration: f32, window: LoudnessWindow) -> Self {
+        Self {
+            window,
+            meter: BlockMeter::new(48000.0),
+            blocks: VecDeque::with_capacity(window.blocks()),
+            gated: GatingHistogram::new(),
+            loudness: LOUDNESS_FLOOR,
+            prev: LOUDNESS_FLOOR,
+            size: 1,
+            duration,
+            sample_rate: 48000.0,
+            t: 0.0,
+            sample_delta: 1.0,
+        }
+    }
+
+    /// Clears the measurement, e.g. to restart the integrated loudness.
+    pub fn reset(&mut self) {
+        self.meter.reset();
+        self.blocks.clear();
+        self.gated.clear();
+        self.loudness = LOUDNESS_FLOOR;
+    }
+
+    fn update(self: &mut Self) {
+        self.sample_delta = sample_delta(self.size, self.sample_rate, self.duration);
+        self.t = 0.0;
+    }
+
+    /// The mean square over the most recent `count` blocks.
+    fn power(&self, count: usize) -> f64 {
+        let count = count.min(self.blocks.len());
+        if count == 0 {
+            return 0.0;
+        }
+        self.blocks.iter().rev().take(count).sum::<f64>() / count as f64
+    }
+
+    fn finish_block(&mut self, power: f64) {
+        if self.blocks.len() == self.window.blocks() {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(power);
+
+        self.loudness = match self.window {
+            LoudnessWindow::Momentary | LoudnessWindow::ShortTerm => {
+                loudness(self.power(self.window.blocks()))
+            }
+            LoudnessWindow::Integrated => {
+                // Only full 400 ms windows count towards the integrated loudness
+                if self.blocks.len() == 4 {
+                    self.gated.push(self.power(4));
+                }
+                self.gated.integrated()
+            }
+        };
+    }
+}
+
+impl Accumulator for LoudnessAccumulator {
+    #[inline]
+    fn accumulate(&mut self, sample: f32) -> Option<f32> {
+        if let Some(power) = self.meter.process(sample) {
+            self.finish_block(power);
+        }
+
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            self.t -= self.sample_delta;
+            self.prev = self.loudness;
+
+            Some(self.loudness)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn prev(&self) -> f32 {
+        self.prev
+    }
+
+    #[inline]
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        // The filter can't be designed for an unknown sample rate
+        if sample_rate.is_nan() || sample_rate <= 0.0 {
+            return;
+        }
+
+        self.sample_rate = sample_rate;
+        self.meter = BlockMeter::new(sample_rate);
+        self.reset();
+        self.update();
+    }
+
+    #[inline]
+    fn set_size(&mut self, size: usize) {
+        self.size = size;
+        self.update();
+    }
+
+    #[inline]
+    fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+        self.update();
+    }
+}
+
+/// Measures the loudness range (LRA) of a signal in LU, according to EBU Tech 3342.
+///
+/// The loudness range describes how much the loudness of a program varies over
+/// time. It's computed from the distribution of the short-term loudness, which is
+/// measured every 100 ms. Values quieter than -70 LUFS are ignored, and then those
+/// more than 20 LU below the loudness of the remaining ones. The loudness range is
+/// the difference between the 10th and the 95th percentile of what remains, so
+/// that neither brief silences nor single loud events dominate it.
+///
+/// Unlike the [`LoudnessAccumulator`], this yields a single value rather than one
+/// that evolves over time, so it isn't an [`Accumulator`]. To display it, use a
+/// [`LoudnessRangeReadout`](crate::visualizers::LoudnessRangeReadout).
+pub struct LoudnessRange {
+    meter: BlockMeter,
+    /// Mean squares of the most recent blocks, spanning a short-term window
+    blocks: VecDeque<f64>,
+    /// Short-term mean squares, one per block
+    history: VecDeque<f64>,
+    /// The number of short-term values to keep, or `None` to keep all of them
+    history_len: Option<usize>,
+    /// The duration of the history, in seconds
+    duration: Option<f32>,
+}
+
+impl LoudnessRange {
+    /// Creates a new [`LoudnessRange`].
+    ///
+    /// The loudness range is measured over the last `duration` seconds, or since it
+    /// was created or last reset if `duration` is `None`. The latter matches EBU
+    /// Tech 3342, which measures the whole program.
+    pub fn new(duration: Option<f32>) -> Self {
+        let mut range = Self {
+            meter: BlockMeter::new(48000.0),
+            blocks: VecDeque::with_capacity(LoudnessWindow::ShortTerm.blocks()),
+            history: VecDeque::new(),
+            history_len: None,
+            duration,
+        };
+        range.set_sample_rate(48000.0);
+        range
+    }
+
+    /// Processes a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: f32) {
+        let Some(power) = self.meter.process(sample) else {
+            return;
+        };
+
+        if self.blocks.len() == LoudnessWindow::ShortTerm.blocks() {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(power);
+
+        // Only full 3 second windows count towards the loudness range
+        if self.blocks.len() < LoudnessWindow::ShortTerm.blocks() {
+            return;
+        }
+
+        if self
+            .history_len
+            .is_some_and(|len| self.history.len() >= len)
+        {
+            self.history.pop_front();
+        }
+        self.history.push_back(mean(self.blocks.iter().copied()));
+    }
+
+    /// The current loudness range, in LU.
+    ///
+    /// Returns 0 until at least one short-term value passes the gates.
+    pub fn value(&self) -> f32 {
+        let above_absolute_gate = || {
+            self.history
+                .iter()
+                .copied()
+                .filter(|p| loudness(*p) > ABSOLUTE_GATE)
+        };
+
+        let threshold = loudness(mean(above_absolute_gate())) + RANGE_RELATIVE_GATE;
+
+        let mut gated = above_absolute_gate()
+            .map(loudness)
+            .filter(|l| *l > threshold)
+            .collect::<Vec<_>>();
+
+        if gated.is_empty() {
+            return 0.0;
+        }
+
+        gated.sort_by(f32::total_cmp);
+
+        let percentile = |p: f32| gated[((gated.len() - 1) as f32 * p).round() as usize];
+        percentile(RANGE_PERCENTILES.1) - percentile(RANGE_PERCENTILES.0)
+    }
+
+    /// Clears the measurement.
+    pub fn reset(&mut self) {
+        self.meter.reset();
+        self.blocks.clear();
+        self.history.clear();
+    }
+
+    /// Sets the sample rate of the measured signal, which clears the measurement.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        // The filter can't be designed for an unknown sample rate
+        if sample_rate.is_nan() || sample_rate <= 0.0 {
+            return;
+        }
+
+        self.meter = BlockMeter::new(sample_rate);
+        self.history_len = self
+            .duration
+            .map(|duration| (duration / BLOCK_LENGTH).ceil().max(1.0) as usize);
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure(window: LoudnessWindow, amplitude: f32, seconds: f32) -> f32 {
+        let sample_rate = 48000.0;
+        let mut acc = LoudnessAccumulator::new(1.0, window);
+        acc.set_sample_rate(sample_rate);
+        acc.set_size(sample_rate as usize);
+
+        for i in 0..(sample_rate * seconds) as usize {
+            let t = i as f32 / sample_rate;
+            acc.accumulate(amplitude * (std::f32::consts::TAU * 1000.0 * t).sin());
+        }
+
+        acc.prev()
+    }
+
+    #[test]
+    fn full_scale_sine_reads_minus_3_lufs() {
+        // BS.1770: a 0 dBFS 1 kHz sine in a single channel reads -3.01 LUFS
+        for window in [
+            LoudnessWindow::Momentary,
+            LoudnessWindow::ShortTerm,
+            LoudnessWindow::Integrated,
+        ] {
+            assert!((measure(window, 1.0, 4.0) + 3.01).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn loudness_range_spans_quiet_and_loud_passages() {
+        let sample_rate = 48000.0;
+        let mut range = LoudnessRange::new(None);
+        range.set_sample_rate(sample_rate);
+
+        // 10 seconds of a sine at -20 dBFS, followed by 10 seconds at -30 dBFS
+        for (i, amplitude) in [0.1, 0.1f32.powf(1.5)]
+            .iter()
+            .flat_map(|a| std::iter::repeat(*a).take(10 * sample_rate as usize))
+            .enumerate()
+        {
+            let t = i as f32 / sample_rate;
+            range.process(amplitude * (std::f32::consts::TAU * 1000.0 * t).sin());
+        }
+
+        assert!((range.value() - 10.0).abs() < 0.5);
+
+        range.reset();
+        assert_eq!(range.value(), 0.0);
+    }
+
+    #[test]
+    fn quiet_passages_are_gated_from_the_integrated_loudness() {
+        let sample_rate = 48000.0;
+        let mut acc = LoudnessAccumulator::new(1.0, LoudnessWindow::Integrated);
+        acc.set_sample_rate(sample_rate);
+        acc.set_size(sample_rate as usize);
+
+        // 10 seconds of a sine at -20 dBFS, followed by 10 seconds at -40 dBFS,
+        // which lies below the relative gate
+        for (i, amplitude) in [0.1, 0.01f32]
+            .iter()
+            .flat_map(|a| std::iter::repeat(*a).take(10 * sample_rate as usize))
+            .enumerate()
+        {
+            let t = i as f32 / sample_rate;
+            acc.accumulate(amplitude * (std::f32::consts::TAU * 1000.0 * t).sin());
+        }
+
+        assert!((acc.prev() + 23.01).abs() < 0.2);
+    }
+
+    #[test]
+    fn silence_is_gated() {
+        assert_eq!(measure(LoudnessWindow::Momentary, 0.0, 1.0), LOUDNESS_FLOOR);
+        assert_eq!(
+            measure(LoudnessWindow::Integrated, 0.0, 1.0),
+            LOUDNESS_FLOOR
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Timestamped markers that can be sent from your plug-in and drawn over views.
+//!
+//! Markers can be used to annotate the history shown by a
+//! [`Graph`](crate::visualizers::Graph) with discrete occurrences, such as clip
+//! events, preset changes or section boundaries.
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How a [`Marker`] is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerStyle {
+    /// A thin vertical line.
+    Line,
+    /// A vertical line with a small flag at its top.
+    Flag,
+}
+
+/// A single timestamped marker.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Marker {
+    /// The point in time at which the marker was placed.
+    pub time: Instant,
+    pub style: MarkerStyle,
+}
+
+/// Places markers from the plug-in, which are received by a [`MarkerOutput`].
+#[derive(Clone)]
+pub struct MarkerInput {
+    sender: Sender<Marker>,
+}
+
+impl MarkerInput {
+    /// Creates a new marker input and output pair.
+    ///
+    /// The `capacity` dictates how many markers can be in flight, and also how
+    /// many markers the output keeps around at most.
+    pub fn new(capacity: usize) -> (MarkerInput, MarkerOutput) {
+        let (sender, receiver) = bounded(capacity);
+
+        (
+            MarkerInput { sender },
+            MarkerOutput {
+                receiver,
+                markers: VecDeque::with_capacity(capacity),
+                capacity,
+                max_age: Duration::from_secs(60),
+            },
+        )
+    }
+
+    /// Places a marker at the current point in time.
+    ///
+    /// This operation will silently fail if the channel is congested.
+    #[inline]
+    pub fn send(&self, style: MarkerStyle) {
+        let _ = self.sender.try_send(Marker {
+            time: Instant::now(),
+            style,
+        });
+    }
+}
+
+/// Receives markers placed by a [`MarkerInput`] and keeps a history of them.
+///
+/// Multiple views can share the same output, since reading the markers does not
+/// remove them from the history.
+pub struct MarkerOutput {
+    receiver: Receiver<Marker>,
+    markers: VecDeque<Marker>,
+    capacity: usize,
+    max_age: Duration,
+}
+
+impl MarkerOutput {
+    /// Receives any new markers and returns the history of all markers that are
+    /// not older than the maximum age, oldest first.
+    pub fn markers(&mut self) -> &VecDeque<Marker> {
+        for marker in self.receiver.try_iter() {
+            if self.markers.len() >= self.capacity {
+                self.markers.pop_front();
+            }
+            self.markers.push_back(marker);
+        }
+
+        let now = Instant::now();
+        while let Some(marker) = self.markers.front() {
+            if now.saturating_duration_since(marker.time) > self.max_age {
+                self.markers.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        &self.markers
+    }
+
+    /// Sets how long markers are kept around. Defaults to 60 seconds.
+    pub fn set_max_age(&mut self, max_age: Duration) {
+        self.max_age = max_age;
+    }
+
+    /// Removes all markers from the history.
+    pub fn clear(&mut self) {
+        self.markers.clear();
+    }
+}
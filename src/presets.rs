@@ -0,0 +1,170 @@
+//! Ready-made scales for common metering standards.
+//!
+//! Each [`MeterScale`] bundles a range with the marks that belong to it, so that a
+//! [`Meter`](crate::visualizers::Meter), the [`Grid`](crate::visualizers::Grid)
+//! behind it and the [`UnitRuler`](crate::visualizers::UnitRuler) next to it all
+//! line up, without repeating the same numbers for each of them.
+//!
+//! Marks are always given in the unit of the scale's range, so rulers and grids
+//! display them using [`ValueScaling::Linear`] - only the meter itself might need
+//! a different [`scaling`](MeterScale::scaling), e.g. to convert gain into dB.
+//!
+//! # Example
+//!
+//! A momentary loudness meter on the EBU +9 scale.
+//!
+//! ```
+//! let scale = presets::EBU_PLUS_9;
+//!
+//! HStack::new(cx, |cx| {
+//!     ZStack::new(cx, |cx| {
+//!         Grid::new(
+//!             cx,
+//!             ValueScaling::Linear,
+//!             scale.range,
+//!             scale.lines(),
+//!             Orientation::Horizontal,
+//!         )
+//!         .color(Color::rgb(30, 30, 30));
+//!         Meter::lufs_momentary(cx, bus.clone(), scale.range, Orientation::Vertical)
+//!             .background_color(Color::rgba(255, 255, 255, 60));
+//!     });
+//!     UnitRuler::new(
+//!         cx,
+//!         scale.range,
+//!         ValueScaling::Linear,
+//!         scale.markings(),
+//!         Orientation::Vertical,
+//!     )
+//!     .font_size(12.)
+//!     .width(Pixels(32.));
+//! });
+//! ```
+
+use crate::utils::ValueScaling;
+
+/// A range, along with the marks that belong to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterScale {
+    /// The range of the scale, in its unit.
+    pub range: (f32, f32),
+    /// The scaling a meter on this scale uses.
+    pub scaling: ValueScaling,
+    marks: &'static [(f32, &'static str)],
+}
+
+impl MeterScale {
+    /// Marks for a [`UnitRuler`](crate::visualizers::UnitRuler).
+    pub fn markings(&self) -> Vec<(f32, &'static str)> {
+        self.marks.to_vec()
+    }
+
+    /// The values of all marks, as lines for a [`Grid`](crate::visualizers::Grid).
+    pub fn lines(&self) -> Vec<f32> {
+        self.marks.iter().map(|(value, _)| *value).collect()
+    }
+}
+
+/// Full-scale peak level in dBFS, from -60 to 0.
+pub const DBFS: MeterScale = MeterScale {
+    range: (-60.0, 0.0),
+    scaling: ValueScaling::Decibels,
+    marks: &[
+        (0.0, "0"),
+        (-6.0, "-6"),
+        (-12.0, "-12"),
+        (-18.0, "-18"),
+        (-24.0, "-24"),
+        (-30.0, "-30"),
+        (-36.0, "-36"),
+        (-42.0, "-42"),
+        (-48.0, "-48"),
+        (-54.0, "-54"),
+        (-60.0, "-60"),
+    ],
+};
+
+/// The EBU +9 loudness scale of EBU Tech 3341, from -18 to +9 LU.
+///
+/// The range is given in LUFS, while the marks are labelled in LU relative to the
+/// -23 LUFS target of EBU R128.
+pub const EBU_PLUS_9: MeterScale = MeterScale {
+    range: (-41.0, -14.0),
+    scaling: ValueScaling::Linear,
+    marks: &[
+        (-14.0, "+9"),
+        (-17.0, "+6"),
+        (-20.0, "+3"),
+        (-23.0, "0"),
+        (-26.0, "-3"),
+        (-29.0, "-6"),
+        (-32.0, "-9"),
+        (-35.0, "-12"),
+        (-38.0, "-15"),
+        (-41.0, "-18"),
+    ],
+};
+
+/// The EBU +18 loudness scale of EBU Tech 3341, from -36 to +18 LU.
+///
+/// The range is given in LUFS, while the marks are labelled in LU relative to the
+/// -23 LUFS target of EBU R128.
+pub const EBU_PLUS_18: MeterScale = MeterScale {
+    range: (-59.0, -5.0),
+    scaling: ValueScaling::Linear,
+    marks: &[
+        (-5.0, "+18"),
+        (-11.0, "+12"),
+        (-17.0, "+6"),
+        (-23.0, "0"),
+        (-29.0, "-6"),
+        (-35.0, "-12"),
+        (-41.0, "-18"),
+        (-47.0, "-24"),
+        (-53.0, "-30"),
+        (-59.0, "-36"),
+    ],
+};
+
+/// Absolute loudness in LUFS, from -36 to 0.
+pub const LUFS: MeterScale = MeterScale {
+    range: (-36.0, 0.0),
+    scaling: ValueScaling::Linear,
+    marks: &[
+        (0.0, "0"),
+        (-6.0, "-6"),
+        (-12.0, "-12"),
+        (-18.0, "-18"),
+        (-24.0, "-24"),
+        (-30.0, "-30"),
+        (-36.0, "-36"),
+    ],
+};
+
+/// Phase correlation, from -1 (fully out of phase) to +1 (mono).
+pub const CORRELATION: MeterScale = MeterScale {
+    range: (-1.0, 1.0),
+    scaling: ValueScaling::Linear,
+    marks: &[
+        (1.0, "+1"),
+        (0.5, "+.5"),
+        (0.0, "0"),
+        (-0.5, "-.5"),
+        (-1.0, "-1"),
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_lie_within_their_range() {
+        for scale in [DBFS, EBU_PLUS_9, EBU_PLUS_18, LUFS, CORRELATION] {
+            let (min, max) = scale.range;
+
+            assert!(scale.lines().iter().all(|x| (min..=max).contains(x)));
+            assert!(scale.lines().contains(&min) && scale.lines().contains(&max));
+        }
+    }
+}
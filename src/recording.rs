@@ -0,0 +1,289 @@
+//! Long-horizon recordings of a signal, for reviewing a whole session at once.
+//!
+//! A [`SessionRecorder`] keeps an [`Overview`] of everything that went through a
+//! bus - e.g. during a bounce - as min/max pairs at a low resolution. It can be
+//! displayed using a [`SessionOverview`](crate::visualizers::SessionOverview), or
+//! exported as an SVG image for diagnostics.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+
+/// The minimum and maximum of a signal over time, at a resolution that gets coarser
+/// the longer the signal is.
+///
+/// Each pair spans the same number of samples. Once all pairs are used up,
+/// neighbouring pairs are merged and the number of samples per pair doubles, so
+/// that the overview always spans the whole signal.
+#[derive(Debug, Clone)]
+pub struct Overview {
+    pairs: Vec<(f32, f32)>,
+    capacity: usize,
+    samples_per_pair: usize,
+    /// The pair currently being filled, and how many samples it spans so far
+    pending: Option<((f32, f32), usize)>,
+    samples: u64,
+}
+
+impl Overview {
+    /// Creates a new, empty [`Overview`] of at most `capacity` pairs.
+    ///
+    /// The capacity is rounded up to an even number of at least 2.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = (capacity.max(2) + 1) & !1;
+
+        Self {
+            pairs: Vec::with_capacity(capacity),
+            capacity,
+            samples_per_pair: 1,
+            pending: None,
+            samples: 0,
+        }
+    }
+
+    /// Adds a sample to the overview.
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        self.samples += 1;
+
+        let ((min, max), count) = self.pending.get_or_insert(((sample, sample), 0));
+        *min = min.min(sample);
+        *max = max.max(sample);
+        *count += 1;
+
+        if *count >= self.samples_per_pair {
+            if self.pairs.len() == self.capacity {
+                // The finished pair only spans half as many samples as the merged
+                // ones, so it keeps filling up at the new resolution
+                self.compact();
+            } else {
+                let pair = (*min, *max);
+                self.pending = None;
+                self.pairs.push(pair);
+            }
+        }
+    }
+
+    /// Merges neighbouring pairs, halving the resolution.
+    fn compact(&mut self) {
+        self.pairs = self
+            .pairs
+            .chunks(2)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), pair| {
+                        (min.min(pair.0), max.max(pair.1))
+                    })
+            })
+            .collect();
+        self.samples_per_pair *= 2;
+    }
+
+    /// The recorded min/max pairs, oldest first.
+    pub fn pairs(&self) -> &[(f32, f32)] {
+        &self.pairs
+    }
+
+    /// The number of samples each pair spans.
+    pub fn samples_per_pair(&self) -> usize {
+        self.samples_per_pair
+    }
+
+    /// The total number of samples that were added to the overview.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Removes everything from the overview and resets its resolution.
+    pub fn clear(&mut self) {
+        self.pairs.clear();
+        self.samples_per_pair = 1;
+        self.pending = None;
+        self.samples = 0;
+    }
+
+    /// Renders the overview as an SVG image of the given size.
+    ///
+    /// The waveform is drawn as a single filled shape, from -1 at the bottom to +1
+    /// at the top, using the given CSS `color`.
+    pub fn write_svg(
+        &self,
+        mut writer: impl Write,
+        width: usize,
+        height: usize,
+        color: &str,
+    ) -> io::Result<()> {
+        let (w, h) = (width as f32, height as f32);
+        let step = w / self.pairs.len().max(1) as f32;
+        let to_y = |value: f32| h * (1. - value.clamp(-1., 1.)) / 2.;
+
+        writeln!(
+            writer,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )?;
+
+        if !self.pairs.is_empty() {
+            // Along the maxima from left to right, then back along the minima
+            let outline = self
+                .pairs
+                .iter()
+                .enumerate()
+                .map(|(i, (_, max))| (i as f32 * step, to_y(*max)))
+                .chain(
+                    self.pairs
+                        .iter()
+                        .enumerate()
+                        .rev()
+                        .map(|(i, (min, _))| (i as f32 * step, to_y(*min))),
+                )
+                .map(|(x, y)| format!("{x:.2},{y:.2}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            writeln!(writer, r#"  <polygon points="{outline}" fill="{color}"/>"#)?;
+        }
+
+        writeln!(writer, "</svg>")
+    }
+}
+
+/// Records an [`Overview`] of everything that goes through a bus.
+///
+/// The recording starts as soon as the recorder is created, and goes on for as
+/// long as it, or any of its clones, is alive. Since buses are updated by the
+/// editor, only what goes through the bus while the editor is open is recorded.
+///
+/// # Example
+///
+/// ```
+/// let recorder = SessionRecorder::new(bus.clone(), 4096);
+///
+/// // Display the whole session
+/// SessionOverview::new(cx, &recorder)
+///     .background_color(Color::rgba(255, 255, 255, 120));
+///
+/// // Or export it, e.g. once the bounce is done
+/// recorder.write_svg(File::create("session.svg")?, 1200, 200, "black")?;
+/// ```
+pub struct SessionRecorder<B: Bus<f32> + 'static> {
+    bus: Arc<B>,
+    overview: Arc<Mutex<Overview>>,
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+}
+
+impl<B: Bus<f32> + 'static> Clone for SessionRecorder<B> {
+    fn clone(&self) -> Self {
+        Self {
+            bus: self.bus.clone(),
+            overview: self.overview.clone(),
+            dispatcher_handle: self.dispatcher_handle.clone(),
+        }
+    }
+}
+
+impl<B: Bus<f32> + 'static> SessionRecorder<B> {
+    /// Creates a new [`SessionRecorder`] keeping at most `capacity` min/max pairs.
+    pub fn new(bus: Arc<B>, capacity: usize) -> Self {
+        let overview = Arc::new(Mutex::new(Overview::new(capacity)));
+
+        let overview_c = overview.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut overview = lock_or_recover(&overview_c);
+            for sample in samples {
+                overview.push(*sample);
+            }
+        });
+
+        Self {
+            bus,
+            overview,
+            dispatcher_handle,
+        }
+    }
+
+    /// A copy of the overview recorded so far.
+    pub fn overview(&self) -> Overview {
+        lock_or_recover(&self.overview).clone()
+    }
+
+    /// Calls the given function with the overview recorded so far, without copying
+    /// it.
+    pub fn with_overview<T>(&self, f: impl FnOnce(&Overview) -> T) -> T {
+        f(&lock_or_recover(&self.overview))
+    }
+
+    /// The duration of the recording, in seconds.
+    ///
+    /// Returns 0 if the bus' sample rate isn't known yet.
+    pub fn duration(&self) -> f32 {
+        let sample_rate = self.bus.sample_rate();
+        let samples = lock_or_recover(&self.overview).samples();
+
+        if sample_rate > 0.0 {
+            (samples as f64 / sample_rate as f64) as f32
+        } else {
+            0.0
+        }
+    }
+
+    /// Discards the recording and starts over.
+    pub fn clear(&self) {
+        lock_or_recover(&self.overview).clear();
+    }
+
+    /// Renders the recording as an SVG image. See [`Overview::write_svg`].
+    pub fn write_svg(
+        &self,
+        writer: impl Write,
+        width: usize,
+        height: usize,
+        color: &str,
+    ) -> io::Result<()> {
+        lock_or_recover(&self.overview).write_svg(writer, width, height, color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Overview;
+
+    #[test]
+    fn overview_spans_the_whole_signal() {
+        let mut overview = Overview::new(8);
+
+        for i in 0..100 {
+            overview.push(if i == 3 { -1.0 } else { (i as f32) / 100.0 });
+        }
+
+        assert!(overview.pairs().len() <= 8);
+        assert_eq!(overview.samples(), 100);
+        assert_eq!(overview.samples_per_pair(), 16);
+
+        // The extremes survive every compaction, while the last 4 samples are still
+        // waiting to fill up a pair
+        assert_eq!(overview.pairs()[0].0, -1.0);
+        assert_eq!(overview.pairs().last().unwrap().1, 0.95);
+    }
+
+    #[test]
+    fn pairs_span_the_same_samples_after_compacting() {
+        let mut overview = Overview::new(8);
+
+        // Each pair's min and max are the indices of its first and last sample
+        for i in 0..100 {
+            overview.push(i as f32);
+        }
+
+        let samples_per_pair = overview.samples_per_pair();
+        assert_eq!(samples_per_pair, 16);
+        assert_eq!(overview.pairs().len(), 6);
+
+        for (i, &(min, max)) in overview.pairs().iter().enumerate() {
+            assert_eq!(min as usize, i * samples_per_pair);
+            assert_eq!(max as usize, (i + 1) * samples_per_pair - 1);
+        }
+    }
+}
@@ -19,23 +19,623 @@
 
 //! Necessary for processing and sending spectral information to the [`SpectrumAnalyzer`](crate::visualizers::SpectrumAnalyzer).
 
+use crossbeam_channel::{bounded, Receiver, Sender};
 use nih_plug::prelude::*;
 use nih_plug::util::window::multiply_with_window;
 use realfft::num_complex::Complex32;
 use realfft::{RealFftPlanner, RealToComplex};
+use std::any::Any;
 use std::f32;
-use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use triple_buffer::TripleBuffer;
 
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, RingBuffer, ValueScaling};
+
+/// A good default window size, for when neither latency nor low-end resolution
+/// are of particular concern.
 pub const SPECTRUM_WINDOW_SIZE: usize = 2048;
-const SPECTRUM_WINDOW_OVERLAP: usize = 2;
+/// The smallest window size a [`SpectrumInput`] can be created with.
+pub const MIN_SPECTRUM_WINDOW_SIZE: usize = 1024;
+/// The largest window size a [`SpectrumInput`] can be created with.
+pub const MAX_SPECTRUM_WINDOW_SIZE: usize = 16384;
+/// The default number of times consecutive windows overlap.
+pub const SPECTRUM_WINDOW_OVERLAP: usize = 2;
+/// The highest number of times consecutive windows can overlap.
+pub const MAX_SPECTRUM_WINDOW_OVERLAP: usize = 16;
+/// How many windowed frames can be in flight between a deferred [`SpectrumInput`]
+/// and its [`SpectrumOutput`]. Frames are dropped if the editor falls behind.
+const DEFERRED_FRAMES: usize = 32;
+
+/// A window function that a [`SpectrumInput`] applies before each FFT.
+///
+/// All windows are gain compensated, so that a sine shows up at the same level
+/// regardless of the window. They differ in how they trade frequency resolution
+/// for leakage and level accuracy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowFunction {
+    /// A good all-round window, and the default.
+    Hann,
+    /// A 4-term Blackman-Harris window, which leaks far less than a Hann window
+    /// at the cost of a wider main lobe.
+    BlackmanHarris,
+    /// A Kaiser window with the given `β`. Higher values reduce leakage and widen
+    /// the main lobe - around 6 to 9 is common for spectrum analysis.
+    Kaiser(f32),
+    /// A flat-top window, which measures the level of a sine accurately no
+    /// matter where it lies between two bins. Use this for level-accurate
+    /// measurements, where frequency resolution matters less.
+    FlatTop,
+}
+
+impl WindowFunction {
+    /// The window's coefficients, compensated so that a sine at the center of a
+    /// bin has the same magnitude as with a [`Hann`](Self::Hann) window.
+    fn compensated_coefficients(&self, size: usize) -> Vec<f32> {
+        let window = match self {
+            // Kept as is, so that existing spectra don't change
+            WindowFunction::Hann => util::window::hann(size),
+            WindowFunction::BlackmanHarris => {
+                cosine_sum(size, &[0.35875, 0.48829, 0.14128, 0.01168])
+            }
+            WindowFunction::Kaiser(beta) => {
+                let denominator = bessel_i0(*beta);
+                (0..size)
+                    .map(|i| {
+                        let x = 2. * i as f32 / (size - 1).max(1) as f32 - 1.;
+                        bessel_i0(beta * (1. - x * x).max(0.).sqrt()) / denominator
+                    })
+                    .collect()
+            }
+            WindowFunction::FlatTop => cosine_sum(
+                size,
+                &[0.21557895, 0.41663158, 0.27726316, 0.083578947, 0.006947368],
+            ),
+        };
+
+        // A Hann window sums to half its size, and the original gain compensation
+        // divided it by its size
+        let compensation = 0.5 / window.iter().sum::<f32>();
+        window.into_iter().map(|x| x * compensation).collect()
+    }
+
+    /// The window's equivalent noise bandwidth, in bins.
+    ///
+    /// The energy of a sine is spread over this many bins, so it is needed to sum
+    /// the bins of a band without overestimating the level of a sine within it.
+    pub fn noise_bandwidth(&self, size: usize) -> f32 {
+        let window = self.compensated_coefficients(size);
+        let sum = window.iter().sum::<f32>();
+        let sum_of_squares = window.iter().map(|x| x * x).sum::<f32>();
+
+        size as f32 * sum_of_squares / (sum * sum)
+    }
+}
+
+/// A window made up of cosine terms with the given coefficients, which alternate
+/// in sign.
+fn cosine_sum(size: usize, coefficients: &[f32]) -> Vec<f32> {
+    let scale = f32::consts::TAU / (size - 1).max(1) as f32;
+
+    (0..size)
+        .map(|i| {
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(k, a)| {
+                    let sign = if k % 2 == 0 { 1. } else { -1. };
+                    sign * a * (scale * (k * i) as f32).cos()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// The zeroth order modified Bessel function of the first kind.
+fn bessel_i0(x: f32) -> f32 {
+    let half = x as f64 / 2.;
+    let (mut sum, mut term, mut k) = (1f64, 1f64, 1f64);
+
+    while term > sum * 1e-12 {
+        term *= (half / k) * (half / k);
+        sum += term;
+        k += 1.;
+    }
+
+    sum as f32
+}
+
+/// Which spectra a [`SpectrumInput`] computes from its channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelMode {
+    /// A single spectrum, which all channels are folded into.
+    Mix,
+    /// One spectrum for each channel, in order.
+    PerChannel,
+    /// The spectra of the mid and side signals of the first two channels, in
+    /// that order.
+    ///
+    /// Mono inputs fall back to [`Mix`](Self::Mix), since they have no side signal.
+    MidSide,
+}
+
+impl ChannelMode {
+    /// The mode that is used for the given number of channels.
+    fn for_channels(self, num_channels: usize) -> Self {
+        match self {
+            ChannelMode::MidSide if num_channels < 2 => ChannelMode::Mix,
+            mode => mode,
+        }
+    }
+
+    /// The number of spectra computed for the given number of channels.
+    fn traces(&self, num_channels: usize) -> usize {
+        match self {
+            ChannelMode::Mix => 1,
+            ChannelMode::PerChannel => num_channels.max(1),
+            ChannelMode::MidSide => 2,
+        }
+    }
+
+    /// The number of channels folded into each spectrum.
+    fn channels_per_trace(&self, num_channels: usize) -> usize {
+        match self {
+            ChannelMode::Mix => num_channels,
+            ChannelMode::PerChannel | ChannelMode::MidSide => 1,
+        }
+    }
+
+    /// Sends a channel's window to the spectrum (or spectra) it belongs to.
+    ///
+    /// For mid and side, the first channel's window is held in the
+    /// `mid_side_buffer` until the second one arrives.
+    fn send_window(
+        &self,
+        sink: &mut InputSink,
+        mid_side_buffer: &mut [f32],
+        channel_idx: usize,
+        window: &mut [f32],
+        smoothing_decay_weight: f32,
+    ) {
+        match self {
+            ChannelMode::Mix => sink.send(0, window, smoothing_decay_weight),
+            ChannelMode::PerChannel => sink.send(channel_idx, window, smoothing_decay_weight),
+            ChannelMode::MidSide => match channel_idx {
+                0 => mid_side_buffer.copy_from_slice(window),
+                1 => {
+                    // Windowing is linear, so the windowed channels can be turned
+                    // into the windowed mid and side directly
+                    for (left, right) in mid_side_buffer.iter_mut().zip(window.iter_mut()) {
+                        (*left, *right) = ((*left + *right) / 2., (*left - *right) / 2.);
+                    }
+
+                    sink.send(0, mid_side_buffer, smoothing_decay_weight);
+                    sink.send(1, window, smoothing_decay_weight);
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// A sample made up of one value per channel, which a bus-driven spectrum can be
+/// computed from.
+///
+/// See [`SpectrumInput::from_bus`].
+pub trait ChannelSample: Clone + Copy + Sized + Send + Sync + 'static {
+    /// The number of channels.
+    const CHANNELS: usize;
+
+    /// The value of the channel with the given index.
+    fn channel(&self, index: usize) -> f32;
+}
+
+impl ChannelSample for f32 {
+    const CHANNELS: usize = 1;
+
+    fn channel(&self, _index: usize) -> f32 {
+        *self
+    }
+}
+
+impl<const C: usize> ChannelSample for [f32; C] {
+    const CHANNELS: usize = C;
+
+    fn channel(&self, index: usize) -> f32 {
+        self[index]
+    }
+}
+
+/// The weight that makes a bin decay by 12 dB over `decay` ms.
+fn smoothing_decay_weight(
+    decay: f32,
+    sample_rate: f32,
+    window_size: usize,
+    overlap: usize,
+    channels_per_trace: usize,
+) -> f32 {
+    // NOTE: The effective sample rate accounts for the STFT interval, **and** for the number of
+    //       channels if we average them to mono-ish.
+    let effective_sample_rate =
+        sample_rate / window_size as f32 * overlap as f32 * channels_per_trace as f32;
+    let decay_samples = (decay / 1000.0 * effective_sample_rate) as f64;
+
+    0.25f64.powf(decay_samples.recip()) as f32
+}
+
+/// The decay of a spectrum, shared between its input and output so that it can
+/// be changed while the plug-in is running.
+struct SmoothingDecay {
+    /// The time (in ms) it takes for a bin to decrease by 12 dB
+    decay: AtomicF32,
+    /// The weight resulting from the decay at the current sample rate
+    weight: AtomicF32,
+    window_size: usize,
+    overlap: usize,
+    channels_per_trace: usize,
+}
+
+impl SmoothingDecay {
+    fn new(decay: f32, window_size: usize, overlap: usize, channels_per_trace: usize) -> Self {
+        Self {
+            decay: AtomicF32::new(decay),
+            // This is set once the sample rate is known
+            weight: AtomicF32::new(0.0),
+            window_size,
+            overlap,
+            channels_per_trace,
+        }
+    }
+
+    fn weight(&self) -> f32 {
+        self.weight.load(Ordering::Relaxed)
+    }
+
+    /// Recomputes the weight for the given sample rate.
+    fn update(&self, sample_rate: f32) {
+        self.weight.store(
+            smoothing_decay_weight(
+                self.decay.load(Ordering::Relaxed),
+                sample_rate,
+                self.window_size,
+                self.overlap,
+                self.channels_per_trace,
+            ),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn set_decay(&self, decay: f32, sample_rate: f32) {
+        self.decay.store(decay, Ordering::Relaxed);
+        self.update(sample_rate);
+    }
+}
 
 /// The amplitudes of all frequency bins in a windowed FFT output.
-pub type Spectrum = [f32; SPECTRUM_WINDOW_SIZE / 2 + 1];
+///
+/// Holds `window_size / 2 + 1` bins, for the window size of the [`SpectrumInput`]
+/// it was computed by.
+pub type Spectrum = Box<[f32]>;
+
+/// Turns windowed frames into a smoothed spectrum.
+struct SpectrumProcessor {
+    plan: Arc<dyn RealToComplex<f32>>,
+    complex_fft_buffer: Vec<Complex32>,
+    spectrum_result_buffer: Spectrum,
+}
+
+impl SpectrumProcessor {
+    fn new(window_size: usize) -> Self {
+        Self {
+            plan: RealFftPlanner::new().plan_fft_forward(window_size),
+            complex_fft_buffer: vec![Complex32::default(); window_size / 2 + 1],
+            spectrum_result_buffer: vec![0.0; window_size / 2 + 1].into_boxed_slice(),
+        }
+    }
+
+    /// Computes the FFT of an already windowed frame and folds it into the result.
+    fn process(&mut self, frame: &mut [f32], smoothing_decay_weight: f32) {
+        self.plan
+            .process_with_scratch(
+                frame,
+                &mut self.complex_fft_buffer,
+                // We don't actually need a scratch buffer
+                &mut [],
+            )
+            .unwrap();
+
+        // We'll use peak meter-like behavior for the spectrum analyzer to make things
+        // easier to dial in. Values that are higher than the old value snap to the new
+        // value immediately, lower values decay gradually. In the `Mix` channel mode,
+        // this results in quasi-mono summing since this is called for every channel.
+        // Gain compensation has already been baked into the window function.
+        for (bin, spectrum_result) in self
+            .complex_fft_buffer
+            .iter()
+            .zip(self.spectrum_result_buffer.iter_mut())
+        {
+            let magnitude = bin.norm();
+            if magnitude > *spectrum_result {
+                *spectrum_result = magnitude;
+            } else {
+                *spectrum_result = (*spectrum_result * smoothing_decay_weight)
+                    + (magnitude * (1.0 - smoothing_decay_weight));
+            }
+        }
+    }
+}
+
+/// A windowed frame, along with the index of the spectrum it belongs to.
+type Frame = (usize, Box<[f32]>);
+
+/// Where a [`SpectrumOutput`] gets its spectra from.
+enum OutputSource {
+    /// Spectra computed on the audio thread.
+    Realtime(Vec<triple_buffer::Output<Spectrum>>),
+    /// Windowed frames, which are processed when the spectra are read.
+    Deferred {
+        processors: Vec<SpectrumProcessor>,
+        frames: Receiver<Frame>,
+        recycled: Sender<Box<[f32]>>,
+    },
+}
+
 /// A receiver for a spectrum computed by [`SpectrumInput`].
 pub struct SpectrumOutput {
-    pub(crate) output: triple_buffer::Output<Spectrum>,
-    pub(crate) sample_rate: f32,
+    source: OutputSource,
+    /// Shared with the [`SpectrumInput`], so that it follows sample rate changes
+    pub(crate) sample_rate: Arc<AtomicF32>,
+    noise_bandwidth: f32,
+    smoothing: Arc<SmoothingDecay>,
+    /// Keeps the dispatcher and sample rate listener of a bus-driven output alive
+    bus_handles: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+impl SpectrumOutput {
+    /// The most recent spectrum.
+    ///
+    /// If the input computes several spectra, this is the first of them - see
+    /// [`ChannelMode`]. If the input is [deferred](SpectrumInput::new_deferred),
+    /// this first runs the FFT on all frames that were sent since the last read,
+    /// right here on the calling thread - usually within the editor's draw call.
+    pub fn read(&mut self) -> &Spectrum {
+        self.read_trace(0)
+            .expect("A spectrum output always has at least one spectrum")
+    }
+
+    /// The most recent spectrum with the given index, if the input computes it.
+    ///
+    /// See [`ChannelMode`] for which spectra are computed, and in which order.
+    pub fn read_trace(&mut self, index: usize) -> Option<&Spectrum> {
+        match &mut self.source {
+            OutputSource::Realtime(outputs) => outputs.get_mut(index).map(|o| o.read()),
+            OutputSource::Deferred {
+                processors,
+                frames,
+                recycled,
+            } => {
+                let smoothing_decay_weight = self.smoothing.weight();
+                for (trace, mut frame) in frames.try_iter() {
+                    if let Some(processor) = processors.get_mut(trace) {
+                        processor.process(&mut frame, smoothing_decay_weight);
+                    }
+                    // Hand the frame back so the audio thread never has to allocate
+                    let _ = recycled.try_send(frame);
+                }
+                processors.get(index).map(|p| &p.spectrum_result_buffer)
+            }
+        }
+    }
+
+    /// The number of spectra the input computes.
+    pub fn num_traces(&self) -> usize {
+        match &self.source {
+            OutputSource::Realtime(outputs) => outputs.len(),
+            OutputSource::Deferred { processors, .. } => processors.len(),
+        }
+    }
+
+    /// The sample rate the spectrum was computed at, as last set using
+    /// [`SpectrumInput::update_sample_rate`].
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+
+    /// The Nyquist frequency of the spectrum, which its last bin lies at.
+    pub fn nyquist(&self) -> f32 {
+        self.sample_rate() / 2.
+    }
+
+    /// The equivalent noise bandwidth (in bins) of the window the spectrum was
+    /// computed with.
+    ///
+    /// See [`WindowFunction::noise_bandwidth`].
+    pub fn noise_bandwidth(&self) -> f32 {
+        self.noise_bandwidth
+    }
+
+    /// The time (in ms) it takes for a bin to decrease by 12 dB.
+    pub fn decay(&self) -> f32 {
+        self.smoothing.decay.load(Ordering::Relaxed)
+    }
+
+    /// Changes the time (in ms) it takes for a bin to decrease by 12 dB.
+    ///
+    /// This takes effect immediately, and is shared with the [`SpectrumInput`], so
+    /// the editor can offer an "analyzer speed" control without going through
+    /// the audio thread.
+    pub fn set_decay(&self, decay: f32) {
+        self.smoothing.set_decay(decay, self.sample_rate());
+    }
+}
+
+/// The center frequency of a bin, in a spectrum with `len` bins spanning 0 Hz up
+/// to and including the Nyquist frequency.
+pub(crate) fn bin_frequency(bin_idx: usize, len: usize, nyquist: f32) -> f32 {
+    bin_idx as f32 / (len - 1).max(1) as f32 * nyquist
+}
+
+/// Where the magnitude of a point of a [`SpectrumResampler`] comes from.
+#[derive(Debug, Clone, PartialEq)]
+enum PointSource {
+    /// The loudest of several bins, so that narrow peaks stay visible.
+    Bins(std::ops::Range<usize>),
+    /// A point in between two neighbouring bins, interpolated from the lower bin
+    /// with the given weight.
+    Between(usize, f32),
+}
+
+/// Resamples a spectrum onto a fixed number of points, evenly spaced along a
+/// scaled frequency axis - usually one per pixel of the view displaying it.
+///
+/// Which bins each point is made of is worked out once, up front. Resampling a
+/// spectrum then comes down to a single pass over its bins, and only the points
+/// need to be scaled and drawn, no matter how large the FFT window is. Where many
+/// bins fall onto one point, as in the highs of a logarithmic axis, the point
+/// takes the loudest of them. Where points lie closer together than the bins, as
+/// in the lows, they are interpolated linearly.
+///
+/// The points end at the Nyquist frequency, so there may be fewer than requested.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpectrumResampler {
+    frequencies: Vec<f32>,
+    sources: Vec<PointSource>,
+    /// What the resampler was built for, so that it can be rebuilt on changes
+    layout: (usize, ValueScaling, (f32, f32), usize, f32),
+}
+
+impl SpectrumResampler {
+    /// Creates a new [`SpectrumResampler`] for `points` points along the given
+    /// frequency range and scaling, taking spectra with `len` bins up to the
+    /// `nyquist` frequency.
+    pub fn new(
+        points: usize,
+        scaling: ValueScaling,
+        range: (f32, f32),
+        len: usize,
+        nyquist: f32,
+    ) -> Self {
+        let points = points.max(2);
+        let frequency_at = |position: f32| {
+            scaling.normalized_to_value(position / (points - 1) as f32, range.0, range.1)
+        };
+        let bin_position =
+            |frequency: f32| frequency / nyquist * len.saturating_sub(1).max(1) as f32;
+
+        let frequencies = (0..points)
+            .map(|point| frequency_at(point as f32))
+            .take_while(|frequency| *frequency <= nyquist)
+            .collect::<Vec<f32>>();
+
+        let sources = (0..frequencies.len())
+            .map(|point| {
+                // Each point covers the bins up to halfway to its neighbours
+                let first = bin_position(frequency_at(point as f32 - 0.5))
+                    .max(0.)
+                    .ceil() as usize;
+                let end = (bin_position(frequency_at(point as f32 + 0.5)).ceil() as usize).min(len);
+
+                if end > first {
+                    PointSource::Bins(first..end)
+                } else {
+                    let position = bin_position(frequencies[point]);
+                    let bin = (position.floor() as usize).min(len.saturating_sub(2));
+                    PointSource::Between(bin, 1. - (position - bin as f32).clamp(0., 1.))
+                }
+            })
+            .collect();
+
+        Self {
+            frequencies,
+            sources,
+            layout: (points, scaling, range, len, nyquist),
+        }
+    }
+
+    /// Whether this resampler was built using the given parameters.
+    pub fn matches(
+        &self,
+        points: usize,
+        scaling: ValueScaling,
+        range: (f32, f32),
+        len: usize,
+        nyquist: f32,
+    ) -> bool {
+        self.layout == (points.max(2), scaling, range, len, nyquist)
+    }
+
+    /// The frequency of each point.
+    pub fn frequencies(&self) -> &[f32] {
+        &self.frequencies
+    }
+
+    /// Resamples a spectrum onto the points.
+    ///
+    /// The spectrum needs to have the number of bins the resampler was built for.
+    pub fn resample(&self, spectrum: &[f32]) -> Vec<f32> {
+        self.sources
+            .iter()
+            .map(|source| match source {
+                PointSource::Bins(bins) => spectrum
+                    .get(bins.clone())
+                    .map_or(0., |bins| bins.iter().copied().fold(0., f32::max)),
+                PointSource::Between(bin, weight) => {
+                    let lower = spectrum.get(*bin).copied().unwrap_or_default();
+                    let upper = spectrum.get(bin + 1).copied().unwrap_or(lower);
+                    lower * weight + upper * (1. - weight)
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where a [`SpectrumInput`] sends its results to.
+enum InputSink {
+    /// The FFT runs on the audio thread, and finished spectra are sent.
+    Realtime {
+        processors: Vec<SpectrumProcessor>,
+        triple_buffer_inputs: Vec<triple_buffer::Input<Spectrum>>,
+    },
+    /// Windowed frames are sent, and the FFT runs in the editor.
+    Deferred {
+        frames: Sender<Frame>,
+        recycled: Receiver<Box<[f32]>>,
+    },
+}
+
+impl InputSink {
+    /// Sends a windowed frame belonging to the spectrum with the given index.
+    fn send(&mut self, trace: usize, frame: &mut [f32], smoothing_decay_weight: f32) {
+        match self {
+            InputSink::Realtime {
+                processors,
+                triple_buffer_inputs,
+            } => {
+                let (Some(processor), Some(triple_buffer_input)) = (
+                    processors.get_mut(trace),
+                    triple_buffer_inputs.get_mut(trace),
+                ) else {
+                    return;
+                };
+
+                processor.process(frame, smoothing_decay_weight);
+                // Copy into the triple buffer, so that nothing is allocated here
+                triple_buffer_input
+                    .input_buffer()
+                    .copy_from_slice(&processor.spectrum_result_buffer);
+                triple_buffer_input.publish();
+            }
+            InputSink::Deferred { frames, recycled } => {
+                // If the editor has fallen behind, there's no frame to fill and this
+                // one gets dropped
+                if let Ok(mut buffer) = recycled.try_recv() {
+                    buffer.copy_from_slice(frame);
+                    let _ = frames.try_send((trace, buffer));
+                }
+            }
+        }
+    }
 }
 
 /// Continuously compute spectrums and send them to the connected [`SpectrumOutput`].
@@ -44,115 +644,558 @@ pub struct SpectrumInput {
     stft: util::StftHelper,
     /// The number of channels we're working on.
     num_channels: usize,
-    sample_rate: f32,
+    channel_mode: ChannelMode,
+    /// The number of samples in each FFT window.
+    window_size: usize,
+    /// The number of times consecutive windows overlap.
+    overlap: usize,
+    sample_rate: Arc<AtomicF32>,
+
+    /// The decay time for a bin to decrease by -12dB, shared with the
+    /// [`SpectrumOutput`]
+    smoothing: Arc<SmoothingDecay>,
 
-    /// The decay time for a bin to decrease by -12dB.
+    sink: InputSink,
+
+    compensated_window_function: Vec<f32>,
+    /// Holds the first channel's window, until the mid and side can be computed
+    mid_side_buffer: Vec<f32>,
+}
+
+/// The time (in ms) it takes for a bin to decrease by 12 dB, by default.
+const DEFAULT_DECAY: f32 = 100.0;
+
+/// Configures a [`SpectrumInput`] and its [`SpectrumOutput`], or a bus-driven
+/// [`SpectrumOutput`].
+///
+/// Unless changed, all channels are folded into a single spectrum, computed from
+/// windows of [`SPECTRUM_WINDOW_SIZE`] samples that are shaped by a
+/// [`Hann`](WindowFunction::Hann) window and overlap [`SPECTRUM_WINDOW_OVERLAP`]
+/// times. Bins decrease by 12 dB within 100 ms.
+///
+/// # Example
+///
+/// ```
+/// let (spectrum_input, spectrum_output) = SpectrumInput::builder()
+///     .channel_mode(ChannelMode::MidSide)
+///     .window_size(8192)
+///     .window(WindowFunction::BlackmanHarris)
+///     .build(2);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SpectrumInputBuilder {
+    channel_mode: ChannelMode,
+    window_size: usize,
+    window: WindowFunction,
+    overlap: usize,
     decay: f32,
-    smoothing_decay_weight: f32,
+}
 
-    triple_buffer_input: triple_buffer::Input<Spectrum>,
-    spectrum_result_buffer: Spectrum,
+impl SpectrumInputBuilder {
+    fn new() -> Self {
+        Self {
+            channel_mode: ChannelMode::Mix,
+            window_size: SPECTRUM_WINDOW_SIZE,
+            window: WindowFunction::Hann,
+            overlap: SPECTRUM_WINDOW_OVERLAP,
+            decay: DEFAULT_DECAY,
+        }
+    }
 
-    plan: Arc<dyn RealToComplex<f32>>,
+    /// Sets which spectra are computed from the channels.
+    ///
+    /// By default, all channels are folded into a single spectrum. The channels
+    /// can instead be split up, such as into the mid and side signals, which can
+    /// then be drawn on top of each other.
+    pub fn channel_mode(mut self, channel_mode: ChannelMode) -> Self {
+        self.channel_mode = channel_mode;
+        self
+    }
 
-    compensated_window_function: Vec<f32>,
+    /// Sets the number of samples in each FFT window.
+    ///
+    /// The window size trades latency for resolution: larger windows resolve the
+    /// low end in more detail, but take longer to react. It is clamped to
+    /// [`MIN_SPECTRUM_WINDOW_SIZE`] and [`MAX_SPECTRUM_WINDOW_SIZE`], and rounded up
+    /// to the next power of two.
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size
+            .clamp(MIN_SPECTRUM_WINDOW_SIZE, MAX_SPECTRUM_WINDOW_SIZE)
+            .next_power_of_two();
+        self
+    }
 
-    complex_fft_buffer: Vec<Complex32>,
+    /// Sets the [`WindowFunction`] each window is shaped by before the FFT.
+    pub fn window(mut self, window: WindowFunction) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets how many times per window size a new window is analyzed.
+    ///
+    /// Higher overlap makes the spectrum move more smoothly, especially for
+    /// transient material, at the cost of more FFTs. It is clamped to
+    /// [`MAX_SPECTRUM_WINDOW_OVERLAP`], and rounded up to the next power of two.
+    /// The decay stays the same regardless of the overlap.
+    pub fn overlap(mut self, overlap: usize) -> Self {
+        self.overlap = overlap
+            .clamp(1, MAX_SPECTRUM_WINDOW_OVERLAP)
+            .next_power_of_two();
+        self
+    }
+
+    /// Sets the time (in ms) it takes for a bin to decrease by 12 dB.
+    pub fn decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Builds an input and output pair for the given number of channels.
+    ///
+    /// See [`SpectrumInput::new`].
+    pub fn build(mut self, num_channels: usize) -> (SpectrumInput, SpectrumOutput) {
+        self.channel_mode = self.channel_mode.for_channels(num_channels);
+        let traces = self.channel_mode.traces(num_channels);
+
+        let (triple_buffer_inputs, triple_buffer_outputs) = (0..traces)
+            .map(|_| {
+                TripleBuffer::new(&vec![0.0; self.window_size / 2 + 1].into_boxed_slice()).split()
+            })
+            .unzip();
+
+        SpectrumInput::with_sink(
+            self,
+            num_channels,
+            InputSink::Realtime {
+                processors: (0..traces)
+                    .map(|_| SpectrumProcessor::new(self.window_size))
+                    .collect(),
+                triple_buffer_inputs,
+            },
+            OutputSource::Realtime(triple_buffer_outputs),
+        )
+    }
+
+    /// Builds an input and output pair for the given number of channels, which
+    /// runs the FFT in the editor instead of on the audio thread.
+    ///
+    /// See [`SpectrumInput::new_deferred`].
+    pub fn build_deferred(mut self, num_channels: usize) -> (SpectrumInput, SpectrumOutput) {
+        self.channel_mode = self.channel_mode.for_channels(num_channels);
+        let traces = self.channel_mode.traces(num_channels);
+
+        let (frames_sender, frames_receiver) = bounded(DEFERRED_FRAMES);
+        let (recycled_sender, recycled_receiver) = bounded(DEFERRED_FRAMES);
+
+        // The frames are allocated up front and passed back and forth from then on
+        for _ in 0..DEFERRED_FRAMES {
+            let _ = recycled_sender.try_send(vec![0.0; self.window_size].into_boxed_slice());
+        }
+
+        SpectrumInput::with_sink(
+            self,
+            num_channels,
+            InputSink::Deferred {
+                frames: frames_sender,
+                recycled: recycled_receiver,
+            },
+            OutputSource::Deferred {
+                processors: (0..traces)
+                    .map(|_| SpectrumProcessor::new(self.window_size))
+                    .collect(),
+                frames: frames_receiver,
+                recycled: recycled_sender,
+            },
+        )
+    }
+
+    /// Builds an output that computes its spectra from the samples of a bus.
+    ///
+    /// See [`SpectrumInput::from_bus`].
+    pub fn build_from_bus<T: ChannelSample, B: Bus<T>>(mut self, bus: Arc<B>) -> SpectrumOutput {
+        self.channel_mode = self.channel_mode.for_channels(T::CHANNELS);
+        let Self {
+            channel_mode,
+            window_size,
+            window,
+            overlap,
+            decay,
+        } = self;
+
+        let (triple_buffer_inputs, triple_buffer_outputs) = (0..channel_mode.traces(T::CHANNELS))
+            .map(|_| TripleBuffer::new(&vec![0.0; window_size / 2 + 1].into_boxed_slice()).split())
+            .unzip();
+
+        let smoothing = Arc::new(SmoothingDecay::new(
+            decay,
+            window_size,
+            overlap,
+            channel_mode.channels_per_trace(T::CHANNELS),
+        ));
+
+        let analysis = Arc::new(Mutex::new(BusAnalysis {
+            channel_mode,
+            hop: window_size / overlap,
+            buffers: (0..T::CHANNELS)
+                .map(|_| RingBuffer::new(window_size))
+                .collect(),
+            countdown: window_size / overlap,
+            window: vec![0.0; window_size],
+            compensated_window_function: window.compensated_coefficients(window_size),
+            mid_side_buffer: vec![0.0; window_size],
+            sink: InputSink::Realtime {
+                processors: (0..channel_mode.traces(T::CHANNELS))
+                    .map(|_| SpectrumProcessor::new(window_size))
+                    .collect(),
+                triple_buffer_inputs,
+            },
+            smoothing: smoothing.clone(),
+        }));
+
+        let bus_sample_rate = bus.sample_rate();
+        let sample_rate = Arc::new(AtomicF32::new(if bus_sample_rate.is_finite() {
+            bus_sample_rate
+        } else {
+            44100.0
+        }));
+
+        let update_sample_rate = {
+            let smoothing = smoothing.clone();
+            let sample_rate_c = sample_rate.clone();
+            move |rate: f32| {
+                sample_rate_c.store(rate, Ordering::Relaxed);
+                smoothing.update(rate);
+            }
+        };
+        update_sample_rate(sample_rate.load(Ordering::Relaxed));
+        let sample_rate_handle = bus.register_sample_rate_listener(update_sample_rate);
+
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut analysis = lock_or_recover(&analysis);
+            for sample in samples {
+                analysis.process(sample);
+            }
+        });
+
+        SpectrumOutput {
+            source: OutputSource::Realtime(triple_buffer_outputs),
+            sample_rate,
+            noise_bandwidth: window.noise_bandwidth(window_size),
+            smoothing,
+            bus_handles: vec![Box::new(dispatcher_handle), Box::new(sample_rate_handle)],
+        }
+    }
 }
 
 impl SpectrumInput {
+    /// Creates a [`SpectrumInputBuilder`], for choosing the window, overlap and
+    /// channel mode of a spectrum.
+    pub fn builder() -> SpectrumInputBuilder {
+        SpectrumInputBuilder::new()
+    }
+
     /// Create a new spectrum input and output pair.
     ///
     /// The output can be used by the editor to display a
     /// [`SpectrumAnalyzer`](crate::visualizers::SpectrumAnalyzer) in your
     /// editor. The `decay` dictates how long (in ms) it should take for a bin
     /// to decrease by -12dB.
+    ///
+    /// All channels are folded into a single spectrum, using the defaults of the
+    /// [`SpectrumInputBuilder`]. To change the window, the overlap or the channel
+    /// mode, use [`builder()`](Self::builder) instead.
     pub fn new(num_channels: usize, decay: f32) -> (SpectrumInput, SpectrumOutput) {
-        let (triple_buffer_input, triple_buffer_output) =
-            TripleBuffer::new(&[0.0; SPECTRUM_WINDOW_SIZE / 2 + 1]).split();
+        Self::builder().decay(decay).build(num_channels)
+    }
+
+    /// Create a new spectrum input and output pair, which runs the FFT in the
+    /// editor instead of on the audio thread.
+    ///
+    /// [`compute()`](Self::compute) then only copies the windowed samples, which
+    /// makes it a lot cheaper - useful if your plug-in runs many analyzers. The FFT
+    /// and smoothing happen whenever the [`SpectrumOutput`] is read, on the thread
+    /// reading it. For a [`SpectrumAnalyzer`](crate::visualizers::SpectrumAnalyzer),
+    /// that is the editor's draw call on the GUI thread, so the FFT cost is added
+    /// to each frame that is drawn. While the editor is closed, the spectrum costs
+    /// next to nothing. Otherwise, this behaves just like [`new()`](Self::new). Use
+    /// [`SpectrumInputBuilder::build_deferred`] to configure it.
+    pub fn new_deferred(num_channels: usize, decay: f32) -> (SpectrumInput, SpectrumOutput) {
+        Self::builder().decay(decay).build_deferred(num_channels)
+    }
+
+    /// Create a spectrum output that computes its spectra from the samples of a
+    /// bus, instead of requiring a [`SpectrumInput`] to be fed from your plug-in's
+    /// [`process()`](nih_plug::plugin::Plugin::process) function.
+    ///
+    /// The STFT and FFT then run wherever the bus gets updated - usually the thread
+    /// started by [`Bus::subscribe`] - which keeps their cost off the audio thread
+    /// and lets the spectrum share a bus with other visualizers. Mono and
+    /// multichannel buses are supported, and the `decay` behaves just like it does
+    /// for [`new()`](Self::new). Use [`SpectrumInputBuilder::build_from_bus`] to
+    /// configure it. The output follows the bus' sample rate.
+    ///
+    /// Since the bus needs to be updated for the spectrum to move, create the
+    /// output inside your editor, after subscribing to the bus.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // Inside your editor
+    /// bus.subscribe(cx);
+    ///
+    /// let spectrum = SpectrumInput::from_bus(bus.clone(), 100.);
+    /// ```
+    pub fn from_bus<T: ChannelSample, B: Bus<T>>(bus: Arc<B>, decay: f32) -> SpectrumOutput {
+        Self::builder().decay(decay).build_from_bus(bus)
+    }
+
+    fn with_sink(
+        builder: SpectrumInputBuilder,
+        num_channels: usize,
+        sink: InputSink,
+        source: OutputSource,
+    ) -> (SpectrumInput, SpectrumOutput) {
+        let SpectrumInputBuilder {
+            channel_mode,
+            window_size,
+            window,
+            overlap,
+            decay,
+        } = builder;
+
+        let sample_rate = Arc::new(AtomicF32::new(44100.0));
+        // The weight is set in `initialize()` based on the sample rate
+        let smoothing = Arc::new(SmoothingDecay::new(
+            decay,
+            window_size,
+            overlap,
+            channel_mode.channels_per_trace(num_channels),
+        ));
 
         let input = Self {
-            stft: util::StftHelper::new(num_channels, SPECTRUM_WINDOW_SIZE, 0),
+            stft: util::StftHelper::new(num_channels, window_size, 0),
             num_channels,
-            sample_rate: 44100.0,
+            channel_mode,
+            window_size,
+            overlap,
+            sample_rate: sample_rate.clone(),
 
-            decay,
-            // This is set in `initialize()` based on the sample rate
-            smoothing_decay_weight: 0.0,
+            smoothing: smoothing.clone(),
 
-            triple_buffer_input,
-            spectrum_result_buffer: [0.0; SPECTRUM_WINDOW_SIZE / 2 + 1],
+            sink,
 
-            plan: RealFftPlanner::new().plan_fft_forward(SPECTRUM_WINDOW_SIZE),
-            compensated_window_function: util::window::hann(SPECTRUM_WINDOW_SIZE)
-                .into_iter()
-                // Include the gain compensation in the window function to save some multiplications
-                .map(|x| x / SPECTRUM_WINDOW_SIZE as f32)
-                .collect(),
-            complex_fft_buffer: vec![Complex32::default(); SPECTRUM_WINDOW_SIZE / 2 + 1],
+            // Include the gain compensation in the window function to save some multiplications
+            compensated_window_function: window.compensated_coefficients(window_size),
+            mid_side_buffer: vec![0.0; window_size],
         };
 
         (
             input,
             SpectrumOutput {
-                output: triple_buffer_output,
-                sample_rate: 44100.0,
+                source,
+                sample_rate,
+                noise_bandwidth: window.noise_bandwidth(window_size),
+                smoothing,
+                bus_handles: vec![],
             },
         )
     }
 
+    /// The number of samples in each FFT window.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// The number of times consecutive windows overlap.
+    pub fn overlap(&self) -> usize {
+        self.overlap
+    }
+
     /// Update the smoothing using the specified sample rate. Called in `initialize()`.
+    ///
+    /// The connected [`SpectrumOutput`] picks up the new sample rate as well, so that
+    /// views map its bins to the right frequencies.
     pub fn update_sample_rate(&mut self, sample_rate: f32) {
         // We'll express the decay rate in the time it takes for the moving average to drop by 12 dB
-        // NOTE: The effective sample rate accounts for the STFT interval, **and** for the number of
-        //       channels. We'll average both channels to mono-ish.
-        let effective_sample_rate = sample_rate / SPECTRUM_WINDOW_SIZE as f32
-            * SPECTRUM_WINDOW_OVERLAP as f32
-            * self.num_channels as f32;
-        let decay_samples = (self.decay / 1000.0 * effective_sample_rate) as f64;
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+        self.smoothing.update(sample_rate);
+    }
 
-        self.sample_rate = sample_rate;
-        self.smoothing_decay_weight = 0.25f64.powf(decay_samples.recip()) as f32
+    /// Changes the time (in ms) it takes for a bin to decrease by 12 dB.
+    ///
+    /// The connected [`SpectrumOutput`] follows along - see
+    /// [`SpectrumOutput::set_decay`].
+    pub fn set_decay(&mut self, decay: f32) {
+        self.smoothing
+            .set_decay(decay, self.sample_rate.load(Ordering::Relaxed));
     }
 
     /// Compute the spectrum for a buffer and send it to the corresponding output pair.
+    ///
+    /// For a [deferred](Self::new_deferred) input, this only sends the windowed
+    /// samples.
     pub fn compute(&mut self, buffer: &Buffer) {
+        let smoothing_decay_weight = self.smoothing.weight();
+
         self.stft.process_analyze_only(
             buffer,
-            SPECTRUM_WINDOW_OVERLAP,
-            |_channel_idx, real_fft_scratch_buffer| {
+            self.overlap,
+            |channel_idx, real_fft_scratch_buffer| {
                 multiply_with_window(real_fft_scratch_buffer, &self.compensated_window_function);
 
-                self.plan
-                    .process_with_scratch(
-                        real_fft_scratch_buffer,
-                        &mut self.complex_fft_buffer,
-                        // We don't actually need a scratch buffer
-                        &mut [],
-                    )
-                    .unwrap();
-
-                // We'll use peak meter-like behavior for the spectrum analyzer to make things
-                // easier to dial in. Values that are higher than the old value snap to the new
-                // value immediately, lower values decay gradually. This also results in quasi-mono
-                // summing since this same callback will be called for both channels. Gain
-                // compensation has already been baked into the window function.
-                for (bin, spectrum_result) in self
-                    .complex_fft_buffer
-                    .iter()
-                    .zip(&mut self.spectrum_result_buffer)
-                {
-                    let magnitude = bin.norm();
-                    if magnitude > *spectrum_result {
-                        *spectrum_result = magnitude;
-                    } else {
-                        *spectrum_result = (*spectrum_result * self.smoothing_decay_weight)
-                            + (magnitude * (1.0 - self.smoothing_decay_weight));
-                    }
-                }
-
-                self.triple_buffer_input.write(self.spectrum_result_buffer);
+                self.channel_mode.send_window(
+                    &mut self.sink,
+                    &mut self.mid_side_buffer,
+                    channel_idx,
+                    real_fft_scratch_buffer,
+                    smoothing_decay_weight,
+                );
             },
         );
     }
 }
+
+/// Runs the STFT on the samples of a bus, for [`SpectrumInput::from_bus`].
+struct BusAnalysis {
+    channel_mode: ChannelMode,
+    /// The number of samples in between two windows
+    hop: usize,
+    /// The most recent samples of each channel, one window long
+    buffers: Vec<RingBuffer<f32>>,
+    /// Samples left until the next window
+    countdown: usize,
+    window: Vec<f32>,
+    compensated_window_function: Vec<f32>,
+    mid_side_buffer: Vec<f32>,
+    sink: InputSink,
+    smoothing: Arc<SmoothingDecay>,
+}
+
+impl BusAnalysis {
+    fn process<T: ChannelSample>(&mut self, sample: &T) {
+        for (channel_idx, buffer) in self.buffers.iter_mut().enumerate() {
+            buffer.enqueue(sample.channel(channel_idx));
+        }
+
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return;
+        }
+        self.countdown = self.hop;
+
+        for (channel_idx, buffer) in self.buffers.iter().enumerate() {
+            for (i, x) in self.window.iter_mut().enumerate() {
+                *x = buffer[i];
+            }
+            multiply_with_window(&mut self.window, &self.compensated_window_function);
+
+            self.channel_mode.send_window(
+                &mut self.sink,
+                &mut self.mid_side_buffer,
+                channel_idx,
+                &mut self.window,
+                self.smoothing.weight(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_are_gain_compensated() {
+        let windows = [
+            (WindowFunction::Hann, 1.5),
+            (WindowFunction::BlackmanHarris, 2.0),
+            (WindowFunction::Kaiser(8.), 1.67),
+            (WindowFunction::FlatTop, 3.77),
+        ];
+
+        for (window, noise_bandwidth) in windows {
+            // A sine at the center of a bin sums up its coefficients
+            let sum = window.compensated_coefficients(2048).iter().sum::<f32>();
+            assert!((sum - 0.5).abs() < 1e-4, "{window:?}");
+
+            assert!(
+                (window.noise_bandwidth(2048) - noise_bandwidth).abs() < 0.05,
+                "{window:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn resampled_spectra_keep_their_peaks() {
+        let mut spectrum = vec![0.; 1025];
+        spectrum[800] = 1.;
+        spectrum[2] = 0.5;
+
+        let resampler =
+            SpectrumResampler::new(200, ValueScaling::Frequency, (20., 20_000.), 1025, 24_000.);
+        let points = resampler.resample(&spectrum);
+        assert_eq!(points.len(), 200);
+
+        // The peak in the highs lands on exactly one point, at full magnitude
+        assert_eq!(points.iter().filter(|m| **m == 1.).count(), 1);
+
+        // The one in the lows is spread out over several interpolated points
+        let low_end = resampler
+            .frequencies()
+            .iter()
+            .position(|f| *f > 100.)
+            .unwrap();
+        assert!(points[..low_end].iter().any(|m| *m > 0. && *m < 0.5));
+        assert!(points[..low_end].iter().all(|m| *m <= 0.5));
+
+        // Points above the Nyquist frequency are left out
+        let resampler =
+            SpectrumResampler::new(200, ValueScaling::Frequency, (20., 20_000.), 1025, 8_000.);
+        assert!(resampler.frequencies().iter().all(|f| *f <= 8_000.));
+        assert_eq!(
+            resampler.resample(&spectrum).len(),
+            resampler.frequencies().len()
+        );
+
+        // An empty spectrum, e.g. a trace that was just reset, resamples to silence
+        let resampler =
+            SpectrumResampler::new(200, ValueScaling::Frequency, (20., 20_000.), 0, 24_000.);
+        assert!(resampler.resample(&[]).iter().all(|m| *m == 0.));
+    }
+
+    #[test]
+    fn bus_driven_spectra_follow_the_bus() {
+        use crate::bus::MonoBus;
+        use crate::testing::{sine, Playback};
+
+        let bus = Arc::new(MonoBus::new(4096));
+        let mut spectrum = SpectrumInput::from_bus(bus.clone(), 100.);
+
+        let mut playback = Playback::new(bus, 48_000.);
+        assert_eq!(spectrum.sample_rate(), 48_000.);
+
+        // A sine right at the center of a bin
+        let frequency = 100. * 48_000. / SPECTRUM_WINDOW_SIZE as f32;
+        playback.play(sine(frequency, 1., 48_000.).take(SPECTRUM_WINDOW_SIZE * 4));
+
+        let spectrum = spectrum.read();
+        let peak = (0..spectrum.len())
+            .max_by(|a, b| spectrum[*a].total_cmp(&spectrum[*b]))
+            .unwrap();
+        assert_eq!(peak, 100);
+        assert!((spectrum[100] - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn mid_side_falls_back_to_mix_for_mono_inputs() {
+        let (_, output) = SpectrumInput::builder()
+            .channel_mode(ChannelMode::MidSide)
+            .build(1);
+        assert_eq!(output.num_traces(), 1);
+
+        let (_, output) = SpectrumInput::builder()
+            .channel_mode(ChannelMode::MidSide)
+            .build_deferred(2);
+        assert_eq!(output.num_traces(), 2);
+    }
+}
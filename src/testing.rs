@@ -0,0 +1,213 @@
+//! Utilities for testing accumulators, dispatchers and views deterministically.
+//!
+//! In a plug-in, buses are fed by the audio thread and updated by the GUI at its
+//! own pace, so what a dispatcher receives depends on timing. A [`Playback`]
+//! instead feeds a fixed sequence of samples through a real bus in blocks, and
+//! updates the bus after each block. Time is kept by a [`VirtualClock`], which
+//! only advances with the samples that have been played. This way, tests always
+//! see the same samples, in the same blocks, at the same point in time.
+//!
+//! The samples can be recorded ones, or synthesized using the generators in this
+//! module, such as [`sine`] and [`silence`].
+//!
+//! # Example
+//!
+//! ```
+//! let bus = Arc::new(MonoBus::new(4096));
+//!
+//! let received = Arc::new(Mutex::new(vec![]));
+//! let received_c = received.clone();
+//! let _handle = bus.register_dispatcher(move |samples| {
+//!     received_c.lock().unwrap().extend(samples);
+//! });
+//!
+//! let mut playback = Playback::new(bus.clone(), 48_000.0);
+//! playback.play(testing::sine(1_000.0, 0.5, 48_000.0).take(4_800));
+//!
+//! assert_eq!(playback.clock().samples(), 4_800);
+//! assert_eq!(received.lock().unwrap().len(), 4_800);
+//! ```
+
+use std::f64::consts::TAU;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bus::{Bus, MonoBus, MultiChannelBus, ValueBus};
+
+/// The default number of samples a [`Playback`] sends between two updates.
+const DEFAULT_BLOCK_SIZE: usize = 512;
+
+/// A bus that samples can be sent to directly.
+pub trait SendSample<T: Clone + Copy + Sized + 'static>: Bus<T> {
+    /// Sends a single sample, like your plug-in would.
+    fn send_sample(&self, sample: T);
+}
+
+impl SendSample<f32> for MonoBus {
+    fn send_sample(&self, sample: f32) {
+        self.send(sample);
+    }
+}
+
+impl SendSample<f32> for ValueBus {
+    fn send_sample(&self, sample: f32) {
+        self.send(sample);
+    }
+}
+
+impl<const C: usize> SendSample<[f32; C]> for MultiChannelBus<C> {
+    fn send_sample(&self, sample: [f32; C]) {
+        self.send(sample);
+    }
+}
+
+/// A clock that advances with the samples played through a [`Playback`], instead
+/// of the wall clock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualClock {
+    samples: u64,
+    sample_rate: f32,
+}
+
+impl VirtualClock {
+    /// Creates a new [`VirtualClock`] at the start of time.
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            samples: 0,
+            sample_rate,
+        }
+    }
+
+    /// The number of samples that have been played.
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// The time that has passed, given the clock's sample rate.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64(self.samples as f64 / self.sample_rate as f64)
+    }
+
+    /// Advances the clock by the given number of samples.
+    pub fn advance(&mut self, samples: u64) {
+        self.samples += samples;
+    }
+}
+
+/// Feeds a sequence of samples through a bus, in blocks.
+///
+/// After each block, the bus gets updated, so that its dispatchers receive the
+/// block at once - just like they would if the plug-in processed a buffer of that
+/// size in between two updates. The block size defaults to 512 samples, and needs
+/// to fit within the bus' capacity, since samples sent to a full bus are dropped.
+pub struct Playback<B: SendSample<T>, T: Clone + Copy + Sized + 'static> {
+    bus: Arc<B>,
+    clock: VirtualClock,
+    block_size: usize,
+    block: Vec<T>,
+}
+
+impl<B: SendSample<T>, T: Clone + Copy + Sized + 'static> Playback<B, T> {
+    /// Creates a new [`Playback`] through the given bus, and sets the bus' sample
+    /// rate.
+    pub fn new(bus: Arc<B>, sample_rate: f32) -> Self {
+        bus.set_sample_rate(sample_rate);
+
+        Self {
+            bus,
+            clock: VirtualClock::new(sample_rate),
+            block_size: DEFAULT_BLOCK_SIZE,
+            block: Vec::with_capacity(DEFAULT_BLOCK_SIZE),
+        }
+    }
+
+    /// Sets the number of samples sent between two updates of the bus.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Plays the given samples through the bus.
+    ///
+    /// The last block may be shorter than the block size. Once this returns, the
+    /// bus' dispatchers have received all of the samples.
+    pub fn play(&mut self, samples: impl IntoIterator<Item = T>) {
+        for sample in samples {
+            self.block.push(sample);
+
+            if self.block.len() == self.block_size {
+                self.flush();
+            }
+        }
+
+        self.flush();
+    }
+
+    /// Sends the pending block and updates the bus.
+    fn flush(&mut self) {
+        if self.block.is_empty() {
+            return;
+        }
+
+        let len = self.block.len();
+
+        for sample in self.block.drain(..) {
+            self.bus.send_sample(sample);
+        }
+        self.bus.update();
+        self.clock.advance(len as u64);
+    }
+
+    /// The clock, which has advanced by every sample played so far.
+    pub fn clock(&self) -> VirtualClock {
+        self.clock
+    }
+
+    /// The bus the samples are played through.
+    pub fn bus(&self) -> &Arc<B> {
+        &self.bus
+    }
+}
+
+/// An endless sine wave of the given frequency (in Hz) and amplitude.
+pub fn sine(frequency: f32, amplitude: f32, sample_rate: f32) -> impl Iterator<Item = f32> {
+    let step = frequency as f64 / sample_rate as f64;
+
+    (0u64..).map(move |i| amplitude * (TAU * (i as f64 * step).fract()).sin() as f32)
+}
+
+/// The given number of samples of silence.
+pub fn silence(samples: usize) -> impl Iterator<Item = f32> {
+    std::iter::repeat(0.0).take(samples)
+}
+
+/// A single sample of the given amplitude, followed by silence, the given number
+/// of samples long.
+pub fn impulse(amplitude: f32, samples: usize) -> impl Iterator<Item = f32> {
+    std::iter::once(amplitude).chain(silence(samples.saturating_sub(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn playback_is_dispatched_in_blocks() {
+        let bus = Arc::new(MonoBus::new(64));
+
+        let blocks: Arc<Mutex<Vec<usize>>> = Default::default();
+        let blocks_c = blocks.clone();
+        let _handle = bus.register_dispatcher(move |samples| {
+            blocks_c.lock().unwrap().push(samples.len());
+        });
+
+        let mut playback = Playback::new(bus.clone(), 1000.0).block_size(32);
+        playback.play(impulse(1.0, 80));
+
+        assert_eq!(*blocks.lock().unwrap(), vec![32, 32, 16]);
+        assert_eq!(playback.clock().samples(), 80);
+        assert!((playback.clock().elapsed().as_secs_f64() - 0.08).abs() < 1e-9);
+        assert_eq!(bus.sample_rate(), 1000.0);
+    }
+}
@@ -0,0 +1,107 @@
+use nih_plug_vizia::vizia::prelude::Color;
+
+/// Maps normalized values to colors.
+///
+/// A `ColorMap` consists of a number of color stops, each placed at some
+/// position between 0 and 1. Values that lie between two stops are linearly
+/// interpolated.
+///
+/// # Example
+///
+/// ```
+/// let color_map = ColorMap::new(vec![
+///     (0.0, Color::rgb(0, 0, 0)),
+///     (0.5, Color::rgb(255, 0, 0)),
+///     (1.0, Color::rgb(255, 255, 255)),
+/// ]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorMap {
+    stops: Vec<(f32, Color)>,
+}
+
+impl ColorMap {
+    /// Creates a new [`ColorMap`] from a list of color stops.
+    ///
+    /// The stops don't need to be sorted by their position.
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// A color map going from black to white.
+    pub fn grayscale() -> Self {
+        Self::new(vec![
+            (0.0, Color::rgb(0, 0, 0)),
+            (1.0, Color::rgb(255, 255, 255)),
+        ])
+    }
+
+    /// A color map going from black through purple, red and yellow to white.
+    pub fn heat() -> Self {
+        Self::new(vec![
+            (0.0, Color::rgb(0, 0, 0)),
+            (0.25, Color::rgb(80, 16, 120)),
+            (0.5, Color::rgb(220, 40, 60)),
+            (0.75, Color::rgb(250, 180, 30)),
+            (1.0, Color::rgb(255, 255, 255)),
+        ])
+    }
+
+    /// A color map going from red through yellow to green.
+    ///
+    /// Suited for correlation, where 0 maps to -1 (out of phase) and 1 to +1
+    /// (mono).
+    pub fn correlation() -> Self {
+        Self::new(vec![
+            (0.0, Color::rgb(230, 50, 50)),
+            (0.5, Color::rgb(240, 200, 40)),
+            (1.0, Color::rgb(60, 200, 90)),
+        ])
+    }
+
+    /// The color stops of this color map, sorted by their position.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Returns the color at the given normalized value.
+    ///
+    /// Values outside of the range of stops take on the color of the nearest stop.
+    pub fn color_at(&self, value: f32) -> Color {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Color::transparent();
+        };
+
+        if value <= first.0 {
+            return first.1;
+        }
+        if value >= last.0 {
+            return last.1;
+        }
+
+        let upper = self.stops.partition_point(|stop| stop.0 <= value);
+        let (from_pos, from) = self.stops[upper - 1];
+        let (to_pos, to) = self.stops[upper];
+
+        let t = if to_pos > from_pos {
+            (value - from_pos) / (to_pos - from_pos)
+        } else {
+            0.0
+        };
+        let lerp = |a: u8, b: u8| -> u8 { (a as f32 + (b as f32 - a as f32) * t).round() as u8 };
+
+        Color::rgba(
+            lerp(from.r(), to.r()),
+            lerp(from.g(), to.g()),
+            lerp(from.b(), to.b()),
+            lerp(from.a(), to.a()),
+        )
+    }
+}
+
+impl Default for ColorMap {
+    fn default() -> Self {
+        Self::grayscale()
+    }
+}
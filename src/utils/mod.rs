@@ -1,13 +1,37 @@
 //! Generic utility functions and structures.
 
+mod color_map;
+mod note;
+mod offscreen;
 mod ring_buffer;
+mod sample_pairs;
+pub use color_map::*;
+pub(crate) use note::*;
+pub(crate) use offscreen::*;
 pub(crate) use ring_buffer::*;
+pub(crate) use sample_pairs::*;
 
+use nih_plug::nih_log;
 use nih_plug::util::db_to_gain;
 use nih_plug_vizia::vizia::binding::Res;
 use nih_plug_vizia::vizia::context::{Context, EventContext};
 use nih_plug_vizia::vizia::entity::Entity;
 use nih_plug_vizia::vizia::prelude::Data;
+use std::sync::{Mutex, MutexGuard};
+
+/// Locks a mutex, recovering its data if the lock has been poisoned.
+///
+/// A poisoned lock means that some thread panicked while holding it. Instead of
+/// propagating that panic into the editor, the error is logged, the poison is
+/// cleared and the data is used as-is.
+pub(crate) fn lock_or_recover<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        nih_log!("Recovering from poisoned lock: {}", poisoned);
+        let guard = poisoned.into_inner();
+        mutex.clear_poison();
+        guard
+    })
+}
 
 /// Analogous to VIZIA's own ValueScaling.
 #[derive(Debug, Clone, Copy, PartialEq, Data)]
@@ -15,10 +39,40 @@ pub enum ValueScaling {
     Linear,
     Power(f32),
     Frequency,
+    /// Logarithmic scaling for linear gain values, for a range given in dB.
+    ///
+    /// A gain of 0.5 lies at about -6 dB, so with a range of `(-12.0, 0.0)`, it is
+    /// normalized to 0.5 - and 0.5 is mapped back to the same gain.
     Decibels,
+    /// Perceptual frequency scaling along the mel scale, for a range given in Hz.
+    Mel,
+    /// Perceptual frequency scaling along the Bark scale of critical bands, for a
+    /// range given in Hz.
+    Bark,
+}
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10.0f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Traunmüller's approximation of the Bark scale.
+fn hz_to_bark(hz: f32) -> f32 {
+    26.81 * hz / (1960.0 + hz) - 0.53
+}
+
+fn bark_to_hz(bark: f32) -> f32 {
+    1960.0 * (bark + 0.53) / (26.28 - bark)
 }
 
 impl ValueScaling {
+    /// Maps a normalized position within the range back to a value.
+    ///
+    /// This is the inverse of [`value_to_normalized`](Self::value_to_normalized)
+    /// for positions from 0 to 1.
     pub fn normalized_to_value(&self, normalized: f32, min: f32, max: f32) -> f32 {
         let map = |x: f32| -> f32 { (x * (max - min)) + min };
 
@@ -33,7 +87,15 @@ impl ValueScaling {
                 2.0f32.powf((normalized * range) + minl)
             }
 
-            ValueScaling::Decibels => db_to_gain(normalized),
+            ValueScaling::Decibels => db_to_gain(map(normalized)),
+
+            ValueScaling::Mel => {
+                mel_to_hz(normalized * (hz_to_mel(max) - hz_to_mel(min)) + hz_to_mel(min))
+            }
+
+            ValueScaling::Bark => {
+                bark_to_hz(normalized * (hz_to_bark(max) - hz_to_bark(min)) + hz_to_bark(min))
+            }
         }
     }
 
@@ -55,10 +117,77 @@ impl ValueScaling {
                 const CONVERSION_FACTOR: f32 = std::f32::consts::LOG10_E * 20.0;
                 value.ln() * CONVERSION_FACTOR
             }),
+
+            ValueScaling::Mel => {
+                (hz_to_mel(value) - hz_to_mel(min)) / (hz_to_mel(max) - hz_to_mel(min))
+            }
+
+            ValueScaling::Bark => {
+                (hz_to_bark(value) - hz_to_bark(min)) / (hz_to_bark(max) - hz_to_bark(min))
+            }
         }
         .clamp(0., 1.)
     }
 
+    /// Normalizes all `values` at once, writing the results to `out`.
+    ///
+    /// Equivalent to calling [`value_to_normalized`](Self::value_to_normalized) for
+    /// each value, but the scaling is only matched once, and the constants it needs
+    /// are computed up front. This leaves a tight loop for each scaling, which the
+    /// compiler can vectorize - useful for views drawing a point per pixel or bin.
+    ///
+    /// Only as many values as fit into `out` are normalized.
+    pub fn value_to_normalized_slice(&self, values: &[f32], out: &mut [f32], min: f32, max: f32) {
+        let values = values.iter().zip(out.iter_mut());
+        let scale = (max - min).recip();
+
+        match self {
+            ValueScaling::Linear => {
+                for (value, out) in values {
+                    *out = ((value - min) * scale).clamp(0., 1.);
+                }
+            }
+
+            ValueScaling::Power(exponent) => {
+                let exponent = exponent.recip();
+                for (value, out) in values {
+                    *out = ((value - min) * scale).powf(exponent).clamp(0., 1.);
+                }
+            }
+
+            ValueScaling::Frequency => {
+                let minl = min.log2();
+                let scale = (max.log2() - minl).recip();
+                for (value, out) in values {
+                    *out = ((value.log2() - minl) * scale).clamp(0., 1.);
+                }
+            }
+
+            ValueScaling::Decibels => {
+                const CONVERSION_FACTOR: f32 = std::f32::consts::LOG10_E * 20.0;
+                for (value, out) in values {
+                    *out = ((value.ln() * CONVERSION_FACTOR - min) * scale).clamp(0., 1.);
+                }
+            }
+
+            ValueScaling::Mel => {
+                let minm = hz_to_mel(min);
+                let scale = (hz_to_mel(max) - minm).recip();
+                for (value, out) in values {
+                    *out = ((hz_to_mel(*value) - minm) * scale).clamp(0., 1.);
+                }
+            }
+
+            ValueScaling::Bark => {
+                let minb = hz_to_bark(min);
+                let scale = (hz_to_bark(max) - minb).recip();
+                for (value, out) in values {
+                    *out = ((hz_to_bark(*value) - minb) * scale).clamp(0., 1.);
+                }
+            }
+        }
+    }
+
     pub fn value_to_normalized_optional(&self, value: f32, min: f32, max: f32) -> Option<f32> {
         let unmap = |x: f32| -> f32 { (x - min) / (max - min) };
 
@@ -77,6 +206,14 @@ impl ValueScaling {
                 const CONVERSION_FACTOR: f32 = std::f32::consts::LOG10_E * 20.0;
                 value.ln() * CONVERSION_FACTOR
             }),
+
+            ValueScaling::Mel => {
+                (hz_to_mel(value) - hz_to_mel(min)) / (hz_to_mel(max) - hz_to_mel(min))
+            }
+
+            ValueScaling::Bark => {
+                (hz_to_bark(value) - hz_to_bark(min)) / (hz_to_bark(max) - hz_to_bark(min))
+            }
         };
         if (0.0..=1.0).contains(&value) {
             Some(value)
@@ -102,3 +239,66 @@ impl Res<ValueScaling> for ValueScaling {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ValueScaling;
+
+    #[test]
+    fn decibels_round_trip() {
+        let scaling = ValueScaling::Decibels;
+
+        assert!((scaling.value_to_normalized(0.5, -12.0, 0.0) - 0.5).abs() < 0.01);
+        assert!((scaling.normalized_to_value(0.5, -12.0, 0.0) - 0.5).abs() < 0.01);
+        assert!((scaling.normalized_to_value(0.0, -60.0, 6.0) - 0.001).abs() < 1e-6);
+
+        for gain in [0.001, 0.1, 0.5, 1.0, 1.5] {
+            let normalized = scaling.value_to_normalized(gain, -60.0, 6.0);
+            let round_trip = scaling.normalized_to_value(normalized, -60.0, 6.0);
+
+            assert!((round_trip - gain).abs() / gain < 1e-4);
+        }
+    }
+
+    #[test]
+    fn slices_match_single_values() {
+        let values = [0.0, 0.001, 0.25, 0.5, 1.0, 2.0, 100.0, 1_000.0, 30_000.0];
+
+        for (scaling, min, max) in [
+            (ValueScaling::Linear, -1.0, 1.0),
+            (ValueScaling::Power(0.5), 0.0, 1.0),
+            (ValueScaling::Frequency, 20.0, 20_000.0),
+            (ValueScaling::Decibels, -60.0, 6.0),
+            (ValueScaling::Mel, 20.0, 20_000.0),
+            (ValueScaling::Bark, 20.0, 20_000.0),
+        ] {
+            let mut out = [0.0; 9];
+            scaling.value_to_normalized_slice(&values, &mut out, min, max);
+
+            for (value, normalized) in values.iter().zip(out) {
+                let expected = scaling.value_to_normalized(*value, min, max);
+                assert!((normalized - expected).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn perceptual_scalings_round_trip() {
+        for scaling in [ValueScaling::Mel, ValueScaling::Bark] {
+            for hz in [20.0, 100.0, 1_000.0, 8_000.0, 20_000.0] {
+                let normalized = scaling.value_to_normalized(hz, 20.0, 20_000.0);
+                let round_trip = scaling.normalized_to_value(normalized, 20.0, 20_000.0);
+
+                assert!((round_trip - hz).abs() / hz < 1e-3);
+            }
+
+            // Perceptual scales give low frequencies less room than a log scale
+            let mid = scaling.value_to_normalized(632.0, 20.0, 20_000.0);
+            assert!(mid < 0.5);
+            assert_eq!(
+                scaling.value_to_normalized_optional(30_000.0, 20.0, 20_000.0),
+                None
+            );
+        }
+    }
+}
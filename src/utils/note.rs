@@ -0,0 +1,52 @@
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// A frequency, expressed as the nearest note of the equal-tempered scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Note {
+    /// The MIDI note number, where 69 is A4
+    pub number: i32,
+    /// How far (in cents) the frequency lies above the note
+    pub cents: f32,
+}
+
+impl Note {
+    pub fn from_frequency(frequency: f32, reference: f32) -> Self {
+        let semitones = 69.0 + 12.0 * (frequency / reference).log2();
+        let number = semitones.round();
+
+        Self {
+            number: number as i32,
+            cents: (semitones - number) * 100.0,
+        }
+    }
+
+    /// The note's name, including its octave - e.g. `A4`.
+    pub fn name(&self) -> String {
+        format!(
+            "{}{}",
+            NOTE_NAMES[self.number.rem_euclid(12) as usize],
+            self.number.div_euclid(12) - 1
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Note;
+
+    #[test]
+    fn notes_are_named_after_the_nearest_semitone() {
+        let a4 = Note::from_frequency(440.0, 440.0);
+        assert_eq!(a4.name(), "A4");
+        assert!(a4.cents.abs() < 1e-3);
+
+        let c4 = Note::from_frequency(263.0, 440.0);
+        assert_eq!(c4.name(), "C4");
+        assert!((c4.cents - 9.0).abs() < 1.0);
+
+        assert_eq!(Note::from_frequency(440.0, 442.0).name(), "A4");
+        assert!(Note::from_frequency(440.0, 442.0).cents < -7.0);
+    }
+}
@@ -0,0 +1,106 @@
+use nih_plug::nih_log;
+use nih_plug_vizia::vizia::vg;
+use nih_plug_vizia::vizia::view::Canvas;
+
+/// An offscreen image that dense views can render into incrementally.
+///
+/// Instead of re-stroking thousands of primitives each frame, a view only renders
+/// what has changed since the last frame into this image, and then blits the whole
+/// image onto the screen.
+pub(crate) struct OffscreenImage {
+    id: vg::ImageId,
+    width: usize,
+    height: usize,
+}
+
+impl OffscreenImage {
+    /// Makes sure that the image inside `slot` has the given size.
+    ///
+    /// If there is no image yet, or it has a different size, a new, cleared image
+    /// is created. Returns the image, along with whether it was just created.
+    pub fn ensure<'a>(
+        slot: &'a mut Option<Self>,
+        canvas: &mut Canvas,
+        width: usize,
+        height: usize,
+        flags: vg::ImageFlags,
+    ) -> Option<(&'a mut Self, bool)> {
+        let mut created = false;
+
+        if slot
+            .as_ref()
+            .map_or(true, |image| image.width != width || image.height != height)
+        {
+            if let Some(old) = slot.take() {
+                canvas.delete_image(old.id);
+            }
+
+            match canvas.create_image_empty(width, height, vg::PixelFormat::Rgba8, flags) {
+                Ok(id) => {
+                    *slot = Some(Self { id, width, height });
+                    created = true;
+                }
+                Err(e) => {
+                    nih_log!("Couldn't create offscreen image: {:?}", e);
+                    return None;
+                }
+            }
+        }
+
+        let image = slot.as_mut()?;
+
+        if created {
+            image.render(canvas, |canvas| {
+                canvas.clear_rect(
+                    0,
+                    0,
+                    width as u32,
+                    height as u32,
+                    vg::Color::rgba(0, 0, 0, 0),
+                );
+            });
+        }
+
+        Some((image, created))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Renders into the image.
+    ///
+    /// Inside the closure, coordinates are relative to the image's top left corner.
+    pub fn render(&self, canvas: &mut Canvas, f: impl FnOnce(&mut Canvas)) {
+        canvas.save();
+        canvas.reset_transform();
+        canvas.reset_scissor();
+        canvas.set_render_target(vg::RenderTarget::Image(self.id));
+
+        f(canvas);
+
+        canvas.set_render_target(vg::RenderTarget::Screen);
+        canvas.restore();
+    }
+
+    /// Draws the image onto the given rectangle on screen.
+    ///
+    /// The image is shifted to the left by `offset_x` pixels. If it was created
+    /// with [`REPEAT_X`](vg::ImageFlags::REPEAT_X), it wraps around.
+    pub fn blit(&self, canvas: &mut Canvas, x: f32, y: f32, w: f32, h: f32, offset_x: f32) {
+        let mut rect = vg::Path::new();
+        rect.rect(x, y, w, h);
+        canvas.fill_path(
+            &rect,
+            &vg::Paint::image(
+                self.id,
+                x - offset_x,
+                y,
+                self.width as f32,
+                self.height as f32,
+                0.,
+                1.,
+            ),
+        );
+    }
+}
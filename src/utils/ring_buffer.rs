@@ -13,6 +13,8 @@ pub struct RingBuffer<T> {
     head: usize,
     size: usize,
     data: Vec<T>,
+    /// The value empty space is filled with
+    fill: T,
 }
 
 impl<T: Default + Copy> Default for RingBuffer<T> {
@@ -24,10 +26,20 @@ impl<T: Default + Copy> Default for RingBuffer<T> {
 impl<T: Default + Copy> RingBuffer<T> {
     /// Constructs a new RingBuffer with the given size.
     pub fn new(size: usize) -> Self {
+        Self::filled(size, T::default())
+    }
+
+    /// Constructs a new RingBuffer with the given size, filled with the given
+    /// value.
+    ///
+    /// The same value is used to fill the extra space when the buffer grows, and
+    /// when it is cleared.
+    pub fn filled(size: usize, fill: T) -> Self {
         Self {
             head: 0,
             size,
-            data: vec![T::default(); size],
+            data: vec![fill; size],
+            fill,
         }
     }
 
@@ -55,8 +67,10 @@ impl<T: Default + Copy> RingBuffer<T> {
 
     /// Grows the RingBuffer.
     ///
-    /// The extra space is filled with the default values for your data type
-    /// (usually 0). This operation keeps the order of the values intact.
+    /// The extra space is filled with the buffer's fill value - the default value
+    /// for your data type (usually 0), unless it was created using
+    /// [`filled()`](`Self::filled()`). This operation keeps the order of the values
+    /// intact.
     pub fn grow(self: &mut Self, size: usize) {
         let mut data = vec![];
 
@@ -66,7 +80,7 @@ impl<T: Default + Copy> RingBuffer<T> {
         data.extend_from_slice(&self.data[0..self.head]);
 
         for _ in self.size..size {
-            data.push(T::default());
+            data.push(self.fill);
         }
 
         self.data = data;
@@ -107,9 +121,10 @@ impl<T: Default + Copy> RingBuffer<T> {
         self.data[(self.size + self.head) % self.size]
     }
 
-    /// Clears the entire buffer, filling it with default values (usually 0)
+    /// Clears the entire buffer, filling it with its fill value (usually 0)
     pub fn clear(self: &mut Self) {
-        self.data.iter_mut().for_each(|x| *x = T::default());
+        let fill = self.fill;
+        self.data.iter_mut().for_each(|x| *x = fill);
     }
 
     pub fn len(self: &Self) -> usize {
@@ -193,6 +208,20 @@ mod tests {
         assert_eq!(rb.data, vec![0; 4]);
     }
 
+    #[test]
+    fn filled() {
+        let mut rb = RingBuffer::<i32>::filled(2, 7);
+        assert_eq!(rb.data, vec![7; 2]);
+
+        rb.enqueue(1);
+        rb.enqueue(2);
+        rb.grow(4);
+        assert_eq!(rb.data, vec![1, 2, 7, 7]);
+
+        rb.clear();
+        assert_eq!(rb.data, vec![7; 4]);
+    }
+
     #[test]
     fn resize() {
         let mut rb = RingBuffer::<i32>::new(4);
@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+/// Pairs up the samples of two buses, so that samples sent at the same time end up
+/// together.
+///
+/// Each bus calls its own dispatchers when it is updated, so one side usually
+/// arrives before the other. The samples of each side are queued until the other
+/// side catches up. If one side runs ahead by more than `capacity` samples - e.g.
+/// because the other bus isn't being updated at all - its oldest samples are
+/// dropped, so that both sides line up again once they are.
+pub(crate) struct SamplePairs {
+    queues: [VecDeque<f32>; 2],
+    capacity: usize,
+}
+
+impl SamplePairs {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queues: Default::default(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Queues samples of one side - 0 for the first bus, 1 for the second - and
+    /// calls `f` with each pair this completes, in order.
+    pub fn push(
+        &mut self,
+        side: usize,
+        samples: impl IntoIterator<Item = f32>,
+        mut f: impl FnMut([f32; 2]),
+    ) {
+        let other = 1 - side;
+
+        for sample in samples {
+            match self.queues[other].pop_front() {
+                Some(other_sample) => {
+                    let mut pair = [0.; 2];
+                    pair[side] = sample;
+                    pair[other] = other_sample;
+                    f(pair);
+                }
+                None => {
+                    if self.queues[side].len() >= self.capacity {
+                        self.queues[side].pop_front();
+                    }
+                    self.queues[side].push_back(sample);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SamplePairs;
+
+    #[test]
+    fn samples_are_paired_in_order() {
+        let mut pairs = SamplePairs::new(4);
+        let mut received = vec![];
+
+        pairs.push(0, [1., 2., 3.], |pair| received.push(pair));
+        pairs.push(1, [10., 20.], |pair| received.push(pair));
+        pairs.push(1, [30., 40.], |pair| received.push(pair));
+        pairs.push(0, [4.], |pair| received.push(pair));
+
+        assert_eq!(received, vec![[1., 10.], [2., 20.], [3., 30.], [4., 40.]]);
+
+        // A side that runs too far ahead only keeps its most recent samples
+        received.clear();
+        pairs.push(0, [1., 2., 3., 4., 5., 6.], |pair| received.push(pair));
+        pairs.push(1, [10., 20.], |pair| received.push(pair));
+
+        assert_eq!(received, vec![[3., 10.], [4., 20.]]);
+    }
+}
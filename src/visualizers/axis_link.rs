@@ -0,0 +1,73 @@
+use nih_plug_vizia::vizia::prelude::*;
+
+use crate::utils::ValueScaling;
+
+/// A range and scaling that several views can share, so that they always line up.
+///
+/// Grids, rulers and visualizers that are stacked on top of each other need to
+/// display the exact same range in order to match. Instead of repeating it for each
+/// view, store an [`AxisLink`] in your editor's model and link the views to it. If
+/// the link changes, e.g. because the user zooms in, all linked views follow.
+///
+/// Views implementing [`RangeModifiers`](super::RangeModifiers) are linked via
+/// [`axis`](super::RangeModifiers::axis). The frequency axis of a
+/// [`SpectrumAnalyzer`](super::SpectrumAnalyzer) is linked via
+/// [`frequency_axis`](super::SpectrumAnalyzerModifiers::frequency_axis), and a
+/// [`UnitRuler`](super::UnitRuler) can be created from a link using
+/// [`UnitRuler::linked`](super::UnitRuler::linked). A link's range alone can be
+/// passed to any view that takes a range, as `Data::axis.then(AxisLink::range)`.
+///
+/// # Example
+///
+/// ```
+/// #[derive(Lens, Clone)]
+/// pub(crate) struct Data {
+///     pub(crate) spectrum: Arc<Mutex<SpectrumOutput>>,
+///     pub(crate) frequency_axis: AxisLink,
+/// }
+///
+/// // Inside your editor
+/// ZStack::new(cx, |cx| {
+///     Grid::new(
+///         cx,
+///         ValueScaling::Frequency,
+///         Data::frequency_axis.then(AxisLink::range),
+///         vec![100., 1_000., 10_000.],
+///         Orientation::Vertical,
+///     )
+///     .axis(Data::frequency_axis);
+///     SpectrumAnalyzer::new(
+///         cx,
+///         Data::spectrum,
+///         SpectrumAnalyzerVariant::LINE,
+///         ValueScaling::Frequency,
+///         (10., 21_000.),
+///         ValueScaling::Decibels,
+///         (-110., 6.),
+///     )
+///     .frequency_axis(Data::frequency_axis);
+///     UnitRuler::linked(
+///         cx,
+///         Data::frequency_axis,
+///         vec![(100., "100"), (1_000., "1k"), (10_000., "10k")],
+///         Orientation::Horizontal,
+///     );
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Data, Lens)]
+pub struct AxisLink {
+    pub scaling: ValueScaling,
+    pub range: (f32, f32),
+}
+
+impl AxisLink {
+    /// Creates a new [`AxisLink`].
+    pub fn new(scaling: ValueScaling, range: (f32, f32)) -> Self {
+        Self { scaling, range }
+    }
+
+    /// A logarithmic frequency axis spanning the audible range, from 10 Hz to 21 kHz.
+    pub fn frequency() -> Self {
+        Self::new(ValueScaling::Frequency, (10., 21_000.))
+    }
+}
@@ -0,0 +1,176 @@
+use nih_plug::util::gain_to_db;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+use crate::accumulators::BallisticsAccumulator;
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+
+type Sample = [f32; 2];
+
+/// The running level difference between the two channels of a stereo signal.
+///
+/// The energy of both channels is averaged with an exponential moving average, so
+/// that the balance can be updated sample by sample.
+struct Balance {
+    /// The time (in ms) it takes to cover 75% of a change in balance.
+    window: f32,
+    weight: f32,
+    /// Average of left²
+    left: f32,
+    /// Average of right²
+    right: f32,
+}
+
+impl Balance {
+    fn new(window: f32, sample_rate: f32) -> Self {
+        let mut balance = Self {
+            window,
+            weight: 0.0,
+            left: 0.0,
+            right: 0.0,
+        };
+        balance.set_sample_rate(sample_rate);
+        balance
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.weight = BallisticsAccumulator::weight(self.window, sample_rate);
+    }
+
+    fn process(&mut self, [left, right]: Sample) {
+        let w = self.weight;
+
+        self.left = self.left * w + left * left * (1.0 - w);
+        self.right = self.right * w + right * right * (1.0 - w);
+    }
+
+    /// How much louder (in dB) the right channel is than the left one.
+    ///
+    /// Negative values lean to the left. Silence is centered, so 0 is returned for
+    /// it, while a signal that is silent on one side leans fully to the other one.
+    fn value(&self) -> f32 {
+        match (self.left > f32::EPSILON, self.right > f32::EPSILON) {
+            (true, true) => gain_to_db((self.right / self.left).sqrt()),
+            (true, false) => f32::NEG_INFINITY,
+            (false, true) => f32::INFINITY,
+            (false, false) => 0.0,
+        }
+    }
+}
+
+/// Displays the balance between the left and right channel of a signal.
+///
+/// The RMS levels of both channels are compared, and their difference is shown as
+/// a horizontal bar growing from the center - to the left if the left channel is
+/// louder, to the right otherwise. The bar reaches the edge at a difference of
+/// `range` dB. It is filled using the `background_color`, with a line in the
+/// `color` marking its end.
+///
+/// # Example
+///
+/// Balance meter averaging over 300 ms, reaching its edges at 12 dB.
+///
+/// ```
+/// BalanceMeter::new(cx, bus.clone(), 300.0, 12.0)
+///     .width(Pixels(128.0))
+///     .height(Pixels(8.0))
+///     .color(Color::rgb(255, 255, 255))
+///     .background_color(Color::rgba(255, 255, 255, 60));
+/// ```
+pub struct BalanceMeter<B: Bus<Sample> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<Sample>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    balance: Arc<Mutex<Balance>>,
+    range: f32,
+}
+
+impl<B: Bus<Sample> + 'static> BalanceMeter<B> {
+    /// Creates a new [`BalanceMeter`].
+    ///
+    /// The `window` dictates how long (in ms) it takes for the meter to cover 75%
+    /// of a change in balance.
+    pub fn new(cx: &mut Context, bus: Arc<B>, window: f32, range: f32) -> Handle<Self> {
+        let balance = Arc::new(Mutex::new(Balance::new(window, bus.sample_rate())));
+
+        let balance_c = balance.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut balance = lock_or_recover(&balance_c);
+            for sample in samples {
+                balance.process(*sample);
+            }
+        });
+
+        let balance_c = balance.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&balance_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            balance,
+            range,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<Sample> + 'static> View for BalanceMeter<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("balance-meter")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let balance = lock_or_recover(&self.balance).value();
+        let normalized = (balance / self.range.max(f32::EPSILON)).clamp(-1.0, 1.0);
+
+        let center_x = x + w / 2.;
+        let end_x = center_x + normalized * w / 2.;
+
+        let mut bar = vg::Path::new();
+        bar.rect(center_x.min(end_x), y, (end_x - center_x).abs(), h);
+        canvas.fill_path(&bar, &vg::Paint::color(cx.background_color().into()));
+
+        let mut indicator = vg::Path::new();
+        indicator.move_to(end_x, y);
+        indicator.line_to(end_x, y + h);
+        canvas.stroke_path(
+            &indicator,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Balance;
+
+    fn balance(samples: impl Iterator<Item = [f32; 2]>) -> f32 {
+        let mut balance = Balance::new(500.0, 1000.0);
+        samples.for_each(|sample| balance.process(sample));
+        balance.value()
+    }
+
+    #[test]
+    fn balance_of_typical_signals() {
+        let sine = |i: usize| (i as f32 * 0.1).sin();
+
+        let centered = balance((0..5000).map(|i| [sine(i), sine(i)]));
+        let right = balance((0..5000).map(|i| [sine(i) * 0.5, sine(i)]));
+
+        assert!(centered.abs() < 1e-3);
+        assert!((right - 6.02).abs() < 0.01);
+        assert_eq!(
+            balance((0..5000).map(|i| [sine(i), 0.0])),
+            f32::NEG_INFINITY
+        );
+        assert_eq!(balance((0..5000).map(|_| [0.0, 0.0])), 0.0);
+    }
+}
@@ -0,0 +1,207 @@
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::readout::TextReadout;
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+/// How long (in ms) the indicator flashes on each beat by default.
+const DEFAULT_FLASH: f32 = 100.0;
+
+/// How often the bar:beat text is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(25);
+
+/// A position within the song, counted from 1 like a DAW does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BeatPosition {
+    bar: i64,
+    beat: u32,
+}
+
+impl BeatPosition {
+    /// Finds the bar and beat at the given position, in beats since the start of
+    /// the song.
+    fn new(position: f64, beats_per_bar: u32) -> Self {
+        let beats_per_bar = beats_per_bar.max(1) as i64;
+        let beats = position.floor() as i64;
+
+        Self {
+            bar: beats.div_euclid(beats_per_bar) + 1,
+            beat: beats.rem_euclid(beats_per_bar) as u32 + 1,
+        }
+    }
+
+    fn is_downbeat(&self) -> bool {
+        self.beat == 1
+    }
+}
+
+/// Displays the host's transport position, flashing on each beat.
+///
+/// The view is fed by a bus carrying the song position in beats - usually a
+/// [`ValueBus`](crate::bus::ValueBus) your plug-in sends `pos_beats()` of its
+/// transport to, once per buffer. Whenever a new beat starts, the indicator is
+/// filled using the `background_color` for a moment, and slightly longer on the
+/// first beat of each bar. The current position is shown as `bar:beat` text, in
+/// the `color`. The number of beats per bar can be fixed, or bound to a lens -
+/// e.g. to follow the time signature reported by the host.
+///
+/// # Example
+///
+/// ```
+/// let transport_bus: Arc<ValueBus> = Default::default();
+///
+/// // Inside your plug-in's process() function
+/// if let Some(position) = context.transport().pos_beats() {
+///     transport_bus.send(position as f32);
+/// }
+///
+/// // Inside your editor
+/// BeatIndicator::new(cx, transport_bus.clone(), Data::beats_per_bar)
+///     .width(Pixels(48.0))
+///     .height(Pixels(24.0))
+///     .color(Color::rgb(255, 255, 255))
+///     .background_color(Color::rgba(255, 255, 255, 60));
+/// ```
+pub struct BeatIndicator<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    /// The most recent position, in beats
+    position: Arc<Mutex<Option<f64>>>,
+    beats_per_bar: u32,
+    /// The flash duration in ms
+    flash: f32,
+    last_beat: Cell<Option<(BeatPosition, Instant)>>,
+}
+
+enum BeatIndicatorEvents {
+    RefreshText,
+    UpdateBeatsPerBar(u32),
+}
+
+impl<B: Bus<f32> + 'static> BeatIndicator<B> {
+    /// Creates a new [`BeatIndicator`] for a time signature with the given number
+    /// of beats per bar.
+    pub fn new(cx: &mut Context, bus: Arc<B>, beats_per_bar: impl Res<u32>) -> Handle<Self> {
+        let position = Arc::new(Mutex::new(None));
+
+        let position_c = position.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            if let Some(sample) = samples.last() {
+                *lock_or_recover(&position_c) = Some(*sample as f64);
+            }
+        });
+
+        let mut handle = Self {
+            dispatcher_handle,
+            position,
+            beats_per_bar: beats_per_bar.get_val(cx),
+            flash: DEFAULT_FLASH,
+            last_beat: Cell::new(None),
+        }
+        .build(cx, |cx| {
+            TextReadout::attach(cx, String::new(), REFRESH_INTERVAL, || {
+                BeatIndicatorEvents::RefreshText
+            })
+            .position_type(PositionType::SelfDirected)
+            .space(Stretch(1.0));
+        });
+
+        let e = handle.entity();
+        beats_per_bar.set_or_bind(handle.context(), e, move |cx, b| {
+            (*cx).emit_to(e, BeatIndicatorEvents::UpdateBeatsPerBar(b));
+        });
+
+        handle
+    }
+
+    /// The current bar and beat, if a position has been received.
+    fn beat_position(&self) -> Option<BeatPosition> {
+        lock_or_recover(&self.position).map(|p| BeatPosition::new(p, self.beats_per_bar))
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for BeatIndicator<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("beat-indicator")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let Some(beat) = self.beat_position() else {
+            return;
+        };
+
+        let last_beat = match self.last_beat.get() {
+            Some((last, t)) if last == beat => t,
+            _ => {
+                let now = Instant::now();
+                self.last_beat.set(Some((beat, now)));
+                now
+            }
+        };
+
+        // Downbeats flash twice as long
+        let flash = if beat.is_downbeat() {
+            self.flash * 2.
+        } else {
+            self.flash
+        };
+        let elapsed = last_beat.elapsed().as_secs_f32() * 1000.;
+        if elapsed >= flash {
+            return;
+        }
+
+        // The flash fades out over its duration
+        let mut color: vg::Color = cx.background_color().into();
+        color.a *= 1. - elapsed / flash;
+
+        let mut light = vg::Path::new();
+        light.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&light, &vg::Paint::color(color));
+    }
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            BeatIndicatorEvents::UpdateBeatsPerBar(beats_per_bar) => {
+                self.beats_per_bar = *beats_per_bar;
+                cx.needs_redraw();
+            }
+            BeatIndicatorEvents::RefreshText => {
+                let text = match self.beat_position() {
+                    Some(position) => format!("{}:{}", position.bar, position.beat),
+                    None => String::new(),
+                };
+                TextReadout::update(cx, text);
+            }
+        });
+    }
+}
+
+pub trait BeatIndicatorModifiers {
+    fn flash(self, flash: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> BeatIndicatorModifiers for Handle<'a, BeatIndicator<B>> {
+    /// Sets how long (in ms) the indicator flashes on each beat.
+    ///
+    /// The first beat of each bar flashes twice as long. Defaults to 100 ms.
+    fn flash(self, flash: f32) -> Self {
+        self.modify(|indicator| indicator.flash = flash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BeatPosition;
+
+    #[test]
+    fn positions_are_counted_from_one() {
+        assert_eq!(BeatPosition::new(0.0, 4), BeatPosition { bar: 1, beat: 1 });
+        assert_eq!(BeatPosition::new(5.99, 4), BeatPosition { bar: 2, beat: 2 });
+        assert_eq!(BeatPosition::new(6.0, 3), BeatPosition { bar: 3, beat: 1 });
+
+        // Pre-roll counts down towards the first bar
+        assert_eq!(BeatPosition::new(-0.5, 4), BeatPosition { bar: 0, beat: 4 });
+    }
+}
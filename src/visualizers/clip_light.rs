@@ -0,0 +1,249 @@
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::readout::TextReadout;
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+/// How long (in ms) a clip light stays lit by default.
+const DEFAULT_HOLD: f32 = 1500.0;
+
+/// How often the overs count is refreshed.
+const COUNT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Detects samples above a threshold, counting each run of them as one over.
+struct ClipDetector {
+    threshold: f32,
+    /// Whether the previous sample was above the threshold
+    in_over: bool,
+    /// Whether the signal clipped since the light was last drawn
+    clipped: bool,
+    overs: usize,
+}
+
+impl ClipDetector {
+    fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            in_over: false,
+            clipped: false,
+            overs: 0,
+        }
+    }
+
+    fn process(&mut self, sample: f32) {
+        let over = sample.abs() >= self.threshold;
+
+        if over {
+            self.clipped = true;
+            if !self.in_over {
+                self.overs += 1;
+            }
+        }
+
+        self.in_over = over;
+    }
+
+    fn reset(&mut self) {
+        self.in_over = false;
+        self.clipped = false;
+        self.overs = 0;
+    }
+}
+
+/// A light that turns on whenever a signal clips.
+///
+/// The signal counts as clipping whenever a sample's absolute value reaches the
+/// threshold. The light then stays lit for a moment, so that short clips don't go
+/// unnoticed - or until it is clicked, if it is
+/// [`latching`](ClipLightModifiers::latching). Clicking the light always turns it
+/// off and resets the overs count, as does sending a [`ClipLightEvent::Reset`] to
+/// it.
+///
+/// The lit light is filled using the `background_color`, and a dimmed version of
+/// it is shown while the light is off. Optionally, the number of overs - runs of
+/// consecutive clipping samples - can be shown using
+/// [`count_overs`](ClipLightModifiers::count_overs), as text in the `color`.
+///
+/// # Example
+///
+/// Latching clip light for 0 dBFS, which counts the overs.
+///
+/// ```
+/// ClipLight::new(cx, bus.clone(), 1.0)
+///     .latching()
+///     .count_overs()
+///     .size(Pixels(16.0))
+///     .color(Color::rgb(255, 255, 255))
+///     .background_color(Color::rgb(255, 64, 64));
+/// ```
+pub struct ClipLight<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    detector: Arc<Mutex<ClipDetector>>,
+    /// The hold time in ms, or `None` if the light stays lit until it is reset
+    hold: Option<f32>,
+    last_clip: Cell<Option<Instant>>,
+    count: bool,
+}
+
+/// Events that can be sent to a [`ClipLight`].
+pub enum ClipLightEvent {
+    /// Turns the light off and resets the overs count.
+    Reset,
+}
+
+enum ClipLightEvents {
+    RefreshCount,
+}
+
+impl<B: Bus<f32> + 'static> ClipLight<B> {
+    /// Creates a new [`ClipLight`].
+    ///
+    /// The `threshold` is given as a linear gain - e.g. `1.0` for 0 dBFS.
+    pub fn new(cx: &mut Context, bus: Arc<B>, threshold: f32) -> Handle<Self> {
+        let detector = Arc::new(Mutex::new(ClipDetector::new(threshold)));
+
+        let detector_c = detector.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut detector = lock_or_recover(&detector_c);
+            for sample in samples {
+                detector.process(*sample);
+            }
+        });
+
+        Self {
+            dispatcher_handle,
+            detector,
+            hold: Some(DEFAULT_HOLD),
+            last_clip: Cell::new(None),
+            count: false,
+        }
+        .build(cx, |_| {})
+    }
+
+    /// The number of overs, or nothing if there were none.
+    fn count_text(&self) -> String {
+        match lock_or_recover(&self.detector).overs {
+            0 => String::new(),
+            overs => overs.to_string(),
+        }
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for ClipLight<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("clip-light")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let clipped = std::mem::take(&mut lock_or_recover(&self.detector).clipped);
+        if clipped {
+            self.last_clip.set(Some(Instant::now()));
+        }
+
+        let lit = self.last_clip.get().is_some_and(|t| match self.hold {
+            Some(hold) => t.elapsed().as_secs_f32() * 1000. < hold,
+            None => true,
+        });
+
+        let mut color: vg::Color = cx.background_color().into();
+        if !lit {
+            color.a *= 0.2;
+        }
+
+        let mut light = vg::Path::new();
+        light.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(&light, &vg::Paint::color(color));
+    }
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            ClipLightEvent::Reset => {
+                lock_or_recover(&self.detector).reset();
+                self.last_clip.set(None);
+                if self.count {
+                    TextReadout::update(cx, self.count_text());
+                }
+            }
+        });
+
+        event.map(|e, _| match e {
+            ClipLightEvents::RefreshCount => {
+                if self.count {
+                    TextReadout::update(cx, self.count_text());
+                }
+            }
+        });
+
+        event.map(|window_event, meta| {
+            if let WindowEvent::MouseDown(MouseButton::Left) = window_event {
+                cx.emit(ClipLightEvent::Reset);
+                meta.consume();
+            }
+        });
+    }
+}
+
+pub trait ClipLightModifiers {
+    fn hold(self, hold: f32) -> Self;
+    fn latching(self) -> Self;
+    fn count_overs(self) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> ClipLightModifiers for Handle<'a, ClipLight<B>> {
+    /// Sets how long (in ms) the light stays lit after the signal has clipped.
+    ///
+    /// Defaults to 1.5 seconds.
+    fn hold(self, hold: f32) -> Self {
+        self.modify(|light| light.hold = Some(hold))
+    }
+    /// Keeps the light lit until it is clicked, or a [`ClipLightEvent::Reset`] is
+    /// sent to it.
+    fn latching(self) -> Self {
+        self.modify(|light| light.hold = None)
+    }
+    /// Shows the number of overs since the light was last reset, centered on the
+    /// light.
+    ///
+    /// Each run of consecutive samples above the threshold counts as one over. The
+    /// count is drawn in the light's `color` and refreshed a few times per second.
+    fn count_overs(mut self) -> Self {
+        let e = self.entity();
+
+        let mut text = String::new();
+        self = self.modify(|light| {
+            light.count = true;
+            text = light.count_text();
+        });
+
+        self.context().with_current(e, |cx| {
+            TextReadout::attach(cx, text, COUNT_INTERVAL, || ClipLightEvents::RefreshCount)
+                .position_type(PositionType::SelfDirected)
+                .space(Stretch(1.0));
+        });
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClipDetector;
+
+    #[test]
+    fn consecutive_clipping_samples_are_one_over() {
+        let mut detector = ClipDetector::new(1.0);
+
+        for sample in [0.5, 1.0, -1.2, 0.9, -1.0, 0.0, 0.99] {
+            detector.process(sample);
+        }
+
+        assert!(detector.clipped);
+        assert_eq!(detector.overs, 2);
+
+        detector.reset();
+        assert_eq!(detector.overs, 0);
+    }
+}
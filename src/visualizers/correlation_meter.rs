@@ -0,0 +1,179 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+use crate::accumulators::BallisticsAccumulator;
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+
+type Sample = [f32; 2];
+
+/// A running Pearson correlation between two channels.
+///
+/// Instead of a rectangular window, the products of both channels are averaged
+/// with an exponential moving average, so that the correlation can be updated
+/// sample by sample.
+pub(super) struct Correlation {
+    /// The time (in ms) it takes to cover 75% of a change in correlation.
+    window: f32,
+    weight: f32,
+    /// Average of left * right
+    lr: f32,
+    /// Average of left²
+    ll: f32,
+    /// Average of right²
+    rr: f32,
+}
+
+impl Correlation {
+    pub(super) fn new(window: f32, sample_rate: f32) -> Self {
+        let mut correlation = Self {
+            window,
+            weight: 0.0,
+            lr: 0.0,
+            ll: 0.0,
+            rr: 0.0,
+        };
+        correlation.set_sample_rate(sample_rate);
+        correlation
+    }
+
+    pub(super) fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.weight = BallisticsAccumulator::weight(self.window, sample_rate);
+    }
+
+    pub(super) fn process(&mut self, [left, right]: Sample) {
+        let w = self.weight;
+
+        self.lr = self.lr * w + left * right * (1.0 - w);
+        self.ll = self.ll * w + left * left * (1.0 - w);
+        self.rr = self.rr * w + right * right * (1.0 - w);
+    }
+
+    /// The current correlation, from -1 (fully out of phase) to +1 (mono).
+    ///
+    /// Silence has no correlation, so 0 is returned for it.
+    pub(super) fn value(&self) -> f32 {
+        let energy = (self.ll * self.rr).sqrt();
+
+        if energy > f32::EPSILON {
+            (self.lr / energy).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Displays the correlation between the left and right channel of a stereo
+/// signal, as a horizontal bar from -1 to +1.
+///
+/// A correlation of +1 means both channels are identical, i.e. the signal is
+/// mono. A correlation around 0 means the channels are unrelated, as for a very
+/// wide signal, and negative values mean the channels are out of phase - which
+/// leads to cancellations when the signal is summed to mono.
+///
+/// The bar is drawn from the center outwards, using the `background_color`. A
+/// thin line at the current correlation is drawn using the `color`.
+///
+/// # Example
+///
+/// Correlation meter averaging over 300 ms.
+///
+/// ```
+/// CorrelationMeter::new(cx, bus.clone(), 300.0)
+///     .height(Pixels(8.0))
+///     .color(Color::rgb(255, 255, 255))
+///     .background_color(Color::rgba(255, 255, 255, 60));
+/// ```
+pub struct CorrelationMeter<B: Bus<Sample> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<Sample>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    correlation: Arc<Mutex<Correlation>>,
+}
+
+impl<B: Bus<Sample> + 'static> CorrelationMeter<B> {
+    /// Creates a new [`CorrelationMeter`].
+    ///
+    /// The `window` dictates how long (in ms) it takes for the meter to cover 75%
+    /// of a change in correlation. Longer windows are steadier, while shorter ones
+    /// reveal brief phase issues, e.g. on transients.
+    pub fn new(cx: &mut Context, bus: Arc<B>, window: f32) -> Handle<Self> {
+        let correlation = Arc::new(Mutex::new(Correlation::new(window, bus.sample_rate())));
+
+        let correlation_c = correlation.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut correlation = lock_or_recover(&correlation_c);
+            for sample in samples {
+                correlation.process(*sample);
+            }
+        });
+
+        let correlation_c = correlation.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&correlation_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            correlation,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<Sample> + 'static> View for CorrelationMeter<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("correlation-meter")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let correlation = lock_or_recover(&self.correlation).value();
+
+        let center_x = x + w / 2.;
+        let value_x = center_x + correlation * w / 2.;
+
+        let mut bar = vg::Path::new();
+        bar.rect(center_x.min(value_x), y, (value_x - center_x).abs(), h);
+        canvas.fill_path(&bar, &vg::Paint::color(cx.background_color().into()));
+
+        let mut indicator = vg::Path::new();
+        indicator.move_to(value_x, y);
+        indicator.line_to(value_x, y + h);
+        canvas.stroke_path(
+            &indicator,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Correlation;
+
+    fn correlate(samples: impl Iterator<Item = [f32; 2]>) -> f32 {
+        let mut correlation = Correlation::new(500.0, 1000.0);
+        samples.for_each(|sample| correlation.process(sample));
+        correlation.value()
+    }
+
+    #[test]
+    fn correlation_of_typical_signals() {
+        let sine = |i: usize| (i as f32 * 0.1).sin();
+        let cosine = |i: usize| (i as f32 * 0.1).cos();
+
+        let mono = correlate((0..5000).map(|i| [sine(i), sine(i)]));
+        let inverted = correlate((0..5000).map(|i| [sine(i), -sine(i)]));
+        let quadrature = correlate((0..5000).map(|i| [sine(i), cosine(i)]));
+
+        assert!((mono - 1.0).abs() < 1e-3);
+        assert!((inverted + 1.0).abs() < 1e-3);
+        assert!(quadrature.abs() < 0.1);
+        assert_eq!(correlate((0..5000).map(|_| [0.0, 0.0])), 0.0);
+    }
+}
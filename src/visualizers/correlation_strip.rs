@@ -0,0 +1,161 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+use super::correlation_meter::Correlation;
+use crate::accumulators::sample_delta;
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, ColorMap, RingBuffer};
+
+type Sample = [f32; 2];
+
+/// The correlation of a stereo signal over time, sampled once per column.
+struct CorrelationHistory {
+    correlation: Correlation,
+    buffer: RingBuffer<f32>,
+    duration: f32,
+    sample_rate: f32,
+    /// The current time, counts up from 0 to sample_delta
+    t: f32,
+    sample_delta: f32,
+}
+
+impl CorrelationHistory {
+    fn new(window: f32, duration: f32, sample_rate: f32) -> Self {
+        let mut history = Self {
+            correlation: Correlation::new(window, sample_rate),
+            buffer: RingBuffer::new(1),
+            duration,
+            sample_rate,
+            t: 0.0,
+            sample_delta: 1.0,
+        };
+        history.update();
+        history
+    }
+
+    fn update(&mut self) {
+        self.sample_delta = sample_delta(self.buffer.len(), self.sample_rate, self.duration);
+        self.t = 0.0;
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.correlation.set_sample_rate(sample_rate);
+        self.update();
+    }
+
+    fn set_size(&mut self, size: usize) {
+        self.buffer.resize(size);
+        self.update();
+    }
+
+    fn process(&mut self, sample: Sample) {
+        self.correlation.process(sample);
+        self.t += 1.0;
+
+        if self.t > self.sample_delta {
+            self.t -= self.sample_delta;
+            self.buffer.enqueue(self.correlation.value());
+        }
+    }
+}
+
+/// Displays the correlation of a stereo signal over time, as a band of color.
+///
+/// Each column of the strip shows the correlation at one point in time, colored
+/// using a [`ColorMap`] - from -1 at its start to +1 at its end. Older columns
+/// scroll to the left. Compared to a [`Graph`](super::Graph), this takes up very
+/// little space, so it can stay visible at all times, e.g. on a master bus. See
+/// [`CorrelationMeter`](super::CorrelationMeter) for what the correlation means.
+///
+/// # Example
+///
+/// Correlation over the last 30 seconds, averaged over 300 ms, from red (-1)
+/// through yellow to green (+1).
+///
+/// ```
+/// CorrelationStrip::new(cx, bus.clone(), 300.0, 30.0, ColorMap::correlation())
+///     .height(Pixels(6.0));
+/// ```
+pub struct CorrelationStrip<B: Bus<Sample> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<Sample>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    history: Arc<Mutex<CorrelationHistory>>,
+    color_map: ColorMap,
+}
+
+impl<B: Bus<Sample> + 'static> CorrelationStrip<B> {
+    /// Creates a new [`CorrelationStrip`] spanning the last `duration` seconds.
+    ///
+    /// The `window` dictates how long (in ms) it takes for the correlation to cover
+    /// 75% of a change.
+    pub fn new(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window: f32,
+        duration: f32,
+        color_map: ColorMap,
+    ) -> Handle<Self> {
+        let history = Arc::new(Mutex::new(CorrelationHistory::new(
+            window,
+            duration,
+            bus.sample_rate(),
+        )));
+
+        let history_c = history.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut history = lock_or_recover(&history_c);
+            for sample in samples {
+                history.process(*sample);
+            }
+        });
+
+        let history_c = history.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&history_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            history,
+            color_map,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<Sample> + 'static> View for CorrelationStrip<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("correlation-strip")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let mut history = lock_or_recover(&self.history);
+
+        let width_ceil = w.ceil() as usize;
+        if width_ceil == 0 {
+            return;
+        }
+        if history.buffer.len() != width_ceil {
+            history.set_size(width_ceil);
+        }
+
+        for i in 0..history.buffer.len() {
+            let normalized = (history.buffer[i] + 1.) / 2.;
+
+            let mut column = vg::Path::new();
+            column.rect(x + i as f32, y, 1., h);
+            canvas.fill_path(
+                &column,
+                &vg::Paint::color(self.color_map.color_at(normalized).into()),
+            );
+        }
+    }
+}
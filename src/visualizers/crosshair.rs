@@ -0,0 +1,242 @@
+use nih_plug::util::gain_to_db;
+use nih_plug_vizia::vizia::prelude::*;
+
+use super::readout::{draw_guides, HoverReadout};
+use super::AxisLink;
+use crate::utils::ValueScaling;
+
+/// Emitted by a [`Crosshair`] as the cursor moves across it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrosshairHover {
+    /// The cursor points at the given values of the x and y axes.
+    Moved { x: f32, y: f32 },
+    /// The cursor has left the crosshair.
+    Left,
+}
+
+enum CrosshairEvents {
+    UpdateXRange((f32, f32)),
+    UpdateXScaling(ValueScaling),
+    UpdateYRange((f32, f32)),
+    UpdateYScaling(ValueScaling),
+}
+
+/// The value at a normalized position along an axis, in the units of its range.
+fn axis_value(scaling: ValueScaling, normalized: f32, range: (f32, f32)) -> f32 {
+    let value = scaling.normalized_to_value(normalized, range.0, range.1);
+
+    match scaling {
+        // The range of a decibel axis is given in dB, while its values are gains
+        ValueScaling::Decibels => gain_to_db(value),
+        _ => value,
+    }
+}
+
+/// Overlay that follows the cursor with a pair of guide lines, and reads out the
+/// values underneath it.
+///
+/// Put this inside the same [`ZStack`] as your visualizers, on top of them, and
+/// give it the same ranges and scalings they use - it then works the same way for
+/// graphs, spectra, histograms or anything else. As the cursor moves, the guide
+/// lines are drawn through it in the `color` at reduced opacity, and a
+/// [`CrosshairHover`] event is emitted with the values under it, so your editor
+/// can show them wherever it likes. A readout right next to the cursor can be
+/// added using [`with_readout`](CrosshairModifiers::with_readout).
+///
+/// The values are in the units of each range - e.g. in dB for
+/// [`ValueScaling::Decibels`], and in Hz for [`ValueScaling::Frequency`]. As the
+/// crosshair receives the mouse events, the views below it won't.
+///
+/// # Example
+///
+/// ```
+/// ZStack::new(cx, |cx| {
+///     SpectrumAnalyzer::new(
+///         cx,
+///         Data::spectrum,
+///         SpectrumAnalyzerVariant::LINE,
+///         ValueScaling::Frequency,
+///         (10., 21_000.),
+///         ValueScaling::Decibels,
+///         (-110., 6.),
+///     )
+///     .color(Color::rgba(255, 255, 255, 160));
+///     Crosshair::new(
+///         cx,
+///         (10., 21_000.),
+///         ValueScaling::Frequency,
+///         (-110., 6.),
+///         ValueScaling::Decibels,
+///     )
+///     .with_readout(|frequency, magnitude| {
+///         format!("{} Hz  {:.1} dB", frequency.round(), magnitude)
+///     })
+///     .color(Color::rgb(160, 160, 160));
+/// });
+/// ```
+pub struct Crosshair {
+    x_range: (f32, f32),
+    x_scaling: ValueScaling,
+    y_range: (f32, f32),
+    y_scaling: ValueScaling,
+    /// Turns the values under the cursor into the readout's text, if there is one
+    format: Option<Box<dyn Fn(f32, f32) -> String>>,
+    /// The position of the cursor, while it hovers over the crosshair
+    hover: Option<(f32, f32)>,
+}
+
+impl Crosshair {
+    /// Creates a new [`Crosshair`], for an x axis growing to the right and a y
+    /// axis growing upwards.
+    pub fn new(
+        cx: &mut Context,
+        x_range: impl Res<(f32, f32)>,
+        x_scaling: impl Res<ValueScaling>,
+        y_range: impl Res<(f32, f32)>,
+        y_scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        let mut handle = Self {
+            x_range: x_range.get_val(cx),
+            x_scaling: x_scaling.get_val(cx),
+            y_range: y_range.get_val(cx),
+            y_scaling: y_scaling.get_val(cx),
+            format: None,
+            hover: None,
+        }
+        .build(cx, |_| {});
+
+        let e = handle.entity();
+        x_range.set_or_bind(handle.context(), e, move |cx, r| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateXRange(r));
+        });
+        x_scaling.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateXScaling(s));
+        });
+        y_range.set_or_bind(handle.context(), e, move |cx, r| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateYRange(r));
+        });
+        y_scaling.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateYScaling(s));
+        });
+
+        handle
+    }
+
+    /// The values of both axes at the given position.
+    fn value_at(&self, (x, y): (f32, f32), bounds: BoundingBox) -> (f32, f32) {
+        (
+            axis_value(
+                self.x_scaling,
+                ((x - bounds.x) / bounds.w).clamp(0., 1.),
+                self.x_range,
+            ),
+            axis_value(
+                self.y_scaling,
+                (1. - (y - bounds.y) / bounds.h).clamp(0., 1.),
+                self.y_range,
+            ),
+        )
+    }
+}
+
+impl View for Crosshair {
+    fn element(&self) -> Option<&'static str> {
+        Some("crosshair")
+    }
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            CrosshairEvents::UpdateXRange(range) => self.x_range = *range,
+            CrosshairEvents::UpdateXScaling(scaling) => self.x_scaling = *scaling,
+            CrosshairEvents::UpdateYRange(range) => self.y_range = *range,
+            CrosshairEvents::UpdateYScaling(scaling) => self.y_scaling = *scaling,
+        });
+
+        event.map(|window_event, _| match window_event {
+            WindowEvent::MouseMove(x, y) => {
+                let (value_x, value_y) = self.value_at((*x, *y), cx.bounds());
+
+                if let Some(format) = &self.format {
+                    HoverReadout::show(cx, format(value_x, value_y), (*x, *y));
+                }
+                cx.emit(CrosshairHover::Moved {
+                    x: value_x,
+                    y: value_y,
+                });
+
+                self.hover = Some((*x, *y));
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseLeave => {
+                if self.format.is_some() {
+                    HoverReadout::hide(cx);
+                }
+                cx.emit(CrosshairHover::Left);
+
+                self.hover = None;
+                cx.needs_redraw();
+            }
+            _ => {}
+        });
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        if let Some(hover) = self.hover {
+            draw_guides(cx, canvas, hover);
+        }
+    }
+}
+
+pub trait CrosshairModifiers {
+    fn with_readout(self, format: impl Fn(f32, f32) -> String + 'static) -> Self;
+    fn x_axis(self, link: impl Lens<Target = AxisLink>) -> Self;
+    fn y_axis(self, link: impl Lens<Target = AxisLink>) -> Self;
+}
+
+impl<'a> CrosshairModifiers for Handle<'a, Crosshair> {
+    /// Shows the values under the cursor right next to it, as text created by the
+    /// given `format` function from the x and y values.
+    ///
+    /// The readout is drawn in the `color`, and hidden once the cursor leaves.
+    fn with_readout(mut self, format: impl Fn(f32, f32) -> String + 'static) -> Self {
+        self = self.modify(|crosshair| crosshair.format = Some(Box::new(format)));
+
+        let e = self.entity();
+        self.context().with_current(e, HoverReadout::attach);
+
+        self
+    }
+    /// Keeps the range and scaling of the x axis in sync with an [`AxisLink`].
+    fn x_axis(mut self, link: impl Lens<Target = AxisLink>) -> Self {
+        let e = self.entity();
+
+        link.set_or_bind(self.context(), e, move |cx, axis| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateXRange(axis.range));
+            (*cx).emit_to(e, CrosshairEvents::UpdateXScaling(axis.scaling));
+        });
+
+        self
+    }
+    /// Keeps the range and scaling of the y axis in sync with an [`AxisLink`].
+    fn y_axis(mut self, link: impl Lens<Target = AxisLink>) -> Self {
+        let e = self.entity();
+
+        link.set_or_bind(self.context(), e, move |cx, axis| {
+            (*cx).emit_to(e, CrosshairEvents::UpdateYRange(axis.range));
+            (*cx).emit_to(e, CrosshairEvents::UpdateYScaling(axis.scaling));
+        });
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::axis_value;
+    use crate::utils::ValueScaling;
+
+    #[test]
+    fn values_are_read_out_in_the_units_of_the_range() {
+        assert_eq!(axis_value(ValueScaling::Linear, 0.25, (-1., 1.)), -0.5);
+        assert!((axis_value(ValueScaling::Decibels, 0.5, (-60., 0.)) + 30.).abs() < 1e-3);
+        assert!((axis_value(ValueScaling::Frequency, 0.5, (20., 20_000.)) - 632.46).abs() < 0.1);
+    }
+}
@@ -0,0 +1,364 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::bus::{Bus, TimedEvent};
+use crate::utils::lock_or_recover;
+
+/// How much of the width the sustain stage takes up, as it has no duration.
+const SUSTAIN_WIDTH: f32 = 0.2;
+
+/// The stages of an envelope, with times in ms and the sustain level from 0 to 1.
+///
+/// For a plain ADSR envelope, create it using [`adsr`](Self::adsr) - the delay and
+/// hold stages are then skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub struct EnvelopeShape {
+    pub delay: f32,
+    pub attack: f32,
+    pub hold: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+impl EnvelopeShape {
+    /// Creates an ADSR envelope.
+    pub fn adsr(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self::dahdsr(0., attack, 0., decay, sustain, release)
+    }
+
+    /// Creates a DAHDSR envelope.
+    pub fn dahdsr(
+        delay: f32,
+        attack: f32,
+        hold: f32,
+        decay: f32,
+        sustain: f32,
+        release: f32,
+    ) -> Self {
+        Self {
+            delay: delay.max(0.),
+            attack: attack.max(0.),
+            hold: hold.max(0.),
+            decay: decay.max(0.),
+            sustain: sustain.clamp(0., 1.),
+            release: release.max(0.),
+        }
+    }
+
+    /// The time (in ms) it takes to reach the sustain stage.
+    fn until_sustain(&self) -> f32 {
+        self.delay + self.attack + self.hold + self.decay
+    }
+
+    /// The level of the envelope, the given time (in ms) after the note started,
+    /// while it is still held.
+    pub fn level_at(&self, time: f32) -> f32 {
+        let mut time = time - self.delay;
+        if time < 0. {
+            return 0.;
+        }
+
+        if time < self.attack {
+            return time / self.attack;
+        }
+        time -= self.attack;
+
+        if time < self.hold {
+            return 1.;
+        }
+        time -= self.hold;
+
+        if time < self.decay {
+            return 1. - (1. - self.sustain) * time / self.decay;
+        }
+
+        self.sustain
+    }
+
+    /// The level of the envelope, the given time (in ms) after a note was
+    /// released at the given level.
+    ///
+    /// Returns `None` once the release stage is over.
+    pub fn release_level_at(&self, time: f32, released_at: f32) -> Option<f32> {
+        (time < self.release).then(|| released_at * (1. - time / self.release))
+    }
+
+    /// The corners of the envelope, as time and level. The sustain stage is
+    /// `sustain` ms long.
+    fn corners(&self, sustain: f32) -> [(f32, f32); 7] {
+        let attack_start = self.delay;
+        let hold_start = attack_start + self.attack;
+        let decay_start = hold_start + self.hold;
+        let sustain_start = decay_start + self.decay;
+        let release_start = sustain_start + sustain;
+
+        [
+            (0., 0.),
+            (attack_start, 0.),
+            (hold_start, 1.),
+            (decay_start, 1.),
+            (sustain_start, self.sustain),
+            (release_start, self.sustain),
+            (release_start + self.release, 0.),
+        ]
+    }
+}
+
+/// Whether a note is held, which drives the playhead of an [`EnvelopeDisplay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeGate {
+    /// A note started, restarting the envelope.
+    On,
+    /// The note was released, starting the release stage.
+    Off,
+}
+
+/// When the current note started and was released.
+#[derive(Default)]
+struct Playhead {
+    on: Option<Instant>,
+    off: Option<Instant>,
+}
+
+/// Draws the shape of an ADSR or DAHDSR envelope, like the envelope section of a
+/// synth.
+///
+/// The stages are drawn along a linear time axis, so that a longer attack takes up
+/// more space than a shorter one. By default, the whole envelope is fit into the
+/// view, with the sustain stage taking up a fifth of its width. Using
+/// [`time_span`](EnvelopeDisplayModifiers::time_span), the time axis can be fixed
+/// instead, so that the shape doesn't get squashed whenever a stage gets longer.
+///
+/// The envelope is drawn as a line in the `color`, and filled using the
+/// `background_color`. Using [`playhead`](EnvelopeDisplayModifiers::playhead), the
+/// current position within the envelope can be shown as well.
+///
+/// # Example
+///
+/// ```
+/// EnvelopeDisplay::new(
+///     cx,
+///     Data::params.map(|params| {
+///         EnvelopeShape::adsr(
+///             params.attack.value(),
+///             params.decay.value(),
+///             params.sustain.value(),
+///             params.release.value(),
+///         )
+///     }),
+/// )
+/// .playhead(gate_bus.clone())
+/// .color(Color::rgb(255, 255, 255))
+/// .background_color(Color::rgba(255, 255, 255, 40));
+/// ```
+pub struct EnvelopeDisplay {
+    shape: EnvelopeShape,
+    /// The time (in ms) the view spans, excluding the sustain stage
+    time_span: Option<f32>,
+    playhead: Option<Arc<Mutex<Playhead>>>,
+    playhead_handle: Option<Box<dyn Any + Send + Sync>>,
+}
+
+enum EnvelopeDisplayEvents {
+    UpdateShape(EnvelopeShape),
+}
+
+impl EnvelopeDisplay {
+    /// Creates a new [`EnvelopeDisplay`].
+    pub fn new(cx: &mut Context, shape: impl Res<EnvelopeShape>) -> Handle<Self> {
+        let mut handle = Self {
+            shape: shape.get_val(cx),
+            time_span: None,
+            playhead: None,
+            playhead_handle: None,
+        }
+        .build(cx, |_| {});
+
+        let e = handle.entity();
+        shape.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, EnvelopeDisplayEvents::UpdateShape(s));
+        });
+
+        handle
+    }
+
+    /// The current time (in ms) within the displayed envelope and the current
+    /// level, if a note is playing.
+    fn playhead_position(&self, sustain: f32) -> Option<(f32, f32)> {
+        let playhead = lock_or_recover(self.playhead.as_ref()?);
+        let on = playhead.on?;
+
+        let shape = &self.shape;
+        let until_sustain = shape.until_sustain();
+
+        match playhead.off {
+            None => {
+                let time = on.elapsed().as_secs_f32() * 1000.;
+                Some((time.min(until_sustain), shape.level_at(time)))
+            }
+            Some(off) => {
+                let held = off.saturating_duration_since(on).as_secs_f32() * 1000.;
+                let time = off.elapsed().as_secs_f32() * 1000.;
+                let level = shape.release_level_at(time, shape.level_at(held))?;
+                Some((until_sustain + sustain + time, level))
+            }
+        }
+    }
+}
+
+impl View for EnvelopeDisplay {
+    fn element(&self) -> Option<&'static str> {
+        Some("envelope-display")
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            EnvelopeDisplayEvents::UpdateShape(shape) => self.shape = *shape,
+        });
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        if w <= 0. {
+            return;
+        }
+
+        let line_width = cx.scale_factor() * 1.5;
+
+        // The sustain stage has a fixed width, and the other stages share the rest
+        let time_span = self
+            .time_span
+            .unwrap_or(self.shape.until_sustain() + self.shape.release);
+        let ms_per_px = time_span.max(f32::EPSILON) / (w * (1. - SUSTAIN_WIDTH));
+        let sustain = w * SUSTAIN_WIDTH * ms_per_px;
+
+        let to_point = |(time, level): (f32, f32)| {
+            (
+                x + time / ms_per_px,
+                y + line_width / 2. + (h - line_width) * (1. - level),
+            )
+        };
+
+        let mut line = vg::Path::new();
+        for (i, corner) in self.shape.corners(sustain).into_iter().enumerate() {
+            let (point_x, point_y) = to_point(corner);
+            if i == 0 {
+                line.move_to(point_x, point_y);
+            } else {
+                line.line_to(point_x, point_y);
+            }
+        }
+
+        let mut fill = line.clone();
+        fill.close();
+
+        canvas.save();
+        canvas.intersect_scissor(x, y, w, h);
+
+        canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+        canvas.stroke_path(
+            &line,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(line_width),
+        );
+
+        if let Some(position) = self.playhead_position(sustain) {
+            let (point_x, point_y) = to_point(position);
+
+            let mut dot = vg::Path::new();
+            dot.circle(point_x, point_y, line_width * 2.);
+            canvas.fill_path(&dot, &vg::Paint::color(cx.font_color().into()));
+        }
+
+        canvas.restore();
+    }
+}
+
+pub trait EnvelopeDisplayModifiers {
+    fn time_span(self, time_span: f32) -> Self;
+    fn playhead<B: Bus<TimedEvent<EnvelopeGate>> + 'static>(self, bus: Arc<B>) -> Self;
+}
+
+impl EnvelopeDisplayModifiers for Handle<'_, EnvelopeDisplay> {
+    /// Fixes the time (in ms) the view spans, not counting the sustain stage.
+    ///
+    /// By default, the view spans the whole envelope, so the shape is squashed
+    /// whenever a stage gets longer. With a fixed time span, stages keep their
+    /// size, and anything beyond the span is cut off.
+    fn time_span(self, time_span: f32) -> Self {
+        self.modify(|display| display.time_span = Some(time_span))
+    }
+    /// Shows the current position within the envelope as a dot, driven by the
+    /// [`EnvelopeGate`] events your plug-in sends through the given bus.
+    ///
+    /// The envelope restarts on each [`EnvelopeGate::On`], stays at the start of
+    /// the sustain stage while the note is held, and moves through the release
+    /// stage after an [`EnvelopeGate::Off`]. The position is derived from the time
+    /// at which each event was sent, so there's no need to send it continuously.
+    ///
+    /// ```
+    /// let gate_bus: Arc<EventBus<EnvelopeGate>> = Default::default();
+    ///
+    /// // Inside your plug-in's process() function
+    /// match event {
+    ///     NoteEvent::NoteOn { .. } => gate_bus.send(EnvelopeGate::On),
+    ///     NoteEvent::NoteOff { .. } => gate_bus.send(EnvelopeGate::Off),
+    ///     _ => (),
+    /// }
+    ///
+    /// // Inside your editor
+    /// EnvelopeDisplay::new(cx, Data::envelope).playhead(gate_bus.clone());
+    /// ```
+    fn playhead<B: Bus<TimedEvent<EnvelopeGate>> + 'static>(self, bus: Arc<B>) -> Self {
+        let playhead: Arc<Mutex<Playhead>> = Default::default();
+
+        let playhead_c = playhead.clone();
+        let handle = bus.register_dispatcher(move |events| {
+            let mut playhead = lock_or_recover(&playhead_c);
+            for event in events {
+                match event.event {
+                    EnvelopeGate::On => {
+                        playhead.on = Some(event.time);
+                        playhead.off = None;
+                    }
+                    EnvelopeGate::Off => {
+                        if playhead.on.is_some() && playhead.off.is_none() {
+                            playhead.off = Some(event.time);
+                        }
+                    }
+                }
+            }
+        });
+
+        self.modify(|display| {
+            display.playhead = Some(playhead);
+            display.playhead_handle = Some(Box::new(handle));
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EnvelopeShape;
+
+    #[test]
+    fn levels_follow_the_stages() {
+        let shape = EnvelopeShape::dahdsr(10., 20., 10., 40., 0.5, 100.);
+
+        assert_eq!(shape.level_at(5.), 0.);
+        assert_eq!(shape.level_at(20.), 0.5);
+        assert_eq!(shape.level_at(35.), 1.);
+        assert_eq!(shape.level_at(60.), 0.75);
+        assert_eq!(shape.level_at(1_000.), 0.5);
+
+        // Releasing during the attack starts from wherever the envelope was
+        assert_eq!(shape.release_level_at(50., shape.level_at(20.)), Some(0.25));
+        assert_eq!(shape.release_level_at(100., 0.5), None);
+    }
+}
@@ -0,0 +1,231 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::Arc;
+
+use super::{DurationModifiers, GraphSource, RangeModifiers};
+use crate::accumulators::PeakAccumulator;
+use crate::bus::Bus;
+use crate::utils::ValueScaling;
+
+/// Plots a dynamics processor's detection envelope against the envelope of its
+/// input, on a shared time axis.
+///
+/// Both envelopes are received through their own bus, as linear values - e.g. the
+/// rectified input and the output of the processor's envelope follower. The input
+/// envelope is drawn as a translucent line, and the detection envelope as a solid
+/// line, both using the `color`. The area between them is tinted, depending on
+/// whether the detector lags behind a rising input (attack) or is still holding on
+/// to a falling one (release). This makes it easy to see how attack and release
+/// times shape the detector's response.
+///
+/// # Example
+///
+/// ```
+/// EnvelopeGraph::new(
+///     cx,
+///     input_bus.clone(),
+///     detector_bus.clone(),
+///     2.0,
+///     (-48.0, 6.0),
+///     ValueScaling::Decibels,
+/// )
+/// .attack_color(Color::rgba(255, 160, 64, 80))
+/// .release_color(Color::rgba(64, 160, 255, 80))
+/// .color(Color::rgb(255, 255, 255));
+/// ```
+pub struct EnvelopeGraph<B: Bus<f32> + 'static> {
+    input: GraphSource<B, PeakAccumulator>,
+    detection: GraphSource<B, PeakAccumulator>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+    attack_color: Option<Color>,
+    release_color: Option<Color>,
+}
+
+enum EnvelopeGraphEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+    UpdateDuration(f32),
+}
+
+impl<B: Bus<f32> + 'static> EnvelopeGraph<B> {
+    /// Creates a new [`EnvelopeGraph`] showing the last `duration` seconds of both
+    /// envelopes.
+    pub fn new(
+        cx: &mut Context,
+        input_bus: Arc<B>,
+        detection_bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        // Without any decay, each pixel shows the exact maximum of the envelope
+        // within the time it spans
+        let initial_duration = duration.get_val(cx);
+        let accumulator = || PeakAccumulator::new(initial_duration, 0.0);
+
+        Self {
+            input: GraphSource::new(input_bus, accumulator()),
+            detection: GraphSource::new(detection_bus, accumulator()),
+            range: range.get_val(cx),
+            scaling: scaling.get_val(cx),
+            attack_color: None,
+            release_color: None,
+        }
+        .build(cx, |_| {})
+        .range(range)
+        .scaling(scaling)
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for EnvelopeGraph<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("envelope-graph")
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            EnvelopeGraphEvents::UpdateRange(v) => self.range = *v,
+            EnvelopeGraphEvents::UpdateScaling(s) => self.scaling = *s,
+            EnvelopeGraphEvents::UpdateDuration(duration) => {
+                self.input.set_duration(*duration);
+                self.detection.set_duration(*duration);
+            }
+        });
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let line_width = cx.scale_factor();
+
+        let width_ceil = w.ceil() as usize;
+        if width_ceil == 0 {
+            return;
+        }
+
+        let to_y = |value: f32| {
+            let normalized = self
+                .scaling
+                .value_to_normalized(value, self.range.0, self.range.1);
+            y + h * (1. - normalized)
+        };
+
+        let input = self.input.history(width_ceil);
+        let detection = self.detection.history(width_ceil);
+
+        let mut input_line = vg::Path::new();
+        let mut detection_line = vg::Path::new();
+        let mut attack = vg::Path::new();
+        let mut release = vg::Path::new();
+
+        for (i, (input, detection)) in input.iter().zip(detection.iter()).enumerate() {
+            let column_x = x + i as f32;
+            let (input_y, detection_y) = (to_y(*input), to_y(*detection));
+
+            if i == 0 {
+                input_line.move_to(column_x, input_y);
+                detection_line.move_to(column_x, detection_y);
+            } else {
+                input_line.line_to(column_x, input_y);
+                detection_line.line_to(column_x, detection_y);
+            }
+
+            // The detector is still catching up with a louder input while attacking,
+            // and holding on to a louder level than the input while releasing
+            let lag = if detection < input {
+                &mut attack
+            } else {
+                &mut release
+            };
+            lag.rect(
+                column_x,
+                input_y.min(detection_y),
+                1.,
+                (input_y - detection_y).abs(),
+            );
+        }
+
+        let mut faint: vg::Color = cx.font_color().into();
+        faint.a *= 0.3;
+
+        canvas.save();
+        canvas.intersect_scissor(x, y, w, h);
+
+        canvas.fill_path(
+            &attack,
+            &vg::Paint::color(self.attack_color.map_or(faint, Into::into)),
+        );
+        canvas.fill_path(
+            &release,
+            &vg::Paint::color(self.release_color.map_or(faint, Into::into)),
+        );
+        canvas.stroke_path(
+            &input_line,
+            &vg::Paint::color(faint).with_line_width(line_width),
+        );
+        canvas.stroke_path(
+            &detection_line,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(line_width * 1.5),
+        );
+
+        canvas.restore();
+    }
+}
+
+pub trait EnvelopeGraphModifiers {
+    fn attack_color(self, color: Color) -> Self;
+    fn release_color(self, color: Color) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> EnvelopeGraphModifiers for Handle<'a, EnvelopeGraph<B>> {
+    /// Sets the color of the areas where the detector lags behind a rising input.
+    ///
+    /// Defaults to a translucent version of the `color`.
+    fn attack_color(self, color: Color) -> Self {
+        self.modify(|graph| graph.attack_color = Some(color))
+    }
+    /// Sets the color of the areas where the detector holds on to a level above the
+    /// falling input.
+    ///
+    /// Defaults to a translucent version of the `color`.
+    fn release_color(self, color: Color) -> Self {
+        self.modify(|graph| graph.release_color = Some(color))
+    }
+}
+
+impl<'a, B: Bus<f32> + 'static> RangeModifiers for Handle<'a, EnvelopeGraph<B>> {
+    fn range(mut self, range: impl Res<(f32, f32)>) -> Self {
+        let e = self.entity();
+
+        range.set_or_bind(self.context(), e, move |cx, r| {
+            (*cx).emit_to(e, EnvelopeGraphEvents::UpdateRange(r));
+        });
+
+        self
+    }
+    fn scaling(mut self, scaling: impl Res<ValueScaling>) -> Self {
+        let e = self.entity();
+
+        scaling.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, EnvelopeGraphEvents::UpdateScaling(s));
+        });
+
+        self
+    }
+}
+
+impl<'a, B: Bus<f32> + 'static> DurationModifiers for Handle<'a, EnvelopeGraph<B>> {
+    fn duration(mut self, duration: impl Res<f32>) -> Self {
+        let e = self.entity();
+
+        duration.set_or_bind(self.context(), e, move |cx, d| {
+            (*cx).emit_to(e, EnvelopeGraphEvents::UpdateDuration(d));
+        });
+
+        self
+    }
+}
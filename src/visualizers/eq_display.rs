@@ -0,0 +1,177 @@
+use nih_plug_vizia::vizia::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use super::{
+    AxisLink, BiquadCoefficients, FilterResponse, Grid, GridModifiers, RangeModifiers,
+    SpectrumAnalyzer, SpectrumAnalyzerModifiers, SpectrumAnalyzerVariant, UnitRuler,
+};
+use crate::spectrum::SpectrumOutput;
+use crate::utils::ValueScaling;
+
+/// The frequencies the vertical grid lines are drawn at, one for each decade.
+const FREQUENCY_LINES: [f32; 3] = [100., 1_000., 10_000.];
+
+/// The frequencies the less prominent vertical grid lines are drawn at.
+const MINOR_FREQUENCY_LINES: [f32; 24] = [
+    20., 30., 40., 50., 60., 70., 80., 90., 200., 300., 400., 500., 600., 700., 800., 900., 2_000.,
+    3_000., 4_000., 5_000., 6_000., 7_000., 8_000., 9_000.,
+];
+
+/// The frequencies that are labeled below the display.
+const FREQUENCY_LABELS: [(f32, &str); 9] = [
+    (20., "20"),
+    (50., "50"),
+    (100., "100"),
+    (200., "200"),
+    (500., "500"),
+    (1_000., "1k"),
+    (2_000., "2k"),
+    (5_000., "5k"),
+    (10_000., "10k"),
+];
+
+/// The gains (in dB) the horizontal grid lines are drawn at - every 6 dB within
+/// the range.
+fn gain_lines(range: (f32, f32)) -> Vec<f32> {
+    let (min, max) = (range.0.min(range.1), range.0.max(range.1));
+
+    ((min / 6.).ceil() as i32..=(max / 6.).floor() as i32)
+        .map(|step| step as f32 * 6.)
+        .collect()
+}
+
+/// The typical display of an EQ: the response curve of its filters, drawn on top of
+/// the live spectrum of the signal.
+///
+/// This stacks a [`Grid`], a [`SpectrumAnalyzer`], a [`FilterResponse`] and a
+/// [`UnitRuler`], and links their frequency axes to the same [`AxisLink`] - if the
+/// link changes, e.g. because the user zooms in, all of them follow together. The
+/// spectrum is scaled to the `magnitude_range` (in dB), while the response curve
+/// and the horizontal grid lines are scaled to the `gain_range` (in dB).
+///
+/// Each part has a class, so it can be styled using CSS:
+///
+/// ```css
+/// eq-display .frequency-grid, eq-display .gain-grid { color: #ffffff20; }
+/// eq-display .spectrum { color: #ffffff40; background-color: #ffffff10; }
+/// eq-display .response { color: #ff9f40; background-color: #ff9f4028; }
+/// eq-display .frequency-ruler { color: #ffffff80; font-size: 11; }
+/// ```
+///
+/// # Example
+///
+/// ```
+/// #[derive(Lens, Clone)]
+/// pub(crate) struct Data {
+///     pub(crate) spectrum: Arc<Mutex<SpectrumOutput>>,
+///     pub(crate) eq_stages: Vec<BiquadCoefficients>,
+///     pub(crate) sample_rate: f32,
+///     pub(crate) frequency_axis: AxisLink,
+/// }
+///
+/// // Inside your editor
+/// EqDisplay::new(
+///     cx,
+///     Data::spectrum,
+///     Data::eq_stages,
+///     Data::sample_rate,
+///     Data::frequency_axis,
+///     (-90., 6.),
+///     (-24., 24.),
+/// );
+/// ```
+pub struct EqDisplay {}
+
+impl EqDisplay {
+    /// Creates a new [`EqDisplay`].
+    pub fn new<LSpectrum, LAxis>(
+        cx: &mut Context,
+        spectrum: LSpectrum,
+        stages: impl Res<Vec<BiquadCoefficients>>,
+        sample_rate: impl Res<f32>,
+        frequency_axis: LAxis,
+        magnitude_range: (f32, f32),
+        gain_range: (f32, f32),
+    ) -> Handle<Self>
+    where
+        LSpectrum: Lens<Target = Arc<Mutex<SpectrumOutput>>>,
+        LAxis: Lens<Target = AxisLink>,
+    {
+        Self {}.build(cx, |cx| {
+            let axis = frequency_axis.get(cx);
+
+            ZStack::new(cx, |cx| {
+                Grid::new(
+                    cx,
+                    axis.scaling,
+                    axis.range,
+                    FREQUENCY_LINES.to_vec(),
+                    Orientation::Vertical,
+                )
+                .minor_lines(MINOR_FREQUENCY_LINES.to_vec())
+                .axis(frequency_axis.clone())
+                .class("frequency-grid");
+                Grid::new(
+                    cx,
+                    ValueScaling::Linear,
+                    gain_range,
+                    gain_lines(gain_range),
+                    Orientation::Horizontal,
+                )
+                .class("gain-grid");
+                SpectrumAnalyzer::new(
+                    cx,
+                    spectrum,
+                    SpectrumAnalyzerVariant::LINE,
+                    axis.scaling,
+                    axis.range,
+                    ValueScaling::Decibels,
+                    magnitude_range,
+                )
+                .frequency_axis(frequency_axis.clone())
+                .class("spectrum");
+                FilterResponse::new(
+                    cx,
+                    stages,
+                    sample_rate,
+                    axis.scaling,
+                    axis.range,
+                    gain_range,
+                )
+                .axis(frequency_axis.clone())
+                .class("response");
+                UnitRuler::linked(
+                    cx,
+                    frequency_axis.clone(),
+                    FREQUENCY_LABELS.to_vec(),
+                    Orientation::Horizontal,
+                )
+                .height(Pixels(16.))
+                .top(Stretch(1.))
+                .bottom(Pixels(4.))
+                .hoverable(false)
+                .class("frequency-ruler");
+            });
+        })
+    }
+}
+
+impl View for EqDisplay {
+    fn element(&self) -> Option<&'static str> {
+        Some("eq-display")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gain_lines;
+
+    #[test]
+    fn gain_lines_fall_on_multiples_of_six() {
+        assert_eq!(
+            gain_lines((-24., 24.)),
+            vec![-24., -18., -12., -6., 0., 6., 12., 18., 24.]
+        );
+        assert_eq!(gain_lines((-15., 10.)), vec![-12., -6., 0., 6.]);
+    }
+}
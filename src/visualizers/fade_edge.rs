@@ -0,0 +1,101 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+/// The side of its parent a [`FadeEdge`] is placed along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FadeSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// A fade from transparent to a solid color towards one side of its parent.
+///
+/// Put this inside a [`ZStack`], on top of a visualizer, to give labels such as a
+/// [`UnitRuler`](super::UnitRuler) a backdrop that fades into the panel's
+/// background. It positions and sizes itself, and doesn't react to the mouse, so
+/// the visualizer underneath stays interactive.
+///
+/// # Example
+///
+/// Here's how to fade a spectrum analyzer to the background at the bottom, as a
+/// backdrop for a unit ruler.
+///
+/// ```
+/// ZStack::new(cx, |cx| {
+///     SpectrumAnalyzer::new(
+///         cx,
+///         Data::spectrum,
+///         SpectrumAnalyzerVariant::LINE,
+///         ValueScaling::Frequency,
+///         (10., 21_000.),
+///         ValueScaling::Decibels,
+///         (-110., 6.),
+///     );
+///     FadeEdge::new(cx, FadeSide::Bottom, 48., Color::rgb(16, 16, 16));
+///     UnitRuler::new(
+///         cx,
+///         (10., 21_000.),
+///         ValueScaling::Frequency,
+///         vec![(100., "100"), (1_000., "1k"), (10_000., "10k")],
+///         Orientation::Horizontal,
+///     )
+///     .height(Pixels(16.))
+///     .top(Stretch(1.))
+///     .bottom(Pixels(8.));
+/// })
+/// .background_color(Color::rgb(16, 16, 16));
+/// ```
+pub struct FadeEdge {
+    side: FadeSide,
+    color: Color,
+}
+
+impl FadeEdge {
+    /// Creates a new [`FadeEdge`] that fades to the given `color` over `length`
+    /// pixels towards the given `side`.
+    pub fn new(cx: &mut Context, side: FadeSide, length: f32, color: Color) -> Handle<Self> {
+        let handle = Self { side, color }.build(cx, |_| {}).hoverable(false);
+
+        match side {
+            FadeSide::Top => handle.height(Pixels(length)).bottom(Stretch(1.)),
+            FadeSide::Bottom => handle.height(Pixels(length)).top(Stretch(1.)),
+            FadeSide::Left => handle.width(Pixels(length)).right(Stretch(1.)),
+            FadeSide::Right => handle.width(Pixels(length)).left(Stretch(1.)),
+        }
+    }
+}
+
+impl View for FadeEdge {
+    fn element(&self) -> Option<&'static str> {
+        Some("fade-edge")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        // Fading to a transparent version of the same color avoids a dark fringe
+        let solid: vg::Color = self.color.into();
+        let mut transparent = solid;
+        transparent.a = 0.;
+
+        // From the inner edge to the side
+        let (start, end) = match self.side {
+            FadeSide::Top => ((x, y + h), (x, y)),
+            FadeSide::Bottom => ((x, y), (x, y + h)),
+            FadeSide::Left => ((x + w, y), (x, y)),
+            FadeSide::Right => ((x, y), (x + w, y)),
+        };
+
+        let mut path = vg::Path::new();
+        path.rect(x, y, w, h);
+        canvas.fill_path(
+            &path,
+            &vg::Paint::linear_gradient(start.0, start.1, end.0, end.1, transparent, solid),
+        );
+    }
+}
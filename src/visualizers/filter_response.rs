@@ -0,0 +1,305 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::f64::consts::TAU;
+
+use super::RangeModifiers;
+use crate::utils::ValueScaling;
+
+/// Gains below this level (in dB) are clamped, so that the zeros of a notch don't
+/// shoot off to negative infinity.
+const FLOOR: f32 = -120.0;
+
+/// The coefficients of a single biquad stage, normalized so that `a0` is 1.
+///
+/// This is the transfer function
+///
+/// ```text
+///         b0 + b1 z^-1 + b2 z^-2
+/// H(z) = ------------------------
+///          1 + a1 z^-1 + a2 z^-2
+/// ```
+///
+/// which most EQ and filter implementations - such as the ones following the
+/// Audio EQ Cookbook - compute their coefficients for.
+#[derive(Debug, Clone, Copy, PartialEq, Data)]
+pub struct BiquadCoefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl BiquadCoefficients {
+    /// Creates a new set of coefficients, normalizing them by `a0`.
+    pub fn new(b: [f32; 3], a: [f32; 3]) -> Self {
+        Self {
+            b0: b[0] / a[0],
+            b1: b[1] / a[0],
+            b2: b[2] / a[0],
+            a1: a[1] / a[0],
+            a2: a[2] / a[0],
+        }
+    }
+
+    /// A stage that passes everything through unchanged.
+    pub fn identity() -> Self {
+        Self::new([1., 0., 0.], [1., 0., 0.])
+    }
+
+    /// The gain (in dB) of this stage at the given frequency, for a filter running
+    /// at the given sample rate.
+    ///
+    /// The gain never falls below -120 dB, even right at the zero of a notch.
+    pub fn gain_at(&self, frequency: f32, sample_rate: f32) -> f32 {
+        let omega = TAU * frequency as f64 / sample_rate as f64;
+        let (cos_1, sin_1) = (omega.cos(), omega.sin());
+        let (cos_2, sin_2) = ((2. * omega).cos(), (2. * omega).sin());
+
+        // |H(e^jw)|² as the ratio of the squared magnitudes of both polynomials
+        let squared_magnitude = |c0: f32, c1: f32, c2: f32| {
+            let (c0, c1, c2) = (c0 as f64, c1 as f64, c2 as f64);
+            let real = c0 + c1 * cos_1 + c2 * cos_2;
+            let imaginary = c1 * sin_1 + c2 * sin_2;
+            real * real + imaginary * imaginary
+        };
+
+        let numerator = squared_magnitude(self.b0, self.b1, self.b2);
+        let denominator = squared_magnitude(1., self.a1, self.a2);
+
+        ((10. * (numerator / denominator).log10()) as f32).max(FLOOR)
+    }
+}
+
+/// The combined gain (in dB) of several biquad stages in series.
+fn combined_gain_at(stages: &[BiquadCoefficients], frequency: f32, sample_rate: f32) -> f32 {
+    stages
+        .iter()
+        .map(|stage| stage.gain_at(frequency, sample_rate))
+        .sum()
+}
+
+/// Draws the magnitude response of a filter made of biquad stages.
+///
+/// The stages are applied in series, so their gains add up. The response is
+/// evaluated at each pixel along the frequency axis, using the given scaling and
+/// range, so it lines up with a [`Grid`](super::Grid),
+/// [`UnitRuler`](super::UnitRuler) or [`SpectrumAnalyzer`](super::SpectrumAnalyzer)
+/// with the same axis. The `gain_range` is given in dB.
+///
+/// The curve is drawn using the `color`, and the area between the curve and 0 dB
+/// is filled using the `background_color`.
+///
+/// Both the stages and the sample rate can be bound to lenses, so the curve
+/// follows along as your plug-in's parameters change. The frequency axis can be
+/// bound as well, via [`RangeModifiers`].
+///
+/// # Example
+///
+/// ```
+/// #[derive(Lens, Clone)]
+/// pub(crate) struct Data {
+///     pub(crate) eq_stages: Vec<BiquadCoefficients>,
+///     pub(crate) sample_rate: f32,
+/// }
+///
+/// // Inside your editor
+/// FilterResponse::new(
+///     cx,
+///     Data::eq_stages,
+///     Data::sample_rate,
+///     ValueScaling::Frequency,
+///     (20., 20_000.),
+///     (-24., 24.),
+/// )
+/// .color(Color::rgb(255, 160, 64))
+/// .background_color(Color::rgba(255, 160, 64, 40));
+/// ```
+pub struct FilterResponse {
+    stages: Vec<BiquadCoefficients>,
+    sample_rate: f32,
+    frequency_scaling: ValueScaling,
+    frequency_range: (f32, f32),
+    gain_range: (f32, f32),
+}
+
+enum FilterResponseEvents {
+    UpdateStages(Vec<BiquadCoefficients>),
+    UpdateSampleRate(f32),
+    UpdateFrequencyRange((f32, f32)),
+    UpdateFrequencyScaling(ValueScaling),
+}
+
+impl FilterResponse {
+    /// Creates a new [`FilterResponse`].
+    pub fn new(
+        cx: &mut Context,
+        stages: impl Res<Vec<BiquadCoefficients>>,
+        sample_rate: impl Res<f32>,
+        frequency_scaling: ValueScaling,
+        frequency_range: (f32, f32),
+        gain_range: (f32, f32),
+    ) -> Handle<Self> {
+        let mut handle = Self {
+            stages: stages.get_val(cx),
+            sample_rate: sample_rate.get_val(cx),
+            frequency_scaling,
+            frequency_range,
+            gain_range,
+        }
+        .build(cx, |_| {})
+        // Let views underneath, such as an analyzer, receive the mouse
+        .hoverable(false);
+
+        let e = handle.entity();
+        stages.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, FilterResponseEvents::UpdateStages(s));
+        });
+        sample_rate.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, FilterResponseEvents::UpdateSampleRate(s));
+        });
+
+        handle
+    }
+}
+
+impl View for FilterResponse {
+    fn element(&self) -> Option<&'static str> {
+        Some("filter-response")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            FilterResponseEvents::UpdateStages(stages) => self.stages = stages.clone(),
+            FilterResponseEvents::UpdateSampleRate(sample_rate) => self.sample_rate = *sample_rate,
+            FilterResponseEvents::UpdateFrequencyRange(range) => self.frequency_range = *range,
+            FilterResponseEvents::UpdateFrequencyScaling(scaling) => {
+                self.frequency_scaling = *scaling
+            }
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        if w <= 0. || !self.sample_rate.is_finite() || self.sample_rate <= 0. {
+            return;
+        }
+
+        let gain_to_y = |gain: f32| {
+            let normalized = ValueScaling::Linear.value_to_normalized(
+                gain,
+                self.gain_range.0,
+                self.gain_range.1,
+            );
+            y + h * (1. - normalized)
+        };
+
+        // The response is only defined up to the Nyquist frequency
+        let nyquist = self.sample_rate / 2.;
+
+        let mut line = vg::Path::new();
+        let mut last_x = x;
+        for i in 0..=w.ceil() as usize {
+            let frequency = self.frequency_scaling.normalized_to_value(
+                i as f32 / w,
+                self.frequency_range.0,
+                self.frequency_range.1,
+            );
+            if frequency >= nyquist {
+                break;
+            }
+
+            let point_y = gain_to_y(combined_gain_at(&self.stages, frequency, self.sample_rate));
+
+            if i == 0 {
+                line.move_to(x, point_y);
+            } else {
+                line.line_to(x + i as f32, point_y);
+            }
+            last_x = x + i as f32;
+        }
+
+        let zero_y = gain_to_y(0.);
+        let mut fill = line.clone();
+        fill.line_to(last_x, zero_y);
+        fill.line_to(x, zero_y);
+        fill.close();
+
+        canvas.save();
+        canvas.intersect_scissor(x, y, w, h);
+
+        canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+        canvas.stroke_path(
+            &line,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor() * 1.5),
+        );
+
+        canvas.restore();
+    }
+}
+
+impl<'a> RangeModifiers for Handle<'a, FilterResponse> {
+    /// Sets the frequency range (in Hz) the response is drawn over.
+    fn range(mut self, range: impl Res<(f32, f32)>) -> Self {
+        let e = self.entity();
+
+        range.set_or_bind(self.context(), e, move |cx, r| {
+            (*cx).emit_to(e, FilterResponseEvents::UpdateFrequencyRange(r));
+        });
+
+        self
+    }
+    /// Sets the scaling of the frequency axis.
+    fn scaling(mut self, scaling: impl Res<ValueScaling>) -> Self {
+        let e = self.entity();
+
+        scaling.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, FilterResponseEvents::UpdateFrequencyScaling(s));
+        });
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{combined_gain_at, BiquadCoefficients, FLOOR};
+
+    /// A low pass from the Audio EQ Cookbook.
+    fn low_pass(cutoff: f32, q: f32, sample_rate: f32) -> BiquadCoefficients {
+        let omega = std::f32::consts::TAU * cutoff / sample_rate;
+        let alpha = omega.sin() / (2. * q);
+        let cos = omega.cos();
+
+        BiquadCoefficients::new(
+            [(1. - cos) / 2., 1. - cos, (1. - cos) / 2.],
+            [1. + alpha, -2. * cos, 1. - alpha],
+        )
+    }
+
+    #[test]
+    fn stages_add_up() {
+        let stage = low_pass(1_000., std::f32::consts::FRAC_1_SQRT_2, 48_000.);
+
+        assert!(stage.gain_at(10., 48_000.).abs() < 0.01);
+        assert!((stage.gain_at(1_000., 48_000.) + 3.01).abs() < 0.05);
+        assert!(stage.gain_at(10_000., 48_000.) < -36.);
+
+        let cascade = [stage, stage, BiquadCoefficients::identity()];
+        assert!((combined_gain_at(&cascade, 1_000., 48_000.) + 6.02).abs() < 0.1);
+    }
+
+    #[test]
+    fn zeros_are_clamped_to_the_floor() {
+        // A zero right at DC
+        let stage = BiquadCoefficients::new([1., -1., 0.], [1., 0., 0.]);
+
+        assert_eq!(stage.gain_at(0., 48_000.), FLOOR);
+        assert!(stage.gain_at(12_000., 48_000.) > FLOOR);
+    }
+}
@@ -1,10 +1,19 @@
-use super::{FillFrom, FillModifiers, RangeModifiers};
+use super::{
+    AxisMorph, AxisMorphModifiers, FillFrom, FillModifiers, GradientFillModifiers, OutOfRange,
+    OutOfRangeModifiers, RangeModifiers, GAIN_REDUCTION_DECAY,
+};
 use crate::accumulators::*;
 use crate::bus::Bus;
+use crate::clock::GraphClock;
+use crate::loudness::{LoudnessAccumulator, LoudnessWindow};
+use crate::markers::{MarkerOutput, MarkerStyle};
 use crate::prelude::DurationModifiers;
-use crate::utils::{RingBuffer, ValueScaling};
+use crate::utils::{lock_or_recover, RingBuffer, ValueScaling};
+use nih_plug::util::db_to_gain;
 use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 /// A graph visualizer plotting a value over time.
 ///
@@ -13,43 +22,147 @@ use std::sync::{Arc, Mutex};
 ///    - [`peak`](Self::peak) - Its peak amplitude
 ///    - [`minima`](Self::minima) - Its minimal amplitude
 ///    - [`rms`](Self::rms) - Its root mean squared level
+///    - [`loudness`](Self::loudness) - Its loudness in LUFS, optionally against a
+///      target via [`lufs`](Self::lufs)
+///    - [`loudness_history`](Self::loudness_history) - Its short-term loudness over
+///      very long durations
+///    - [`crest_factor`](Self::crest_factor) - The ratio of its peak to its RMS level
 ///
 /// It's also possible to define your own [`Accumulator`] in order to display some
 /// other information about the incoming signal.
+///
+/// Timestamped markers can be drawn over the graph's history using
+/// [`with_markers`](GraphModifiers::with_markers). Multiple graphs can be aligned
+/// in time using a [`GraphClock`].
+///
+/// Wherever the value lies outside of the graph's range, a thin strip is drawn
+/// along the corresponding edge using the `color`. This can be disabled via
+/// [`out_of_range_indicators`](OutOfRangeModifiers::out_of_range_indicators).
+///
+/// When displaying many identical graphs at once, they can share a single
+/// [`GraphSource`] using [`from_source`](Self::from_source), so that incoming
+/// samples only get accumulated once.
 pub struct Graph<B: Bus<f32> + 'static, A: Accumulator + 'static> {
-    buffer: Arc<Mutex<RingBuffer<f32>>>,
+    bus: Arc<B>,
+    source: GraphSource<B, A>,
     range: (f32, f32),
     scaling: ValueScaling,
     fill_from: FillFrom,
+    /// The bottom and top colors of the fill, if it is a gradient
+    fill_gradient: Option<(Color, Color)>,
+    duration: Option<f32>,
+    markers: Option<Arc<Mutex<MarkerOutput>>>,
+    clock: Option<GraphClock>,
+    /// How many pixels back in time the graph's history ends
+    offset: usize,
+    /// What the graph displays, e.g. "RMS, 250 ms window"
+    description: Option<String>,
+    legend: bool,
+    /// The threshold above which the graph is tinted, and the color to tint it with
+    clip_highlight: Option<(f32, Color)>,
+    /// The level a horizontal reference line is drawn at, e.g. a loudness target
+    target_line: Option<f32>,
+    range_indicators: bool,
+    /// The transition to the current range and scaling, if the axis morphs
+    morph: Option<AxisMorph>,
+}
+
+/// The text of a graph's legend.
+#[derive(Lens)]
+struct GraphLegend {
+    text: String,
+}
+
+enum GraphLegendEvents {
+    Update(String),
+}
+
+impl Model for GraphLegend {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            GraphLegendEvents::Update(text) => self.text = text.clone(),
+        });
+    }
+}
+
+/// The accumulated history behind one or more [`Graph`]s.
+///
+/// A source registers a single dispatcher to its bus and accumulates the incoming
+/// samples into a single buffer. Any number of graphs can then be created from it
+/// via [`Graph::from_source`]. For dashboards showing lots of graphs of the same
+/// signal, this means the cost of processing the signal doesn't grow with the
+/// number of graphs. Each graph can show the history with a different offset, see
+/// [`with_offset`](GraphModifiers::with_offset).
+///
+/// Graphs created from the same source should have the same width.
+///
+/// # Example
+///
+/// ```
+/// let source = GraphSource::new(bus.clone(), PeakAccumulator::new(10.0, 50.0));
+///
+/// for i in 0..16 {
+///     Graph::from_source(cx, &source, (-32.0, 8.0), ValueScaling::Decibels)
+///         .duration(10.0)
+///         .with_offset(i * 10);
+/// }
+/// ```
+pub struct GraphSource<B: Bus<f32> + 'static, A: Accumulator + 'static> {
+    bus: Arc<B>,
+    buffer: Arc<Mutex<RingBuffer<f32>>>,
     accumulator: Arc<Mutex<A>>,
     dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Sync + Send + 'static>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Sync + Send>,
+    /// The number of samples received since the source was created
+    received: Arc<AtomicU64>,
+    /// The width of the graphs the accumulator is currently sized for
+    width: Arc<AtomicUsize>,
+    /// The value the history starts out at
+    fill: f32,
 }
 
-enum GraphEvents {
-    UpdateRange((f32, f32)),
-    UpdateScaling(ValueScaling),
-    UpdateDuration(f32),
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Clone for GraphSource<B, A> {
+    fn clone(&self) -> Self {
+        Self {
+            bus: self.bus.clone(),
+            buffer: self.buffer.clone(),
+            accumulator: self.accumulator.clone(),
+            dispatcher_handle: self.dispatcher_handle.clone(),
+            sample_rate_handle: self.sample_rate_handle.clone(),
+            received: self.received.clone(),
+            width: self.width.clone(),
+            fill: self.fill,
+        }
+    }
 }
 
-impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Graph<B, A> {
-    /// Creates a new [`Graph`] which uses the provided [`Accumulator`].
-    pub fn with_accumulator(
-        cx: &mut Context,
-        bus: Arc<B>,
-        mut accumulator: A,
-        range: impl Res<(f32, f32)> + Clone,
-        scaling: impl Res<ValueScaling> + Clone,
-    ) -> Handle<Self> {
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> GraphSource<B, A> {
+    /// Creates a new [`GraphSource`] which uses the provided [`Accumulator`].
+    pub fn new(bus: Arc<B>, accumulator: A) -> Self {
+        Self::with_fill(bus, accumulator, 0.0)
+    }
+
+    /// Creates a new [`GraphSource`] whose history starts out at the given value,
+    /// rather than at 0.
+    ///
+    /// The value also fills the history wherever it is extended, e.g. when the
+    /// graphs are resized. For a gain, this should be 1 - unity gain - so that the
+    /// history doesn't start out at -inf dB.
+    pub fn with_fill(bus: Arc<B>, mut accumulator: A, fill: f32) -> Self {
         accumulator.set_sample_rate(bus.sample_rate());
 
-        let buffer: Arc<Mutex<RingBuffer<f32>>> = Default::default();
+        let buffer = Arc::new(Mutex::new(RingBuffer::filled(1, fill)));
         let buffer_c = buffer.clone();
 
         let accumulator = Arc::new(Mutex::new(accumulator));
         let accumulator_c = accumulator.clone();
 
+        let received = Arc::new(AtomicU64::new(0));
+        let received_c = received.clone();
+
         let dispatcher_handle = bus.register_dispatcher(move |samples| {
             if let (Ok(mut buf), Ok(mut acc)) = (buffer_c.lock(), accumulator_c.lock()) {
+                received_c.fetch_add(samples.len() as u64, Ordering::Relaxed);
                 for sample in samples {
                     if let Some(sample) = acc.accumulate(*sample) {
                         buf.enqueue(sample);
@@ -58,29 +171,172 @@ impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Graph<B, A> {
             }
         });
 
+        let accumulator_c = accumulator.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&accumulator_c).set_sample_rate(sample_rate);
+        });
+
         Self {
+            bus,
             buffer,
+            accumulator,
+            dispatcher_handle,
+            sample_rate_handle,
+            received,
+            width: Default::default(),
+            fill,
+        }
+    }
+
+    /// The most recent `width` values, for views drawing the whole history at once.
+    pub(super) fn history(&self, width: usize) -> Vec<f32> {
+        let ring_buf = &mut lock_or_recover(&self.buffer);
+
+        if self.width.swap(width, Ordering::Relaxed) != width || ring_buf.len() < width {
+            self.resize(ring_buf, width, width);
+        }
+
+        let start = ring_buf.len() - width;
+        (start..ring_buf.len()).map(|i| ring_buf[i]).collect()
+    }
+
+    pub(super) fn set_duration(&self, duration: f32) {
+        let ring_buf = &mut lock_or_recover(&self.buffer);
+        let mut acc = lock_or_recover(&self.accumulator);
+
+        acc.set_duration(duration);
+        self.rebuild(ring_buf, &acc);
+    }
+
+    /// Resizes the buffer to hold `len` values, for graphs `width` pixels wide.
+    fn resize(&self, ring_buf: &mut RingBuffer<f32>, len: usize, width: usize) {
+        ring_buf.resize(len);
+
+        let mut acc = lock_or_recover(&self.accumulator);
+        acc.set_size(width);
+        self.rebuild(ring_buf, &acc);
+    }
+
+    /// Replaces the buffer's values with the accumulator's own history, if it keeps
+    /// one, so that resizing the graphs or changing their duration doesn't throw
+    /// away what they have shown so far.
+    fn rebuild(&self, ring_buf: &mut RingBuffer<f32>, acc: &A) {
+        if let Some(history) = acc.history(ring_buf.len()) {
+            for value in history {
+                ring_buf.enqueue(value.unwrap_or(self.fill));
+            }
+        }
+    }
+}
+
+enum GraphEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+    UpdateDuration(f32),
+}
+
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Graph<B, A> {
+    /// Creates a new [`Graph`] which uses the provided [`Accumulator`].
+    pub fn with_accumulator(
+        cx: &mut Context,
+        bus: Arc<B>,
+        accumulator: A,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        Self::from_source(cx, &GraphSource::new(bus, accumulator), range, scaling)
+    }
+
+    /// Creates a new [`Graph`] which displays the history of a shared
+    /// [`GraphSource`].
+    ///
+    /// Since the source's accumulator is shared, its duration is set by whichever
+    /// graph was last given a duration.
+    pub fn from_source(
+        cx: &mut Context,
+        source: &GraphSource<B, A>,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        Self {
+            bus: source.bus.clone(),
+            source: source.clone(),
             range: range.get_val(cx),
             scaling: scaling.get_val(cx),
             fill_from: FillFrom::Bottom,
-            accumulator,
-            dispatcher_handle,
+            fill_gradient: None,
+            duration: None,
+            markers: None,
+            clock: None,
+            offset: 0,
+            description: None,
+            legend: false,
+            clip_highlight: None,
+            target_line: None,
+            range_indicators: true,
+            morph: None,
         }
         .build(cx, |_| {})
         .range(range)
         .scaling(scaling)
     }
 }
+impl<B: Bus<f32>, A: Accumulator + 'static> Graph<B, A> {
+    /// Normalizes a level given in the units of the range, like the clip threshold
+    /// or the target, the same way as the graph's values - following the axis
+    /// while it morphs.
+    fn level_to_normalized(&self, level: f32) -> f32 {
+        // The range of a decibel axis is given in dB, while its values are gains
+        let value = match self.scaling {
+            ValueScaling::Decibels => db_to_gain(level),
+            _ => level,
+        };
+
+        match &self.morph {
+            Some(morph) => morph.value_to_normalized(value, self.range, self.scaling),
+            None => self
+                .scaling
+                .value_to_normalized(value, self.range.0, self.range.1),
+        }
+    }
+
+    /// The text shown by the legend, e.g. "RMS, 250 ms window, 10 s".
+    fn legend_text(&self) -> String {
+        let duration = self.duration.map(|duration| format!("{duration} s"));
+
+        [self.description.clone(), duration]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
 impl<B: Bus<f32>, A: Accumulator + 'static> View for Graph<B, A> {
     fn element(&self) -> Option<&'static str> {
         Some("graph")
     }
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|e, _| match e {
-            GraphEvents::UpdateRange(v) => self.range = *v,
-            GraphEvents::UpdateScaling(s) => self.scaling = *s,
+            GraphEvents::UpdateRange(v) => {
+                if let Some(morph) = self.morph.as_mut().filter(|_| self.range != *v) {
+                    morph.begin(self.range, self.scaling);
+                }
+                self.range = *v;
+            }
+            GraphEvents::UpdateScaling(s) => {
+                if let Some(morph) = self.morph.as_mut().filter(|_| self.scaling != *s) {
+                    morph.begin(self.range, self.scaling);
+                }
+                self.scaling = *s;
+            }
             GraphEvents::UpdateDuration(duration) => {
-                self.accumulator.lock().unwrap().set_duration(*duration)
+                self.duration = Some(*duration);
+                self.source.set_duration(*duration);
+
+                if self.legend {
+                    cx.emit(GraphLegendEvents::Update(self.legend_text()));
+                }
             }
         });
     }
@@ -96,40 +352,129 @@ impl<B: Bus<f32>, A: Accumulator + 'static> View for Graph<B, A> {
 
         // Update buffer
 
-        let ring_buf = &mut (self.buffer.lock().unwrap());
+        let ring_buf = &mut lock_or_recover(&self.source.buffer);
+
+        let width_ceil = w.ceil() as usize;
 
+        // Graphs sharing a source may end their history at different offsets, so the
+        // buffer needs to reach back far enough for the one with the largest offset
         {
-            let mut acc = self.accumulator.lock().unwrap();
+            let len = width_ceil + self.offset;
+            let width_changed = self.source.width.swap(width_ceil, Ordering::Relaxed) != width_ceil;
 
-            let width_ceil = w.ceil() as usize;
-            if ring_buf.len() != width_ceil {
-                ring_buf.resize(width_ceil);
-                acc.set_size(width_ceil);
+            if width_changed || ring_buf.len() < len {
+                self.source.resize(ring_buf, len, width_ceil);
             }
         }
 
-        if ring_buf.len() == 0 {
+        if width_ceil == 0 {
             return;
         }
 
-        let mut peak = self
-            .scaling
-            .value_to_normalized(ring_buf[0], self.range.0, self.range.1);
+        let start = ring_buf.len() - width_ceil - self.offset;
+
+        // If this graph is ahead of the other graphs on its clock, the samples it
+        // received in the meantime are shifted out of view
+        let offset = match (&self.clock, self.duration) {
+            (Some(clock), Some(duration)) => {
+                let lead = self
+                    .source
+                    .received
+                    .load(Ordering::Relaxed)
+                    .saturating_sub(clock.position());
+                let samples_per_pixel = self.bus.sample_rate() * duration / w;
+
+                if samples_per_pixel > 0.0 {
+                    (lead as f32 / samples_per_pixel).min(w)
+                } else {
+                    0.0
+                }
+            }
+            _ => 0.0,
+        };
+
+        canvas.save();
+        canvas.intersect_scissor(x, y, w, h);
+
+        let values = (start..start + width_ceil)
+            .map(|i| ring_buf[i])
+            .collect::<Vec<f32>>();
+        let mut normalized = vec![0.; width_ceil];
+        match &self.morph {
+            Some(morph) => {
+                morph.value_to_normalized_slice(&values, &mut normalized, self.range, self.scaling)
+            }
+            None => self.scaling.value_to_normalized_slice(
+                &values,
+                &mut normalized,
+                self.range.0,
+                self.range.1,
+            ),
+        }
 
         // Draw
 
         let mut stroke = vg::Path::new();
 
-        stroke.move_to(x, y + h * (1. - peak));
+        stroke.move_to(x - offset, y + h * (1. - normalized[0]));
 
-        for i in 1..ring_buf.len() {
-            // Normalize peak value
-            peak = self
-                .scaling
-                .value_to_normalized(ring_buf[i], self.range.0, self.range.1);
+        // Columns in which the value exceeds the clip threshold are merged into bands
+        let clip_threshold = self
+            .clip_highlight
+            .map(|(threshold, _)| self.level_to_normalized(threshold));
+        let mut clip_bands = vg::Path::new();
+        let mut clip_start = None;
+
+        // Columns in which the value lies outside of the range are marked along the
+        // edge it exceeds
+        let indicator_height = 2. * cx.scale_factor();
+        let mut indicators = vg::Path::new();
 
+        for (i, &peak) in normalized.iter().enumerate() {
             // Draw peak as a new point
-            stroke.line_to(x + i as f32, y + h * (1. - peak));
+            if i > 0 {
+                stroke.line_to(x + i as f32 - offset, y + h * (1. - peak));
+            }
+
+            if self.range_indicators {
+                let edge = match OutOfRange::check(values[i], self.scaling, self.range) {
+                    Some(OutOfRange::Above) => Some(y),
+                    Some(OutOfRange::Below) => Some(y + h - indicator_height),
+                    None => None,
+                };
+                if let Some(edge_y) = edge {
+                    indicators.rect(x + i as f32 - offset, edge_y, 1., indicator_height);
+                }
+            }
+
+            if let Some(threshold) = clip_threshold {
+                match (peak > threshold, clip_start) {
+                    (true, None) => clip_start = Some(i),
+                    (false, Some(band_start)) => {
+                        clip_bands.rect(
+                            x + band_start as f32 - offset,
+                            y,
+                            (i - band_start) as f32,
+                            h,
+                        );
+                        clip_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if let Some(band_start) = clip_start {
+            clip_bands.rect(
+                x + band_start as f32 - offset,
+                y,
+                (width_ceil - band_start) as f32,
+                h,
+            );
+        }
+
+        if let Some((_, color)) = self.clip_highlight {
+            canvas.fill_path(&clip_bands, &vg::Paint::color(color.into()));
         }
 
         let mut fill = stroke.clone();
@@ -141,16 +486,182 @@ impl<B: Bus<f32>, A: Accumulator + 'static> View for Graph<B, A> {
             }
         };
 
-        fill.line_to(x + w, y + h * fill_from_n);
-        fill.line_to(x, y + h * fill_from_n);
+        fill.line_to(x + w - offset, y + h * fill_from_n);
+        fill.line_to(x - offset, y + h * fill_from_n);
         fill.close();
 
-        canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+        let fill_paint = match self.fill_gradient {
+            Some((bottom, top)) => {
+                vg::Paint::linear_gradient(x, y + h, x, y, bottom.into(), top.into())
+            }
+            None => vg::Paint::color(cx.background_color().into()),
+        };
+        canvas.fill_path(&fill, &fill_paint);
 
         canvas.stroke_path(
             &stroke,
             &vg::Paint::color(cx.font_color().into()).with_line_width(line_width),
         );
+
+        canvas.fill_path(&indicators, &vg::Paint::color(cx.font_color().into()));
+
+        if let Some(target) = self.target_line {
+            let target_y = y + h * (1. - self.level_to_normalized(target));
+
+            // Dashes, so the line stands apart from the graph itself
+            let dash = 4. * cx.scale_factor();
+            let mut line = vg::Path::new();
+            let mut dash_x = x;
+            while dash_x < x + w {
+                line.move_to(dash_x, target_y);
+                line.line_to((dash_x + dash).min(x + w), target_y);
+                dash_x += dash * 2.;
+            }
+
+            let mut color: vg::Color = cx.font_color().into();
+            color.a *= 0.5;
+            canvas.stroke_path(&line, &vg::Paint::color(color).with_line_width(line_width));
+        }
+
+        canvas.restore();
+
+        // Markers can only be placed on the time axis if the duration is known
+        if let (Some(markers), Some(duration)) = (&self.markers, self.duration) {
+            let now = Instant::now();
+            let flag_size = 6.0 * cx.scale_factor();
+
+            let mut lines = vg::Path::new();
+            let mut flags = vg::Path::new();
+
+            for marker in lock_or_recover(markers).markers() {
+                let age = now.saturating_duration_since(marker.time).as_secs_f32();
+                if age > duration {
+                    continue;
+                }
+
+                let marker_x = x + w * (1. - age / duration);
+
+                lines.move_to(marker_x, y);
+                lines.line_to(marker_x, y + h);
+
+                if marker.style == MarkerStyle::Flag {
+                    flags.move_to(marker_x, y);
+                    flags.line_to(marker_x + flag_size, y + flag_size / 2.);
+                    flags.line_to(marker_x, y + flag_size);
+                    flags.close();
+                }
+            }
+
+            canvas.stroke_path(
+                &lines,
+                &vg::Paint::color(cx.font_color().into()).with_line_width(line_width),
+            );
+            canvas.fill_path(&flags, &vg::Paint::color(cx.font_color().into()));
+        }
+    }
+}
+
+pub trait GraphModifiers {
+    fn with_markers<L>(self, markers: L) -> Self
+    where
+        L: Lens<Target = Arc<Mutex<MarkerOutput>>>;
+    fn with_clock(self, clock: GraphClock) -> Self;
+    fn with_offset(self, offset: usize) -> Self;
+    fn with_legend(self) -> Self;
+    fn with_clip_highlight(self, threshold: f32, color: Color) -> Self;
+    fn with_target_line(self, target: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> GraphModifiers
+    for Handle<'a, Graph<B, A>>
+{
+    /// Draws the markers received by a [`MarkerOutput`] over the graph's history.
+    ///
+    /// Each marker is drawn as a vertical line at the point in time at which it
+    /// was placed, using the graph's `color`. Markers are only shown if the
+    /// graph has a duration. All constructors except
+    /// [`with_accumulator`](Graph::with_accumulator) set one, otherwise use
+    /// [`duration`](DurationModifiers::duration).
+    fn with_markers<L>(mut self, markers: L) -> Self
+    where
+        L: Lens<Target = Arc<Mutex<MarkerOutput>>>,
+    {
+        let markers = markers.get(self.context());
+        self.modify(|graph| graph.markers = Some(markers))
+    }
+    /// Aligns the graph's time axis with all other views on the same [`GraphClock`].
+    ///
+    /// Like markers, this requires the graph to have a duration.
+    fn with_clock(self, clock: GraphClock) -> Self {
+        self.modify(|graph| {
+            clock.register(&graph.source.received);
+            graph.clock = Some(clock);
+        })
+    }
+    /// Ends the graph's history the given number of pixels back in time.
+    ///
+    /// This is mostly useful for graphs created from a shared [`GraphSource`], so
+    /// that each of them shows a different part of the same history.
+    fn with_offset(self, offset: usize) -> Self {
+        self.modify(|graph| graph.offset = offset)
+    }
+    /// Shows a small legend in the graph's top left corner, describing what it
+    /// displays.
+    ///
+    /// The legend lists the graph's duration, and for graphs created via
+    /// [`peak`](Graph::peak), [`minima`](Graph::minima) or [`rms`](Graph::rms), their
+    /// decay or window length. It follows changes to the duration, so screenshots
+    /// of the graph always document how it was set up. The legend is drawn in the
+    /// graph's `color`, and its font size can be set via `font_size`.
+    fn with_legend(mut self) -> Self {
+        let e = self.entity();
+
+        let mut text = String::new();
+        self = self.modify(|graph| {
+            graph.legend = true;
+            text = graph.legend_text();
+        });
+
+        self.context().with_current(e, |cx| {
+            GraphLegend { text }.build(cx);
+
+            Label::new(cx, GraphLegend::text)
+                .position_type(PositionType::SelfDirected)
+                .left(Pixels(4.0))
+                .top(Pixels(2.0))
+                .hoverable(false);
+        });
+
+        self
+    }
+    /// Tints the regions of the graph where its value exceeded a threshold.
+    ///
+    /// The `threshold` is given in the same unit as the graph's range - e.g. `0.0`
+    /// for 0 dBFS on a graph using [`ValueScaling::Decibels`]. Each region is drawn
+    /// as a band spanning the graph's full height, behind the graph itself, so a
+    /// translucent `color` works best.
+    fn with_clip_highlight(self, threshold: f32, color: Color) -> Self {
+        self.modify(|graph| graph.clip_highlight = Some((threshold, color)))
+    }
+    /// Draws a dashed horizontal line at a target level, e.g. -14 LUFS on a
+    /// loudness graph.
+    ///
+    /// The `target` is given in the same unit as the graph's range. The line is
+    /// drawn in the graph's `color`, at half its opacity.
+    fn with_target_line(self, target: f32) -> Self {
+        self.modify(|graph| graph.target_line = Some(target))
+    }
+}
+
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> OutOfRangeModifiers
+    for Handle<'a, Graph<B, A>>
+{
+    /// Enables or disables the strips drawn along the graph's edges wherever its
+    /// value lies outside of its range.
+    ///
+    /// The indicators are enabled by default.
+    fn out_of_range_indicators(self, enabled: bool) -> Self {
+        self.modify(|graph| graph.range_indicators = enabled)
     }
 }
 
@@ -169,6 +680,14 @@ impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> FillModifiers
     }
 }
 
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> GradientFillModifiers
+    for Handle<'a, Graph<B, A>>
+{
+    fn fill_gradient(self, bottom: Color, top: Color) -> Self {
+        self.modify(|graph| graph.fill_gradient = Some((bottom, top)))
+    }
+}
+
 impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> RangeModifiers
     for Handle<'a, Graph<B, A>>
 {
@@ -192,6 +711,19 @@ impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> RangeModifiers
     }
 }
 
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> AxisMorphModifiers
+    for Handle<'a, Graph<B, A>>
+{
+    /// Lets the graph glide to a new range or scaling over 150 ms, instead of
+    /// snapping to it.
+    ///
+    /// Let the [`Grid`](super::Grid) behind the graph morph as well, so that both
+    /// keep lining up while they glide.
+    fn morph_axis(self) -> Self {
+        self.modify(|graph| graph.morph = Some(AxisMorph::new(graph.range, graph.scaling)))
+    }
+}
+
 impl<B: Bus<f32> + 'static> Graph<B, PeakAccumulator> {
     /// Creates a peak graph.
     ///
@@ -226,6 +758,7 @@ impl<B: Bus<f32> + 'static> Graph<B, PeakAccumulator> {
             range,
             scaling,
         )
+        .modify(|graph| graph.description = Some(format!("Peak, {decay} ms decay")))
         .duration(duration)
     }
 }
@@ -265,8 +798,53 @@ impl<B: Bus<f32> + 'static> Graph<B, MinimumAccumulator> {
             range,
             scaling,
         )
+        .modify(|graph| graph.description = Some(format!("Minima, {decay} ms decay")))
         .duration(duration)
     }
+
+    /// Creates a gain reduction graph.
+    ///
+    /// The bus should carry the gain that your processor applies, as a linear
+    /// factor - e.g. 0.5 for 6 dB of gain reduction. A [`ValueBus`] lets you send
+    /// this gain once per buffer. The graph uses a decibel scale, is filled from the
+    /// top, and smoothly releases after each reduction.
+    ///
+    /// ## Example
+    ///
+    /// 10-second gain reduction graph showing up to 24 dB of reduction.
+    ///
+    /// ```
+    /// Graph::gain_reduction(cx, gain_reduction_bus.clone(), 10.0, (-24.0, 0.0))
+    ///     .color(Color::rgba(255, 92, 92, 160))
+    ///     .background_color(Color::rgba(255, 92, 92, 60));
+    /// ```
+    ///
+    /// [`ValueBus`]: crate::bus::ValueBus
+    pub fn gain_reduction(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        let source = gain_reduction_source(bus, duration.get_val(cx));
+
+        Self::from_source(cx, &source, range, ValueScaling::Decibels)
+            .fill_from_max()
+            .modify(|graph| graph.description = Some("Gain reduction".to_owned()))
+            .duration(duration)
+    }
+}
+
+/// The source of a gain reduction graph, whose history starts out at unity gain.
+fn gain_reduction_source<B: Bus<f32> + 'static>(
+    bus: Arc<B>,
+    duration: f32,
+) -> GraphSource<B, MinimumAccumulator> {
+    GraphSource::with_fill(
+        bus,
+        MinimumAccumulator::new(duration, GAIN_REDUCTION_DECAY),
+        1.0,
+    )
 }
 impl<B: Bus<f32> + 'static> Graph<B, RMSAccumulator> {
     /// Creates a graph showing the root mean squared level over time.
@@ -301,6 +879,288 @@ impl<B: Bus<f32> + 'static> Graph<B, RMSAccumulator> {
             range,
             scaling,
         )
+        .modify(|graph| graph.description = Some(format!("RMS, {window_size} ms window")))
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, BallisticsAccumulator> {
+    /// Creates a graph showing the level over time, as seen through the given
+    /// [`Ballistics`].
+    ///
+    /// ## Example
+    ///
+    /// 10-second graph with VU ballistics.
+    ///
+    /// ```
+    /// Graph::with_ballistics(
+    ///     cx,
+    ///     bus.clone(),
+    ///     10.0,
+    ///     Ballistics::VU,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    /// )
+    /// .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn with_ballistics(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        ballistics: Ballistics,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            BallisticsAccumulator::new(duration.get_val(cx), ballistics),
+            range,
+            scaling,
+        )
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, SlewAccumulator> {
+    /// Creates a graph showing the rate at which the level changes over time, in
+    /// dB/s.
+    ///
+    /// The graph is filled from 0 dB/s. This is useful for visualizing the pumping
+    /// of a compressor, or the behavior of an auto-gain.
+    ///
+    /// ## Example
+    ///
+    /// 10-second slew graph smoothed over 50 ms, showing up to 60 dB/s in either
+    /// direction.
+    ///
+    /// ```
+    /// Graph::slew(cx, bus.clone(), 10.0, 50.0, (-60.0, 60.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn slew(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window_size: f32,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            SlewAccumulator::new(duration.get_val(cx), window_size),
+            range,
+            ValueScaling::Linear,
+        )
+        .fill_from_value(0.0)
+        .modify(|graph| graph.description = Some(format!("Slew, {window_size} ms window")))
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, CrestFactorAccumulator> {
+    /// Creates a graph showing the crest factor over time, in dB.
+    ///
+    /// The crest factor is the ratio of the peak to the RMS level, both measured
+    /// over the given window (in ms). It shows how squashed a signal is - the more
+    /// it gets compressed or limited, the lower its crest factor.
+    ///
+    /// ## Example
+    ///
+    /// 10-second crest factor graph over a 300 ms window, showing up to 24 dB.
+    ///
+    /// ```
+    /// Graph::crest_factor(cx, bus.clone(), 10.0, 300.0, (0.0, 24.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn crest_factor(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window_size: f32,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            CrestFactorAccumulator::new(duration.get_val(cx), window_size),
+            range,
+            ValueScaling::Linear,
+        )
+        .modify(|graph| graph.description = Some(format!("Crest factor, {window_size} ms window")))
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, SpectralCentroidAccumulator> {
+    /// Creates a graph showing the spectral centroid over time, in Hz.
+    ///
+    /// The centroid is the magnitude-weighted mean frequency of the signal,
+    /// measured over the given window (in ms). It rises as the signal gets
+    /// brighter, and falls as it gets darker, which gives feedback on the tonal
+    /// balance that level meters can't. The range is given in Hz, and the graph
+    /// uses a logarithmic frequency scale.
+    ///
+    /// ## Example
+    ///
+    /// 10-second graph of the spectral centroid over a 50 ms window, from 100 Hz to
+    /// 10 kHz.
+    ///
+    /// ```
+    /// Graph::spectral_centroid(cx, bus.clone(), 10.0, 50.0, (100.0, 10_000.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn spectral_centroid(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window_size: f32,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            SpectralCentroidAccumulator::new(duration.get_val(cx), window_size),
+            range,
+            ValueScaling::Frequency,
+        )
+        .modify(|graph| {
+            graph.description = Some(format!("Spectral centroid, {window_size} ms window"))
+        })
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, LoudnessAccumulator> {
+    /// Creates a graph showing the loudness over time in LUFS, measured over the
+    /// given [`LoudnessWindow`].
+    ///
+    /// The range is given in LUFS. See the [`loudness`](crate::loudness) module for
+    /// how the loudness is measured.
+    ///
+    /// ## Example
+    ///
+    /// 30-second graph of the momentary loudness.
+    ///
+    /// ```
+    /// Graph::loudness(cx, bus.clone(), 30.0, LoudnessWindow::Momentary, (-36.0, 0.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn loudness(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window: LoudnessWindow,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        let description = match window {
+            LoudnessWindow::Momentary => "Momentary loudness",
+            LoudnessWindow::ShortTerm => "Short-term loudness",
+            LoudnessWindow::Integrated => "Integrated loudness",
+        };
+
+        Self::with_accumulator(
+            cx,
+            bus,
+            LoudnessAccumulator::new(duration.get_val(cx), window),
+            range,
+            ValueScaling::Linear,
+        )
+        .modify(|graph| graph.description = Some(description.to_owned()))
+        .duration(duration)
+    }
+
+    /// Creates a graph showing the short-term loudness over time in LUFS, measured
+    /// over the last 3 seconds.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// Graph::lufs_short_term(cx, bus.clone(), 60.0, (-36.0, 0.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn lufs_short_term(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        Self::loudness(cx, bus, duration, LoudnessWindow::ShortTerm, range)
+    }
+
+    /// Creates a loudness graph in LUFS, with a dashed line at the given `target`
+    /// loudness - e.g. -14 LUFS for most streaming platforms.
+    ///
+    /// This is a [`loudness`](Self::loudness) graph with a
+    /// [`target line`](GraphModifiers::with_target_line), so you can see at a
+    /// glance how the last few seconds compare to the loudness you're aiming for.
+    ///
+    /// ## Example
+    ///
+    /// Short-term loudness over the last 30 seconds, against -14 LUFS.
+    ///
+    /// ```
+    /// Graph::lufs(
+    ///     cx,
+    ///     bus.clone(),
+    ///     30.0,
+    ///     LoudnessWindow::ShortTerm,
+    ///     (-36.0, 0.0),
+    ///     -14.0,
+    /// )
+    /// .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn lufs(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window: LoudnessWindow,
+        range: impl Res<(f32, f32)> + Clone,
+        target: f32,
+    ) -> Handle<Self> {
+        Self::loudness(cx, bus, duration, window, range).with_target_line(target)
+    }
+}
+
+impl<B: Bus<f32> + 'static> Graph<B, DecimatingAccumulator<LoudnessAccumulator>> {
+    /// Creates a graph showing the short-term loudness in LUFS over a very long
+    /// duration, such as an entire mixing session.
+    ///
+    /// The loudness is measured every 100 ms, and each column of the graph shows the
+    /// loudest of those measurements. This way, nothing gets lost even when a column
+    /// spans many seconds, while memory and drawing cost stay the same regardless
+    /// of the duration. When the graph is resized or its duration changes, its
+    /// history is rebuilt from the measurements kept so far. See
+    /// [`DecimatingAccumulator`].
+    ///
+    /// ## Example
+    ///
+    /// Short-term loudness over the last hour.
+    ///
+    /// ```
+    /// Graph::loudness_history(cx, bus.clone(), 3600.0, (-36.0, 0.0))
+    ///     .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    pub fn loudness_history(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        range: impl Res<(f32, f32)> + Clone,
+    ) -> Handle<Self> {
+        let duration_val = duration.get_val(cx);
+
+        Self::with_accumulator(
+            cx,
+            bus,
+            DecimatingAccumulator::new(
+                LoudnessAccumulator::new(duration_val, LoudnessWindow::ShortTerm),
+                duration_val,
+                0.1,
+            ),
+            range,
+            ValueScaling::Linear,
+        )
+        .modify(|graph| graph.description = Some("Short-term loudness".to_owned()))
         .duration(duration)
     }
 }
@@ -316,3 +1176,23 @@ impl<'a, B: Bus<f32> + 'static, A: Accumulator> DurationModifiers for Handle<'a,
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::gain_reduction_source;
+    use crate::bus::MonoBus;
+    use nih_plug::util::gain_to_db;
+    use std::sync::Arc;
+
+    #[test]
+    fn gain_reduction_history_starts_at_unity_gain() {
+        let source = gain_reduction_source(Arc::new(MonoBus::new(16)), 10.0);
+
+        let history = source.history(8);
+        assert!(history.iter().all(|&gain| gain_to_db(gain) == 0.0));
+
+        // The space added by a resize is filled the same way
+        let history = source.history(32);
+        assert!(history.iter().all(|&gain| gain_to_db(gain) == 0.0));
+    }
+}
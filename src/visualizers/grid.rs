@@ -2,9 +2,13 @@ use nih_plug_vizia::vizia::{prelude::*, vg};
 
 use crate::utils::ValueScaling;
 
-use super::RangeModifiers;
+use super::{AxisMorph, AxisMorphModifiers, RangeModifiers};
 
-/// Generic grid backdrop that displays either horizontal or vertical lines.
+/// The opacity of minor lines, relative to the grid's color, by default.
+const DEFAULT_MINOR_OPACITY: f32 = 0.5;
+
+/// Generic grid backdrop that displays either horizontal or vertical lines, or
+/// polar ones.
 ///
 /// Put this grid inside a [`ZStack`], along with your visualizer of choice.
 ///
@@ -41,16 +45,34 @@ use super::RangeModifiers;
 ///
 /// Note that both the `Graph` and `Grid` have the same range, which is necessary
 /// for them to scale correctly.
+///
+/// A polar grid of concentric circles and radial spokes can be created using
+/// [`Grid::polar`], e.g. as a backdrop for a [`Lissajous`](super::Lissajous).
+///
+/// Dense grids, like the lines of a frequency axis, can be split into major and
+/// minor lines using [`minor_lines`](GridModifiers::minor_lines), so the minor ones
+/// are drawn less prominently.
 pub struct Grid {
     scaling: ValueScaling,
     range: (f32, f32),
     lines: Vec<f32>,
+    /// Less prominent lines in between the main ones
+    minor_lines: Vec<f32>,
+    /// The width (in logical pixels) and opacity of the minor lines, if they don't
+    /// use the defaults
+    minor_style: Option<(f32, f32)>,
     orientation: Orientation,
+    /// The number of spokes, if the grid is polar
+    spokes: Option<usize>,
+    /// The transition to the current range and scaling, if the axis morphs
+    morph: Option<AxisMorph>,
 }
 
 enum GridEvents {
     UpdateRange((f32, f32)),
     UpdateScaling(ValueScaling),
+    UpdateLines(Vec<f32>),
+    UpdateMinorLines(Vec<f32>),
 }
 
 impl Grid {
@@ -66,11 +88,117 @@ impl Grid {
             scaling,
             range: range.get_val(cx),
             lines: lines.get_val(cx),
+            minor_lines: vec![],
+            minor_style: None,
             orientation,
+            spokes: None,
+            morph: None,
         }
         .build(cx, |_| {})
         .range(range)
         .scaling(scaling)
+        .lines(lines)
+    }
+
+    /// Creates a new polar [`Grid`].
+    ///
+    /// Each of the `circles` is drawn around the center of the grid, with its radius
+    /// given by the value scaling - the start of the range lies at the center, and
+    /// its end touches the nearest edge. The `spokes` are spread evenly around the
+    /// center, with the first one pointing straight up.
+    ///
+    /// # Example
+    ///
+    /// Circles at -6, -12 and -18 dB with 8 spokes, so that both the L/R and M/S
+    /// axes of a [`Lissajous`](super::Lissajous) are marked.
+    ///
+    /// ```
+    /// Grid::polar(
+    ///     cx,
+    ///     ValueScaling::Linear,
+    ///     (-24.0, 0.0),
+    ///     vec![0.0, -6.0, -12.0, -18.0],
+    ///     8,
+    /// )
+    /// .color(Color::rgb(30, 30, 30));
+    /// ```
+    pub fn polar(
+        cx: &mut Context,
+        scaling: ValueScaling,
+        range: impl Res<(f32, f32)>,
+        circles: impl Res<Vec<f32>>,
+        spokes: usize,
+    ) -> Handle<Self> {
+        Self {
+            scaling,
+            range: range.get_val(cx),
+            lines: circles.get_val(cx),
+            minor_lines: vec![],
+            minor_style: None,
+            orientation: Orientation::Horizontal,
+            spokes: Some(spokes),
+            morph: None,
+        }
+        .build(cx, |_| {})
+        .range(range)
+        .scaling(scaling)
+        .lines(circles)
+    }
+}
+
+impl Grid {
+    /// The position of a value along the axis, following the morph if there is one.
+    fn normalize(&self, value: f32) -> f32 {
+        match &self.morph {
+            Some(morph) => morph.value_to_normalized(value, self.range, self.scaling),
+            None => self
+                .scaling
+                .value_to_normalized(value, self.range.0, self.range.1),
+        }
+    }
+}
+
+impl Grid {
+    /// Builds the lines at the given values, or the circles if the grid is polar.
+    fn path(&self, values: &[f32], (x, y, w, h): (f32, f32, f32, f32)) -> vg::Path {
+        let mut path = vg::Path::new();
+
+        if self.spokes.is_some() {
+            let center_x = x + w / 2.;
+            let center_y = y + h / 2.;
+            let radius = w.min(h) / 2.;
+
+            for circle in values.iter() {
+                let circle = self.normalize(*circle);
+
+                path.circle(center_x, center_y, radius * circle);
+            }
+        } else {
+            match self.orientation {
+                Orientation::Horizontal => {
+                    for y_line in values.iter() {
+                        let y_line = self.normalize(*y_line);
+
+                        path.move_to(x, y + h * (1. - y_line));
+                        path.line_to(x + w, y + h * (1. - y_line));
+
+                        path.close();
+                    }
+                }
+                Orientation::Vertical => {
+                    for x_line in values.iter() {
+                        let x_line = self.normalize(*x_line);
+
+                        path.move_to(x + w * x_line, y);
+                        path.line_to(x + w * x_line, y + h);
+
+                        path.close();
+                    }
+                }
+            }
+        }
+
+        path
     }
 }
 
@@ -92,50 +220,59 @@ impl View for Grid {
             cx.scale_factor()
         };
 
-        canvas.stroke_path(
-            &{
-                let mut path = vg::Path::new();
+        let color: vg::Color = cx.font_color().into();
 
-                match self.orientation {
-                    Orientation::Horizontal => {
-                        for y_line in self.lines.iter() {
-                            let y_line = self.scaling.value_to_normalized(
-                                *y_line,
-                                self.range.0,
-                                self.range.1,
-                            );
+        if !self.minor_lines.is_empty() {
+            let (minor_width, opacity) = match self.minor_style {
+                Some((width, opacity)) => (width * cx.scale_factor(), opacity),
+                None => (line_width, DEFAULT_MINOR_OPACITY),
+            };
 
-                            path.move_to(x, y + h * (1. - y_line));
-                            path.line_to(x + w, y + h * (1. - y_line));
+            let mut minor_color = color;
+            minor_color.a *= opacity;
 
-                            path.close();
-                        }
-                    }
-                    Orientation::Vertical => {
-                        for x_line in self.lines.iter() {
-                            let x_line = self.scaling.value_to_normalized(
-                                *x_line,
-                                self.range.0,
-                                self.range.1,
-                            );
-
-                            path.move_to(x + w * x_line, y);
-                            path.line_to(x + w * x_line, y + h);
-
-                            path.close();
-                        }
-                    }
-                };
+            canvas.stroke_path(
+                &self.path(&self.minor_lines, (x, y, w, h)),
+                &vg::Paint::color(minor_color).with_line_width(minor_width),
+            );
+        }
+
+        let mut path = self.path(&self.lines, (x, y, w, h));
 
-                path
-            },
-            &vg::Paint::color(cx.font_color().into()).with_line_width(line_width),
-        );
+        if let Some(spokes) = self.spokes {
+            let center_x = x + w / 2.;
+            let center_y = y + h / 2.;
+            let radius = w.min(h) / 2.;
+
+            for i in 0..spokes {
+                let angle = i as f32 / spokes as f32 * std::f32::consts::TAU;
+
+                path.move_to(center_x, center_y);
+                path.line_to(
+                    center_x + radius * angle.sin(),
+                    center_y - radius * angle.cos(),
+                );
+            }
+        }
+
+        canvas.stroke_path(&path, &vg::Paint::color(color).with_line_width(line_width));
     }
     fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
         event.map(|e, _| match e {
-            GridEvents::UpdateRange(v) => self.range = *v,
-            GridEvents::UpdateScaling(v) => self.scaling = *v,
+            GridEvents::UpdateRange(v) => {
+                if let Some(morph) = self.morph.as_mut().filter(|_| self.range != *v) {
+                    morph.begin(self.range, self.scaling);
+                }
+                self.range = *v;
+            }
+            GridEvents::UpdateScaling(v) => {
+                if let Some(morph) = self.morph.as_mut().filter(|_| self.scaling != *v) {
+                    morph.begin(self.range, self.scaling);
+                }
+                self.scaling = *v;
+            }
+            GridEvents::UpdateLines(v) => self.lines = v.clone(),
+            GridEvents::UpdateMinorLines(v) => self.minor_lines = v.clone(),
         });
     }
 }
@@ -160,3 +297,70 @@ impl<'a> RangeModifiers for Handle<'a, Grid> {
         self
     }
 }
+
+pub trait GridModifiers {
+    fn lines(self, lines: impl Res<Vec<f32>>) -> Self;
+    fn minor_lines(self, lines: impl Res<Vec<f32>>) -> Self;
+    fn minor_line_style(self, width: f32, opacity: f32) -> Self;
+}
+
+impl<'a> GridModifiers for Handle<'a, Grid> {
+    /// Sets the values the lines are drawn at - or the circles, for a polar grid.
+    ///
+    /// If a lens is given, the lines follow along as it changes, e.g. to add finer
+    /// lines as the user zooms in. [`Grid::new`] and [`Grid::polar`] already bind
+    /// the lines they are given.
+    fn lines(mut self, lines: impl Res<Vec<f32>>) -> Self {
+        let e = self.entity();
+
+        lines.set_or_bind(self.context(), e, move |cx, l| {
+            (*cx).emit_to(e, GridEvents::UpdateLines(l));
+        });
+
+        self
+    }
+    /// Adds a second set of less prominent lines, drawn behind the main ones.
+    ///
+    /// This keeps dense grids readable - e.g. a frequency grid can mark every
+    /// decade with a major line, and the steps in between with minor ones. Minor
+    /// lines use the grid's `color` at half its opacity, and the same width as the
+    /// main lines, unless styled otherwise via
+    /// [`minor_line_style`](Self::minor_line_style).
+    ///
+    /// ```
+    /// Grid::new(
+    ///     cx,
+    ///     ValueScaling::Frequency,
+    ///     (20., 20_000.),
+    ///     vec![100., 1_000., 10_000.],
+    ///     Orientation::Vertical,
+    /// )
+    /// .minor_lines(vec![
+    ///     20., 30., 40., 50., 60., 70., 80., 90., 200., 300., 400., 500., 600., 700.,
+    ///     800., 900., 2_000., 3_000., 4_000., 5_000., 6_000., 7_000., 8_000., 9_000.,
+    /// ])
+    /// .color(Color::rgb(60, 60, 60));
+    /// ```
+    fn minor_lines(mut self, lines: impl Res<Vec<f32>>) -> Self {
+        let e = self.entity();
+
+        lines.set_or_bind(self.context(), e, move |cx, l| {
+            (*cx).emit_to(e, GridEvents::UpdateMinorLines(l));
+        });
+
+        self
+    }
+    /// Sets the width (in logical pixels) and opacity (from 0 to 1, relative to the
+    /// grid's `color`) of the minor lines.
+    fn minor_line_style(self, width: f32, opacity: f32) -> Self {
+        self.modify(|grid| grid.minor_style = Some((width, opacity)))
+    }
+}
+
+impl<'a> AxisMorphModifiers for Handle<'a, Grid> {
+    /// Lets the grid lines glide to a new range or scaling over 150 ms, instead of
+    /// snapping to it.
+    fn morph_axis(self) -> Self {
+        self.modify(|grid| grid.morph = Some(AxisMorph::new(grid.range, grid.scaling)))
+    }
+}
@@ -5,47 +5,168 @@ use nih_plug_vizia::vizia::{prelude::*, vg};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-struct HistogramState {
+/// The level distribution of a signal, shared between a view and its bus.
+pub(super) struct HistogramState {
     data: [AtomicF32; 2048],
     edges: [AtomicF32; 2047],
 
-    sample_rate: f32,
+    sample_rate: AtomicF32,
     decay: f32,
 
     size: AtomicUsize,
     decay_weight: AtomicF32,
 }
 
+/// A kernel used to smooth out the bins of a [`Histogram`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistogramSmoothing {
+    /// Averages each bin with the given number of bins on either side of it.
+    MovingAverage(usize),
+    /// Applies a Gaussian kernel with the given standard deviation, in bins.
+    Gaussian(f32),
+}
+
+impl HistogramSmoothing {
+    fn kernel(&self) -> Vec<f32> {
+        match *self {
+            HistogramSmoothing::MovingAverage(radius) => vec![1.0; 2 * radius + 1],
+            HistogramSmoothing::Gaussian(sigma) => {
+                let sigma = sigma.max(f32::EPSILON);
+                let radius = (3.0 * sigma).ceil() as i32;
+
+                (-radius..=radius)
+                    .map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp())
+                    .collect()
+            }
+        }
+    }
+
+    /// Convolves the values with this kernel.
+    ///
+    /// At the edges, the kernel is cut off and the result renormalized, so that
+    /// the outermost bins don't get pulled towards zero.
+    fn apply(&self, values: &[f32]) -> Vec<f32> {
+        let kernel = self.kernel();
+        let radius = (kernel.len() / 2) as isize;
+
+        (0..values.len() as isize)
+            .map(|i| {
+                let (sum, weight) = kernel
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(k, kernel_weight)| {
+                        let j = i + k as isize - radius;
+                        (j >= 0)
+                            .then(|| values.get(j as usize))
+                            .flatten()
+                            .map(|value| (value * kernel_weight, *kernel_weight))
+                    })
+                    .fold((0.0, 0.0), |(sum, weight), (value, kernel_weight)| {
+                        (sum + value, weight + kernel_weight)
+                    });
+
+                if weight > 0.0 {
+                    sum / weight
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+}
+
 /// A histogram plot of the most frequent levels in a signal.
+///
+/// With a vertical orientation, levels go from bottom to top and the bins grow
+/// towards the right, so that the histogram can sit next to a meter or to the
+/// right edge of a graph. With a horizontal orientation, levels go from left to
+/// right and the bins grow upwards from the bottom edge.
+///
+/// Sparse data can be smoothed out at draw time using
+/// [`smoothing`](HistogramModifiers::smoothing).
 pub struct Histogram<B: Bus<f32> + 'static> {
     dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
     state: Arc<HistogramState>,
     range: (f32, f32),
     scaling: ValueScaling,
+    orientation: Orientation,
+    smoothing: Option<HistogramSmoothing>,
+    overlay: Option<HistogramOverlay<B>>,
 }
 
-impl<B: Bus<f32> + 'static> Histogram<B> {
-    /// Creates a new [`Histogram`].
-    pub fn new(
-        cx: &mut Context,
-        bus: Arc<B>,
-        decay: f32,
-        range: (f32, f32),
-        scaling: ValueScaling,
-    ) -> Handle<Self> {
-        let state: Arc<_> = HistogramState {
+impl HistogramState {
+    pub(super) fn new(sample_rate: f32, decay: f32) -> Self {
+        Self {
             data: [0f32; 2048].map(|x| x.into()),
             edges: [0f32; 2047].map(|x| x.into()),
-            sample_rate: bus.sample_rate(),
+            sample_rate: sample_rate.into(),
             decay,
             size: 1.into(),
             decay_weight: 0.0.into(),
         }
-        .into();
+    }
+
+    fn current_decay_weight(&self) -> f32 {
+        0.25f64.powf(
+            ((self.decay / 1000.0) as f64 * self.sample_rate.load(Ordering::Relaxed) as f64)
+                .recip(),
+        ) as f32
+    }
+
+    /// Registers a listener to the bus that keeps the decay in line with its
+    /// sample rate.
+    pub(super) fn listen<B: Bus<f32> + 'static>(
+        self: &Arc<Self>,
+        bus: &B,
+    ) -> Arc<dyn Fn(f32) + Send + Sync> {
+        let state_c = self.clone();
+
+        bus.register_sample_rate_listener(move |sample_rate| {
+            state_c.sample_rate.store(sample_rate, Ordering::Relaxed);
+            state_c
+                .decay_weight
+                .store(state_c.current_decay_weight(), Ordering::Relaxed);
+        })
+    }
+
+    /// Lays out `size` bins spanning the given range.
+    pub(super) fn set_bins(&self, size: usize, range: (f32, f32), scaling: ValueScaling) {
+        self.size.store(size, Ordering::Relaxed);
 
-        let state_c = state.clone();
+        (0..size).for_each(|x| {
+            let edge = scaling.normalized_to_value(x as f32 / size as f32, range.0, range.1);
+
+            self.edges[x].store(edge, Ordering::Relaxed);
+        });
+
+        self.decay_weight
+            .store(self.current_decay_weight(), Ordering::Relaxed);
+    }
+
+    /// The number of bins currently in use.
+    pub(super) fn size(&self) -> usize {
+        self.size.load(Ordering::Relaxed)
+    }
 
-        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+    /// The current value of the first `nr_bins + 1` bins.
+    pub(super) fn bins(&self, nr_bins: usize) -> Vec<f32> {
+        self.data
+            .iter()
+            .take(nr_bins + 1)
+            .map(|x| x.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Registers a dispatcher to the bus that sorts its samples into this state's
+    /// bins.
+    pub(super) fn register<B: Bus<f32> + 'static>(
+        self: &Arc<Self>,
+        bus: &B,
+    ) -> Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync> {
+        let state_c = self.clone();
+
+        bus.register_dispatcher(move |samples| {
             let decay_weight = state_c.decay_weight.load(Ordering::Relaxed);
             let total_decay_weight = decay_weight.powi(samples.len() as i32);
 
@@ -88,37 +209,115 @@ impl<B: Bus<f32> + 'static> Histogram<B> {
                 }]
                 .fetch_add(1.0 - decay_weight, Ordering::Relaxed);
             }
-        });
+        })
+    }
+}
+
+/// The second distribution shown by an overlaid [`Histogram`].
+struct HistogramOverlay<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    state: Arc<HistogramState>,
+    color: Option<Color>,
+}
+
+impl<B: Bus<f32> + 'static> Histogram<B> {
+    /// Creates a new [`Histogram`].
+    pub fn new(
+        cx: &mut Context,
+        bus: Arc<B>,
+        decay: f32,
+        range: (f32, f32),
+        scaling: ValueScaling,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        let state = Arc::new(HistogramState::new(bus.sample_rate(), decay));
 
         Self {
-            dispatcher_handle,
+            dispatcher_handle: state.register(bus.as_ref()),
+            sample_rate_handle: state.listen(bus.as_ref()),
             state,
             range,
             scaling,
+            orientation,
+            smoothing: None,
+            overlay: None,
         }
         .build(cx, |_| {})
     }
 
-    fn update(&self) {
-        let size: usize = self.state.size.load(Ordering::Relaxed);
+    /// Creates a new [`Histogram`] which overlays the level distributions of two
+    /// buses.
+    ///
+    /// This is useful to see how some processing reshapes the distribution - for
+    /// example, by showing a compressor's input and output. The first bus is drawn
+    /// like a regular histogram, the second one on top of it as a translucent area
+    /// outlined in the color set via
+    /// [`overlay_color`](HistogramModifiers::overlay_color). Both distributions share
+    /// the same scale, so their heights can be compared directly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Histogram::overlaid(
+    ///     cx,
+    ///     input_bus.clone(),
+    ///     output_bus.clone(),
+    ///     250.0,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    ///     Orientation::Vertical,
+    /// )
+    /// .overlay_color(Color::rgb(255, 92, 92));
+    /// ```
+    pub fn overlaid(
+        cx: &mut Context,
+        bus: Arc<B>,
+        overlay_bus: Arc<B>,
+        decay: f32,
+        range: (f32, f32),
+        scaling: ValueScaling,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        let state = Arc::new(HistogramState::new(bus.sample_rate(), decay));
+        let overlay_state = Arc::new(HistogramState::new(overlay_bus.sample_rate(), decay));
 
-        (0..size).for_each(|x| {
-            let scaled = self.range.0 + (x as f32 / size as f32) * (self.range.1 - self.range.0);
-            let edge = self
-                .scaling
-                .normalized_to_value(scaled, self.range.0, self.range.1);
+        Self {
+            dispatcher_handle: state.register(bus.as_ref()),
+            sample_rate_handle: state.listen(bus.as_ref()),
+            state,
+            range,
+            scaling,
+            orientation,
+            smoothing: None,
+            overlay: Some(HistogramOverlay {
+                dispatcher_handle: overlay_state.register(overlay_bus.as_ref()),
+                sample_rate_handle: overlay_state.listen(overlay_bus.as_ref()),
+                state: overlay_state,
+                color: None,
+            }),
+        }
+        .build(cx, |_| {})
+    }
 
-            self.state.edges[x].store(edge, Ordering::Relaxed);
-        });
+    /// The (optionally smoothed) bins of the given state.
+    fn bins(&self, state: &HistogramState, nr_bins: usize) -> Vec<f32> {
+        let bins = state.bins(nr_bins);
 
-        self.state.decay_weight.store(
-            Self::decay_weight(self.state.decay, self.state.sample_rate),
-            Ordering::Relaxed,
-        );
+        match &self.smoothing {
+            Some(smoothing) => smoothing.apply(&bins),
+            None => bins,
+        }
     }
 
-    fn decay_weight(decay: f32, sample_rate: f32) -> f32 {
-        0.25f64.powf(((decay / 1000.0) as f64 * sample_rate as f64).recip()) as f32
+    fn update(&self) {
+        let size: usize = self.state.size.load(Ordering::Relaxed);
+
+        let states = std::iter::once(&self.state).chain(self.overlay.as_ref().map(|o| &o.state));
+
+        for state in states {
+            state.set_bins(size, self.range, self.scaling);
+        }
     }
 }
 
@@ -135,52 +334,156 @@ impl<B: Bus<f32> + 'static> View for Histogram<B> {
         let y = bounds.y;
         let w = bounds.w;
         let h = bounds.h;
-        let h_ceil = bounds.h.ceil() as usize;
 
-        let mut stroke = vg::Path::new();
+        // One bin per pixel along the level axis
+        let length = match self.orientation {
+            Orientation::Vertical => h,
+            Orientation::Horizontal => w,
+        }
+        .ceil() as usize;
+
         let size = self.state.size.load(Ordering::Relaxed);
 
-        let nr_bins = if h_ceil != size && h_ceil < 2048 {
-            self.state.size.store(h_ceil, Ordering::Relaxed);
+        let nr_bins = if length != size && (2..2048).contains(&length) {
+            self.state.size.store(length, Ordering::Relaxed);
             self.update();
-            h_ceil
+            length
         } else {
             size
         };
 
-        let largest = self
-            .state
-            .data
-            .iter()
-            .take(nr_bins)
-            .skip(1)
-            .map(|x| x.load(Ordering::Relaxed))
-            .max_by(|a, b| a.partial_cmp(b).unwrap())
-            .unwrap_or_default();
+        // Nothing sensible can be drawn with fewer than two bins
+        if nr_bins < 2 {
+            return;
+        }
 
-        stroke.move_to(
-            x + self.state.data[nr_bins - 1].load(Ordering::Relaxed) * w,
-            y,
-        );
+        let bins = self.bins(&self.state, nr_bins);
+        let overlay_bins = self
+            .overlay
+            .as_ref()
+            .map(|overlay| self.bins(&overlay.state, nr_bins));
+
+        // Both distributions are drawn on the same scale
+        let peak = |bins: &[f32]| {
+            bins.iter()
+                .take(nr_bins)
+                .skip(1)
+                .copied()
+                .filter(|x| x.is_finite())
+                .fold(0.0, f32::max)
+        };
+        let largest = overlay_bins.as_deref().map_or(0.0, peak).max(peak(&bins));
+
+        // Maps an amount (0 at the base, 1 at the opposite edge) and a position
+        // along the level axis (0 at the highest level, 1 at the lowest) to a point
+        let point = |amount: f32, position: f32| match self.orientation {
+            Orientation::Vertical => (x + amount * w, y + position * h),
+            Orientation::Horizontal => (x + (1.0 - position) * w, y + h - amount * h),
+        };
+
+        // Builds the outline of a distribution, going from the highest level to the
+        // lowest
+        let outline = |bins: &[f32]| {
+            let mut stroke = vg::Path::new();
 
-        if largest > 0.0 {
-            for i in 0..nr_bins {
-                stroke.line_to(
-                    x + (self.state.data[nr_bins - i].load(Ordering::Relaxed) / largest) * w,
-                    y + h * i as f32 / (nr_bins - 1) as f32,
-                );
+            let (start_x, start_y) = point(bins[nr_bins - 1], 0.0);
+            stroke.move_to(start_x, start_y);
+
+            if largest > 0.0 {
+                for i in 0..nr_bins {
+                    let (px, py) =
+                        point(bins[nr_bins - i] / largest, i as f32 / (nr_bins - 1) as f32);
+                    stroke.line_to(px, py);
+                }
             }
-        }
 
-        let mut fill = stroke.clone();
-        fill.line_to(x, y + h);
-        fill.line_to(x, y);
-        fill.close();
+            let mut fill = stroke.clone();
+            let (base_x, base_y) = point(0.0, 1.0);
+            fill.line_to(base_x, base_y);
+            let (base_x, base_y) = point(0.0, 0.0);
+            fill.line_to(base_x, base_y);
+            fill.close();
+
+            (stroke, fill)
+        };
+
+        let (stroke, fill) = outline(&bins);
         canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
 
         canvas.stroke_path(
             &stroke,
             &vg::Paint::color(cx.font_color().into()).with_line_width(line_width),
         );
+
+        if let (Some(overlay), Some(overlay_bins)) = (&self.overlay, overlay_bins) {
+            let color: vg::Color = overlay.color.unwrap_or(cx.font_color()).into();
+            let mut fill_color = color;
+            fill_color.a *= 0.25;
+
+            let (stroke, fill) = outline(&overlay_bins);
+            canvas.fill_path(&fill, &vg::Paint::color(fill_color));
+            canvas.stroke_path(
+                &stroke,
+                &vg::Paint::color(color).with_line_width(line_width),
+            );
+        }
+    }
+}
+
+pub trait HistogramModifiers {
+    fn smoothing(self, smoothing: HistogramSmoothing) -> Self;
+    fn overlay_color(self, color: Color) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> HistogramModifiers for Handle<'a, Histogram<B>> {
+    /// Smooths out the histogram's bins using the given kernel.
+    ///
+    /// The smoothing is only applied when drawing, so the underlying data stays
+    /// untouched. This is useful for sparse data, which would otherwise render as
+    /// a jagged comb.
+    fn smoothing(self, smoothing: HistogramSmoothing) -> Self {
+        self.modify(|histogram| histogram.smoothing = Some(smoothing))
+    }
+    /// Sets the color of the overlaid distribution.
+    ///
+    /// Only has an effect on histograms created via [`overlaid`](Histogram::overlaid).
+    /// Defaults to the `color`.
+    fn overlay_color(self, color: Color) -> Self {
+        self.modify(|histogram| {
+            if let Some(overlay) = &mut histogram.overlay {
+                overlay.color = Some(color);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HistogramSmoothing;
+
+    #[test]
+    fn smoothing_keeps_constant_bins() {
+        let bins = [0.5; 16];
+
+        for smoothing in [
+            HistogramSmoothing::MovingAverage(3),
+            HistogramSmoothing::Gaussian(2.0),
+        ] {
+            for value in smoothing.apply(&bins) {
+                assert!((value - 0.5).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn smoothing_spreads_impulse() {
+        let mut bins = [0.0; 9];
+        bins[4] = 3.0;
+
+        let smoothed = HistogramSmoothing::MovingAverage(1).apply(&bins);
+
+        assert_eq!(&smoothed[3..6], &[1.0, 1.0, 1.0]);
+        assert_eq!(smoothed[2], 0.0);
+        assert_eq!(smoothed[6], 0.0);
     }
 }
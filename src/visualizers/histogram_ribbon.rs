@@ -0,0 +1,168 @@
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use super::histogram::HistogramState;
+use crate::bus::Bus;
+use crate::utils::{ColorMap, OffscreenImage, ValueScaling};
+
+/// The offscreen image the ribbon is rendered into.
+///
+/// Like the [`Spectrogram`](super::Spectrogram)'s image, it acts as a ring buffer
+/// of columns.
+struct RibbonImage {
+    image: Option<OffscreenImage>,
+    /// The column that will be written next.
+    head: usize,
+}
+
+/// A horizontally scrolling ribbon showing how the level distribution of a signal
+/// evolves over time.
+///
+/// Each column is a [`Histogram`](super::Histogram) of the signal at the moment it
+/// was drawn, with levels laid out vertically and the lowest level at the bottom.
+/// How common each level is gets colored by the provided [`ColorMap`], relative to
+/// the most common level in that column. For each drawn frame, a new column is
+/// appended at the right edge, and older columns scroll to the left.
+///
+/// # Example
+///
+/// ```
+/// HistogramRibbon::new(
+///     cx,
+///     bus.clone(),
+///     250.0,
+///     (-60.0, 6.0),
+///     ValueScaling::Decibels,
+///     ColorMap::heat(),
+/// )
+/// .height(Pixels(128.));
+/// ```
+pub struct HistogramRibbon<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    state: Arc<HistogramState>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+    color_map: ColorMap,
+    image: RefCell<RibbonImage>,
+}
+
+impl<B: Bus<f32> + 'static> HistogramRibbon<B> {
+    /// Creates a new [`HistogramRibbon`].
+    ///
+    /// The `decay` (in ms) dictates how quickly old levels fade from the
+    /// distribution, just like for the [`Histogram`](super::Histogram).
+    pub fn new(
+        cx: &mut Context,
+        bus: Arc<B>,
+        decay: f32,
+        range: (f32, f32),
+        scaling: ValueScaling,
+        color_map: ColorMap,
+    ) -> Handle<Self> {
+        let state = Arc::new(HistogramState::new(bus.sample_rate(), decay));
+
+        Self {
+            dispatcher_handle: state.register(bus.as_ref()),
+            sample_rate_handle: state.listen(bus.as_ref()),
+            state,
+            range,
+            scaling,
+            color_map,
+            image: RefCell::new(RibbonImage {
+                image: None,
+                head: 0,
+            }),
+        }
+        .build(cx, |_| ())
+    }
+
+    /// Computes the color stops of a single column, from top to bottom.
+    fn column(&self, nr_bins: usize) -> Vec<(f32, vg::Color)> {
+        let bins = self.state.bins(nr_bins);
+
+        let largest = bins
+            .iter()
+            .take(nr_bins)
+            .skip(1)
+            .copied()
+            .filter(|x| x.is_finite())
+            .fold(0.0, f32::max);
+
+        (0..nr_bins)
+            .map(|row| {
+                let position = row as f32 / (nr_bins - 1) as f32;
+                let value = if largest > 0.0 {
+                    bins[nr_bins - row] / largest
+                } else {
+                    0.0
+                };
+
+                (position, self.color_map.color_at(value).into())
+            })
+            .collect()
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for HistogramRibbon<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("histogram-ribbon")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let width = w.ceil() as usize;
+        let height = h.ceil() as usize;
+
+        // Nothing sensible can be drawn with fewer than two bins
+        if width == 0 || !(2..2048).contains(&height) {
+            return;
+        }
+
+        if self.state.size() != height {
+            self.state.set_bins(height, self.range, self.scaling);
+        }
+
+        let mut state = self.image.borrow_mut();
+        let RibbonImage { image, head } = &mut *state;
+
+        let Some((image, created)) = OffscreenImage::ensure(
+            image,
+            canvas,
+            width,
+            height,
+            vg::ImageFlags::REPEAT_X | vg::ImageFlags::NEAREST,
+        ) else {
+            return;
+        };
+
+        if created {
+            *head = 0;
+        }
+
+        let stops = self.column(height);
+
+        // Render the newest column into the image
+        image.render(canvas, |canvas| {
+            let mut column = vg::Path::new();
+            column.rect(*head as f32, 0., 1., height as f32);
+            canvas.fill_path(
+                &column,
+                &vg::Paint::linear_gradient_stops(0., 0., 0., height as f32, &stops),
+            );
+        });
+
+        *head = (*head + 1) % image.width();
+
+        // Draw the image, offset so that the oldest column is at the left edge
+        image.blit(canvas, x, y, w, h, *head as f32);
+    }
+}
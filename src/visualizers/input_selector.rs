@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::bus::{Bus, SwitchableBus};
+use nih_plug_vizia::vizia::prelude::*;
+
+/// A row of labels, one for each source of a [`SwitchableBus`], that switches
+/// the bus to the source that gets clicked.
+///
+/// All visualizers listening to the bus follow along, so this lets users pick
+/// what an analyzer shows - e.g. the input, output or sidechain of your plug-in.
+/// Each label has the `input` class, and the one of the selected source is
+/// `:checked`, so they can be styled using CSS.
+///
+/// # Example
+///
+/// ```
+/// let analyzer_bus = SwitchableBus::new(vec![
+///     input_bus.clone(),
+///     output_bus.clone(),
+///     sidechain_bus.clone(),
+/// ]);
+///
+/// // Inside your editor
+/// analyzer_bus.subscribe(cx);
+/// InputSelector::new(cx, analyzer_bus.clone(), ["In", "Out", "Side"])
+///     .col_between(Pixels(8.0));
+/// Oscilloscope::new(cx, analyzer_bus.clone(), 4.0, (-1.0, 1.0), ValueScaling::Linear);
+/// ```
+pub struct InputSelector {
+    select: Box<dyn Fn(usize) + Send + Sync>,
+}
+
+enum InputSelectorEvents {
+    Select(usize),
+}
+
+/// The source an input selector currently shows as selected.
+#[derive(Lens)]
+struct InputSelectorState {
+    selected: usize,
+}
+
+impl Model for InputSelectorState {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            InputSelectorEvents::Select(selected) => self.selected = *selected,
+        });
+    }
+}
+
+impl InputSelector {
+    /// Creates a new [`InputSelector`], with one label for each of the bus'
+    /// sources, in order.
+    pub fn new<T: Clone + Copy + Sized + 'static, B: Bus<T>>(
+        cx: &mut Context,
+        bus: Arc<SwitchableBus<T, B>>,
+        labels: impl IntoIterator<Item = impl ToString>,
+    ) -> Handle<Self> {
+        let selected = bus.selection();
+
+        Self {
+            select: Box::new(move |selection| bus.select(selection)),
+        }
+        .build(cx, |cx| {
+            InputSelectorState { selected }.build(cx);
+
+            for (index, label) in labels.into_iter().enumerate() {
+                Label::new(cx, &label.to_string())
+                    .class("input")
+                    .checked(InputSelectorState::selected.map(move |s| *s == index))
+                    .on_press(move |cx| cx.emit(InputSelectorEvents::Select(index)));
+            }
+        })
+        .layout_type(LayoutType::Row)
+    }
+}
+
+impl View for InputSelector {
+    fn element(&self) -> Option<&'static str> {
+        Some("input-selector")
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            InputSelectorEvents::Select(selection) => (self.select)(*selection),
+        });
+    }
+}
@@ -0,0 +1,162 @@
+use nih_plug_vizia::vizia::prelude::*;
+
+use super::unit_ruler::RulerAxis;
+use super::{AxisLink, Grid, GridModifiers, RangeModifiers, UnitRuler};
+use crate::utils::ValueScaling;
+
+/// The lines, axis and label background of a labeled grid.
+#[derive(Lens)]
+struct LabeledGridState {
+    lines: Vec<f32>,
+    axis: AxisLink,
+    knockout: Color,
+}
+
+enum LabeledGridEvents {
+    UpdateLines(Vec<f32>),
+    UpdateRange((f32, f32)),
+    SetKnockout(Color),
+}
+
+impl Model for LabeledGridState {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            LabeledGridEvents::UpdateLines(lines) => self.lines = lines.clone(),
+            LabeledGridEvents::UpdateRange(range) => self.axis.range = *range,
+            LabeledGridEvents::SetKnockout(color) => self.knockout = *color,
+        });
+    }
+}
+
+/// A [`Grid`] that labels each of its lines with its value.
+///
+/// Instead of keeping a [`Grid`] and a [`UnitRuler`](super::UnitRuler) with the
+/// same values in sync, the labels are placed right next to the lines - at the
+/// left edge for horizontal lines, and at the bottom edge for vertical ones. Each
+/// value is turned into text by the given `format` function. If the lines or the
+/// range are bound to a lens, the labels follow along.
+///
+/// The lines are drawn by an inner [`Grid`] in the `color`, one pixel wide. Their
+/// width can be set by styling the inner grid using CSS, e.g.
+/// `labeled-grid > grid { border-width: 0.5px; }`. The labels have the
+/// `grid-label` class, so they can be styled using CSS as well. Using
+/// [`knockout`](LabeledGridModifiers::knockout), the lines can be hidden behind
+/// each label, which keeps the labels readable.
+///
+/// # Example
+///
+/// ```
+/// LabeledGrid::new(
+///     cx,
+///     ValueScaling::Linear,
+///     (-32., 8.),
+///     vec![6.0, 0.0, -6.0, -12.0, -18.0, -24.0, -30.0],
+///     Orientation::Horizontal,
+///     |value| format!("{value} dB"),
+/// )
+/// .knockout(Color::rgb(16, 16, 16))
+/// .color(Color::rgb(60, 60, 60));
+/// ```
+pub struct LabeledGrid {}
+
+impl LabeledGrid {
+    /// Creates a new [`LabeledGrid`].
+    pub fn new(
+        cx: &mut Context,
+        scaling: ValueScaling,
+        range: impl Res<(f32, f32)>,
+        lines: impl Res<Vec<f32>>,
+        orientation: Orientation,
+        format: impl Fn(f32) -> String + 'static,
+    ) -> Handle<Self> {
+        let initial_lines = lines.get_val(cx);
+        let initial_range = range.get_val(cx);
+
+        let mut handle = Self {}.build(cx, |cx| {
+            LabeledGridState {
+                lines: initial_lines,
+                axis: AxisLink::new(scaling, initial_range),
+                knockout: Color::transparent(),
+            }
+            .build(cx);
+
+            Grid::new(
+                cx,
+                scaling,
+                LabeledGridState::axis.then(AxisLink::range),
+                LabeledGridState::lines,
+                orientation,
+            )
+            .axis(LabeledGridState::axis)
+            .hoverable(false);
+
+            // Horizontal lines are labeled along a vertical axis, and vice versa
+            let label_orientation = match orientation {
+                Orientation::Vertical => Orientation::Horizontal,
+                Orientation::Horizontal => Orientation::Vertical,
+            };
+
+            Binding::new(cx, LabeledGridState::lines, move |cx, lines| {
+                let values = lines
+                    .get(cx)
+                    .into_iter()
+                    .map(|line| (line, format(line)))
+                    .collect();
+
+                UnitRuler::place_labels(
+                    cx,
+                    LabeledGridState::axis.map(|axis| RulerAxis::new(*axis)),
+                    values,
+                    label_orientation,
+                    move |label| {
+                        let label = label
+                            .class("grid-label")
+                            .position_type(PositionType::SelfDirected)
+                            .background_color(LabeledGridState::knockout)
+                            .hoverable(false);
+
+                        // Vertical lines are labeled at the bottom edge
+                        if let Orientation::Vertical = orientation {
+                            label.top(Stretch(1.0));
+                        }
+                    },
+                );
+            });
+        });
+
+        let e = handle.entity();
+        range.set_or_bind(handle.context(), e, move |cx, r| {
+            (*cx).emit_to(e, LabeledGridEvents::UpdateRange(r));
+        });
+        lines.set_or_bind(handle.context(), e, move |cx, l| {
+            (*cx).emit_to(e, LabeledGridEvents::UpdateLines(l));
+        });
+
+        handle
+    }
+}
+
+impl View for LabeledGrid {
+    fn element(&self) -> Option<&'static str> {
+        Some("labeled-grid")
+    }
+}
+
+pub trait LabeledGridModifiers {
+    fn knockout(self, color: Color) -> Self;
+}
+
+impl<'a> LabeledGridModifiers for Handle<'a, LabeledGrid> {
+    /// Fills the area behind each label with the given color, so that the lines
+    /// don't run through the text.
+    ///
+    /// Use the background color of your display, so the labels look like they
+    /// are cut out of the lines.
+    fn knockout(mut self, color: Color) -> Self {
+        let e = self.entity();
+        self.context()
+            .emit_to(e, LabeledGridEvents::SetKnockout(color));
+
+        self
+    }
+}
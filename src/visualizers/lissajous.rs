@@ -1,6 +1,10 @@
-use crate::{bus::Bus, utils::RingBuffer};
+use crate::{
+    bus::Bus,
+    utils::{lock_or_recover, ColorMap, OffscreenImage, RingBuffer, ValueScaling},
+};
 
 use lazy_static::lazy_static;
+use nih_plug::prelude::AtomicF32;
 use nih_plug_vizia::vizia::{
     binding::{Lens, LensExt},
     context::{Context, DrawContext},
@@ -8,8 +12,14 @@ use nih_plug_vizia::vizia::{
     view::{Canvas, Handle, View},
 };
 use std::{
+    cell::RefCell,
     f32::consts::PI,
-    sync::{Arc, Mutex},
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
 };
 
 lazy_static! {
@@ -19,6 +29,101 @@ lazy_static! {
 
 type Sample = [f32; 2];
 
+/// The number of colors a frequency-colored [`Lissajous`] uses.
+const FREQUENCY_BANDS: usize = 24;
+
+/// Samples closer to zero than this don't count as crossing it.
+const ZERO_CROSSING_HYSTERESIS: f32 = 1e-4;
+
+/// Estimates the instantaneous frequency of a signal from its zero crossings.
+///
+/// The time between the two most recent zero crossings is taken as half a period.
+/// As long as the signal doesn't cross zero again, the estimate keeps falling, so
+/// that it follows a signal that suddenly gets lower.
+struct ZeroCrossings {
+    positive: bool,
+    /// Samples since the last zero crossing
+    since: u32,
+    /// Samples between the last two zero crossings
+    half_period: u32,
+}
+
+impl Default for ZeroCrossings {
+    fn default() -> Self {
+        Self {
+            positive: false,
+            since: 0,
+            half_period: u32::MAX,
+        }
+    }
+}
+
+impl ZeroCrossings {
+    /// Processes a sample, returning the current estimate in cycles per sample.
+    fn process(&mut self, sample: f32) -> f32 {
+        self.since = self.since.saturating_add(1);
+
+        let positive = if sample > ZERO_CROSSING_HYSTERESIS {
+            true
+        } else if sample < -ZERO_CROSSING_HYSTERESIS {
+            false
+        } else {
+            self.positive
+        };
+
+        if positive != self.positive {
+            self.positive = positive;
+            self.half_period = self.since;
+            self.since = 0;
+        }
+
+        0.5 / self.half_period.max(self.since) as f32
+    }
+}
+
+/// The colors of a [`Lissajous`] colored by frequency.
+struct FrequencyColors {
+    color_map: ColorMap,
+    /// The frequency range (in Hz) the color map spans
+    range: (f32, f32),
+}
+
+impl FrequencyColors {
+    /// The color band a frequency (in Hz) falls into.
+    fn band(&self, frequency: f32) -> usize {
+        let normalized =
+            ValueScaling::Frequency.value_to_normalized(frequency, self.range.0, self.range.1);
+
+        ((normalized * FREQUENCY_BANDS as f32) as usize).min(FREQUENCY_BANDS - 1)
+    }
+
+    fn color(&self, band: usize) -> vg::Color {
+        self.color_map
+            .color_at((band as f32 + 0.5) / FREQUENCY_BANDS as f32)
+            .into()
+    }
+}
+
+/// State of a [`Lissajous`] that is drawn with persistence.
+#[derive(Default)]
+struct Persistence {
+    image: Option<OffscreenImage>,
+    /// The number of samples that had been written at the last frame.
+    seen: usize,
+    last_frame: Option<Instant>,
+}
+
+/// How a [`Lissajous`] draws its samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LissajousMode {
+    /// Each sample is drawn as an isolated dot.
+    Dots,
+    /// Consecutive samples are connected by lines, like on a goniometer.
+    ///
+    /// This stays readable at low levels, where dots would clump together.
+    Lines,
+}
+
 /// Lissajous for stereo audio data.
 ///
 /// The further points are from the horizontal middle, the more stereo your signal
@@ -29,9 +134,31 @@ type Sample = [f32; 2];
 ///
 /// For more information about lissajous curves, check out the
 /// [Wikipedia entry](https://en.wikipedia.org/wiki/Lissajous_curve) on them.
+///
+/// By default, each sample is drawn as a dot. Using
+/// [`mode`](LissajousModifiers::mode), consecutive samples can be connected by
+/// lines instead, which is how goniometers usually display a signal. Using
+/// [`color_by_frequency`](LissajousModifiers::color_by_frequency), each sample can
+/// be colored by the frequency of the signal at that point, showing which bands
+/// are wide or out of phase.
 pub struct Lissajous<B: Bus<Sample> + 'static> {
     buffer: Arc<Mutex<RingBuffer<Sample>>>,
+    /// The estimated frequency of each sample in the buffer, in cycles per sample
+    frequencies: Arc<Mutex<RingBuffer<f32>>>,
     dispatcher: Arc<dyn Fn(<B as Bus<[f32; 2]>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    sample_rate: Arc<AtomicF32>,
+    point_size: f32,
+    max_points: Option<usize>,
+    /// The total number of samples written to the buffer.
+    written: Arc<AtomicUsize>,
+    decay: Option<f32>,
+    persistence: RefCell<Persistence>,
+    mode: LissajousMode,
+    /// How many of the most recent samples are drawn
+    sample_count: Option<usize>,
+    normalize: bool,
+    frequency_colors: Option<FrequencyColors>,
 }
 
 impl<B: Bus<Sample> + 'static> Lissajous<B> {
@@ -40,15 +167,156 @@ impl<B: Bus<Sample> + 'static> Lissajous<B> {
         let buffer = Arc::new(Mutex::new(RingBuffer::<Sample>::new(duration)));
         let buffer_c = buffer.clone();
 
+        let frequencies = Arc::new(Mutex::new(RingBuffer::<f32>::new(duration)));
+        let frequencies_c = frequencies.clone();
+
+        let written = Arc::new(AtomicUsize::new(0));
+        let written_c = written.clone();
+
+        let crossings = Mutex::new([ZeroCrossings::default(), ZeroCrossings::default()]);
         let dispatcher = bus.register_dispatcher(move |samples| {
-            if let Ok(mut buffer) = buffer_c.lock() {
+            if let (Ok(mut buffer), Ok(mut frequencies)) = (buffer_c.lock(), frequencies_c.lock()) {
+                let mut crossings = lock_or_recover(&crossings);
+
+                written_c.fetch_add(samples.len(), Ordering::Relaxed);
                 for sample in samples {
                     buffer.enqueue(*sample);
+
+                    // Both channels are estimated separately, so that out-of-phase
+                    // content, which cancels out in the mid signal, is covered too
+                    let left = crossings[0].process(sample[0]);
+                    let right = crossings[1].process(sample[1]);
+                    frequencies.enqueue((left + right) / 2.);
                 }
             }
         });
 
-        Self { buffer, dispatcher }.build(cx, |_| {})
+        let sample_rate = Arc::new(AtomicF32::new(bus.sample_rate()));
+        let sample_rate_c = sample_rate.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            sample_rate_c.store(sample_rate, Ordering::Relaxed);
+        });
+
+        Self {
+            buffer,
+            frequencies,
+            dispatcher,
+            sample_rate_handle,
+            sample_rate,
+            point_size: 1.0,
+            max_points: None,
+            written,
+            decay: None,
+            persistence: Default::default(),
+            mode: LissajousMode::Dots,
+            sample_count: None,
+            normalize: false,
+            frequency_colors: None,
+        }
+        .build(cx, |_| {})
+    }
+
+    /// The gain that brings the loudest sample in the given range of the buffer
+    /// to full scale.
+    fn normalization_gain(ring_buf: &RingBuffer<Sample>, range: Range<usize>) -> f32 {
+        let peak = range
+            .map(|i| ring_buf[i][0].abs().max(ring_buf[i][1].abs()))
+            .fold(0., f32::max);
+
+        // Silence would otherwise get amplified into noise
+        if peak > 1e-4 {
+            peak.recip()
+        } else {
+            1.
+        }
+    }
+
+    /// Builds paths for the samples in the given range of the buffer, inside the
+    /// given bounds.
+    ///
+    /// Depending on the mode, each sample is drawn as a dot, or connected to the
+    /// previous one with a line. If the lissajous is colored by frequency, there is
+    /// a path for each color band, otherwise there's a single one.
+    #[allow(clippy::too_many_arguments)]
+    fn trace(
+        &self,
+        ring_buf: &RingBuffer<Sample>,
+        frequencies: &RingBuffer<f32>,
+        range: Range<usize>,
+        step: usize,
+        gain: f32,
+        point_size: f32,
+        (x, y, w, h): (f32, f32, f32, f32),
+    ) -> Vec<vg::Path> {
+        let bands = match self.frequency_colors {
+            Some(_) => FREQUENCY_BANDS,
+            None => 1,
+        };
+        let mut paths = vec![vg::Path::new(); bands];
+
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let mut previous = None;
+
+        for i in range.step_by(step) {
+            let left = (ring_buf[i][0] * gain).clamp(-1., 1.);
+            let right = (ring_buf[i][1] * gain).clamp(-1., 1.);
+
+            let dot_x = left * *TRANSLATE_COS - right * *TRANSLATE_SIN;
+            let dot_y = left * *TRANSLATE_SIN + right * *TRANSLATE_COS;
+
+            let point_x = x + w / 2. - dot_x * w / PI;
+            let point_y = y + h / 2. - dot_y * h / PI;
+
+            let path = match &self.frequency_colors {
+                Some(colors) => &mut paths[colors.band(frequencies[i] * sample_rate)],
+                None => &mut paths[0],
+            };
+
+            match (self.mode, previous) {
+                (LissajousMode::Dots, _) => path.rect(
+                    point_x - point_size / 2.,
+                    point_y - point_size / 2.,
+                    point_size,
+                    point_size,
+                ),
+                (LissajousMode::Lines, None) => path.move_to(point_x, point_y),
+                (LissajousMode::Lines, Some((previous_x, previous_y))) => {
+                    // Each band's path is made up of separate segments
+                    if bands > 1 {
+                        path.move_to(previous_x, previous_y);
+                    }
+                    path.line_to(point_x, point_y);
+                }
+            }
+
+            previous = Some((point_x, point_y));
+        }
+
+        paths
+    }
+
+    /// Fills or strokes the paths built by [`trace`](Self::trace), depending on the
+    /// mode.
+    fn draw_trace(
+        &self,
+        canvas: &mut Canvas,
+        trace: &[vg::Path],
+        color: vg::Color,
+        point_size: f32,
+    ) {
+        for (band, path) in trace.iter().enumerate() {
+            let color = match &self.frequency_colors {
+                Some(colors) => colors.color(band),
+                None => color,
+            };
+
+            match self.mode {
+                LissajousMode::Dots => canvas.fill_path(path, &vg::Paint::color(color)),
+                LissajousMode::Lines => {
+                    canvas.stroke_path(path, &vg::Paint::color(color).with_line_width(point_size))
+                }
+            }
+        }
     }
 }
 
@@ -64,31 +332,183 @@ impl<B: Bus<Sample> + 'static> View for Lissajous<B> {
         let w = bounds.w;
         let h = bounds.h;
 
-        let ring_buf = &(self.buffer.lock().unwrap());
+        let ring_buf = &lock_or_recover(&self.buffer);
+        let frequencies = &lock_or_recover(&self.frequencies);
 
-        canvas.fill_path(
-            &{
-                let mut dots = vg::Path::new();
-
-                for i in 0..ring_buf.len() {
-                    let left = ring_buf[i][0].clamp(-1., 1.);
-                    let right = ring_buf[i][1].clamp(-1., 1.);
-
-                    let dot_x = left * *TRANSLATE_COS - right * *TRANSLATE_SIN;
-                    let dot_y = left * *TRANSLATE_SIN + right * *TRANSLATE_COS;
-
-                    dots.rect(
-                        x + w / 2. - dot_x * w / PI,
-                        y + h / 2. - dot_y * h / PI,
-                        1f32,
-                        1f32,
-                    );
-                }
+        let point_size = self.point_size * cx.scale_factor();
 
-                dots
-            },
-            &vg::Paint::color(cx.font_color().into()),
-        );
+        // Only the most recent samples are drawn if a sample count is set
+        let visible = ring_buf.len() - self.sample_count.unwrap_or(usize::MAX).min(ring_buf.len())
+            ..ring_buf.len();
+
+        // If there are more samples than points we're allowed to draw, only every
+        // n-th sample is drawn
+        let step = match self.max_points {
+            Some(max_points) if max_points > 0 => visible.len().div_ceil(max_points).max(1),
+            _ => 1,
+        };
+
+        let gain = if self.normalize {
+            Self::normalization_gain(ring_buf, visible.clone())
+        } else {
+            1.
+        };
+
+        let color: vg::Color = cx.font_color().into();
+
+        if let Some(decay) = self.decay {
+            let mut persistence = self.persistence.borrow_mut();
+
+            // Only the samples that were written since the last frame get drawn
+            let written = self.written.load(Ordering::Relaxed);
+            let new = written.wrapping_sub(persistence.seen).min(ring_buf.len());
+            persistence.seen = written;
+
+            let now = Instant::now();
+            let elapsed = persistence
+                .last_frame
+                .map_or(0., |t| now.duration_since(t).as_secs_f32() * 1000.);
+            persistence.last_frame = Some(now);
+
+            // The trail's opacity drops by 12 dB every `decay` milliseconds
+            let fade = 1. - 0.25f32.powf(elapsed / decay);
+
+            let Some((image, _)) = OffscreenImage::ensure(
+                &mut persistence.image,
+                canvas,
+                w.ceil() as usize,
+                h.ceil() as usize,
+                vg::ImageFlags::empty(),
+            ) else {
+                return;
+            };
+
+            // Lines are continued from the last sample drawn in the previous frame
+            let start = match self.mode {
+                LissajousMode::Dots => ring_buf.len() - new,
+                LissajousMode::Lines => ring_buf.len() - (new + 1).min(ring_buf.len()),
+            };
+
+            let trace = self.trace(
+                ring_buf,
+                frequencies,
+                start..ring_buf.len(),
+                step,
+                gain,
+                point_size,
+                (0., 0., w, h),
+            );
+
+            image.render(canvas, |canvas| {
+                let mut rect = vg::Path::new();
+                rect.rect(0., 0., w, h);
+
+                canvas.global_composite_operation(vg::CompositeOperation::DestinationOut);
+                canvas.fill_path(&rect, &vg::Paint::color(vg::Color::rgbaf(0., 0., 0., fade)));
+                canvas.global_composite_operation(vg::CompositeOperation::SourceOver);
+
+                self.draw_trace(canvas, &trace, color, point_size);
+            });
+
+            image.blit(canvas, x, y, w, h, 0.);
+        } else {
+            let trace = self.trace(
+                ring_buf,
+                frequencies,
+                visible,
+                step,
+                gain,
+                point_size,
+                (x, y, w, h),
+            );
+            self.draw_trace(canvas, &trace, color, point_size);
+        }
+    }
+}
+
+pub trait LissajousModifiers {
+    /// Sets the size of each drawn point, in logical pixels.
+    fn point_size(self, size: f32) -> Self;
+    /// Limits the number of points drawn per frame.
+    fn max_points(self, max_points: usize) -> Self;
+    /// Draws the lissajous with a fading trail.
+    fn persistence(self, decay: f32) -> Self;
+    /// Sets whether samples are drawn as dots or connected lines.
+    fn mode(self, mode: LissajousMode) -> Self;
+    /// Only draws the given number of most recent samples.
+    fn sample_count(self, count: usize) -> Self;
+    /// Scales the drawn samples so that the loudest one reaches full scale.
+    fn normalize(self) -> Self;
+    /// Colors each sample by the frequency of the signal at that point.
+    fn color_by_frequency(self, color_map: ColorMap, range: (f32, f32)) -> Self;
+}
+
+impl<B: Bus<Sample> + 'static> LissajousModifiers for Handle<'_, Lissajous<B>> {
+    /// Sets the size of each drawn point, in logical pixels.
+    ///
+    /// The size is multiplied by the window's scale factor, so points keep the
+    /// same apparent size on high-DPI displays. Defaults to 1.
+    fn point_size(self, size: f32) -> Self {
+        self.modify(|lissajous| lissajous.point_size = size)
+    }
+    /// Limits the number of points drawn per frame.
+    ///
+    /// If the buffer holds more samples than this, it is decimated by only
+    /// drawing every n-th sample. This keeps large buffers readable and cheap
+    /// to render.
+    fn max_points(self, max_points: usize) -> Self {
+        self.modify(|lissajous| lissajous.max_points = Some(max_points))
+    }
+    /// Draws the lissajous with a fading trail, like an analog scope would.
+    ///
+    /// Instead of redrawing every point in the buffer each frame, only new points
+    /// are rendered into an offscreen image, while older ones fade out. The
+    /// `decay` dictates how long (in ms) it takes for a point's opacity to drop by
+    /// -12 dB. This is a lot cheaper for large buffers, and also shows how dense
+    /// the signal is in any given region.
+    fn persistence(self, decay: f32) -> Self {
+        self.modify(|lissajous| lissajous.decay = Some(decay))
+    }
+    /// Sets whether samples are drawn as dots or connected lines.
+    ///
+    /// In [`LissajousMode::Lines`], the `point_size` sets the width of the lines.
+    /// Defaults to [`LissajousMode::Dots`].
+    fn mode(self, mode: LissajousMode) -> Self {
+        self.modify(|lissajous| lissajous.mode = mode)
+    }
+    /// Only draws the given number of most recent samples, instead of the whole
+    /// buffer.
+    ///
+    /// Connected lines quickly turn into a tangle for long buffers, so this is
+    /// mostly useful in [`LissajousMode::Lines`].
+    fn sample_count(self, count: usize) -> Self {
+        self.modify(|lissajous| lissajous.sample_count = Some(count))
+    }
+    /// Scales the drawn samples so that the loudest one reaches full scale.
+    ///
+    /// This keeps the shape of quiet signals readable, at the cost of no longer
+    /// showing their level.
+    fn normalize(self) -> Self {
+        self.modify(|lissajous| lissajous.normalize = true)
+    }
+    /// Colors each sample by the frequency of the signal at that point, using the
+    /// given [`ColorMap`] for frequencies (in Hz) from the start to the end of the
+    /// `range`.
+    ///
+    /// The frequency is estimated from the time between the zero crossings of
+    /// both channels, so it follows the dominant frequency of the signal. This
+    /// makes it possible to see which bands are wide or out of phase - e.g. bass
+    /// that is spread out across the stereo field.
+    ///
+    /// ```
+    /// Lissajous::new(cx, bus.clone(), 2048)
+    ///     .mode(LissajousMode::Lines)
+    ///     .color_by_frequency(ColorMap::default(), (20.0, 20_000.0));
+    /// ```
+    fn color_by_frequency(self, color_map: ColorMap, range: (f32, f32)) -> Self {
+        self.modify(|lissajous| {
+            lissajous.frequency_colors = Some(FrequencyColors { color_map, range })
+        })
     }
 }
 
@@ -150,3 +570,25 @@ impl View for LissajousGrid {
         canvas.stroke_path(&path, &vg::Paint::color(cx.font_color().into()));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ZeroCrossings;
+
+    #[test]
+    fn zero_crossings_follow_the_frequency() {
+        let mut crossings = ZeroCrossings::default();
+
+        let mut estimate = 0.;
+        for i in 0..1000 {
+            estimate = crossings.process((std::f32::consts::TAU * 0.01 * i as f32 + 0.1).sin());
+        }
+        assert!((estimate - 0.01).abs() < 0.001);
+
+        // Without any crossings, the estimate keeps falling
+        for _ in 0..1000 {
+            estimate = crossings.process(1.0);
+        }
+        assert!(estimate < 0.001);
+    }
+}
@@ -0,0 +1,107 @@
+use nih_plug_vizia::vizia::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::readout::TextReadout;
+use crate::bus::Bus;
+use crate::loudness::LoudnessRange;
+use crate::utils::lock_or_recover;
+
+/// How often the loudness range readout is refreshed.
+const READOUT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Displays the loudness range (LRA) of a signal as text, e.g. "6.4 LU".
+///
+/// See [`LoudnessRange`] for how it is measured. The text is refreshed a few times
+/// per second, and can be styled like any [`Label`] - e.g. via `font_size` and
+/// `color`. To restart the measurement, e.g. when playback starts, send a
+/// [`LoudnessRangeEvent::Reset`] to the readout.
+///
+/// # Example
+///
+/// Loudness range of the whole program, as recommended by EBU R128.
+///
+/// ```
+/// LoudnessRangeReadout::new(cx, bus.clone(), None)
+///     .font_size(14.)
+///     .color(Color::rgb(160, 160, 160));
+/// ```
+pub struct LoudnessRangeReadout<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    range: Arc<Mutex<LoudnessRange>>,
+}
+
+/// Events that can be sent to a [`LoudnessRangeReadout`].
+pub enum LoudnessRangeEvent {
+    /// Clears the measurement.
+    Reset,
+}
+
+enum LoudnessRangeReadoutEvents {
+    Refresh,
+}
+
+impl<B: Bus<f32> + 'static> LoudnessRangeReadout<B> {
+    /// Creates a new [`LoudnessRangeReadout`].
+    ///
+    /// The loudness range is measured over the last `duration` seconds, or over
+    /// everything since the readout was created or last reset if `duration` is
+    /// `None`.
+    pub fn new(cx: &mut Context, bus: Arc<B>, duration: Option<f32>) -> Handle<Self> {
+        let mut range = LoudnessRange::new(duration);
+        range.set_sample_rate(bus.sample_rate());
+
+        let range = Arc::new(Mutex::new(range));
+
+        let range_c = range.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut range = lock_or_recover(&range_c);
+            for sample in samples {
+                range.process(*sample);
+            }
+        });
+
+        let range_c = range.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&range_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            range,
+        }
+        .build(cx, |cx| {
+            TextReadout::attach(cx, Self::text(0.0), READOUT_INTERVAL, || {
+                LoudnessRangeReadoutEvents::Refresh
+            })
+            .width(Stretch(1.0))
+            .height(Stretch(1.0));
+        })
+    }
+
+    fn text(value: f32) -> String {
+        format!("{:.1} LU", value)
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for LoudnessRangeReadout<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("loudness-range-readout")
+    }
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            LoudnessRangeEvent::Reset => {
+                lock_or_recover(&self.range).reset();
+                TextReadout::update(cx, Self::text(0.0));
+            }
+        });
+        event.map(|e, _| match e {
+            LoudnessRangeReadoutEvents::Refresh => {
+                let value = lock_or_recover(&self.range).value();
+                TextReadout::update(cx, Self::text(value));
+            }
+        });
+    }
+}
@@ -1,28 +1,139 @@
 use std::sync::{Arc, Mutex};
 
-use super::{FillFrom, FillModifiers, RangeModifiers};
+use super::readout::TextReadout;
+use super::{
+    FillFrom, FillModifiers, OutOfRange, OutOfRangeModifiers, RangeModifiers, GAIN_REDUCTION_DECAY,
+};
 use crate::accumulators::*;
 use crate::bus::Bus;
-use crate::utils::ValueScaling;
+use crate::loudness::{LoudnessAccumulator, LoudnessWindow};
+use crate::utils::{lock_or_recover, ValueScaling};
+use nih_plug::util::gain_to_db;
 use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::time::Duration;
+
+/// How often the deviation readout is refreshed.
+const READOUT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The time (in ms) it takes for a peak-hold line to cover 75% of the way down to
+/// the meter's value once its hold time has passed.
+const PEAK_HOLD_RELEASE: f32 = 300.0;
+
+/// The RMS window (in ms) used by K-System meters.
+const K_SYSTEM_WINDOW: f32 = 600.0;
+
+/// The marks shown next to K-System meters, in dB relative to their 0 mark.
+const K_SYSTEM_MARKS: [(f32, &str); 12] = [
+    (20.0, "+20"),
+    (16.0, "+16"),
+    (12.0, "+12"),
+    (8.0, "+8"),
+    (4.0, "+4"),
+    (0.0, "0"),
+    (-4.0, "-4"),
+    (-8.0, "-8"),
+    (-12.0, "-12"),
+    (-20.0, "-20"),
+    (-30.0, "-30"),
+    (-40.0, "-40"),
+];
 
 /// Displays some metric as a bar.
 ///
 /// Can display different types of information about a signal:
 ///
 ///    - [`peak`](Self::peak) - Its peak amplitude
+///    - [`true_peak`](Self::true_peak) - Its peak amplitude, including inter-sample peaks
 ///    - [`minima`](Self::minima) - Its minimal amplitude
 ///    - [`rms`](Self::rms) - Its root mean squared level
+///    - [`loudness`](Self::loudness) - Its loudness in LUFS
+///    - [`crest_factor`](Self::crest_factor) - The ratio of its peak to its RMS level
 ///
 /// It's also possible to define your own [`Accumulator`] in order to display some
 /// other information about the incoming signal.
+///
+/// For gain staging, a reference level can be marked on the meter using
+/// [`with_reference_line`](MeterModifiers::with_reference_line), and the recent
+/// maximum can be held using [`peak_hold`](MeterModifiers::peak_hold).
+///
+/// If the value lies outside of the meter's range, a small arrow is drawn at the
+/// corresponding end of the meter, using the `color`. This can be disabled via
+/// [`out_of_range_indicators`](OutOfRangeModifiers::out_of_range_indicators).
 pub struct Meter<B: Bus<f32> + 'static, A: Accumulator + 'static> {
     dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
     accumulator: Arc<Mutex<A>>,
     range: (f32, f32),
     scaling: ValueScaling,
     fill_from: FillFrom,
     orientation: Orientation,
+    /// The reference level, in the same unit as the range
+    reference: Option<f32>,
+    readout: bool,
+    range_indicators: bool,
+    peak_hold: Arc<Mutex<PeakHold>>,
+}
+
+/// Holds the maximum of a meter's recent values.
+struct PeakHold {
+    /// The hold time in ms, or `None` if peak-hold is disabled
+    hold: Option<f32>,
+    sample_rate: f32,
+    hold_samples: f32,
+    release_weight: f32,
+    value: f32,
+    /// The number of values since the held value was last reached
+    age: f32,
+}
+
+impl PeakHold {
+    fn new(sample_rate: f32) -> Self {
+        let mut peak_hold = Self {
+            hold: None,
+            sample_rate,
+            hold_samples: 0.0,
+            release_weight: 0.0,
+            value: f32::NEG_INFINITY,
+            age: 0.0,
+        };
+        peak_hold.update();
+        peak_hold
+    }
+
+    fn update(&mut self) {
+        self.hold_samples = self.hold.unwrap_or(0.0) / 1000.0 * self.sample_rate;
+        self.release_weight = 0.25f64
+            .powf(((PEAK_HOLD_RELEASE / 1000.0) as f64 * self.sample_rate as f64).recip())
+            as f32;
+
+        // Both are undefined for an unknown sample rate
+        if !self.hold_samples.is_finite() || !self.release_weight.is_finite() {
+            self.hold_samples = 0.0;
+            self.release_weight = 0.0;
+        }
+    }
+
+    fn set_hold(&mut self, hold: Option<f32>) {
+        self.hold = hold;
+        self.update();
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.update();
+    }
+
+    #[inline]
+    fn process(&mut self, value: f32) {
+        if value >= self.value {
+            self.value = value;
+            self.age = 0.0;
+        } else if self.age < self.hold_samples {
+            self.age += 1.0;
+        } else {
+            self.value = self.value * self.release_weight + value * (1.0 - self.release_weight);
+        }
+    }
 }
 
 impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Meter<B, A> {
@@ -41,21 +152,45 @@ impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Meter<B, A> {
         let accumulator = Arc::new(Mutex::new(accumulator));
         let accumulator_c = accumulator.clone();
 
+        let peak_hold = Arc::new(Mutex::new(PeakHold::new(bus.sample_rate())));
+        let peak_hold_c = peak_hold.clone();
+
         let dispatcher_handle = bus.register_dispatcher(move |samples| {
-            if let Ok(mut acc) = accumulator_c.lock() {
+            if let (Ok(mut acc), Ok(mut peak_hold)) = (accumulator_c.lock(), peak_hold_c.lock()) {
+                let holding = peak_hold.hold.is_some();
+
                 for sample in samples {
-                    let _ = acc.accumulate(*sample);
+                    if let Some(value) = acc.accumulate(*sample) {
+                        if holding {
+                            peak_hold.process(value);
+                        }
+                    }
                 }
             }
         });
 
+        let accumulator_c = accumulator.clone();
+        let peak_hold_c = peak_hold.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            let mut acc = lock_or_recover(&accumulator_c);
+            acc.set_sample_rate(sample_rate);
+            acc.set_size(sample_rate as usize);
+
+            lock_or_recover(&peak_hold_c).set_sample_rate(sample_rate);
+        });
+
         Self {
             dispatcher_handle,
+            sample_rate_handle,
             range: range.get_val(cx),
             scaling: scaling.get_val(cx),
             fill_from: FillFrom::Bottom,
             orientation,
             accumulator,
+            reference: None,
+            readout: false,
+            range_indicators: true,
+            peak_hold,
         }
         .build(cx, |_| {})
         .range(range)
@@ -66,6 +201,23 @@ impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Meter<B, A> {
 enum MeterEvents {
     UpdateRange((f32, f32)),
     UpdateScaling(ValueScaling),
+    RefreshReadout,
+}
+
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> Meter<B, A> {
+    /// The current deviation from the reference level, e.g. "+1.5 dB".
+    fn readout_text(&self) -> String {
+        let Some(reference) = self.reference else {
+            return String::new();
+        };
+
+        let value = lock_or_recover(&self.accumulator).prev();
+
+        match self.scaling {
+            ValueScaling::Decibels => format!("{:+.1} dB", gain_to_db(value) - reference),
+            _ => format!("{:+.2}", value - reference),
+        }
+    }
 }
 
 impl<B: Bus<f32> + 'static, A: Accumulator + 'static> View for Meter<B, A> {
@@ -80,7 +232,7 @@ impl<B: Bus<f32> + 'static, A: Accumulator + 'static> View for Meter<B, A> {
         let w = bounds.w;
         let h = bounds.h;
 
-        let sample = self.accumulator.lock().unwrap().prev();
+        let sample = lock_or_recover(&self.accumulator).prev();
 
         let level = self
             .scaling
@@ -135,15 +287,156 @@ impl<B: Bus<f32> + 'static, A: Accumulator + 'static> View for Meter<B, A> {
                 canvas.fill_path(&path, &vg::Paint::color(cx.background_color().into()));
             }
         };
+
+        if let Some(side) =
+            OutOfRange::check(sample, self.scaling, self.range).filter(|_| self.range_indicators)
+        {
+            let size = (4. * cx.scale_factor()).min(w.min(h) / 2.);
+
+            // An arrow at the end of the meter, pointing outwards
+            let mut arrow = vg::Path::new();
+            match (self.orientation, side) {
+                (Orientation::Vertical, OutOfRange::Above) => {
+                    arrow.move_to(x + w / 2., y);
+                    arrow.line_to(x + w / 2. + size, y + size);
+                    arrow.line_to(x + w / 2. - size, y + size);
+                }
+                (Orientation::Vertical, OutOfRange::Below) => {
+                    arrow.move_to(x + w / 2., y + h);
+                    arrow.line_to(x + w / 2. + size, y + h - size);
+                    arrow.line_to(x + w / 2. - size, y + h - size);
+                }
+                (Orientation::Horizontal, OutOfRange::Above) => {
+                    arrow.move_to(x + w, y + h / 2.);
+                    arrow.line_to(x + w - size, y + h / 2. + size);
+                    arrow.line_to(x + w - size, y + h / 2. - size);
+                }
+                (Orientation::Horizontal, OutOfRange::Below) => {
+                    arrow.move_to(x, y + h / 2.);
+                    arrow.line_to(x + size, y + h / 2. + size);
+                    arrow.line_to(x + size, y + h / 2. - size);
+                }
+            }
+            arrow.close();
+
+            canvas.fill_path(&arrow, &vg::Paint::color(cx.font_color().into()));
+        }
+
+        let peak_hold = {
+            let peak_hold = lock_or_recover(&self.peak_hold);
+            peak_hold.hold.map(|_| peak_hold.value)
+        };
+
+        if let Some(held) = peak_hold.filter(|held| held.is_finite()) {
+            let held_n = self
+                .scaling
+                .value_to_normalized(held, self.range.0, self.range.1);
+
+            let mut line = vg::Path::new();
+            match self.orientation {
+                Orientation::Vertical => {
+                    line.move_to(x, y + h * (1. - held_n));
+                    line.line_to(x + w, y + h * (1. - held_n));
+                }
+                Orientation::Horizontal => {
+                    line.move_to(x + w * held_n, y);
+                    line.line_to(x + w * held_n, y + h);
+                }
+            }
+
+            canvas.stroke_path(
+                &line,
+                &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+            );
+        }
+
+        if let Some(reference) = self.reference {
+            let reference_n =
+                ValueScaling::Linear.value_to_normalized(reference, self.range.0, self.range.1);
+
+            let mut line = vg::Path::new();
+            match self.orientation {
+                Orientation::Vertical => {
+                    line.move_to(x, y + h * (1. - reference_n));
+                    line.line_to(x + w, y + h * (1. - reference_n));
+                }
+                Orientation::Horizontal => {
+                    line.move_to(x + w * reference_n, y);
+                    line.line_to(x + w * reference_n, y + h);
+                }
+            }
+
+            canvas.stroke_path(
+                &line,
+                &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+            );
+        }
     }
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|e, _| match e {
             MeterEvents::UpdateRange(v) => self.range = *v,
             MeterEvents::UpdateScaling(v) => self.scaling = *v,
+            MeterEvents::RefreshReadout => {
+                if self.readout {
+                    TextReadout::update(cx, self.readout_text());
+                }
+            }
         });
     }
 }
 
+pub trait MeterModifiers {
+    fn with_reference_line(self, level: f32) -> Self;
+    fn with_deviation_readout(self) -> Self;
+    fn peak_hold(self, hold: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> MeterModifiers
+    for Handle<'a, Meter<B, A>>
+{
+    /// Draws a line at a reference level, e.g. -18 dBFS for gain staging.
+    ///
+    /// The `level` is given in the same unit as the meter's range - e.g. `-18.0`
+    /// for -18 dBFS on a meter using [`ValueScaling::Decibels`]. The line is drawn
+    /// using the meter's `color`.
+    fn with_reference_line(self, level: f32) -> Self {
+        self.modify(|meter| meter.reference = Some(level))
+    }
+    /// Shows how far the meter's current value deviates from the reference level,
+    /// as text in the meter's top left corner.
+    ///
+    /// Combined with an [`rms`](Meter::rms) meter, this shows how much gain is
+    /// needed to match the reference. The readout is refreshed a few times per
+    /// second and drawn in the meter's `color`. It needs a reference line to be set
+    /// via [`with_reference_line`](Self::with_reference_line).
+    fn with_deviation_readout(mut self) -> Self {
+        let e = self.entity();
+
+        let mut text = String::new();
+        self = self.modify(|meter| {
+            meter.readout = true;
+            text = meter.readout_text();
+        });
+
+        self.context().with_current(e, |cx| {
+            TextReadout::attach(cx, text, READOUT_INTERVAL, || MeterEvents::RefreshReadout)
+                .position_type(PositionType::SelfDirected)
+                .left(Pixels(2.0))
+                .top(Pixels(2.0));
+        });
+
+        self
+    }
+    /// Draws a thin line at the meter's recent maximum.
+    ///
+    /// The line stays at the maximum for `hold` ms, and then falls back towards the
+    /// meter's current value, unless a new maximum is reached in the meantime. It is
+    /// drawn using the meter's `color`.
+    fn peak_hold(self, hold: f32) -> Self {
+        self.modify(|meter| lock_or_recover(&meter.peak_hold).set_hold(Some(hold)))
+    }
+}
+
 impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> FillModifiers
     for Handle<'a, Meter<B, A>>
 {
@@ -165,6 +458,18 @@ impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> FillModifiers
     }
 }
 
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> OutOfRangeModifiers
+    for Handle<'a, Meter<B, A>>
+{
+    /// Enables or disables the arrow drawn at the end of the meter whenever its
+    /// value lies outside of its range.
+    ///
+    /// The indicators are enabled by default.
+    fn out_of_range_indicators(self, enabled: bool) -> Self {
+        self.modify(|meter| meter.range_indicators = enabled)
+    }
+}
+
 impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> RangeModifiers
     for Handle<'a, Meter<B, A>>
 {
@@ -225,6 +530,46 @@ impl<B: Bus<f32> + 'static> Meter<B, PeakAccumulator> {
         )
     }
 }
+impl<B: Bus<f32> + 'static> Meter<B, TruePeakAccumulator> {
+    /// Creates a true peak meter.
+    ///
+    /// Unlike the regular [`peak`](Meter::peak) meter, it also catches peaks in
+    /// between samples, which is what limiters and mastering tools need to display.
+    ///
+    /// # Example
+    ///
+    /// True peak meter with a 50ms-long decay for each peak.
+    ///
+    /// ```
+    /// Meter::true_peak(
+    ///     cx,
+    ///     bus.clone(),
+    ///     50.0,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    ///     Orientation::Vertical,
+    /// )
+    /// .color(Color::rgba(255, 255, 255, 60))
+    /// .background_color(Color::rgba(255, 255, 255, 30));
+    /// ```
+    pub fn true_peak(
+        cx: &mut Context,
+        bus: Arc<B>,
+        decay: f32,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            TruePeakAccumulator::new(1.0, decay),
+            range,
+            scaling,
+            orientation,
+        )
+    }
+}
 impl<B: Bus<f32> + 'static> Meter<B, MinimumAccumulator> {
     /// Creates a peak meter.
     ///
@@ -263,6 +608,39 @@ impl<B: Bus<f32> + 'static> Meter<B, MinimumAccumulator> {
         )
     }
 }
+impl<B: Bus<f32> + 'static> Meter<B, MinimumAccumulator> {
+    /// Creates a gain reduction meter.
+    ///
+    /// The bus should carry the gain that your processor applies, as a linear
+    /// factor - e.g. 0.5 for 6 dB of gain reduction. A [`ValueBus`] lets you send
+    /// this gain once per buffer. The meter uses a decibel scale, is filled from the
+    /// top, and smoothly releases after each reduction.
+    ///
+    /// [`ValueBus`]: crate::bus::ValueBus
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Meter::gain_reduction(cx, gain_reduction_bus.clone(), (-24.0, 0.0), Orientation::Vertical)
+    ///     .background_color(Color::rgba(255, 92, 92, 120));
+    /// ```
+    pub fn gain_reduction(
+        cx: &mut Context,
+        bus: Arc<B>,
+        range: impl Res<(f32, f32)> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::minima(
+            cx,
+            bus,
+            GAIN_REDUCTION_DECAY,
+            range,
+            ValueScaling::Decibels,
+            orientation,
+        )
+        .fill_from_max()
+    }
+}
 impl<B: Bus<f32> + 'static> Meter<B, RMSAccumulator> {
     /// Creates an RMS meter.
     ///
@@ -299,4 +677,286 @@ impl<B: Bus<f32> + 'static> Meter<B, RMSAccumulator> {
             orientation,
         )
     }
+
+    /// Creates a meter following one of Bob Katz's K-System scales.
+    ///
+    /// The meter shows the RMS level over a 600 ms window, with a reference line at
+    /// the scale's 0 mark - e.g. -14 dBFS for [`KSystem::K14`]. Its range reaches
+    /// from 40 dB below the 0 mark up to 0 dBFS. Pair it with a [`UnitRuler`] using
+    /// the scale's [`markings`](KSystem::markings), which are labelled relative to
+    /// the 0 mark.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let k = KSystem::K14;
+    ///
+    /// HStack::new(cx, |cx| {
+    ///     Meter::k_system(cx, bus.clone(), k, Orientation::Vertical)
+    ///         .color(Color::rgb(255, 255, 255))
+    ///         .background_color(Color::rgba(255, 255, 255, 60));
+    ///     UnitRuler::new(
+    ///         cx,
+    ///         k.range(),
+    ///         ValueScaling::Linear,
+    ///         k.markings(),
+    ///         Orientation::Vertical,
+    ///     )
+    ///     .font_size(12.)
+    ///     .width(Pixels(32.));
+    /// });
+    /// ```
+    ///
+    /// [`UnitRuler`]: super::UnitRuler
+    pub fn k_system(
+        cx: &mut Context,
+        bus: Arc<B>,
+        k: KSystem,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::rms(
+            cx,
+            bus,
+            K_SYSTEM_WINDOW,
+            k.range(),
+            ValueScaling::Decibels,
+            orientation,
+        )
+        .with_reference_line(k.reference())
+    }
+}
+
+/// The scales of the K-System, a metering practice proposed by Bob Katz.
+///
+/// Each scale places its 0 mark some amount of dB below full scale, leaving that
+/// much headroom for peaks. Used by [`Meter::k_system`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KSystem {
+    /// 12 dB of headroom, for broadcast.
+    K12,
+    /// 14 dB of headroom, for most pop and rock music.
+    K14,
+    /// 20 dB of headroom, for highly dynamic music and film.
+    K20,
+}
+
+impl KSystem {
+    /// The headroom above the 0 mark, in dB.
+    pub fn headroom(&self) -> f32 {
+        match self {
+            KSystem::K12 => 12.0,
+            KSystem::K14 => 14.0,
+            KSystem::K20 => 20.0,
+        }
+    }
+
+    /// The level of the 0 mark, in dBFS.
+    pub fn reference(&self) -> f32 {
+        -self.headroom()
+    }
+
+    /// The range of a K-System meter, in dBFS.
+    pub fn range(&self) -> (f32, f32) {
+        (self.reference() - 40.0, 0.0)
+    }
+
+    /// Marks for a [`UnitRuler`](super::UnitRuler) next to a K-System meter.
+    ///
+    /// The marks are placed in dBFS, but labelled in dB relative to the 0 mark.
+    pub fn markings(&self) -> Vec<(f32, &'static str)> {
+        K_SYSTEM_MARKS
+            .iter()
+            .filter(|(mark, _)| *mark <= self.headroom())
+            .map(|(mark, label)| (mark + self.reference(), *label))
+            .collect()
+    }
+}
+
+impl<B: Bus<f32> + 'static> Meter<B, BallisticsAccumulator> {
+    /// Creates a meter with the given [`Ballistics`].
+    ///
+    /// This lets you switch between different metering behaviors by changing a
+    /// single argument.
+    ///
+    /// # Example
+    ///
+    /// Type II PPM meter.
+    ///
+    /// ```
+    /// Meter::with_ballistics(
+    ///     cx,
+    ///     bus.clone(),
+    ///     Ballistics::PpmTypeII,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    ///     Orientation::Vertical,
+    /// )
+    /// .background_color(Color::rgba(255, 255, 255, 30));
+    /// ```
+    pub fn with_ballistics(
+        cx: &mut Context,
+        bus: Arc<B>,
+        ballistics: Ballistics,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            BallisticsAccumulator::new(1.0, ballistics),
+            range,
+            scaling,
+            orientation,
+        )
+    }
+
+    /// Creates a VU meter.
+    ///
+    /// It integrates the rectified signal over 300 ms, and briefly overshoots by
+    /// about 1% on sudden changes, just like the needle of an analog VU meter. See
+    /// [`Ballistics::VU`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Meter::vu(
+    ///     cx,
+    ///     bus.clone(),
+    ///     (-20.0, 3.0),
+    ///     ValueScaling::Decibels,
+    ///     Orientation::Horizontal,
+    /// )
+    /// .background_color(Color::rgba(255, 255, 255, 30));
+    /// ```
+    pub fn vu(
+        cx: &mut Context,
+        bus: Arc<B>,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_ballistics(cx, bus, Ballistics::VU, range, scaling, orientation)
+    }
+}
+impl<B: Bus<f32> + 'static> Meter<B, SlewAccumulator> {
+    /// Creates a meter showing the rate at which the level changes, in dB/s.
+    ///
+    /// The meter is filled from 0 dB/s, so that it rises while the level rises
+    /// and falls while the level falls. This is useful for visualizing the pumping
+    /// of a compressor.
+    ///
+    /// # Example
+    ///
+    /// Slew meter smoothed over 50 ms, showing up to 60 dB/s in either direction.
+    ///
+    /// ```
+    /// Meter::slew(cx, bus.clone(), 50.0, (-60.0, 60.0), Orientation::Vertical)
+    ///     .background_color(Color::rgba(255, 255, 255, 60));
+    /// ```
+    pub fn slew(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window_size: f32,
+        range: impl Res<(f32, f32)> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            SlewAccumulator::new(1.0, window_size),
+            range,
+            ValueScaling::Linear,
+            orientation,
+        )
+        .fill_from_value(0.0)
+    }
+}
+impl<B: Bus<f32> + 'static> Meter<B, CrestFactorAccumulator> {
+    /// Creates a meter showing the crest factor, in dB.
+    ///
+    /// The crest factor is the ratio of the peak to the RMS level, both measured
+    /// over the given window (in ms). It shows how squashed a signal is - the more
+    /// it gets compressed or limited, the lower its crest factor.
+    ///
+    /// # Example
+    ///
+    /// Crest factor meter over a 300 ms window, showing up to 24 dB.
+    ///
+    /// ```
+    /// Meter::crest_factor(cx, bus.clone(), 300.0, (0.0, 24.0), Orientation::Vertical)
+    ///     .background_color(Color::rgba(255, 255, 255, 60));
+    /// ```
+    pub fn crest_factor(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window_size: f32,
+        range: impl Res<(f32, f32)> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            CrestFactorAccumulator::new(1.0, window_size),
+            range,
+            ValueScaling::Linear,
+            orientation,
+        )
+    }
+}
+impl<B: Bus<f32> + 'static> Meter<B, LoudnessAccumulator> {
+    /// Creates a loudness meter, measuring in LUFS over the given
+    /// [`LoudnessWindow`].
+    ///
+    /// The range is given in LUFS. See the [`loudness`](crate::loudness) module for
+    /// how the loudness is measured.
+    ///
+    /// # Example
+    ///
+    /// Integrated loudness meter.
+    ///
+    /// ```
+    /// Meter::loudness(
+    ///     cx,
+    ///     bus.clone(),
+    ///     LoudnessWindow::Integrated,
+    ///     (-36.0, 0.0),
+    ///     Orientation::Vertical,
+    /// )
+    /// .background_color(Color::rgba(255, 255, 255, 60));
+    /// ```
+    pub fn loudness(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window: LoudnessWindow,
+        range: impl Res<(f32, f32)> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            LoudnessAccumulator::new(1.0, window),
+            range,
+            ValueScaling::Linear,
+            orientation,
+        )
+    }
+
+    /// Creates a meter showing the momentary loudness in LUFS, measured over the
+    /// last 400 ms.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Meter::lufs_momentary(cx, bus.clone(), (-36.0, 0.0), Orientation::Vertical)
+    ///     .background_color(Color::rgba(255, 255, 255, 60));
+    /// ```
+    pub fn lufs_momentary(
+        cx: &mut Context,
+        bus: Arc<B>,
+        range: impl Res<(f32, f32)> + Clone,
+        orientation: Orientation,
+    ) -> Handle<Self> {
+        Self::loudness(cx, bus, LoudnessWindow::Momentary, range, orientation)
+    }
 }
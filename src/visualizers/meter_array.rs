@@ -0,0 +1,259 @@
+use std::sync::{Arc, Mutex};
+
+use super::RangeModifiers;
+use crate::accumulators::*;
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, ValueScaling};
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+/// The gap between two bars, in logical pixels.
+const BAR_GAP: f32 = 2.0;
+
+/// The height reserved for the channel labels, in logical pixels.
+const LABEL_HEIGHT: f32 = 16.0;
+
+/// Displays some metric of each channel of a multichannel signal, as a row of
+/// bars.
+///
+/// This is the meter bridge of surround, ambisonic and other multichannel
+/// plug-ins: all bars share the same range and scaling, so that channels are easy
+/// to compare. Instead of building a [`Meter`](super::Meter) for each channel, the
+/// whole bus is metered by a single view. Like a meter, each bar is filled using
+/// the `background_color`, with a line at its level in the `color`. Each channel
+/// can be labeled using [`with_labels`](MeterArrayModifiers::with_labels).
+///
+/// # Example
+///
+/// ```
+/// let surround_bus: Arc<MultiChannelBus<6>> = Default::default();
+///
+/// // Inside your editor
+/// MeterArray::peak(
+///     cx,
+///     surround_bus.clone(),
+///     50.0,
+///     (-48.0, 6.0),
+///     ValueScaling::Decibels,
+/// )
+/// .with_labels(["L", "R", "C", "LFE", "Ls", "Rs"])
+/// .color(Color::rgba(255, 255, 255, 160))
+/// .background_color(Color::rgba(255, 255, 255, 40));
+/// ```
+pub struct MeterArray<B: Bus<[f32; C]> + 'static, A: Accumulator + 'static, const C: usize> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<[f32; C]>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    /// One accumulator for each channel
+    accumulators: Arc<Mutex<Vec<A>>>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+    labeled: bool,
+}
+
+enum MeterArrayEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+}
+
+impl<B: Bus<[f32; C]> + 'static, A: Accumulator + 'static, const C: usize> MeterArray<B, A, C> {
+    /// Creates a new [`MeterArray`], using an [`Accumulator`] created by the given
+    /// function for each channel.
+    pub fn with_accumulator(
+        cx: &mut Context,
+        bus: Arc<B>,
+        accumulator: impl Fn() -> A,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        let accumulators = (0..C)
+            .map(|_| {
+                let mut accumulator = accumulator();
+                accumulator.set_sample_rate(bus.sample_rate());
+                accumulator.set_size(bus.sample_rate() as usize);
+                accumulator
+            })
+            .collect::<Vec<A>>();
+
+        let accumulators = Arc::new(Mutex::new(accumulators));
+
+        let accumulators_c = accumulators.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut accumulators = lock_or_recover(&accumulators_c);
+            for sample in samples {
+                for (accumulator, value) in accumulators.iter_mut().zip(sample.iter()) {
+                    accumulator.accumulate(*value);
+                }
+            }
+        });
+
+        let accumulators_c = accumulators.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            for accumulator in lock_or_recover(&accumulators_c).iter_mut() {
+                accumulator.set_sample_rate(sample_rate);
+                accumulator.set_size(sample_rate as usize);
+            }
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            accumulators,
+            range: range.get_val(cx),
+            scaling: scaling.get_val(cx),
+            labeled: false,
+        }
+        .build(cx, |_| {})
+        .range(range)
+        .scaling(scaling)
+    }
+}
+
+impl<B: Bus<[f32; C]> + 'static, const C: usize> MeterArray<B, PeakAccumulator, C> {
+    /// Creates a peak meter for each channel, each with the given `decay` (in ms).
+    pub fn peak(
+        cx: &mut Context,
+        bus: Arc<B>,
+        decay: f32,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        Self::with_accumulator(cx, bus, || PeakAccumulator::new(1.0, decay), range, scaling)
+    }
+}
+
+impl<B: Bus<[f32; C]> + 'static, const C: usize> MeterArray<B, RMSAccumulator, C> {
+    /// Creates an RMS meter for each channel, each measuring over a window of the
+    /// given size (in ms).
+    pub fn rms(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window_size: f32,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            || RMSAccumulator::new(1.0, window_size),
+            range,
+            scaling,
+        )
+    }
+}
+
+impl<B: Bus<[f32; C]> + 'static, A: Accumulator + 'static, const C: usize> View
+    for MeterArray<B, A, C>
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("meter-array")
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            MeterArrayEvents::UpdateRange(range) => self.range = *range,
+            MeterArrayEvents::UpdateScaling(scaling) => self.scaling = *scaling,
+        });
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        if C == 0 {
+            return;
+        }
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = if self.labeled {
+            (bounds.h - LABEL_HEIGHT * cx.scale_factor()).max(0.)
+        } else {
+            bounds.h
+        };
+
+        let gap = BAR_GAP * cx.scale_factor();
+        let bar_width = ((w - gap * (C - 1) as f32) / C as f32).max(0.);
+
+        let levels = lock_or_recover(&self.accumulators)
+            .iter()
+            .map(|accumulator| {
+                self.scaling
+                    .value_to_normalized(accumulator.prev(), self.range.0, self.range.1)
+                    .clamp(0., 1.)
+            })
+            .collect::<Vec<f32>>();
+
+        let mut fills = vg::Path::new();
+        let mut lines = vg::Path::new();
+
+        for (channel, level) in levels.into_iter().enumerate() {
+            let bar_x = x + channel as f32 * (bar_width + gap);
+            let level_y = y + h * (1. - level);
+
+            fills.rect(bar_x, level_y, bar_width, y + h - level_y);
+
+            lines.move_to(bar_x, level_y);
+            lines.line_to(bar_x + bar_width, level_y);
+        }
+
+        canvas.fill_path(&fills, &vg::Paint::color(cx.background_color().into()));
+        canvas.stroke_path(
+            &lines,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+        );
+    }
+}
+
+pub trait MeterArrayModifiers {
+    fn with_labels(self, labels: impl IntoIterator<Item = impl ToString>) -> Self;
+}
+
+impl<'a, B: Bus<[f32; C]> + 'static, A: Accumulator + 'static, const C: usize> MeterArrayModifiers
+    for Handle<'a, MeterArray<B, A, C>>
+{
+    /// Labels each channel's bar, e.g. with the name of its speaker.
+    ///
+    /// The labels are placed below the bars, in order, and drawn in the view's
+    /// `color`. Each has the `channel` class, so it can be styled using CSS.
+    fn with_labels(mut self, labels: impl IntoIterator<Item = impl ToString>) -> Self {
+        let e = self.entity();
+
+        self = self.modify(|meters| meters.labeled = true);
+
+        self.context().with_current(e, |cx| {
+            for (channel, label) in labels.into_iter().take(C).enumerate() {
+                Label::new(cx, &label.to_string())
+                    .class("channel")
+                    .position_type(PositionType::SelfDirected)
+                    .left(Percentage(channel as f32 * 100. / C as f32))
+                    .width(Percentage(100. / C as f32))
+                    .height(Pixels(LABEL_HEIGHT))
+                    .top(Stretch(1.0))
+                    .text_align(TextAlign::Center)
+                    .hoverable(false);
+            }
+        });
+
+        self
+    }
+}
+
+impl<'a, B: Bus<[f32; C]> + 'static, A: Accumulator + 'static, const C: usize> RangeModifiers
+    for Handle<'a, MeterArray<B, A, C>>
+{
+    fn range(mut self, range: impl Res<(f32, f32)>) -> Self {
+        let e = self.entity();
+
+        range.set_or_bind(self.context(), e, move |cx, r| {
+            (*cx).emit_to(e, MeterArrayEvents::UpdateRange(r));
+        });
+
+        self
+    }
+    fn scaling(mut self, scaling: impl Res<ValueScaling>) -> Self {
+        let e = self.entity();
+
+        scaling.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, MeterArrayEvents::UpdateScaling(s));
+        });
+
+        self
+    }
+}
@@ -0,0 +1,237 @@
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::RangeModifiers;
+use crate::accumulators::*;
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, ValueScaling};
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+/// How long the clip indicator stays lit after the signal has clipped.
+const CLIP_HOLD: Duration = Duration::from_millis(1500);
+
+/// A minimal, vertical meter that only takes up a few pixels of width.
+///
+/// It is meant to sit right next to knobs or other controls in tight layouts. Unlike
+/// the [`Meter`](super::Meter), it doesn't support different fill modes or
+/// orientations, and draws just a single rectangle - which makes it very cheap to
+/// draw dozens of them at once.
+///
+/// The bar is drawn using the `background_color`. Optionally, a clip indicator
+/// can be shown at the top of the meter using
+/// [`clip_indicator`](MiniMeterModifiers::clip_indicator), which is drawn using the
+/// `color`.
+pub struct MiniMeter<B: Bus<f32> + 'static, A: Accumulator + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    accumulator: Arc<Mutex<A>>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+    clip_threshold: Arc<Mutex<Option<f32>>>,
+    clipped: Arc<AtomicBool>,
+    last_clip: Cell<Option<Instant>>,
+}
+
+enum MiniMeterEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+}
+
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> MiniMeter<B, A> {
+    /// Creates a new [`MiniMeter`] which uses the provided [`Accumulator`].
+    pub fn with_accumulator(
+        cx: &mut Context,
+        bus: Arc<B>,
+        mut accumulator: A,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        accumulator.set_sample_rate(bus.sample_rate());
+        accumulator.set_size(bus.sample_rate() as usize);
+
+        let accumulator = Arc::new(Mutex::new(accumulator));
+        let accumulator_c = accumulator.clone();
+
+        let clip_threshold: Arc<Mutex<Option<f32>>> = Default::default();
+        let clip_threshold_c = clip_threshold.clone();
+
+        let clipped: Arc<AtomicBool> = Default::default();
+        let clipped_c = clipped.clone();
+
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let threshold = *lock_or_recover(&clip_threshold_c);
+
+            let mut acc = lock_or_recover(&accumulator_c);
+            for sample in samples {
+                let _ = acc.accumulate(*sample);
+
+                if threshold.is_some_and(|t| sample.abs() >= t) {
+                    clipped_c.store(true, Ordering::Relaxed);
+                }
+            }
+        });
+
+        let accumulator_c = accumulator.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            let mut acc = lock_or_recover(&accumulator_c);
+            acc.set_sample_rate(sample_rate);
+            acc.set_size(sample_rate as usize);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            accumulator,
+            range: range.get_val(cx),
+            scaling: scaling.get_val(cx),
+            clip_threshold,
+            clipped,
+            last_clip: Cell::new(None),
+        }
+        .build(cx, |_| {})
+        .range(range)
+        .scaling(scaling)
+    }
+}
+
+impl<B: Bus<f32> + 'static, A: Accumulator + 'static> View for MiniMeter<B, A> {
+    fn element(&self) -> Option<&'static str> {
+        Some("mini-meter")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let level = self.scaling.value_to_normalized(
+            lock_or_recover(&self.accumulator).prev(),
+            self.range.0,
+            self.range.1,
+        );
+
+        let mut bar = vg::Path::new();
+        bar.rect(x, y + h * (1. - level), w, h * level);
+        canvas.fill_path(&bar, &vg::Paint::color(cx.background_color().into()));
+
+        if self.clipped.swap(false, Ordering::Relaxed) {
+            self.last_clip.set(Some(Instant::now()));
+        }
+
+        if self
+            .last_clip
+            .get()
+            .is_some_and(|t| t.elapsed() < CLIP_HOLD)
+        {
+            // The clip indicator is a dot as wide as the meter, at its very top
+            let mut dot = vg::Path::new();
+            dot.circle(x + w / 2., y + w / 2., w / 2.);
+            canvas.fill_path(&dot, &vg::Paint::color(cx.font_color().into()));
+        }
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            MiniMeterEvents::UpdateRange(v) => self.range = *v,
+            MiniMeterEvents::UpdateScaling(v) => self.scaling = *v,
+        });
+    }
+}
+
+pub trait MiniMeterModifiers {
+    fn clip_indicator(self, threshold: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> MiniMeterModifiers
+    for Handle<'a, MiniMeter<B, A>>
+{
+    /// Shows a dot at the top of the meter whenever the signal clips.
+    ///
+    /// The signal counts as clipping whenever a sample's absolute value reaches the
+    /// `threshold`, given as a linear gain - e.g. `1.0` for 0 dBFS. The dot then
+    /// stays lit for a moment, so that short clips don't go unnoticed.
+    fn clip_indicator(self, threshold: f32) -> Self {
+        self.modify(|meter| *lock_or_recover(&meter.clip_threshold) = Some(threshold))
+    }
+}
+
+impl<'a, B: Bus<f32> + 'static, A: Accumulator + 'static> RangeModifiers
+    for Handle<'a, MiniMeter<B, A>>
+{
+    fn range(mut self, range: impl Res<(f32, f32)>) -> Self {
+        let e = self.entity();
+
+        range.set_or_bind(self.context(), e, move |cx, r| {
+            (*cx).emit_to(e, MiniMeterEvents::UpdateRange(r));
+        });
+
+        self
+    }
+    fn scaling(mut self, scaling: impl Res<ValueScaling>) -> Self {
+        let e = self.entity();
+
+        scaling.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, MiniMeterEvents::UpdateScaling(s));
+        });
+
+        self
+    }
+}
+
+impl<B: Bus<f32> + 'static> MiniMeter<B, PeakAccumulator> {
+    /// Creates a peak mini meter.
+    ///
+    /// # Example
+    ///
+    /// Peak mini meter with a 50ms-long decay for each peak, that indicates clipping
+    /// above 0 dBFS.
+    ///
+    /// ```
+    /// MiniMeter::peak(cx, bus.clone(), 50.0, (-32.0, 8.0), ValueScaling::Decibels)
+    ///     .clip_indicator(1.0)
+    ///     .width(Pixels(4.0))
+    ///     .color(Color::rgb(255, 64, 64))
+    ///     .background_color(Color::rgba(255, 255, 255, 120));
+    /// ```
+    pub fn peak(
+        cx: &mut Context,
+        bus: Arc<B>,
+        decay: f32,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        Self::with_accumulator(cx, bus, PeakAccumulator::new(1.0, decay), range, scaling)
+    }
+}
+
+impl<B: Bus<f32> + 'static> MiniMeter<B, RMSAccumulator> {
+    /// Creates an RMS mini meter.
+    ///
+    /// # Example
+    ///
+    /// RMS mini meter showing the RMS level over a 250 ms long window.
+    ///
+    /// ```
+    /// MiniMeter::rms(cx, bus.clone(), 250.0, (-32.0, 8.0), ValueScaling::Decibels)
+    ///     .width(Pixels(4.0))
+    ///     .background_color(Color::rgba(255, 255, 255, 120));
+    /// ```
+    pub fn rms(
+        cx: &mut Context,
+        bus: Arc<B>,
+        window_size: f32,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+    ) -> Handle<Self> {
+        Self::with_accumulator(
+            cx,
+            bus,
+            RMSAccumulator::new(1.0, window_size),
+            range,
+            scaling,
+        )
+    }
+}
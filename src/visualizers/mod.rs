@@ -1,27 +1,83 @@
 //! Views which visualize the audio running through your plug-in.
 
+mod axis_link;
+mod balance_meter;
+mod beat_indicator;
+mod clip_light;
+mod correlation_meter;
+mod correlation_strip;
+mod crosshair;
+mod envelope_display;
+mod envelope_graph;
+mod eq_display;
+mod fade_edge;
+mod filter_response;
 mod graph;
 mod grid;
 mod histogram;
+mod histogram_ribbon;
+mod input_selector;
+mod labeled_grid;
 mod lissajous;
+mod loudness_range;
 mod meter;
+mod meter_array;
+mod mini_meter;
 mod oscilloscope;
+mod readout;
+mod session_overview;
+mod spectral_mask;
+mod spectrogram;
 mod spectrum_analyzer;
+mod spectrum_difference;
+mod stereo_graph;
+mod surround_scope;
+mod tuner;
 mod unit_ruler;
+mod width_meter;
+mod xy_scatter;
 // mod waveform;
 
+pub use axis_link::*;
+pub use balance_meter::*;
+pub use beat_indicator::*;
+pub use clip_light::*;
+pub use correlation_meter::*;
+pub use correlation_strip::*;
+pub use crosshair::*;
+pub use envelope_display::*;
+pub use envelope_graph::*;
+pub use eq_display::*;
+pub use fade_edge::*;
+pub use filter_response::*;
 pub use graph::*;
 pub use grid::*;
 pub use histogram::*;
+pub use histogram_ribbon::*;
+pub use input_selector::*;
+pub use labeled_grid::*;
 pub use lissajous::*;
+pub use loudness_range::*;
 pub use meter::*;
+pub use meter_array::*;
+pub use mini_meter::*;
 pub use oscilloscope::*;
+pub use session_overview::*;
+pub use spectral_mask::*;
+pub use spectrogram::*;
 pub use spectrum_analyzer::*;
+pub use spectrum_difference::*;
+pub use stereo_graph::*;
+pub use surround_scope::*;
+pub use tuner::*;
 pub use unit_ruler::*;
+pub use width_meter::*;
+pub use xy_scatter::*;
 // pub use waveform::*;
 
 use super::utils::ValueScaling;
-use nih_plug_vizia::vizia::binding::Res;
+use nih_plug_vizia::vizia::binding::{Lens, LensExt, Res};
+use std::time::{Duration, Instant};
 
 pub trait RangeModifiers {
     /// Sets the minimum and maximum values that can be displayed by the view
@@ -32,14 +88,198 @@ pub trait RangeModifiers {
     fn range(self, range: impl Res<(f32, f32)>) -> Self;
     /// Specifies what scaling the view should use
     fn scaling(self, scaling: impl Res<ValueScaling>) -> Self;
+    /// Keeps the range and scaling in sync with an [`AxisLink`]
+    ///
+    /// All views linked to the same [`AxisLink`] display the same range, so they
+    /// line up when stacked on top of each other.
+    fn axis(self, link: impl Lens<Target = AxisLink>) -> Self
+    where
+        Self: Sized,
+    {
+        self.range(link.clone().then(AxisLink::range))
+            .scaling(link.then(AxisLink::scaling))
+    }
+}
+
+pub trait AxisMorphModifiers {
+    /// Lets the view glide to a new range or scaling over 150 ms, instead of
+    /// snapping to it
+    fn morph_axis(self) -> Self;
+}
+
+/// How long it takes for an axis to glide to a new range or scaling.
+const AXIS_MORPH_DURATION: Duration = Duration::from_millis(150);
+
+/// A scaling and range that is part of a morphing axis, with its weight.
+type AxisLayer = (ValueScaling, (f32, f32), f32);
+
+/// The transition of a value axis from whatever it displayed before to its current
+/// range and scaling.
+///
+/// The displayed axis is a blend of one range per scaling, each with its own
+/// weight. Ranges of the same scaling glide into each other, while different
+/// scalings are crossfaded by interpolating the normalized positions of values on
+/// each of them. A new transition starts from what is displayed at that moment, so
+/// changing the axis mid-glide doesn't jump, and a range and scaling changed at
+/// once end up in one transition.
+#[derive(Debug, Clone)]
+pub(crate) struct AxisMorph {
+    /// The displayed axis at the start of the transition
+    from: Vec<AxisLayer>,
+    start: Option<Instant>,
+}
+
+impl AxisMorph {
+    pub(crate) fn new(range: (f32, f32), scaling: ValueScaling) -> Self {
+        Self {
+            from: vec![(scaling, range, 1.)],
+            start: None,
+        }
+    }
+
+    /// Starts gliding away from what is displayed for the given range and scaling,
+    /// which were the axis' target so far.
+    pub(crate) fn begin(&mut self, range: (f32, f32), scaling: ValueScaling) {
+        self.from = self
+            .layers(range, scaling)
+            .unwrap_or_else(|| vec![(scaling, range, 1.)]);
+        self.start = Some(Instant::now());
+    }
+
+    /// Whether the axis is still gliding.
+    pub(crate) fn is_running(&self) -> bool {
+        self.progress().is_some()
+    }
+
+    /// How far the transition has progressed, eased in and out, or `None` once it
+    /// has ended.
+    fn progress(&self) -> Option<f32> {
+        let t = self.start?.elapsed().as_secs_f32() / AXIS_MORPH_DURATION.as_secs_f32();
+
+        (t < 1.).then_some(t * t * (3. - 2. * t))
+    }
+
+    /// The displayed axis on the way to the given range and scaling, or `None` once
+    /// the transition has ended.
+    fn layers(&self, range: (f32, f32), scaling: ValueScaling) -> Option<Vec<AxisLayer>> {
+        let t = self.progress()?;
+
+        let mut layers: Vec<_> = self
+            .from
+            .iter()
+            .map(|&(scaling, range, weight)| (scaling, range, weight * (1. - t)))
+            .collect();
+
+        match layers.iter_mut().find(|(s, ..)| *s == scaling) {
+            Some((_, from, weight)) => {
+                *from = glide_range(scaling, *from, range, t / (*weight + t));
+                *weight += t;
+            }
+            None => layers.push((scaling, range, t)),
+        }
+
+        Some(layers)
+    }
+
+    pub(crate) fn value_to_normalized(
+        &self,
+        value: f32,
+        range: (f32, f32),
+        scaling: ValueScaling,
+    ) -> f32 {
+        match self.layers(range, scaling) {
+            Some(layers) => layers
+                .iter()
+                .map(|(scaling, range, weight)| {
+                    weight * scaling.value_to_normalized(value, range.0, range.1)
+                })
+                .sum(),
+            None => scaling.value_to_normalized(value, range.0, range.1),
+        }
+    }
+
+    pub(crate) fn value_to_normalized_slice(
+        &self,
+        values: &[f32],
+        out: &mut [f32],
+        range: (f32, f32),
+        scaling: ValueScaling,
+    ) {
+        let Some(layers) = self.layers(range, scaling) else {
+            scaling.value_to_normalized_slice(values, out, range.0, range.1);
+            return;
+        };
+
+        out.fill(0.);
+        let mut normalized = vec![0.; out.len()];
+        for (scaling, range, weight) in layers {
+            scaling.value_to_normalized_slice(values, &mut normalized, range.0, range.1);
+
+            for (out, normalized) in out.iter_mut().zip(&normalized) {
+                *out += weight * normalized;
+            }
+        }
+    }
+}
+
+/// Moves both ends of a range `t` of the way towards those of another range of
+/// the same scaling.
+fn glide_range(scaling: ValueScaling, from: (f32, f32), to: (f32, f32), t: f32) -> (f32, f32) {
+    let glide = |from: f32, to: f32| match scaling {
+        // Frequencies are laid out logarithmically, so they glide geometrically
+        ValueScaling::Frequency => (from.log2() + (to.log2() - from.log2()) * t).exp2(),
+        _ => from + (to - from) * t,
+    };
+
+    (glide(from.0, to.0), glide(from.1, to.1))
 }
 
+/// The release time (in ms) used by gain reduction meters and graphs.
+pub(crate) const GAIN_REDUCTION_DECAY: f32 = 150.0;
+
 pub(crate) enum FillFrom {
     Top,
     Bottom,
     Value(f32),
 }
 
+/// The side of the displayed range a value lies on, if it can't be displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutOfRange {
+    Below,
+    Above,
+}
+
+impl OutOfRange {
+    /// Checks whether a value lies outside of the displayed range.
+    pub(crate) fn check(value: f32, scaling: ValueScaling, range: (f32, f32)) -> Option<Self> {
+        // Silence can't be displayed in decibels, but isn't out of range either
+        if !value.is_finite() || (scaling == ValueScaling::Decibels && value <= 0.0) {
+            return None;
+        }
+
+        if scaling
+            .value_to_normalized_optional(value, range.0, range.1)
+            .is_some()
+        {
+            return None;
+        }
+
+        // Out-of-range values are clamped to either end of the normalized range
+        if scaling.value_to_normalized(value, range.0, range.1) > 0.5 {
+            Some(OutOfRange::Above)
+        } else {
+            Some(OutOfRange::Below)
+        }
+    }
+}
+
+pub trait OutOfRangeModifiers {
+    /// Enables or disables the indicators shown when a value lies outside of the
+    /// displayed range.
+    fn out_of_range_indicators(self, enabled: bool) -> Self;
+}
+
 pub trait FillModifiers {
     /// Allows for the view to be filled from the max instead of the min value.
     fn fill_from_max(self) -> Self;
@@ -48,6 +288,51 @@ pub trait FillModifiers {
     fn fill_from_value(self, level: f32) -> Self;
 }
 
+pub trait GradientFillModifiers {
+    /// Fills the area under the trace with a vertical gradient, going from the
+    /// `bottom` color at the bottom edge of the view to the `top` color at its top
+    /// edge, instead of the flat `background_color`.
+    ///
+    /// A transparent `bottom` color makes the fill fade in towards the trace.
+    fn fill_gradient(self, bottom: Color, top: Color) -> Self;
+}
+
 pub trait DurationModifiers {
     fn duration(self, duration: impl Res<f32>) -> Self;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AxisMorph, AXIS_MORPH_DURATION};
+    use crate::utils::ValueScaling;
+    use std::time::Instant;
+
+    #[test]
+    fn retargeting_mid_glide_starts_from_the_displayed_axis() {
+        let mut morph = AxisMorph::new((0., 1.), ValueScaling::Linear);
+        morph.begin((0., 1.), ValueScaling::Linear);
+        morph.start = Some(Instant::now() - AXIS_MORPH_DURATION / 2);
+
+        // Halfway between (0, 1) and (0, 2)
+        let before = morph.value_to_normalized(0.5, (0., 2.), ValueScaling::Linear);
+        assert!((before - 1. / 3.).abs() < 1e-3);
+
+        morph.begin((0., 2.), ValueScaling::Linear);
+        let after = morph.value_to_normalized(0.5, (-1., 1.), ValueScaling::Linear);
+        assert!((after - before).abs() < 1e-3);
+    }
+
+    #[test]
+    fn range_and_scaling_changes_glide_together() {
+        let mut morph = AxisMorph::new((0., 1.), ValueScaling::Linear);
+        morph.begin((0., 1.), ValueScaling::Linear);
+        morph.begin((0., 2.), ValueScaling::Linear);
+
+        let normalized = morph.value_to_normalized(0.5, (0., 2.), ValueScaling::Power(2.));
+        assert!((normalized - 0.5).abs() < 1e-3);
+
+        morph.start = Some(Instant::now() - AXIS_MORPH_DURATION);
+        let normalized = morph.value_to_normalized(0.5, (0., 2.), ValueScaling::Power(2.));
+        assert!((normalized - 0.5).abs() < 1e-6);
+    }
+}
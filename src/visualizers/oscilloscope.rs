@@ -7,7 +7,7 @@ use crate::accumulators::sample_delta;
 use crate::prelude::DurationModifiers;
 use crate::{
     bus::Bus,
-    utils::{RingBuffer, ValueScaling},
+    utils::{lock_or_recover, RingBuffer, ValueScaling},
 };
 
 #[derive(Default, Copy, Clone)]
@@ -92,14 +92,36 @@ impl WaveformAccumulator {
     }
 }
 
+/// How far (in pixels) the cursor needs to be dragged to halve or double the
+/// displayed duration of a zoomable [`Oscilloscope`].
+const DRAG_OCTAVE: f32 = 100.0;
+
+/// How much one step of the mouse wheel zooms a zoomable [`Oscilloscope`].
+const SCROLL_FACTOR: f32 = 0.8;
+
 /// Displays the incoming signal as a waveform.
+///
+/// Using [`zoomable`](OscilloscopeModifiers::zoomable), the time axis can be
+/// zoomed with the mouse wheel, or by dragging horizontally.
 pub struct Oscilloscope<B: Bus<f32> + 'static> {
     bus: Arc<B>,
     dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
     accumulator: Arc<Mutex<WaveformAccumulator>>,
     buffer: Arc<Mutex<RingBuffer<Sample>>>,
     range: (f32, f32),
     scaling: ValueScaling,
+    /// The shortest and longest duration (in seconds) the user can zoom to
+    zoom: Option<(f32, f32)>,
+    /// The cursor position and duration at which a drag started
+    drag: Option<(f32, f32)>,
+}
+
+/// Events emitted by an [`Oscilloscope`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OscilloscopeEvent {
+    /// The user zoomed the time axis to a new duration, in seconds.
+    DurationChanged(f32),
 }
 
 enum OscilloscopeEvents {
@@ -135,13 +157,21 @@ impl<B: Bus<f32> + 'static> Oscilloscope<B> {
             }
         });
 
+        let accumulator_c = accumulator.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&accumulator_c).set_sample_rate(sample_rate);
+        });
+
         Self {
             bus,
             dispatcher_handle,
+            sample_rate_handle,
             accumulator,
             buffer,
             range: range.get_val(cx),
             scaling: scaling.get_val(cx),
+            zoom: None,
+            drag: None,
         }
         .build(cx, |_| {})
         .duration(duration)
@@ -150,6 +180,21 @@ impl<B: Bus<f32> + 'static> Oscilloscope<B> {
     }
 }
 
+impl<B: Bus<f32> + 'static> Oscilloscope<B> {
+    /// Zooms the time axis to the given duration, within the zoom limits.
+    fn zoom_to(&mut self, cx: &mut EventContext, duration: f32) {
+        let Some((min, max)) = self.zoom else {
+            return;
+        };
+
+        let duration = duration.clamp(min, max);
+        lock_or_recover(&self.accumulator).set_duration(duration);
+
+        cx.emit(OscilloscopeEvent::DurationChanged(duration));
+        cx.needs_redraw();
+    }
+}
+
 impl<B: Bus<f32> + 'static> View for Oscilloscope<B> {
     fn element(&self) -> Option<&'static str> {
         Some("oscilloscope")
@@ -164,45 +209,45 @@ impl<B: Bus<f32> + 'static> View for Oscilloscope<B> {
 
         self.bus.update();
 
-        let ring_buf = &mut self.buffer.lock().unwrap();
+        let ring_buf = &mut lock_or_recover(&self.buffer);
 
         {
             let width_ceil = w.ceil() as usize;
             if ring_buf.len() != width_ceil {
                 ring_buf.resize(width_ceil);
-                let mut acc = self.accumulator.lock().unwrap();
+                let mut acc = lock_or_recover(&self.accumulator);
                 acc.set_size(width_ceil);
             }
         }
 
         let len = ring_buf.len();
 
+        if len == 0 {
+            return;
+        }
+
+        let (minima, maxima): (Vec<f32>, Vec<f32>) =
+            (0..len).map(|i| (ring_buf[i].min, ring_buf[i].max)).unzip();
+
+        let mut bottom = vec![0.; len];
+        let mut top = vec![0.; len];
+        self.scaling
+            .value_to_normalized_slice(&minima, &mut bottom, self.range.0, self.range.1);
+        self.scaling
+            .value_to_normalized_slice(&maxima, &mut top, self.range.0, self.range.1);
+
         let mut fill = vg::Path::new();
 
         // Local minima (bottom part of waveform)
-        let mut py = self
-            .scaling
-            .value_to_normalized(ring_buf[0].min, self.range.0, self.range.1);
-        fill.move_to(x, y + h * (1. - py) + 1.);
-        for i in 1..len {
-            py = self
-                .scaling
-                .value_to_normalized(ring_buf[i].min, self.range.0, self.range.1);
-
+        fill.move_to(x, y + h * (1. - bottom[0]) + 1.);
+        for (i, py) in bottom.iter().enumerate().skip(1) {
             fill.line_to(x + i as f32, y + h * (1. - py) + cx.scale_factor());
         }
 
         // Local maxima (top part of waveform)
-        py = self
-            .scaling
-            .value_to_normalized(ring_buf[len - 1].max, self.range.0, self.range.1);
-        fill.line_to(x + w, y + h * (1. - py) + 1.);
+        fill.line_to(x + w, y + h * (1. - top[len - 1]) + 1.);
         for i in 1..len {
-            py =
-                self.scaling
-                    .value_to_normalized(ring_buf[len - i].max, self.range.0, self.range.1);
-
-            fill.line_to(x + len as f32 - i as f32, y + h * (1. - py));
+            fill.line_to(x + len as f32 - i as f32, y + h * (1. - top[len - i]));
         }
 
         fill.close();
@@ -211,14 +256,46 @@ impl<B: Bus<f32> + 'static> View for Oscilloscope<B> {
             &vg::Paint::color(cx.font_color().into()).with_line_width(0.),
         );
     }
-    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|e, _| match e {
             OscilloscopeEvents::UpdateRange(v) => self.range = *v,
             OscilloscopeEvents::UpdateScaling(v) => self.scaling = *v,
             OscilloscopeEvents::UpdateDuration(v) => {
-                self.accumulator.lock().unwrap().set_duration(*v)
+                lock_or_recover(&self.accumulator).set_duration(*v)
             }
         });
+
+        if self.zoom.is_none() {
+            return;
+        }
+
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseScroll(_, y) => {
+                let duration = lock_or_recover(&self.accumulator).duration;
+                self.zoom_to(cx, duration * SCROLL_FACTOR.powf(*y));
+                meta.consume();
+            }
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let duration = lock_or_recover(&self.accumulator).duration;
+                self.drag = Some((cx.mouse().cursorx, duration));
+                cx.capture();
+                meta.consume();
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.drag.take().is_some() {
+                    cx.release();
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, _) => {
+                // Dragging to the right zooms in, dragging to the left zooms out
+                if let Some((start_x, start_duration)) = self.drag {
+                    let octaves = (*x - start_x) / (DRAG_OCTAVE * cx.scale_factor());
+                    self.zoom_to(cx, start_duration * 0.5f32.powf(octaves));
+                }
+            }
+            _ => {}
+        });
     }
 }
 
@@ -243,6 +320,31 @@ impl<'a, B: Bus<f32> + 'static> RangeModifiers for Handle<'a, Oscilloscope<B>> {
     }
 }
 
+pub trait OscilloscopeModifiers {
+    fn zoomable(self, min: f32, max: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> OscilloscopeModifiers for Handle<'a, Oscilloscope<B>> {
+    /// Lets the user zoom the time axis, between `min` and `max` seconds.
+    ///
+    /// Scrolling up or dragging to the right zooms in, scrolling down or dragging to
+    /// the left zooms out. Whenever the duration changes, the oscilloscope emits an
+    /// [`OscilloscopeEvent::DurationChanged`], so that a bound duration can be kept
+    /// in sync. What has already been drawn stays at its previous resolution, and
+    /// gets replaced by incoming audio at the new one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Oscilloscope::new(cx, bus.clone(), Data::duration, (-1.0, 1.0), ValueScaling::Linear)
+    ///     .zoomable(0.01, 10.0)
+    ///     .color(Color::rgba(255, 255, 255, 120));
+    /// ```
+    fn zoomable(self, min: f32, max: f32) -> Self {
+        self.modify(|oscilloscope| oscilloscope.zoom = Some((min, max)))
+    }
+}
+
 impl<'a, B: Bus<f32> + 'static> DurationModifiers for Handle<'a, Oscilloscope<B>> {
     fn duration(mut self, duration: impl Res<f32>) -> Self {
         let e = self.entity();
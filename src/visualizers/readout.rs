@@ -0,0 +1,138 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::time::Duration;
+
+/// The text and position of a readout that follows the cursor.
+#[derive(Lens)]
+pub(crate) struct HoverReadout {
+    text: String,
+    /// The offset (in logical pixels) from the view's top left corner
+    left: f32,
+    top: f32,
+    visible: bool,
+}
+
+enum HoverReadoutEvents {
+    Update { text: String, left: f32, top: f32 },
+    Hide,
+}
+
+impl Model for HoverReadout {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            HoverReadoutEvents::Update { text, left, top } => {
+                self.text = text.clone();
+                self.left = *left;
+                self.top = *top;
+                self.visible = true;
+            }
+            HoverReadoutEvents::Hide => self.visible = false,
+        });
+    }
+}
+
+impl HoverReadout {
+    /// Adds a hidden readout to the view that is currently being built.
+    pub(crate) fn attach(cx: &mut Context) {
+        Self {
+            text: String::new(),
+            left: 0.,
+            top: 0.,
+            visible: false,
+        }
+        .build(cx);
+
+        Label::new(cx, Self::text)
+            .position_type(PositionType::SelfDirected)
+            .left(Self::left.map(|left| Pixels(*left)))
+            .top(Self::top.map(|top| Pixels(*top)))
+            .visibility(Self::visible)
+            .hoverable(false);
+    }
+
+    /// Shows the `text` just below and to the right of the cursor.
+    pub(crate) fn show(cx: &mut EventContext, text: String, (x, y): (f32, f32)) {
+        let bounds = cx.bounds();
+
+        cx.emit(HoverReadoutEvents::Update {
+            text,
+            left: (x - bounds.x) / cx.scale_factor() + 8.,
+            top: (y - bounds.y) / cx.scale_factor() + 8.,
+        });
+    }
+
+    pub(crate) fn hide(cx: &mut EventContext) {
+        cx.emit(HoverReadoutEvents::Hide);
+    }
+}
+
+/// Draws a pair of guide lines through the cursor, across the whole view, in the
+/// `color` at reduced opacity.
+pub(crate) fn draw_guides(
+    cx: &mut DrawContext,
+    canvas: &mut Canvas,
+    (hover_x, hover_y): (f32, f32),
+) {
+    let bounds = cx.bounds();
+
+    let mut color: vg::Color = cx.font_color().into();
+    color.a *= 0.5;
+
+    let mut lines = vg::Path::new();
+    lines.move_to(hover_x, bounds.y);
+    lines.line_to(hover_x, bounds.y + bounds.h);
+    lines.move_to(bounds.x, hover_y);
+    lines.line_to(bounds.x + bounds.w, hover_y);
+    canvas.stroke_path(
+        &lines,
+        &vg::Paint::color(color).with_line_width(cx.scale_factor()),
+    );
+}
+
+/// The text of a readout that its view refreshes every so often, e.g. the current
+/// value of a meter.
+#[derive(Lens)]
+pub(crate) struct TextReadout {
+    text: String,
+}
+
+enum TextReadoutEvents {
+    Update(String),
+}
+
+impl Model for TextReadout {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            TextReadoutEvents::Update(text) => self.text = text.clone(),
+        });
+    }
+}
+
+impl TextReadout {
+    /// Adds a label showing the `text` to the view that is currently being built.
+    ///
+    /// Every `interval`, the view is sent the event created by `refresh`, so it can
+    /// update the text using [`TextReadout::update`].
+    pub(crate) fn attach<M: Send + 'static>(
+        cx: &mut Context,
+        text: String,
+        interval: Duration,
+        refresh: impl Fn() -> M + 'static,
+    ) -> Handle<Label> {
+        let e = cx.current();
+
+        Self { text }.build(cx);
+
+        let timer = cx.add_timer(interval, None, move |cx, action| {
+            if let TimerAction::Tick(_) = action {
+                cx.emit_to(e, refresh());
+            }
+        });
+        cx.start_timer(timer);
+
+        Label::new(cx, Self::text).hoverable(false)
+    }
+
+    pub(crate) fn update(cx: &mut EventContext, text: String) {
+        cx.emit(TextReadoutEvents::Update(text));
+    }
+}
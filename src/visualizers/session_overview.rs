@@ -0,0 +1,66 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+use crate::bus::Bus;
+use crate::recording::SessionRecorder;
+
+/// Displays everything a [`SessionRecorder`] has recorded, as a waveform spanning
+/// the view's full width.
+///
+/// Unlike an [`Oscilloscope`](super::Oscilloscope), which shows the last few
+/// seconds of a signal, the overview always shows the whole session - its
+/// resolution simply gets coarser the longer the session is. The waveform is drawn
+/// from -1 at the bottom to +1 at the top and filled using the `background_color`.
+///
+/// # Example
+///
+/// ```
+/// let recorder = SessionRecorder::new(bus.clone(), 4096);
+///
+/// SessionOverview::new(cx, &recorder)
+///     .background_color(Color::rgba(255, 255, 255, 120));
+/// ```
+pub struct SessionOverview<B: Bus<f32> + 'static> {
+    recorder: SessionRecorder<B>,
+}
+
+impl<B: Bus<f32> + 'static> SessionOverview<B> {
+    /// Creates a new [`SessionOverview`] of the given recorder's recording.
+    pub fn new<'a>(cx: &'a mut Context, recorder: &SessionRecorder<B>) -> Handle<'a, Self> {
+        Self {
+            recorder: recorder.clone(),
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for SessionOverview<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("session-overview")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let (x, y, w, h) = (bounds.x, bounds.y, bounds.w, bounds.h);
+        let to_y = |value: f32| y + h * (1. - value.clamp(-1., 1.)) / 2.;
+
+        let mut path = vg::Path::new();
+
+        self.recorder.with_overview(|overview| {
+            let pairs = overview.pairs();
+            if pairs.is_empty() {
+                return;
+            }
+
+            let step = w / pairs.len() as f32;
+
+            for (i, (min, max)) in pairs.iter().enumerate() {
+                let top = to_y(*max);
+                // Keep silent stretches visible as a hairline
+                let height = (to_y(*min) - top).max(1.0);
+                path.rect(x + i as f32 * step, top, step, height);
+            }
+        });
+
+        canvas.fill_path(&path, &vg::Paint::color(cx.background_color().into()));
+    }
+}
@@ -0,0 +1,318 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+
+use super::ReferenceCurve;
+use crate::utils::ValueScaling;
+
+/// The radius (in logical pixels) of a breakpoint's handle.
+const HANDLE_RADIUS: f32 = 4.0;
+
+/// A frequency-dependent threshold curve, drawn on top of a
+/// [`SpectrumAnalyzer`](super::SpectrumAnalyzer).
+///
+/// The curve goes through a list of `(frequency, dB)` breakpoints. Just like a
+/// [`ReferenceCurve::Points`], it is interpolated linearly on a logarithmic
+/// frequency axis in between them, and stays flat below the first and above the
+/// last one. This makes it the UI foundation for spectral gates, dynamic EQs and
+/// similar processors, which need a threshold for each frequency.
+///
+/// The curve is drawn using the `color`, and the area below it - where a gate
+/// would be closed - is filled using the `background_color`.
+///
+/// Put the mask inside a [`ZStack`], on top of the analyzer, and give it the same
+/// frequency scaling and range. The `level_range` (in dB) needs to match the
+/// analyzer's magnitude range for the curve to line up with the spectrum.
+///
+/// # Editing
+///
+/// Once made [`editable`](SpectralMaskModifiers::editable), each breakpoint gets a
+/// handle that can be dragged around. A breakpoint can't be dragged past its
+/// neighbors. While dragging, the mask emits a
+/// [`SpectralMaskEvent::BreakpointChanged`] which your editor's model can use to
+/// update the corresponding parameters. If the breakpoints are bound to a lens,
+/// the mask follows the model, so it stays in sync with automation as well.
+///
+/// # Example
+///
+/// ```
+/// ZStack::new(cx, |cx| {
+///     SpectrumAnalyzer::new(
+///         cx,
+///         Data::spectrum,
+///         SpectrumAnalyzerVariant::LINE,
+///         ValueScaling::Frequency,
+///         (10., 21_000.),
+///         ValueScaling::Decibels,
+///         (-110., 6.),
+///     );
+///     SpectralMask::new(
+///         cx,
+///         Data::gate_breakpoints,
+///         ValueScaling::Frequency,
+///         (10., 21_000.),
+///         (-110., 6.),
+///     )
+///     .editable()
+///     .color(Color::rgb(255, 160, 64))
+///     .background_color(Color::rgba(255, 160, 64, 30));
+/// });
+/// ```
+pub struct SpectralMask {
+    breakpoints: Vec<(f32, f32)>,
+    frequency_scaling: ValueScaling,
+    frequency_range: (f32, f32),
+    level_range: (f32, f32),
+    editable: bool,
+    hovered: Option<usize>,
+    dragging: Option<usize>,
+}
+
+/// Events emitted by a [`SpectralMask`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpectralMaskEvent {
+    /// A breakpoint has been dragged to a new frequency and level (in dB).
+    BreakpointChanged {
+        index: usize,
+        frequency: f32,
+        level: f32,
+    },
+}
+
+enum SpectralMaskEvents {
+    UpdateBreakpoints(Vec<(f32, f32)>),
+}
+
+impl SpectralMask {
+    /// Creates a new [`SpectralMask`].
+    ///
+    /// The `breakpoints` need to be sorted by frequency.
+    pub fn new(
+        cx: &mut Context,
+        breakpoints: impl Res<Vec<(f32, f32)>>,
+        frequency_scaling: ValueScaling,
+        frequency_range: (f32, f32),
+        level_range: (f32, f32),
+    ) -> Handle<Self> {
+        let mut handle = Self {
+            breakpoints: breakpoints.get_val(cx),
+            frequency_scaling,
+            frequency_range,
+            level_range,
+            editable: false,
+            hovered: None,
+            dragging: None,
+        }
+        .build(cx, |_| {})
+        // Let the analyzer underneath receive the mouse unless the mask is editable
+        .hoverable(false);
+
+        let e = handle.entity();
+        breakpoints.set_or_bind(handle.context(), e, move |cx, b| {
+            (*cx).emit_to(e, SpectralMaskEvents::UpdateBreakpoints(b));
+        });
+
+        handle
+    }
+
+    /// The position of a breakpoint, relative to the view's bounds.
+    fn position(&self, (frequency, level): (f32, f32), bounds: BoundingBox) -> (f32, f32) {
+        let x = self.frequency_scaling.value_to_normalized(
+            frequency,
+            self.frequency_range.0,
+            self.frequency_range.1,
+        );
+        let y =
+            ValueScaling::Linear.value_to_normalized(level, self.level_range.0, self.level_range.1);
+
+        (bounds.x + bounds.w * x, bounds.y + bounds.h * (1. - y))
+    }
+
+    /// The breakpoint whose handle lies under the given point, if any.
+    fn breakpoint_at(
+        &self,
+        x: f32,
+        y: f32,
+        bounds: BoundingBox,
+        scale_factor: f32,
+    ) -> Option<usize> {
+        let radius = HANDLE_RADIUS * 2. * scale_factor;
+
+        self.breakpoints.iter().position(|breakpoint| {
+            let (bx, by) = self.position(*breakpoint, bounds);
+            (bx - x).hypot(by - y) <= radius
+        })
+    }
+
+    /// Moves the given breakpoint to the point under the cursor, keeping it in
+    /// between its neighbors.
+    fn drag(&mut self, index: usize, x: f32, y: f32, bounds: BoundingBox) -> (f32, f32) {
+        let x = ((x - bounds.x) / bounds.w).clamp(0., 1.);
+        let y = ((y - bounds.y) / bounds.h).clamp(0., 1.);
+
+        let mut frequency = self.frequency_scaling.normalized_to_value(
+            x,
+            self.frequency_range.0,
+            self.frequency_range.1,
+        );
+        if let Some(previous) = index.checked_sub(1).and_then(|i| self.breakpoints.get(i)) {
+            frequency = frequency.max(previous.0);
+        }
+        if let Some(next) = self.breakpoints.get(index + 1) {
+            frequency = frequency.min(next.0);
+        }
+
+        let level = ValueScaling::Linear.normalized_to_value(
+            1. - y,
+            self.level_range.0,
+            self.level_range.1,
+        );
+
+        self.breakpoints[index] = (frequency, level);
+        (frequency, level)
+    }
+}
+
+impl View for SpectralMask {
+    fn element(&self) -> Option<&'static str> {
+        Some("spectral-mask")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            SpectralMaskEvents::UpdateBreakpoints(breakpoints) => {
+                // Don't let the model fight the user while they're dragging
+                if self.dragging.is_none() {
+                    self.breakpoints = breakpoints.clone();
+                    // The hovered handle may have moved or be gone, until the next
+                    // mouse move finds it again
+                    self.hovered = None;
+                }
+            }
+        });
+
+        if !self.editable {
+            return;
+        }
+
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                if let Some(index) = self.hovered {
+                    self.dragging = Some(index);
+                    cx.capture();
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.dragging.take().is_some() {
+                    cx.release();
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                let bounds = cx.bounds();
+
+                if let Some(index) = self.dragging {
+                    let (frequency, level) = self.drag(index, *x, *y, bounds);
+                    cx.emit(SpectralMaskEvent::BreakpointChanged {
+                        index,
+                        frequency,
+                        level,
+                    });
+                    cx.needs_redraw();
+                } else {
+                    let hovered = self.breakpoint_at(*x, *y, bounds, cx.scale_factor());
+                    if hovered != self.hovered {
+                        self.hovered = hovered;
+                        cx.needs_redraw();
+                    }
+                }
+            }
+            WindowEvent::MouseLeave => {
+                if self.dragging.is_none() && self.hovered.take().is_some() {
+                    cx.needs_redraw();
+                }
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        if self.breakpoints.is_empty() || w <= 0. {
+            return;
+        }
+
+        let curve = ReferenceCurve::Points(self.breakpoints.clone());
+
+        // Sampled per pixel, so that the curve is drawn correctly for any scaling
+        let mut line = vg::Path::new();
+        for i in 0..=w.ceil() as usize {
+            let frequency = self.frequency_scaling.normalized_to_value(
+                i as f32 / w,
+                self.frequency_range.0,
+                self.frequency_range.1,
+            );
+            let (_, point_y) = self.position((frequency, curve.level_at(frequency)), bounds);
+
+            if i == 0 {
+                line.move_to(x, point_y);
+            } else {
+                line.line_to(x + i as f32, point_y);
+            }
+        }
+
+        let mut fill = line.clone();
+        fill.line_to(x + w.ceil(), y + h);
+        fill.line_to(x, y + h);
+        fill.close();
+
+        canvas.save();
+        canvas.intersect_scissor(x, y, w, h);
+
+        canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+        canvas.stroke_path(
+            &line,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor() * 1.5),
+        );
+
+        canvas.restore();
+
+        if self.editable {
+            for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                let (handle_x, handle_y) = self.position(*breakpoint, bounds);
+
+                let mut handle = vg::Path::new();
+                handle.circle(handle_x, handle_y, HANDLE_RADIUS * cx.scale_factor());
+
+                // Handles are filled while they are hovered or dragged
+                if Some(index) == self.dragging.or(self.hovered) {
+                    canvas.fill_path(&handle, &vg::Paint::color(cx.font_color().into()));
+                } else {
+                    canvas.stroke_path(
+                        &handle,
+                        &vg::Paint::color(cx.font_color().into())
+                            .with_line_width(cx.scale_factor()),
+                    );
+                }
+            }
+        }
+    }
+}
+
+pub trait SpectralMaskModifiers {
+    fn editable(self) -> Self;
+}
+
+impl SpectralMaskModifiers for Handle<'_, SpectralMask> {
+    /// Lets the user drag the breakpoints around.
+    ///
+    /// Each change is emitted as a [`SpectralMaskEvent::BreakpointChanged`].
+    fn editable(self) -> Self {
+        self.modify(|mask| mask.editable = true).hoverable(true)
+    }
+}
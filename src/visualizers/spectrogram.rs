@@ -0,0 +1,177 @@
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+
+use crate::spectrum::SpectrumOutput;
+use crate::utils::{lock_or_recover, ColorMap, OffscreenImage, ValueScaling};
+
+/// The offscreen image the spectrogram is rendered into.
+///
+/// The image acts as a ring buffer of columns. Instead of shifting its contents,
+/// each new column overwrites the oldest one, and the image is drawn with a
+/// horizontal offset so that the newest column ends up at the right edge.
+struct SpectrogramImage {
+    image: Option<OffscreenImage>,
+    /// The column that will be written next.
+    head: usize,
+}
+
+/// Horizontally scrolling spectrogram that shows the magnitude of each frequency
+/// inside a [`SpectrumOutput`] over time.
+///
+/// Frequencies are laid out vertically, with the lowest frequency at the bottom.
+/// For each drawn frame, a new column is appended at the right edge, colored by the
+/// provided [`ColorMap`]. Older columns scroll to the left.
+///
+/// See the [`SpectrumAnalyzer`](super::SpectrumAnalyzer) on how to set up a
+/// `SpectrumOutput`.
+///
+/// # Example
+///
+/// ```
+/// Spectrogram::new(
+///     cx,
+///     Data::spectrum,
+///     ValueScaling::Frequency,
+///     (20., 20_000.),
+///     ValueScaling::Decibels,
+///     (-90., 0.),
+///     ColorMap::heat(),
+/// )
+/// .height(Pixels(128.));
+/// ```
+pub struct Spectrogram {
+    spectrum: Arc<Mutex<SpectrumOutput>>,
+    frequency_scaling: ValueScaling,
+    frequency_range: (f32, f32),
+    magnitude_scaling: ValueScaling,
+    magnitude_range: (f32, f32),
+    color_map: ColorMap,
+    image: RefCell<SpectrogramImage>,
+}
+
+impl Spectrogram {
+    /// Creates a new [`Spectrogram`].
+    pub fn new<LSpectrum>(
+        cx: &mut Context,
+        spectrum: LSpectrum,
+        frequency_scaling: ValueScaling,
+        frequency_range: (f32, f32),
+        magnitude_scaling: ValueScaling,
+        magnitude_range: (f32, f32),
+        color_map: ColorMap,
+    ) -> Handle<Self>
+    where
+        LSpectrum: Lens<Target = Arc<Mutex<SpectrumOutput>>>,
+    {
+        Self {
+            spectrum: spectrum.get(cx),
+            frequency_scaling,
+            frequency_range,
+            magnitude_scaling,
+            magnitude_range,
+            color_map,
+            image: RefCell::new(SpectrogramImage {
+                image: None,
+                head: 0,
+            }),
+        }
+        .build(cx, |_| ())
+    }
+
+    /// Computes the color stops of a single column, from top to bottom.
+    fn column(&self, height: usize) -> Vec<(f32, vg::Color)> {
+        let mut spectrum = lock_or_recover(&self.spectrum);
+        let nyquist = spectrum.nyquist();
+        let spectrum_output = spectrum.read();
+        let last_bin = spectrum_output.len() - 1;
+
+        (0..height)
+            .map(|row| {
+                let position = row as f32 / (height - 1).max(1) as f32;
+
+                let freq = self.frequency_scaling.normalized_to_value(
+                    1. - position,
+                    self.frequency_range.0,
+                    self.frequency_range.1,
+                );
+                let bin = ((freq / nyquist) * last_bin as f32).round() as usize;
+
+                // There is nothing above the Nyquist frequency
+                let magnitude = if bin <= last_bin {
+                    spectrum_output[bin]
+                } else {
+                    0.
+                };
+
+                let magnitude_normalized = self.magnitude_scaling.value_to_normalized(
+                    magnitude,
+                    self.magnitude_range.0,
+                    self.magnitude_range.1,
+                );
+
+                (
+                    position,
+                    self.color_map.color_at(magnitude_normalized).into(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl View for Spectrogram {
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrogram")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let width = w.ceil() as usize;
+        let height = h.ceil() as usize;
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let mut state = self.image.borrow_mut();
+        let SpectrogramImage { image, head } = &mut *state;
+
+        let Some((image, created)) = OffscreenImage::ensure(
+            image,
+            canvas,
+            width,
+            height,
+            vg::ImageFlags::REPEAT_X | vg::ImageFlags::NEAREST,
+        ) else {
+            return;
+        };
+
+        if created {
+            *head = 0;
+        }
+
+        let stops = self.column(height);
+
+        // Render the newest column into the image
+        image.render(canvas, |canvas| {
+            let mut column = vg::Path::new();
+            column.rect(*head as f32, 0., 1., height as f32);
+            canvas.fill_path(
+                &column,
+                &vg::Paint::linear_gradient_stops(0., 0., 0., height as f32, &stops),
+            );
+        });
+
+        *head = (*head + 1) % image.width();
+
+        // Draw the image, offset so that the oldest column is at the left edge
+        image.blit(canvas, x, y, w, h, *head as f32);
+    }
+}
@@ -1,14 +1,20 @@
+use nih_plug::util::{db_to_gain, gain_to_db};
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::vizia::vg;
+use std::cell::RefCell;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
-use crate::spectrum::SpectrumOutput;
-use crate::utils::ValueScaling;
+use super::readout::{draw_guides, HoverReadout};
+use super::{AxisLink, GradientFillModifiers};
+use crate::spectrum::{bin_frequency, SpectrumOutput, SpectrumResampler};
+use crate::utils::{lock_or_recover, ColorMap, Note, ValueScaling};
 
 /// Spectrum analyzer that shows the magnitude of each frequency bin inside a
 /// [`SpectrumOutput`].
 ///
-/// Can either display magnitude as discrete bars, or as a graph.
+/// Can either display magnitude as discrete bars, as a graph, or as bars for
+/// fractional-octave bands.
 ///
 /// # Usage
 ///
@@ -19,7 +25,12 @@ use crate::utils::ValueScaling;
 /// compute the spectrum inside your plug-in's
 /// [`process()`](nih_plug::plugin::Plugin::process) function. The `SpectrumOutput`
 /// can then be sent to your editor inside the
-/// [`editor()`](nih_plug::plugin::Plugin::editor) function.
+/// [`editor()`](nih_plug::plugin::Plugin::editor) function. To keep the FFT off
+/// the audio thread, create the pair using
+/// [`SpectrumInput::new_deferred`](crate::spectrum::SpectrumInput::new_deferred)
+/// instead, which runs it in the analyzer's draw call, or compute the spectrum
+/// from one of your buses using
+/// [`SpectrumInput::from_bus`](crate::spectrum::SpectrumInput::from_bus).
 ///
 /// Here's a detailed guide on how to do this.
 ///
@@ -179,14 +190,7 @@ use crate::utils::ValueScaling;
 ///     .color(Color::rgba(255, 255, 255, 160))
 ///     .background_color(Color::rgba(255, 255, 255, 60));
 ///     // Displays a fade to the background color at the bottom, as a backdrop for the unit ruler
-///     Element::new(cx)
-///         .background_gradient(
-///             LinearGradientBuilder::with_direction("to bottom")
-///                 .add_stop(Color::transparent())
-///                 .add_stop(Color::rgb(16, 16, 16)),
-///         )
-///         .height(Pixels(48.))
-///         .top(Stretch(1.));
+///     FadeEdge::new(cx, FadeSide::Bottom, 48., Color::rgb(16, 16, 16));
 ///     UnitRuler::new(
 ///         cx,
 ///         (10., 21_000.),
@@ -214,6 +218,15 @@ use crate::utils::ValueScaling;
 /// .border_color(Color::rgb(80, 80, 80))
 /// .border_width(Pixels(1.));
 /// ```
+///
+/// # Freezing and snapshots
+///
+/// Once clicked, the analyzer responds to a few keyboard shortcuts that make it
+/// easy to compare spectra: by default, `F` freezes and unfreezes the spectrum, `S`
+/// captures a snapshot that stays visible as a translucent line, and `C` clears
+/// the snapshot. The shortcuts can be changed or disabled via
+/// [`keybindings`](SpectrumAnalyzerModifiers::keybindings). The same actions can
+/// also be triggered by sending a [`SpectrumAnalyzerEvent`] to the analyzer.
 pub struct SpectrumAnalyzer {
     spectrum: Arc<Mutex<SpectrumOutput>>,
     variant: SpectrumAnalyzerVariant,
@@ -222,11 +235,459 @@ pub struct SpectrumAnalyzer {
     magnitude_scaling: ValueScaling,
     magnitude_range: (f32, f32),
     slope: Option<f32>,
+    color_map: Option<ColorMap>,
+    /// The bottom and top colors of the fill, if it is a gradient
+    fill_gradient: Option<(Color, Color)>,
+    traces: RefCell<Option<Traces>>,
+    peak_caps: RefCell<Option<PeakCaps>>,
+    /// Per-bin peaks drawn as a trace, along with its color
+    peak_hold: RefCell<Option<(PeakCaps, Color)>>,
+    /// Another spectrum of the same output drawn on top, along with its color
+    overlay: Option<(usize, Color)>,
+    smoothing: Option<OctaveFraction>,
+    reference: Option<(ReferenceCurve, f32)>,
+    deviation_readout: Option<f32>,
+    keybindings: SpectrumAnalyzerKeybindings,
+    frozen: Option<Vec<f32>>,
+    snapshot: Option<Vec<f32>>,
+    snapshot_color: Option<Color>,
+    /// The width and gap (in logical pixels) of the bars in the `BAR` variant
+    bar_layout: Option<(f32, f32)>,
+    /// Resamples the spectrum onto one point per pixel before drawing lines, if set
+    resampler: Option<RefCell<Option<SpectrumResampler>>>,
+    hover_readout: bool,
+    /// The position of the cursor, while it hovers over the analyzer
+    hover: Option<(f32, f32)>,
 }
 
 pub enum SpectrumAnalyzerVariant {
     BAR,
     LINE,
+    /// Bars for fractional-octave bands, each containing the summed energy of all
+    /// bins within it.
+    BANDS(BandsPerOctave),
+}
+
+/// How many bands each octave is split into, in the `BANDS` variant of a
+/// [`SpectrumAnalyzer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandsPerOctave {
+    /// Full octave bands.
+    One,
+    /// Third-octave bands, as used by most acoustic measurements.
+    Three,
+    /// Sixth-octave bands.
+    Six,
+}
+
+impl BandsPerOctave {
+    fn count(&self) -> i32 {
+        match self {
+            BandsPerOctave::One => 1,
+            BandsPerOctave::Three => 3,
+            BandsPerOctave::Six => 6,
+        }
+    }
+
+    /// The `(low, center, high)` frequencies of all bands overlapping the given
+    /// range, below the Nyquist frequency.
+    ///
+    /// The band centers are evenly spaced on a logarithmic axis, going through
+    /// 1 kHz, and each band reaches halfway to its neighbours.
+    fn bands(&self, range: (f32, f32), nyquist: f32) -> Vec<(f32, f32, f32)> {
+        let count = self.count() as f32;
+        let half_width = 2f32.powf(0.5 / count);
+        let top = range.1.min(nyquist);
+
+        let first = ((range.0.max(1.) / 1000.).log2() * count).floor() as i32;
+        let last = ((top.max(1.) / 1000.).log2() * count).ceil() as i32;
+
+        (first..=last)
+            .map(|band| {
+                let center = 1000. * 2f32.powf(band as f32 / count);
+                (center / half_width, center, center * half_width)
+            })
+            .filter(|(low, _, high)| *high > range.0 && *low < top)
+            .collect()
+    }
+}
+
+/// The width of the band a [`SpectrumAnalyzer`] averages each bin over, when
+/// smoothing its spectrum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OctaveFraction {
+    /// 1/3 octave, for a broad overview of the spectral balance.
+    Third,
+    /// 1/6 octave.
+    Sixth,
+    /// 1/12 octave, or a semitone.
+    Twelfth,
+    /// 1/24 octave, which only takes the edge off.
+    TwentyFourth,
+}
+
+impl OctaveFraction {
+    fn denominator(&self) -> f32 {
+        match self {
+            OctaveFraction::Third => 3.,
+            OctaveFraction::Sixth => 6.,
+            OctaveFraction::Twelfth => 12.,
+            OctaveFraction::TwentyFourth => 24.,
+        }
+    }
+
+    /// Averages the power of each bin over a band of this width, centered on it.
+    ///
+    /// Bins are evenly spaced, so higher bins are averaged over more neighbours,
+    /// which evens out the detail on a logarithmic frequency axis.
+    fn smooth(&self, spectrum: &[f32]) -> Vec<f32> {
+        let half_width = 2f32.powf(0.5 / self.denominator());
+
+        // The power of all bins below each index, so that any band can be summed
+        // up at once
+        let mut cumulative_power = Vec::with_capacity(spectrum.len() + 1);
+        cumulative_power.push(0f64);
+        for magnitude in spectrum {
+            let sum = cumulative_power.last().copied().unwrap_or_default();
+            cumulative_power.push(sum + (*magnitude as f64).powi(2));
+        }
+
+        spectrum
+            .iter()
+            .enumerate()
+            .map(|(bin_idx, magnitude)| {
+                if bin_idx == 0 {
+                    return *magnitude;
+                }
+
+                let low = ((bin_idx as f32 / half_width).ceil() as usize).min(bin_idx);
+                let high = ((bin_idx as f32 * half_width).floor() as usize)
+                    .clamp(bin_idx, spectrum.len() - 1);
+
+                let power = cumulative_power[high + 1] - cumulative_power[low];
+                (power / (high + 1 - low) as f64).sqrt() as f32
+            })
+            .collect()
+    }
+}
+
+/// Sums the energy of all bins within each of the given `(low, center, high)`
+/// bands, returning the magnitude of each band.
+///
+/// The window spreads the energy of a sine over `noise_bandwidth` bins, which the
+/// sum is compensated for. Bands too narrow to contain a bin take the magnitude
+/// of the bin closest to their center, compensated the same way so that there is
+/// no step where the two meet.
+fn band_magnitudes(
+    spectrum: &[f32],
+    nyquist: f32,
+    noise_bandwidth: f32,
+    bands: &[(f32, f32, f32)],
+) -> Vec<f32> {
+    let len = spectrum.len();
+    if len < 2 {
+        return vec![0.; bands.len()];
+    }
+    let bin_width = nyquist / (len - 1) as f32;
+
+    bands
+        .iter()
+        .map(|&(low, center, high)| {
+            let first = (low / bin_width).ceil() as usize;
+            let end = ((high / bin_width).ceil() as usize).min(len);
+
+            if first >= end {
+                let bin = ((center / bin_width).round() as usize).min(len - 1);
+                return spectrum[bin] / noise_bandwidth.sqrt();
+            }
+
+            let power = spectrum[first..end].iter().map(|m| m * m).sum::<f32>();
+            (power / noise_bandwidth).sqrt()
+        })
+        .collect()
+}
+
+/// A reference noise profile, or target curve, that can be overlaid on a
+/// [`SpectrumAnalyzer`].
+///
+/// All curves are relative to their level at 1 kHz.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReferenceCurve {
+    /// White noise, which has equal power at all frequencies.
+    White,
+    /// Pink noise, which falls by 3 dB per octave.
+    Pink,
+    /// A custom slope, in dB per octave.
+    Slope(f32),
+    /// A custom target curve going through the given `(frequency, dB)` points.
+    ///
+    /// The points need to be sorted by frequency. In between them, the curve is
+    /// interpolated linearly on a logarithmic frequency axis. Below the first and
+    /// above the last point, it stays flat.
+    Points(Vec<(f32, f32)>),
+}
+
+impl ReferenceCurve {
+    /// The level of the curve at the given frequency, in dB.
+    pub fn level_at(&self, frequency: f32) -> f32 {
+        let octaves = (frequency / 1000.).log2();
+
+        match self {
+            ReferenceCurve::White => 0.,
+            ReferenceCurve::Pink => -3. * octaves,
+            ReferenceCurve::Slope(slope) => slope * octaves,
+            ReferenceCurve::Points(points) => {
+                let (Some(first), Some(last)) = (points.first(), points.last()) else {
+                    return 0.;
+                };
+                if frequency <= first.0 {
+                    return first.1;
+                }
+                if frequency >= last.0 {
+                    return last.1;
+                }
+
+                points
+                    .windows(2)
+                    .find(|pair| frequency <= pair[1].0)
+                    .map_or(last.1, |pair| {
+                        let (f0, db0) = pair[0];
+                        let (f1, db1) = pair[1];
+                        let t = (frequency / f0).log2() / (f1 / f0).log2();
+                        db0 + (db1 - db0) * t
+                    })
+            }
+        }
+    }
+
+    /// Computes the average deviation of a spectrum from this curve, for each
+    /// octave band.
+    ///
+    /// Returns the center frequency of each band from 31.25 Hz up to 16 kHz that
+    /// lies below the Nyquist frequency, along with the spectrum's average deviation
+    /// (in dB) from the curve within that band. Positive values mean the spectrum
+    /// is louder than the curve.
+    pub fn deviation_per_octave(
+        &self,
+        level: f32,
+        spectrum: &[f32],
+        nyquist: f32,
+    ) -> Vec<(f32, f32)> {
+        (-5..=4)
+            .map(|octave| 1000. * 2f32.powi(octave))
+            .filter(|center| center * std::f32::consts::SQRT_2 <= nyquist)
+            .filter_map(|center| {
+                let low = center / std::f32::consts::SQRT_2;
+                let high = center * std::f32::consts::SQRT_2;
+
+                let (sum, count) = spectrum
+                    .iter()
+                    .enumerate()
+                    .map(|(bin_idx, magnitude)| {
+                        (bin_frequency(bin_idx, spectrum.len(), nyquist), *magnitude)
+                    })
+                    .filter(|(freq, _)| (low..high).contains(freq))
+                    .fold((0., 0), |(sum, count), (freq, magnitude)| {
+                        (
+                            sum + gain_to_db(magnitude) - (level + self.level_at(freq)),
+                            count + 1,
+                        )
+                    });
+
+                (count > 0).then(|| (center, sum / count as f32))
+            })
+            .collect()
+    }
+}
+
+/// Events that can be sent to a [`SpectrumAnalyzer`].
+pub enum SpectrumAnalyzerEvent {
+    /// Resets the max-hold trace.
+    ResetMaxHold,
+    /// Freezes the spectrum if it is live, and unfreezes it otherwise.
+    ToggleFreeze,
+    /// Captures the currently displayed spectrum as a snapshot.
+    CaptureSnapshot,
+    /// Clears the snapshot.
+    ClearSnapshot,
+    /// Changes the time (in ms) it takes for a bin to decrease by 12 dB.
+    ///
+    /// This applies to the [`SpectrumOutput`] itself, so all views showing it
+    /// follow along - see [`SpectrumOutput::set_decay`].
+    SetDecay(f32),
+}
+
+/// Emitted by a [`SpectrumAnalyzer`] with a
+/// [hover readout](SpectrumAnalyzerModifiers::with_hover_readout) as the cursor
+/// moves across it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpectrumAnalyzerHover {
+    /// The cursor points at the given frequency (in Hz) and magnitude (in dB).
+    Moved {
+        frequency: f32,
+        magnitude: f32,
+        /// The name of the nearest note, e.g. `A4`
+        note: String,
+    },
+    /// The cursor has left the analyzer.
+    Left,
+}
+
+enum SpectrumAnalyzerEvents {
+    UpdateFrequencyAxis(AxisLink),
+}
+
+/// The keys a [`SpectrumAnalyzer`] responds to while it is focused.
+///
+/// Setting a key to `None` disables the shortcut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectrumAnalyzerKeybindings {
+    /// Freezes and unfreezes the spectrum.
+    pub freeze: Option<Code>,
+    /// Captures a snapshot of the spectrum.
+    pub capture_snapshot: Option<Code>,
+    /// Clears the snapshot.
+    pub clear_snapshot: Option<Code>,
+}
+
+impl Default for SpectrumAnalyzerKeybindings {
+    fn default() -> Self {
+        Self {
+            freeze: Some(Code::KeyF),
+            capture_snapshot: Some(Code::KeyS),
+            clear_snapshot: Some(Code::KeyC),
+        }
+    }
+}
+
+impl SpectrumAnalyzerKeybindings {
+    /// Disables all shortcuts.
+    pub fn none() -> Self {
+        Self {
+            freeze: None,
+            capture_snapshot: None,
+            clear_snapshot: None,
+        }
+    }
+
+    /// The event triggered by the given key, if any.
+    fn event(&self, code: Code) -> Option<SpectrumAnalyzerEvent> {
+        if self.freeze == Some(code) {
+            Some(SpectrumAnalyzerEvent::ToggleFreeze)
+        } else if self.capture_snapshot == Some(code) {
+            Some(SpectrumAnalyzerEvent::CaptureSnapshot)
+        } else if self.clear_snapshot == Some(code) {
+            Some(SpectrumAnalyzerEvent::ClearSnapshot)
+        } else {
+            None
+        }
+    }
+}
+
+/// The averaged and max-hold spectra, drawn alongside the instantaneous one.
+struct Traces {
+    /// The time (in ms) it takes for the average to cover 75% of a change.
+    averaging_time: f32,
+    average: Vec<f32>,
+    max: Vec<f32>,
+    last_frame: Option<Instant>,
+}
+
+impl Traces {
+    fn new(averaging_time: f32) -> Self {
+        Self {
+            averaging_time,
+            average: vec![],
+            max: vec![],
+            last_frame: None,
+        }
+    }
+
+    fn update(&mut self, spectrum: &[f32]) {
+        if self.average.len() != spectrum.len() {
+            self.average = spectrum.to_vec();
+        }
+        if self.max.len() != spectrum.len() {
+            self.max = spectrum.to_vec();
+        }
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_frame
+            .map_or(0., |t| now.duration_since(t).as_secs_f32() * 1000.);
+        self.last_frame = Some(now);
+
+        let weight = if self.averaging_time > 0. {
+            0.25f32.powf(elapsed / self.averaging_time)
+        } else {
+            0.
+        };
+
+        for ((magnitude, average), max) in spectrum
+            .iter()
+            .zip(self.average.iter_mut())
+            .zip(self.max.iter_mut())
+        {
+            *average = *average * weight + magnitude * (1. - weight);
+            *max = max.max(*magnitude);
+        }
+    }
+}
+
+/// Falling per-bin peaks, drawn as caps above the bars of the `BAR` variant, or
+/// as a peak-hold trace.
+struct PeakCaps {
+    /// The time (in ms) a cap holds before it starts falling.
+    hold: f32,
+    /// The speed (in dB/s) at which a cap falls.
+    fall: f32,
+    values: Vec<f32>,
+    /// The time (in ms) each cap has been holding for
+    ages: Vec<f32>,
+    last_frame: Option<Instant>,
+}
+
+impl PeakCaps {
+    fn new(hold: f32, fall: f32) -> Self {
+        Self {
+            hold,
+            fall,
+            values: vec![],
+            ages: vec![],
+            last_frame: None,
+        }
+    }
+
+    fn update(&mut self, spectrum: &[f32]) {
+        if self.values.len() != spectrum.len() {
+            self.values = spectrum.to_vec();
+            self.ages = vec![0.; spectrum.len()];
+        }
+
+        let now = Instant::now();
+        let elapsed = self
+            .last_frame
+            .map_or(0., |t| now.duration_since(t).as_secs_f32() * 1000.);
+        self.last_frame = Some(now);
+
+        let fall = db_to_gain(-self.fall * elapsed / 1000.);
+
+        for ((magnitude, value), age) in spectrum
+            .iter()
+            .zip(self.values.iter_mut())
+            .zip(self.ages.iter_mut())
+        {
+            if *magnitude >= *value {
+                *value = *magnitude;
+                *age = 0.;
+            } else {
+                *age += elapsed;
+                if *age > self.hold {
+                    *value = (*value * fall).max(*magnitude);
+                }
+            }
+        }
+    }
 }
 
 impl SpectrumAnalyzer {
@@ -250,16 +711,340 @@ impl SpectrumAnalyzer {
             magnitude_scaling,
             magnitude_range,
             slope: None,
+            color_map: None,
+            fill_gradient: None,
+            traces: RefCell::new(None),
+            peak_caps: RefCell::new(None),
+            peak_hold: RefCell::new(None),
+            overlay: None,
+            smoothing: None,
+            reference: None,
+            deviation_readout: None,
+            keybindings: SpectrumAnalyzerKeybindings::default(),
+            frozen: None,
+            snapshot: None,
+            snapshot_color: None,
+            bar_layout: None,
+            resampler: None,
+            hover_readout: false,
+            hover: None,
         }
         .build(cx, |_cx| ())
     }
 }
 
+impl SpectrumAnalyzer {
+    /// Returns a function that normalizes the magnitude of a bin at a given
+    /// frequency, applying the slope if one is set.
+    fn magnitude_normalizer(&self, nyquist: f32) -> impl Fn(f32, f32) -> f32 + '_ {
+        // This will be used to normalize the magnitudes if a slope gets applied to them
+        let magnitude_slope_divisor = match self.slope {
+            Some(slope) => nyquist.log2().powf(slope) / slope,
+            None => 0.,
+        };
+
+        move |magnitude, freq| match self.slope {
+            Some(slope) => self.magnitude_scaling.value_to_normalized(
+                magnitude * ((freq + 1.).log2().powf(slope) / magnitude_slope_divisor),
+                self.magnitude_range.0,
+                self.magnitude_range.1,
+            ),
+            None => self.magnitude_scaling.value_to_normalized(
+                magnitude,
+                self.magnitude_range.0,
+                self.magnitude_range.1,
+            ),
+        }
+    }
+
+    /// Normalizes the magnitudes of all bins at once, applying the slope if one is
+    /// set.
+    fn normalized_magnitudes(&self, magnitudes: &[f32], nyquist: f32) -> Vec<f32> {
+        let mut normalized = vec![0.; magnitudes.len()];
+        let (min, max) = self.magnitude_range;
+
+        match self.slope {
+            Some(slope) => {
+                let magnitude_slope_divisor = nyquist.log2().powf(slope) / slope;
+                let sloped = magnitudes
+                    .iter()
+                    .enumerate()
+                    .map(|(bin_idx, magnitude)| {
+                        let freq = bin_frequency(bin_idx, magnitudes.len(), nyquist);
+                        magnitude * ((freq + 1.).log2().powf(slope) / magnitude_slope_divisor)
+                    })
+                    .collect::<Vec<f32>>();
+
+                self.magnitude_scaling.value_to_normalized_slice(
+                    &sloped,
+                    &mut normalized,
+                    min,
+                    max,
+                );
+            }
+            None => self.magnitude_scaling.value_to_normalized_slice(
+                magnitudes,
+                &mut normalized,
+                min,
+                max,
+            ),
+        }
+
+        normalized
+    }
+
+    /// The frequencies of all bins of a spectrum with `len` bins, along with their
+    /// normalized positions.
+    fn bin_frequencies(&self, len: usize, nyquist: f32) -> (Vec<f32>, Vec<f32>) {
+        let frequencies = (0..len)
+            .map(|bin_idx| bin_frequency(bin_idx, len, nyquist))
+            .collect::<Vec<f32>>();
+
+        let mut normalized = vec![0.; len];
+        self.frequency_scaling.value_to_normalized_slice(
+            &frequencies,
+            &mut normalized,
+            self.frequency_range.0,
+            self.frequency_range.1,
+        );
+
+        (frequencies, normalized)
+    }
+
+    /// The `(low, center, high)` frequencies of each bar, along with its left edge,
+    /// for bars of the given width (in pixels) and gap, below the Nyquist frequency.
+    ///
+    /// The bars are evenly distributed along the frequency axis, whatever its
+    /// scaling, so each covers a range of frequencies proportional to its width.
+    fn bar_columns(&self, w: f32, pitch: f32, nyquist: f32) -> Vec<((f32, f32, f32), f32)> {
+        let frequency_at = |position: f32| {
+            self.frequency_scaling.normalized_to_value(
+                position / w,
+                self.frequency_range.0,
+                self.frequency_range.1,
+            )
+        };
+
+        (0..(w / pitch).floor().max(1.) as usize)
+            .map(|column| {
+                let left = column as f32 * pitch;
+                let band = (
+                    frequency_at(left),
+                    frequency_at(left + pitch / 2.),
+                    frequency_at(left + pitch),
+                );
+                (band, left)
+            })
+            .take_while(|((low, _, _), _)| *low < nyquist)
+            .collect()
+    }
+
+    /// Builds a line going through the given magnitudes.
+    fn line(
+        &self,
+        magnitudes: &[f32],
+        nyquist: f32,
+        (x, y, w, h): (f32, f32, f32, f32),
+    ) -> vg::Path {
+        // A trace that was just reset has no bins yet
+        if magnitudes.len() < 2 {
+            return vg::Path::new();
+        }
+
+        if let Some(resampler) = &self.resampler {
+            return self.resampled_line(resampler, magnitudes, nyquist, (x, y, w, h));
+        }
+
+        let magnitudes_normalized = self.normalized_magnitudes(magnitudes, nyquist);
+        let (frequencies, frequencies_normalized) = self.bin_frequencies(magnitudes.len(), nyquist);
+
+        let mut line = vg::Path::new();
+
+        line.move_to(x, y + (h * (1.0 - magnitudes_normalized[1])));
+
+        for (bin_idx, &freq) in frequencies.iter().enumerate().skip(1) {
+            let magnitude_normalized = magnitudes_normalized[bin_idx];
+
+            // Skip frequencies that are out of range
+            if freq < self.frequency_range.0 {
+                line.move_to(x, y + (h * (1.0 - magnitude_normalized)));
+                continue;
+            }
+            if freq > self.frequency_range.1 {
+                break;
+            }
+
+            line.line_to(
+                x + (w * frequencies_normalized[bin_idx]),
+                y + (h * (1.0 - magnitude_normalized)),
+            );
+        }
+
+        line
+    }
+
+    /// Builds a line going through the given magnitudes, resampled onto one point
+    /// per pixel.
+    ///
+    /// The resampler is only rebuilt when the layout of the analyzer or spectrum
+    /// changes.
+    fn resampled_line(
+        &self,
+        resampler: &RefCell<Option<SpectrumResampler>>,
+        magnitudes: &[f32],
+        nyquist: f32,
+        (x, y, w, h): (f32, f32, f32, f32),
+    ) -> vg::Path {
+        let points = w.ceil() as usize + 1;
+
+        let (scaling, range, len) = (
+            self.frequency_scaling,
+            self.frequency_range,
+            magnitudes.len(),
+        );
+
+        let mut cached = resampler.borrow_mut();
+        if !cached
+            .as_ref()
+            .is_some_and(|r| r.matches(points, scaling, range, len, nyquist))
+        {
+            *cached = None;
+        }
+        let resampler = cached
+            .get_or_insert_with(|| SpectrumResampler::new(points, scaling, range, len, nyquist));
+
+        let normalize = self.magnitude_normalizer(nyquist);
+
+        let mut line = vg::Path::new();
+
+        for (point, (magnitude, freq)) in resampler
+            .resample(magnitudes)
+            .into_iter()
+            .zip(resampler.frequencies())
+            .enumerate()
+        {
+            let (point_x, point_y) = (
+                x + w * point as f32 / (points - 1) as f32,
+                y + h * (1.0 - normalize(magnitude, *freq)),
+            );
+
+            if point == 0 {
+                line.move_to(point_x, point_y);
+            } else {
+                line.line_to(point_x, point_y);
+            }
+        }
+
+        line
+    }
+
+    /// The frequency (in Hz) and magnitude (in dB) at the given point, undoing the
+    /// slope if one is set.
+    fn value_at(&self, (x, y): (f32, f32), bounds: BoundingBox, nyquist: f32) -> (f32, f32) {
+        let frequency = self.frequency_scaling.normalized_to_value(
+            ((x - bounds.x) / bounds.w).clamp(0., 1.),
+            self.frequency_range.0,
+            self.frequency_range.1,
+        );
+        let mut magnitude = self.magnitude_scaling.normalized_to_value(
+            (1. - (y - bounds.y) / bounds.h).clamp(0., 1.),
+            self.magnitude_range.0,
+            self.magnitude_range.1,
+        );
+
+        if let Some(slope) = self.slope {
+            let magnitude_slope_divisor = nyquist.log2().powf(slope) / slope;
+            magnitude /= (frequency + 1.).log2().powf(slope) / magnitude_slope_divisor;
+        }
+
+        (frequency, gain_to_db(magnitude))
+    }
+}
+
 impl View for SpectrumAnalyzer {
     fn element(&self) -> Option<&'static str> {
         Some("spectrum-analyzer")
     }
 
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            SpectrumAnalyzerEvent::ResetMaxHold => {
+                // The traces aren't updated while frozen, so they start over from
+                // the frozen spectrum right away
+                let spectrum = self.frozen.clone().unwrap_or_default();
+
+                if let Some(traces) = self.traces.get_mut() {
+                    traces.max = spectrum.clone();
+                }
+                if let Some((peak_hold, _)) = self.peak_hold.get_mut() {
+                    peak_hold.ages = vec![0.; spectrum.len()];
+                    peak_hold.values = spectrum;
+                }
+            }
+            SpectrumAnalyzerEvent::ToggleFreeze => {
+                self.frozen = match self.frozen {
+                    Some(_) => None,
+                    None => Some(lock_or_recover(&self.spectrum).read().to_vec()),
+                };
+            }
+            SpectrumAnalyzerEvent::CaptureSnapshot => {
+                self.snapshot = Some(match &self.frozen {
+                    Some(frozen) => frozen.clone(),
+                    None => lock_or_recover(&self.spectrum).read().to_vec(),
+                });
+            }
+            SpectrumAnalyzerEvent::ClearSnapshot => self.snapshot = None,
+            SpectrumAnalyzerEvent::SetDecay(decay) => {
+                lock_or_recover(&self.spectrum).set_decay(*decay);
+            }
+        });
+
+        event.map(|e, _| match e {
+            SpectrumAnalyzerEvents::UpdateFrequencyAxis(axis) => {
+                self.frequency_scaling = axis.scaling;
+                self.frequency_range = axis.range;
+            }
+        });
+
+        event.map(|window_event, meta| match window_event {
+            // The analyzer needs to be focused in order to receive key presses
+            WindowEvent::MouseDown(_) => cx.focus(),
+            WindowEvent::KeyDown(code, _) => {
+                if let Some(e) = self.keybindings.event(*code) {
+                    cx.emit(e);
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, y) if self.hover_readout => {
+                let nyquist = lock_or_recover(&self.spectrum).nyquist();
+                let (frequency, magnitude) = self.value_at((*x, *y), cx.bounds(), nyquist);
+                let note = Note::from_frequency(frequency, 440.).name();
+
+                HoverReadout::show(
+                    cx,
+                    format!("{} Hz  {:.1} dB  {}", frequency.round(), magnitude, note),
+                    (*x, *y),
+                );
+                cx.emit(SpectrumAnalyzerHover::Moved {
+                    frequency,
+                    magnitude,
+                    note,
+                });
+
+                self.hover = Some((*x, *y));
+                cx.needs_redraw();
+            }
+            WindowEvent::MouseLeave if self.hover_readout => {
+                HoverReadout::hide(cx);
+                cx.emit(SpectrumAnalyzerHover::Left);
+
+                self.hover = None;
+                cx.needs_redraw();
+            }
+            _ => {}
+        });
+    }
+
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
         let bounds = cx.bounds();
 
@@ -268,137 +1053,391 @@ impl View for SpectrumAnalyzer {
         let w = bounds.w;
         let h = bounds.h;
 
-        let mut spectrum = self.spectrum.lock().unwrap();
-        let half_nyquist = spectrum.sample_rate / 2.;
-        let spectrum_output = spectrum.output.read();
+        let mut spectrum = lock_or_recover(&self.spectrum);
+        let nyquist = spectrum.nyquist();
+        let noise_bandwidth = spectrum.noise_bandwidth();
+        // The overlay is copied, as the main spectrum keeps the output borrowed
+        let overlay = match (&self.frozen, self.overlay) {
+            (None, Some((index, color))) => spectrum.read_trace(index).map(|trace| {
+                let trace = match self.smoothing {
+                    Some(fraction) => fraction.smooth(trace),
+                    None => trace.to_vec(),
+                };
+                (trace, color)
+            }),
+            _ => None,
+        };
+        let spectrum_output: &[f32] = match &self.frozen {
+            Some(frozen) => frozen,
+            None => spectrum.read(),
+        };
+        let smoothed;
+        let spectrum_output = match self.smoothing {
+            Some(fraction) => {
+                smoothed = fraction.smooth(spectrum_output);
+                &smoothed
+            }
+            None => spectrum_output,
+        };
 
         let foreground =
             vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor());
         let background =
             vg::Paint::color(cx.background_color().into()).with_line_width(cx.scale_factor());
 
+        // If a color map is set, the area is filled with a gradient going from the
+        // lows on the left to the highs on the right
+        let (foreground, background) = match &self.color_map {
+            Some(color_map) => {
+                let stops = color_map
+                    .stops()
+                    .iter()
+                    .map(|(position, color)| (*position, (*color).into()))
+                    .collect::<Vec<(f32, vg::Color)>>();
+                let gradient = vg::Paint::linear_gradient_stops(x, y, x + w, y, &stops)
+                    .with_line_width(cx.scale_factor());
+
+                match self.variant {
+                    SpectrumAnalyzerVariant::BAR | SpectrumAnalyzerVariant::BANDS(_) => {
+                        (gradient, background)
+                    }
+                    SpectrumAnalyzerVariant::LINE => (foreground, gradient),
+                }
+            }
+            None => (foreground, background),
+        };
+        // A vertical gradient takes precedence over the color map's fill
+        let background = match self.fill_gradient {
+            Some((bottom, top)) => {
+                vg::Paint::linear_gradient(x, y + h, x, y, bottom.into(), top.into())
+            }
+            None => background,
+        };
+
+        // The spectrum can't contain anything above the Nyquist frequency, so the
+        // traces end there if the sample rate is too low for the displayed range
+        let nyquist_x = x + w * self
+            .frequency_scaling
+            .value_to_normalized(nyquist, self.frequency_range.0, self.frequency_range.1)
+            .clamp(0., 1.);
+
+        // The snapshot is drawn as a ghost behind the live spectrum
+        if let Some(snapshot) = &self.snapshot {
+            let snapshot_color: vg::Color = match self.snapshot_color {
+                Some(color) => color.into(),
+                None => {
+                    let mut color: vg::Color = cx.font_color().into();
+                    color.a *= 0.6;
+                    color
+                }
+            };
+            let snapshot = match self.smoothing {
+                Some(fraction) => fraction.smooth(snapshot),
+                None => snapshot.clone(),
+            };
+
+            canvas.stroke_path(
+                &self.line(&snapshot, nyquist, (x, y, w, h)),
+                &vg::Paint::color(snapshot_color).with_line_width(cx.scale_factor()),
+            );
+        }
+
         match &self.variant {
-            SpectrumAnalyzerVariant::BAR => {
-                let mut path = vg::Path::new();
+            SpectrumAnalyzerVariant::BAR if self.bar_layout.is_some() => {
+                let (bar_width, gap) = self.bar_layout.unwrap_or_default();
+                let bar_width = bar_width * cx.scale_factor();
+                let gap = gap * cx.scale_factor();
 
-                // This will be used to normalize the magnitudes if a slope gets applied to them
-                let magnitude_slope_divisor = if self.slope.is_some() {
-                    half_nyquist.log2().powf(self.slope.unwrap()) / self.slope.unwrap()
-                } else {
-                    0.
-                };
+                let mut bars = vg::Path::new();
+                let mut caps = vg::Path::new();
 
-                for (bin_idx, magnitude) in spectrum_output.iter().enumerate() {
-                    let freq = (bin_idx as f32 / spectrum_output.len() as f32) * half_nyquist;
+                let normalize = self.magnitude_normalizer(nyquist);
 
-                    // Normalize frequency
-                    let freq_normalized = self.frequency_scaling.value_to_normalized(
-                        freq,
-                        self.frequency_range.0,
-                        self.frequency_range.1,
-                    );
+                // Each bar combines the energy of all bins it covers
+                let columns = self.bar_columns(w, bar_width + gap, nyquist);
+                let bands = columns.iter().map(|(band, _)| *band).collect::<Vec<_>>();
+                let magnitudes = band_magnitudes(spectrum_output, nyquist, noise_bandwidth, &bands);
 
-                    // Normalize magnitude and apply slope if one is set
-                    let magnitude_normalized = if self.slope.is_some() {
-                        self.magnitude_scaling.value_to_normalized(
-                            *magnitude
-                                * ((freq + 1.).log2().powf(self.slope.unwrap())
-                                    / magnitude_slope_divisor),
-                            self.magnitude_range.0,
-                            self.magnitude_range.1,
-                        )
-                    } else {
-                        self.magnitude_scaling.value_to_normalized(
-                            *magnitude,
-                            self.magnitude_range.0,
-                            self.magnitude_range.1,
-                        )
-                    };
+                let mut peak_caps = self.peak_caps.borrow_mut();
+                if let Some(peak_caps) = peak_caps.as_mut() {
+                    if self.frozen.is_none() {
+                        peak_caps.update(&magnitudes);
+                    }
+                }
+                let cap_height = 2. * cx.scale_factor();
 
-                    path.move_to(
-                        x + (w * freq_normalized),
-                        y + (h * (1.0 - magnitude_normalized)),
-                    );
-                    path.line_to(x + (w * freq_normalized), y + h);
+                for (bar_idx, (&((_, center, _), left), magnitude)) in
+                    columns.iter().zip(magnitudes).enumerate()
+                {
+                    let left = x + left + gap / 2.;
+
+                    let top = y + h * (1.0 - normalize(magnitude, center).clamp(0., 1.));
+                    bars.rect(left, top, bar_width, y + h - top);
+
+                    if let Some(cap) = peak_caps.as_ref().and_then(|c| c.values.get(bar_idx)) {
+                        let cap_y = y + h * (1.0 - normalize(*cap, center).clamp(0., 1.));
+                        caps.rect(left, cap_y, bar_width, cap_height.min(y + h - cap_y));
+                    }
                 }
 
-                canvas.stroke_path(&path, &foreground);
+                canvas.fill_path(&bars, &foreground);
+                canvas.fill_path(&caps, &foreground);
             }
-            SpectrumAnalyzerVariant::LINE => {
-                let mut line = vg::Path::new();
-
-                let mut magnitude_normalized = self.magnitude_scaling.value_to_normalized(
-                    spectrum_output[1],
-                    self.magnitude_range.0,
-                    self.magnitude_range.1,
-                );
+            SpectrumAnalyzerVariant::BAR => {
+                let mut path = vg::Path::new();
+                let mut caps = vg::Path::new();
 
-                line.move_to(x, y + (h * (1.0 - magnitude_normalized)));
+                let normalize = self.magnitude_normalizer(nyquist);
 
-                // This will be used to normalize the magnitudes if a slope gets applied to them
-                let magnitude_slope_divisor = if self.slope.is_some() {
-                    half_nyquist.log2().powf(self.slope.unwrap()) / self.slope.unwrap()
-                } else {
-                    0.
-                };
+                let mut peak_caps = self.peak_caps.borrow_mut();
+                if let Some(peak_caps) = peak_caps.as_mut() {
+                    // Like the traces, the caps keep their state while frozen
+                    if self.frozen.is_none() {
+                        peak_caps.update(spectrum_output);
+                    }
+                }
+                let cap_height = 2. * cx.scale_factor();
 
-                for (bin_idx, magnitude) in spectrum_output.iter().skip(1).enumerate() {
-                    let freq = (bin_idx as f32 / spectrum_output.len() as f32) * half_nyquist;
-
-                    // Normalize magnitude and apply slope if one is set
-                    magnitude_normalized = if self.slope.is_some() {
-                        self.magnitude_scaling.value_to_normalized(
-                            *magnitude
-                                * ((freq + 1.).log2().powf(self.slope.unwrap())
-                                    / magnitude_slope_divisor),
-                            self.magnitude_range.0,
-                            self.magnitude_range.1,
-                        )
-                    } else {
-                        self.magnitude_scaling.value_to_normalized(
-                            *magnitude,
-                            self.magnitude_range.0,
-                            self.magnitude_range.1,
-                        )
-                    };
+                // Normalize magnitudes and apply slope if one is set
+                let magnitudes_normalized = self.normalized_magnitudes(spectrum_output, nyquist);
+                let (frequencies, frequencies_normalized) =
+                    self.bin_frequencies(spectrum_output.len(), nyquist);
 
+                for (bin_idx, &freq) in frequencies.iter().enumerate() {
                     // Skip frequencies that are out of range
                     if freq < self.frequency_range.0 {
-                        line.move_to(x, y + (h * (1.0 - magnitude_normalized)));
                         continue;
                     }
                     if freq > self.frequency_range.1 {
                         break;
                     }
 
-                    // Normalize frequency
-                    let freq_normalized = self.frequency_scaling.value_to_normalized(
-                        freq,
-                        self.frequency_range.0,
-                        self.frequency_range.1,
-                    );
+                    let freq_normalized = frequencies_normalized[bin_idx];
+                    let magnitude_normalized = magnitudes_normalized[bin_idx];
 
-                    line.line_to(
+                    path.move_to(
                         x + (w * freq_normalized),
                         y + (h * (1.0 - magnitude_normalized)),
                     );
+                    path.line_to(x + (w * freq_normalized), y + h);
+
+                    if let Some(cap) = peak_caps.as_ref().and_then(|c| c.values.get(bin_idx)) {
+                        let cap_y = y + (h * (1.0 - normalize(*cap, freq)));
+
+                        caps.move_to(x + (w * freq_normalized), cap_y);
+                        caps.line_to(x + (w * freq_normalized), (cap_y + cap_height).min(y + h));
+                    }
+                }
+
+                canvas.stroke_path(&path, &foreground);
+                canvas.stroke_path(&caps, &foreground);
+            }
+            SpectrumAnalyzerVariant::BANDS(bands_per_octave) => {
+                let mut bars = vg::Path::new();
+                let mut caps = vg::Path::new();
+
+                let normalize = self.magnitude_normalizer(nyquist);
+
+                let bands = bands_per_octave.bands(self.frequency_range, nyquist);
+                let magnitudes = band_magnitudes(spectrum_output, nyquist, noise_bandwidth, &bands);
+
+                // The caps follow the bands rather than the bins here
+                let mut peak_caps = self.peak_caps.borrow_mut();
+                if let Some(peak_caps) = peak_caps.as_mut() {
+                    if self.frozen.is_none() {
+                        peak_caps.update(&magnitudes);
+                    }
                 }
+                let cap_height = 2. * cx.scale_factor();
+
+                for (band_idx, (&(low, center, high), magnitude)) in
+                    bands.iter().zip(magnitudes).enumerate()
+                {
+                    let [left, right] = [low, high].map(|freq| {
+                        x + w * self
+                            .frequency_scaling
+                            .value_to_normalized(
+                                freq,
+                                self.frequency_range.0,
+                                self.frequency_range.1,
+                            )
+                            .clamp(0., 1.)
+                    });
+                    // Leave a small gap in between neighbouring bars
+                    let gap = ((right - left) * 0.15).max(cx.scale_factor());
+                    let (left, width) = (left + gap / 2., (right - left - gap).max(0.));
+
+                    let top = y + h * (1.0 - normalize(magnitude, center).clamp(0., 1.));
+                    bars.rect(left, top, width, y + h - top);
+
+                    if let Some(cap) = peak_caps.as_ref().and_then(|c| c.values.get(band_idx)) {
+                        let cap_y = y + h * (1.0 - normalize(*cap, center).clamp(0., 1.));
+                        caps.rect(left, cap_y, width, cap_height.min(y + h - cap_y));
+                    }
+                }
+
+                canvas.fill_path(&bars, &foreground);
+                canvas.fill_path(&caps, &foreground);
+            }
+            SpectrumAnalyzerVariant::LINE => {
+                let line = self.line(spectrum_output, nyquist, (x, y, w, h));
 
                 let mut fill = line.clone();
-                fill.line_to(x + w, y + h);
+                fill.line_to(nyquist_x, y + h);
                 fill.line_to(x, y + h);
 
                 fill.close();
 
                 canvas.fill_path(&fill, &background);
-                canvas.stroke_path(&line, &foreground);
+
+                // With the average and max-hold traces, the instantaneous spectrum
+                // is only drawn as a filled area
+                if self.traces.borrow().is_none() {
+                    canvas.stroke_path(&line, &foreground);
+                }
+            }
+        }
+
+        if let Some((overlay, color)) = &overlay {
+            canvas.stroke_path(
+                &self.line(overlay, nyquist, (x, y, w, h)),
+                &vg::Paint::color((*color).into()).with_line_width(cx.scale_factor()),
+            );
+        }
+
+        if let Some(traces) = self.traces.borrow_mut().as_mut() {
+            // The traces keep their state while the spectrum is frozen
+            if self.frozen.is_none() {
+                traces.update(spectrum_output);
+            }
+
+            let mut max_color: vg::Color = cx.font_color().into();
+            max_color.a *= 0.5;
+
+            canvas.stroke_path(
+                &self.line(&traces.max, nyquist, (x, y, w, h)),
+                &vg::Paint::color(max_color).with_line_width(cx.scale_factor()),
+            );
+            canvas.stroke_path(
+                &self.line(&traces.average, nyquist, (x, y, w, h)),
+                &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor() * 1.5),
+            );
+        }
+
+        if let Some((peak_hold, color)) = self.peak_hold.borrow_mut().as_mut() {
+            // Like the other traces, the peaks keep their state while frozen
+            if self.frozen.is_none() {
+                peak_hold.update(spectrum_output);
+            }
+
+            canvas.stroke_path(
+                &self.line(&peak_hold.values, nyquist, (x, y, w, h)),
+                &vg::Paint::color((*color).into()).with_line_width(cx.scale_factor()),
+            );
+        }
+
+        if let Some((curve, level)) = &self.reference {
+            let normalize = self.magnitude_normalizer(nyquist);
+
+            let mut reference = vg::Path::new();
+
+            for i in 0..=w.ceil() as usize {
+                let freq = self.frequency_scaling.normalized_to_value(
+                    i as f32 / w,
+                    self.frequency_range.0,
+                    self.frequency_range.1,
+                );
+                let magnitude = db_to_gain(level + curve.level_at(freq));
+
+                let point = (x + i as f32, y + (h * (1.0 - normalize(magnitude, freq))));
+                if i == 0 {
+                    reference.move_to(point.0, point.1);
+                } else {
+                    reference.line_to(point.0, point.1);
+                }
+            }
+
+            let mut reference_color: vg::Color = cx.font_color().into();
+            reference_color.a *= 0.35;
+
+            canvas.stroke_path(
+                &reference,
+                &vg::Paint::color(reference_color).with_line_width(cx.scale_factor() * 2.),
+            );
+        }
+
+        // Average deviation from the reference curve per octave band, drawn as small
+        // bars along the bottom, going up if the spectrum is louder than the curve
+        if let (Some((curve, level)), Some(readout_height)) =
+            (&self.reference, self.deviation_readout)
+        {
+            let readout_height = readout_height * cx.scale_factor();
+            let center_y = y + h - readout_height / 2.;
+
+            let mut bars = vg::Path::new();
+
+            for (center, deviation) in curve.deviation_per_octave(*level, spectrum_output, nyquist)
+            {
+                let [low, high] = [
+                    center / std::f32::consts::SQRT_2,
+                    center * std::f32::consts::SQRT_2,
+                ]
+                .map(|freq| {
+                    self.frequency_scaling
+                        .value_to_normalized(freq, self.frequency_range.0, self.frequency_range.1)
+                        .clamp(0., 1.)
+                });
+                if high <= low {
+                    continue;
+                }
+
+                // Bars reach the edge of the readout at a deviation of 12 dB
+                let bar_height = (deviation / 12.).clamp(-1., 1.) * readout_height / 2.;
+                let bar_width = (high - low) * w * 0.6;
+
+                bars.rect(
+                    x + w * (low + high) / 2. - bar_width / 2.,
+                    center_y - bar_height.max(0.),
+                    bar_width,
+                    bar_height.abs().max(cx.scale_factor()),
+                );
             }
+
+            canvas.fill_path(&bars, &vg::Paint::color(cx.font_color().into()));
+        }
+
+        // Gray out the region above the Nyquist frequency
+        if nyquist_x < x + w {
+            let mut shade_color: vg::Color = cx.font_color().into();
+            shade_color.a *= 0.15;
+
+            let mut shade = vg::Path::new();
+            shade.rect(nyquist_x, y, x + w - nyquist_x, h);
+            canvas.fill_path(&shade, &vg::Paint::color(shade_color));
+        }
+
+        if let Some(hover) = self.hover {
+            draw_guides(cx, canvas, hover);
         }
     }
 }
 
 pub trait SpectrumAnalyzerModifiers {
     fn with_slope(self, slope: f32) -> Self;
+    fn with_color_map(self, color_map: ColorMap) -> Self;
+    fn with_average_and_max(self, averaging_time: f32) -> Self;
+    fn with_peak_caps(self, hold: f32, fall: f32) -> Self;
+    fn with_peak_hold(self, hold: f32, fall: f32, color: Color) -> Self;
+    fn with_bar_width(self, width: f32, gap: f32) -> Self;
+    fn with_display_resolution(self) -> Self;
+    fn overlay_trace(self, index: usize, color: Color) -> Self;
+    fn smoothing(self, fraction: OctaveFraction) -> Self;
+    fn snapshot_color(self, color: Color) -> Self;
+    fn with_hover_readout(self) -> Self;
+    fn with_reference_curve(self, curve: ReferenceCurve, level: f32) -> Self;
+    fn with_deviation_readout(self, height: f32) -> Self;
+    fn keybindings(self, keybindings: SpectrumAnalyzerKeybindings) -> Self;
+    fn frequency_axis(self, link: impl Lens<Target = AxisLink>) -> Self;
 }
 impl SpectrumAnalyzerModifiers for Handle<'_, SpectrumAnalyzer> {
     /// Sets a slope in db/oct.
@@ -409,4 +1448,321 @@ impl SpectrumAnalyzerModifiers for Handle<'_, SpectrumAnalyzer> {
     fn with_slope(self, slope: f32) -> Self {
         self.modify(|spectrum| spectrum.slope = Some(slope))
     }
+    /// Colors the spectrum by frequency, using a [`ColorMap`].
+    ///
+    /// The color map is laid out from the lowest displayed frequency on the left to
+    /// the highest one on the right. For the `LINE` variant, it replaces the
+    /// `background_color` used to fill the area below the line. For the `BAR`
+    /// variant, it replaces the `color` of the bars.
+    fn with_color_map(self, color_map: ColorMap) -> Self {
+        self.modify(|spectrum| spectrum.color_map = Some(color_map))
+    }
+    /// Additionally draws an averaged and a max-hold spectrum.
+    ///
+    /// The instantaneous spectrum is then drawn as a filled area using the
+    /// `background_color`, the averaged spectrum as a line using the `color`, and
+    /// the max-hold spectrum as a thinner, translucent line. The `averaging_time`
+    /// dictates how long (in ms) it takes for the average to cover 75% of a change
+    /// in magnitude. The max-hold trace can be reset by sending a
+    /// [`SpectrumAnalyzerEvent::ResetMaxHold`] to the analyzer.
+    fn with_average_and_max(self, averaging_time: f32) -> Self {
+        self.modify(|spectrum| {
+            spectrum.traces = RefCell::new(Some(Traces::new(averaging_time)));
+        })
+    }
+    /// Draws a falling peak cap above each bar of the `BAR` and `BANDS` variants.
+    ///
+    /// Whenever a bar reaches above its cap, the cap jumps up to it. It then holds
+    /// for `hold` ms, before falling at a rate of `fall` dB per second until it
+    /// meets the bar again. Caps are drawn using the same paint as the bars. This
+    /// has no effect on the `LINE` variant.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::BAR,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .with_peak_caps(500.0, 20.0)
+    /// .color(Color::rgba(255, 255, 255, 160));
+    /// ```
+    fn with_peak_caps(self, hold: f32, fall: f32) -> Self {
+        self.modify(|spectrum| {
+            spectrum.peak_caps = RefCell::new(Some(PeakCaps::new(hold, fall)));
+        })
+    }
+    /// Additionally draws a trace holding the peak magnitude of each bin.
+    ///
+    /// Whenever a bin reaches above its peak, the peak jumps up to it. It then
+    /// holds for `hold` ms, before falling at a rate of `fall` dB per second until
+    /// it meets the bin again - so a very long hold time keeps the peaks until they
+    /// are reset by sending a [`SpectrumAnalyzerEvent::ResetMaxHold`]. The trace is
+    /// drawn as a line in the given `color`, for both variants.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::LINE,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .with_peak_hold(2000.0, 12.0, Color::rgb(255, 160, 64));
+    /// ```
+    fn with_peak_hold(self, hold: f32, fall: f32, color: Color) -> Self {
+        self.modify(|spectrum| {
+            spectrum.peak_hold = RefCell::new(Some((PeakCaps::new(hold, fall), color)));
+        })
+    }
+    /// Smooths the spectrum over the given fraction of an octave before drawing it.
+    ///
+    /// Each bin shows the average power of all bins within a band of that width
+    /// around it. This irons out the jagged detail of the raw bins - especially in
+    /// the highs - which is useful for judging the overall tonal balance, e.g. when
+    /// matching an EQ curve. Everything drawn from the spectrum is smoothed,
+    /// including the traces, peak caps and the overlay.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::LINE,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .smoothing(OctaveFraction::Sixth);
+    /// ```
+    fn smoothing(self, fraction: OctaveFraction) -> Self {
+        self.modify(|spectrum| spectrum.smoothing = Some(fraction))
+    }
+    /// Sets the color of the snapshot, which is drawn as a ghost trace behind the
+    /// live spectrum.
+    ///
+    /// A snapshot is captured using [`SpectrumAnalyzerEvent::CaptureSnapshot`] and
+    /// cleared using [`SpectrumAnalyzerEvent::ClearSnapshot`], or their
+    /// [keybindings](SpectrumAnalyzerKeybindings). This lets users compare the
+    /// spectrum before and after a change. By default, the snapshot is drawn in
+    /// the analyzer's font color - as set by its `color` style property - at 60%
+    /// of its opacity.
+    fn snapshot_color(self, color: Color) -> Self {
+        self.modify(|spectrum| spectrum.snapshot_color = Some(color))
+    }
+    /// Resamples the spectrum onto one point per pixel before drawing it as a line,
+    /// using a [`SpectrumResampler`].
+    ///
+    /// Without this, every bin gets scaled and drawn on each frame, which adds up
+    /// for large FFT windows and wide editors. With it, the mapping from bins to
+    /// pixels is worked out once, and only the points are scaled and connected.
+    /// Where many bins fall onto one pixel, the loudest of them is shown, so narrow
+    /// peaks stay visible. This applies to all traces drawn as lines.
+    fn with_display_resolution(self) -> Self {
+        self.modify(|spectrum| spectrum.resampler = Some(RefCell::new(None)))
+    }
+    /// Shows a crosshair and a readout of the frequency, magnitude and nearest note
+    /// under the cursor while it hovers over the analyzer.
+    ///
+    /// The values take the analyzer's scaling, range and slope into account. They
+    /// are also emitted as a [`SpectrumAnalyzerHover`] event, so your editor can
+    /// show them elsewhere as well. The crosshair is drawn in the `color` at
+    /// reduced opacity, and the readout in the `color` itself.
+    fn with_hover_readout(mut self) -> Self {
+        self = self.modify(|spectrum| spectrum.hover_readout = true);
+
+        let e = self.entity();
+        self.context().with_current(e, HoverReadout::attach);
+
+        self
+    }
+    /// Draws the `BAR` variant as bars of the given `width`, with a `gap` in between
+    /// (both in logical pixels), instead of a thin line per bin.
+    ///
+    /// The bars are evenly distributed along the frequency axis, so on a
+    /// logarithmic axis, bars in the highs group together many bins, whose energy
+    /// is summed up just like in the `BANDS` variant. Bars in the lows, which
+    /// cover less than a bin, take the magnitude of the nearest bin. Peak caps
+    /// follow the bars.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::BAR,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .with_bar_width(6., 2.)
+    /// .color(Color::rgb(255, 255, 255));
+    /// ```
+    fn with_bar_width(self, width: f32, gap: f32) -> Self {
+        self.modify(|spectrum| spectrum.bar_layout = Some((width.max(1.), gap.max(0.))))
+    }
+    /// Draws another spectrum of the same [`SpectrumOutput`] on top, as a line in
+    /// the given `color`.
+    ///
+    /// The analyzer shows the output's first spectrum as usual. If its input
+    /// computes several spectra using a [`ChannelMode`](crate::spectrum::ChannelMode),
+    /// this overlays the one with the given `index` - e.g. the side signal on top of
+    /// the mid signal, or the right channel on top of the left one. While the
+    /// analyzer is frozen, only the first spectrum is shown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// // In your plug-in
+    /// let (spectrum_input, spectrum_output) = SpectrumInput::builder()
+    ///     .channel_mode(ChannelMode::MidSide)
+    ///     .build(2);
+    ///
+    /// // Inside your editor
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::LINE,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .overlay_trace(1, Color::rgb(255, 120, 80));
+    /// ```
+    fn overlay_trace(self, index: usize, color: Color) -> Self {
+        self.modify(|spectrum| spectrum.overlay = Some((index, color)))
+    }
+    /// Overlays a reference curve, such as a pink noise profile, on the spectrum.
+    ///
+    /// Useful for mixing towards a certain spectral balance. The `level` (in dB)
+    /// sets the curve's level at 1 kHz. If a slope is set, it gets applied to the
+    /// reference curve as well.
+    fn with_reference_curve(self, curve: ReferenceCurve, level: f32) -> Self {
+        self.modify(|spectrum| spectrum.reference = Some((curve, level)))
+    }
+    /// Shows how far the spectrum deviates from the reference curve, per octave.
+    ///
+    /// The average deviation within each octave band is drawn as a small bar in a
+    /// strip of the given `height` (in pixels) along the bottom of the analyzer. Bars
+    /// go up where the spectrum is louder than the curve, and down where it is
+    /// quieter, reaching the edge of the strip at a deviation of 12 dB. This needs a
+    /// reference curve to be set via
+    /// [`with_reference_curve`](Self::with_reference_curve).
+    fn with_deviation_readout(self, height: f32) -> Self {
+        self.modify(|spectrum| spectrum.deviation_readout = Some(height))
+    }
+    /// Sets the keyboard shortcuts for freezing the spectrum and capturing or
+    /// clearing snapshots.
+    ///
+    /// Use [`SpectrumAnalyzerKeybindings::none`] to disable all of them, e.g. when
+    /// the keys are already used for something else in your editor.
+    fn keybindings(self, keybindings: SpectrumAnalyzerKeybindings) -> Self {
+        self.modify(|spectrum| spectrum.keybindings = keybindings)
+    }
+    /// Keeps the frequency range and scaling in sync with an [`AxisLink`].
+    ///
+    /// Link a [`Grid`](super::Grid) and a [`UnitRuler`](super::UnitRuler) to the
+    /// same [`AxisLink`], and they will always line up with the analyzer.
+    fn frequency_axis(mut self, link: impl Lens<Target = AxisLink>) -> Self {
+        let e = self.entity();
+
+        link.set_or_bind(self.context(), e, move |cx, axis| {
+            (*cx).emit_to(e, SpectrumAnalyzerEvents::UpdateFrequencyAxis(axis));
+        });
+
+        self
+    }
+}
+
+impl GradientFillModifiers for Handle<'_, SpectrumAnalyzer> {
+    /// Fills the area under the `LINE` variant with a vertical gradient, going
+    /// from the `bottom` color at the bottom edge to the `top` color at the top
+    /// edge, instead of the flat `background_color`.
+    ///
+    /// This takes precedence over the fill of a
+    /// [color map](SpectrumAnalyzerModifiers::with_color_map).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// SpectrumAnalyzer::new(
+    ///     cx,
+    ///     Data::spectrum,
+    ///     SpectrumAnalyzerVariant::LINE,
+    ///     ValueScaling::Frequency,
+    ///     (10., 21_000.),
+    ///     ValueScaling::Decibels,
+    ///     (-110., 6.),
+    /// )
+    /// .fill_gradient(Color::rgba(255, 255, 255, 0), Color::rgba(255, 255, 255, 120))
+    /// .color(Color::rgb(255, 255, 255));
+    /// ```
+    fn fill_gradient(self, bottom: Color, top: Color) -> Self {
+        self.modify(|spectrum| spectrum.fill_gradient = Some((bottom, top)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoothing_averages_the_power_around_each_bin() {
+        let flat = vec![0.5; 1025];
+        assert!(OctaveFraction::Third
+            .smooth(&flat)
+            .iter()
+            .all(|magnitude| (magnitude - 0.5).abs() < 1e-6));
+
+        let mut peak = vec![0.; 1025];
+        peak[600] = 1.;
+        let smoothed = OctaveFraction::Third.smooth(&peak);
+
+        // Spread out over its neighbours within a third of an octave
+        assert!(smoothed[600] < 0.2);
+        assert!(smoothed[560] > 0. && smoothed[640] > 0.);
+        assert_eq!(smoothed[400], 0.);
+        assert_eq!(smoothed[100], 0.);
+    }
+
+    #[test]
+    fn bands_sum_the_energy_of_their_bins() {
+        let third_octaves = BandsPerOctave::Three.bands((20., 20_000.), 24_000.);
+        assert_eq!(third_octaves.len(), 31);
+        assert!(third_octaves.iter().any(|(_, center, _)| *center == 1000.));
+
+        // A sine at 1 kHz, spread over three bins by the window
+        let mut spectrum = vec![0.; 1025];
+        let bin = (1000. / (24_000. / 1024.)) as usize;
+        spectrum[bin - 1] = 0.25;
+        spectrum[bin] = 0.5;
+        spectrum[bin + 1] = 0.25;
+
+        let magnitudes = band_magnitudes(&spectrum, 24_000., 1.5, &third_octaves);
+        let (idx, _) = third_octaves
+            .iter()
+            .enumerate()
+            .find(|(_, (low, _, high))| (*low..*high).contains(&1000.))
+            .unwrap();
+        assert!((magnitudes[idx] - 0.5).abs() < 1e-6);
+        assert_eq!(magnitudes[0], 0.);
+
+        // Bands narrower than a bin are compensated just like the wider ones
+        let narrow = band_magnitudes(&spectrum, 24_000., 1.5, &[(985., 986., 987.)]);
+        assert!((narrow[0] - 0.5 / 1.5f32.sqrt()).abs() < 1e-6);
+    }
 }
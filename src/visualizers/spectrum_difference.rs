@@ -0,0 +1,194 @@
+use nih_plug::util::gain_to_db;
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+use std::sync::{Arc, Mutex};
+
+use crate::spectrum::SpectrumOutput;
+use crate::utils::{lock_or_recover, ValueScaling};
+
+/// Magnitudes below this level (in dB) are treated as silence, so that the
+/// difference doesn't shoot off to infinity.
+const FLOOR: f32 = -120.0;
+
+/// The magnitude of a spectrum at the given frequency, interpolated linearly
+/// between the two nearest bins.
+///
+/// Returns `None` above the Nyquist frequency, where the spectrum has no bins.
+fn magnitude_at(spectrum: &[f32], nyquist: f32, frequency: f32) -> Option<f32> {
+    if spectrum.len() < 2 || !(0.0..=nyquist).contains(&frequency) {
+        return None;
+    }
+
+    let position = frequency / nyquist * (spectrum.len() - 1) as f32;
+    let index = (position.floor() as usize).min(spectrum.len() - 2);
+    let t = position - index as f32;
+
+    Some(spectrum[index] * (1. - t) + spectrum[index + 1] * t)
+}
+
+/// Plots the difference (in dB) between two spectra, around a centerline at 0 dB.
+///
+/// This is the core display of EQ-matching and null-test tools: wherever the first
+/// spectrum is louder than the second, the curve rises above the centerline, and
+/// wherever it is quieter, the curve falls below it. The two spectra don't need to
+/// share a window size or sample rate - both are interpolated at each frequency
+/// that gets drawn. Differences beyond the `range` (in dB) are clipped at the edges
+/// of the view.
+///
+/// The curve is drawn in the `color`, and the area between the curve and the
+/// centerline is filled using the `background_color`. The centerline itself is
+/// drawn in the `color` at reduced opacity.
+///
+/// See the [`SpectrumAnalyzer`](super::SpectrumAnalyzer) on how to set up a
+/// [`SpectrumOutput`].
+///
+/// # Example
+///
+/// The difference between the output and the input of an EQ, within ±12 dB.
+///
+/// ```
+/// SpectrumDifference::new(
+///     cx,
+///     Data::output_spectrum,
+///     Data::input_spectrum,
+///     ValueScaling::Frequency,
+///     (20., 20_000.),
+///     12.,
+/// )
+/// .color(Color::rgb(255, 255, 255))
+/// .background_color(Color::rgba(255, 255, 255, 40));
+/// ```
+pub struct SpectrumDifference {
+    a: Arc<Mutex<SpectrumOutput>>,
+    b: Arc<Mutex<SpectrumOutput>>,
+    frequency_scaling: ValueScaling,
+    frequency_range: (f32, f32),
+    /// The difference (in dB) at the top and bottom edges
+    range: f32,
+}
+
+impl SpectrumDifference {
+    /// Creates a new [`SpectrumDifference`], showing how much louder `a` is than `b`.
+    pub fn new<LA, LB>(
+        cx: &mut Context,
+        a: LA,
+        b: LB,
+        frequency_scaling: ValueScaling,
+        frequency_range: (f32, f32),
+        range: f32,
+    ) -> Handle<Self>
+    where
+        LA: Lens<Target = Arc<Mutex<SpectrumOutput>>>,
+        LB: Lens<Target = Arc<Mutex<SpectrumOutput>>>,
+    {
+        Self {
+            a: a.get(cx),
+            b: b.get(cx),
+            frequency_scaling,
+            frequency_range,
+            range,
+        }
+        .build(cx, |_| ())
+    }
+
+    /// The levels (in dB) of a spectrum at the given frequencies, until its Nyquist
+    /// frequency.
+    ///
+    /// Only one spectrum is locked at a time, so that both can be the same.
+    fn levels(spectrum: &Mutex<SpectrumOutput>, frequencies: &[f32]) -> Vec<f32> {
+        let mut spectrum = lock_or_recover(spectrum);
+        let nyquist = spectrum.nyquist();
+        let magnitudes = spectrum.read();
+
+        frequencies
+            .iter()
+            .map_while(|frequency| magnitude_at(magnitudes, nyquist, *frequency))
+            .map(|magnitude| gain_to_db(magnitude).max(FLOOR))
+            .collect()
+    }
+}
+
+impl View for SpectrumDifference {
+    fn element(&self) -> Option<&'static str> {
+        Some("spectrum-difference")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let center_y = y + h / 2.;
+
+        let frequencies = (0..=w.ceil() as usize)
+            .map(|i| {
+                self.frequency_scaling.normalized_to_value(
+                    i as f32 / w,
+                    self.frequency_range.0,
+                    self.frequency_range.1,
+                )
+            })
+            .collect::<Vec<f32>>();
+
+        let a = Self::levels(&self.a, &frequencies);
+        let b = Self::levels(&self.b, &frequencies);
+
+        let mut line = vg::Path::new();
+        let mut last_x = x;
+        for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+            let difference = ((a - b) / self.range).clamp(-1., 1.);
+            let point = (x + i as f32, center_y - difference * h / 2.);
+
+            if i == 0 {
+                line.move_to(point.0, point.1);
+            } else {
+                line.line_to(point.0, point.1);
+            }
+            last_x = point.0;
+        }
+
+        let mut fill = line.clone();
+        fill.line_to(last_x, center_y);
+        fill.line_to(x, center_y);
+        fill.close();
+        canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+
+        let mut center_color: vg::Color = cx.font_color().into();
+        center_color.a *= 0.5;
+
+        let mut center = vg::Path::new();
+        center.move_to(x, center_y);
+        center.line_to(x + w, center_y);
+        canvas.stroke_path(
+            &center,
+            &vg::Paint::color(center_color).with_line_width(cx.scale_factor()),
+        );
+
+        canvas.stroke_path(
+            &line,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::magnitude_at;
+
+    #[test]
+    fn spectra_of_different_sizes_line_up() {
+        // Both rise linearly from 0 Hz to their Nyquist frequency
+        let small = (0..513).map(|i| i as f32 / 512.).collect::<Vec<f32>>();
+        let large = (0..2049).map(|i| i as f32 / 2048.).collect::<Vec<f32>>();
+
+        for frequency in [0., 440., 1_234.5, 20_000., 24_000.] {
+            let small = magnitude_at(&small, 24_000., frequency).unwrap();
+            let large = magnitude_at(&large, 24_000., frequency).unwrap();
+            assert!((small - large).abs() < 1e-4);
+        }
+
+        assert_eq!(magnitude_at(&small, 24_000., 24_001.), None);
+    }
+}
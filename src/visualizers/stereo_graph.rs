@@ -0,0 +1,335 @@
+use super::RangeModifiers;
+use crate::accumulators::*;
+use crate::bus::Bus;
+use crate::prelude::DurationModifiers;
+use crate::utils::{lock_or_recover, RingBuffer, ValueScaling};
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+type Sample = [f32; 2];
+
+/// How the two traces of a [`StereoGraph`] are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoGraphLayout {
+    /// Both traces are drawn on top of each other.
+    Overlaid,
+    /// The left channel is drawn upwards from the horizontal center, the right
+    /// channel downwards.
+    Mirrored,
+}
+
+/// A graph visualizer plotting a value over time, for both channels of a stereo
+/// signal.
+///
+/// This is the stereo counterpart to the [`Graph`](super::Graph). It displays the
+/// left and right channel as separate traces, without requiring a separate bus for
+/// each channel.
+///
+///    - [`peak`](Self::peak) - Their peak amplitude
+///    - [`rms`](Self::rms) - Their root mean squared level
+///
+/// The left channel is drawn using the `color`. The right channel uses the color set
+/// via [`right_color`](StereoGraphModifiers::right_color), or the `color` as well if
+/// none is set.
+pub struct StereoGraph<B: Bus<Sample> + 'static, A: Accumulator + 'static> {
+    buffers: Arc<Mutex<[RingBuffer<f32>; 2]>>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+    layout: StereoGraphLayout,
+    right_color: Option<Color>,
+    accumulators: Arc<Mutex<[A; 2]>>,
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<Sample>>::O<'_>) + Sync + Send + 'static>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Sync + Send>,
+}
+
+enum StereoGraphEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+    UpdateDuration(f32),
+}
+
+impl<B: Bus<Sample> + 'static, A: Accumulator + 'static> StereoGraph<B, A> {
+    /// Creates a new [`StereoGraph`] which uses the provided [`Accumulator`]s for
+    /// the left and right channel.
+    pub fn with_accumulators(
+        cx: &mut Context,
+        bus: Arc<B>,
+        mut left: A,
+        mut right: A,
+        layout: StereoGraphLayout,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        left.set_sample_rate(bus.sample_rate());
+        right.set_sample_rate(bus.sample_rate());
+
+        let buffers: Arc<Mutex<[RingBuffer<f32>; 2]>> = Default::default();
+        let buffers_c = buffers.clone();
+
+        let accumulators = Arc::new(Mutex::new([left, right]));
+        let accumulators_c = accumulators.clone();
+
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut bufs = lock_or_recover(&buffers_c);
+            let mut accs = lock_or_recover(&accumulators_c);
+            for sample in samples {
+                for channel in 0..2 {
+                    if let Some(value) = accs[channel].accumulate(sample[channel]) {
+                        bufs[channel].enqueue(value);
+                    }
+                }
+            }
+        });
+
+        let accumulators_c = accumulators.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&accumulators_c)
+                .iter_mut()
+                .for_each(|acc| acc.set_sample_rate(sample_rate));
+        });
+
+        Self {
+            buffers,
+            range: range.get_val(cx),
+            scaling: scaling.get_val(cx),
+            layout,
+            right_color: None,
+            accumulators,
+            dispatcher_handle,
+            sample_rate_handle,
+        }
+        .build(cx, |_| {})
+        .range(range)
+        .scaling(scaling)
+    }
+}
+
+impl<B: Bus<Sample> + 'static, A: Accumulator + 'static> View for StereoGraph<B, A> {
+    fn element(&self) -> Option<&'static str> {
+        Some("stereo-graph")
+    }
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            StereoGraphEvents::UpdateRange(v) => self.range = *v,
+            StereoGraphEvents::UpdateScaling(s) => self.scaling = *s,
+            StereoGraphEvents::UpdateDuration(duration) => lock_or_recover(&self.accumulators)
+                .iter_mut()
+                .for_each(|acc| acc.set_duration(*duration)),
+        });
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let line_width = cx.scale_factor();
+
+        let bufs = &mut lock_or_recover(&self.buffers);
+
+        {
+            let width_ceil = w.ceil() as usize;
+            if bufs[0].len() != width_ceil {
+                let mut accs = lock_or_recover(&self.accumulators);
+                for channel in 0..2 {
+                    bufs[channel].resize(width_ceil);
+                    accs[channel].set_size(width_ceil);
+                }
+            }
+        }
+
+        let len = bufs[0].len();
+
+        if len == 0 {
+            return;
+        }
+
+        // Maps a normalized value of the given channel onto the y axis
+        let to_y = |channel: usize, normalized: f32| -> f32 {
+            match (self.layout, channel) {
+                (StereoGraphLayout::Overlaid, _) => y + h * (1. - normalized),
+                (StereoGraphLayout::Mirrored, 0) => y + h / 2. * (1. - normalized),
+                (StereoGraphLayout::Mirrored, _) => y + h / 2. * (1. + normalized),
+            }
+        };
+        let baseline = match self.layout {
+            StereoGraphLayout::Overlaid => y + h,
+            StereoGraphLayout::Mirrored => y + h / 2.,
+        };
+
+        let left_color: vg::Color = cx.font_color().into();
+        let right_color: vg::Color = self.right_color.unwrap_or(cx.font_color()).into();
+
+        for (channel, color) in [(0, left_color), (1, right_color)] {
+            let buf = &bufs[channel];
+
+            let mut stroke = vg::Path::new();
+
+            for i in 0..len {
+                let value = self
+                    .scaling
+                    .value_to_normalized(buf[i], self.range.0, self.range.1);
+
+                if i == 0 {
+                    stroke.move_to(x, to_y(channel, value));
+                } else {
+                    stroke.line_to(x + i as f32, to_y(channel, value));
+                }
+            }
+
+            let mut fill = stroke.clone();
+            fill.line_to(x + w, baseline);
+            fill.line_to(x, baseline);
+            fill.close();
+
+            canvas.fill_path(&fill, &vg::Paint::color(cx.background_color().into()));
+
+            canvas.stroke_path(
+                &stroke,
+                &vg::Paint::color(color).with_line_width(line_width),
+            );
+        }
+    }
+}
+
+pub trait StereoGraphModifiers {
+    fn right_color(self, color: Color) -> Self;
+}
+
+impl<'a, B: Bus<Sample> + 'static, A: Accumulator + 'static> StereoGraphModifiers
+    for Handle<'a, StereoGraph<B, A>>
+{
+    /// Sets the color of the right channel's trace.
+    fn right_color(self, color: Color) -> Self {
+        self.modify(|graph| graph.right_color = Some(color))
+    }
+}
+
+impl<'a, B: Bus<Sample> + 'static, A: Accumulator + 'static> RangeModifiers
+    for Handle<'a, StereoGraph<B, A>>
+{
+    fn range(mut self, range: impl Res<(f32, f32)>) -> Self {
+        let e = self.entity();
+
+        range.set_or_bind(self.context(), e, move |cx, r| {
+            (*cx).emit_to(e, StereoGraphEvents::UpdateRange(r));
+        });
+
+        self
+    }
+    fn scaling(mut self, scaling: impl Res<ValueScaling>) -> Self {
+        let e = self.entity();
+
+        scaling.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, StereoGraphEvents::UpdateScaling(s))
+        });
+
+        self
+    }
+}
+
+impl<'a, B: Bus<Sample> + 'static, A: Accumulator + 'static> DurationModifiers
+    for Handle<'a, StereoGraph<B, A>>
+{
+    fn duration(mut self, duration: impl Res<f32>) -> Self {
+        let e = self.entity();
+
+        duration.set_or_bind(self.context(), e, move |cx, s| {
+            (*cx).emit_to(e, StereoGraphEvents::UpdateDuration(s))
+        });
+
+        self
+    }
+}
+
+impl<B: Bus<Sample> + 'static> StereoGraph<B, PeakAccumulator> {
+    /// Creates a stereo peak graph.
+    ///
+    /// # Example
+    ///
+    /// 10-second stereo peak graph with a 50ms-long decay for each peak, with the
+    /// channels mirrored around the horizontal center.
+    ///
+    /// ```
+    /// StereoGraph::peak(
+    ///     cx,
+    ///     stereo_bus.clone(),
+    ///     10.0,
+    ///     50.0,
+    ///     StereoGraphLayout::Mirrored,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    /// )
+    /// .color(Color::rgba(255, 255, 255, 160))
+    /// .background_color(Color::rgba(255, 255, 255, 30));
+    /// ```
+    pub fn peak(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        decay: f32,
+        layout: StereoGraphLayout,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        let duration_val = duration.get_val(cx);
+
+        Self::with_accumulators(
+            cx,
+            bus,
+            PeakAccumulator::new(duration_val, decay),
+            PeakAccumulator::new(duration_val, decay),
+            layout,
+            range,
+            scaling,
+        )
+        .duration(duration)
+    }
+}
+
+impl<B: Bus<Sample> + 'static> StereoGraph<B, RMSAccumulator> {
+    /// Creates a stereo graph showing the root mean squared level over time.
+    ///
+    /// # Example
+    ///
+    /// 10-second stereo RMS graph with a 250 ms long window, with the right channel
+    /// drawn in a different color.
+    ///
+    /// ```
+    /// StereoGraph::rms(
+    ///     cx,
+    ///     stereo_bus.clone(),
+    ///     10.0,
+    ///     250.0,
+    ///     StereoGraphLayout::Overlaid,
+    ///     (-32.0, 8.0),
+    ///     ValueScaling::Decibels,
+    /// )
+    /// .color(Color::rgba(255, 92, 92, 160))
+    /// .right_color(Color::rgba(92, 92, 255, 160));
+    /// ```
+    pub fn rms(
+        cx: &mut Context,
+        bus: Arc<B>,
+        duration: impl Res<f32> + Clone,
+        window_size: f32,
+        layout: StereoGraphLayout,
+        range: impl Res<(f32, f32)> + Clone,
+        scaling: impl Res<ValueScaling> + Clone,
+    ) -> Handle<Self> {
+        let duration_val = duration.get_val(cx);
+
+        Self::with_accumulators(
+            cx,
+            bus,
+            RMSAccumulator::new(duration_val, window_size),
+            RMSAccumulator::new(duration_val, window_size),
+            layout,
+            range,
+            scaling,
+        )
+        .duration(duration)
+    }
+}
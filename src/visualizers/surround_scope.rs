@@ -0,0 +1,241 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+use crate::accumulators::BallisticsAccumulator;
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, ValueScaling};
+
+/// The positions of the speakers a [`SurroundScope`] lays its channels out on.
+///
+/// Positions are given as angles in degrees, clockwise from the front center. A
+/// channel without a position, like the LFE, is not displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpeakerLayout {
+    /// 5.1 surround, with the channels ordered L, R, C, LFE, Ls, Rs.
+    Surround51,
+    /// 7.1 surround, with the channels ordered L, R, C, LFE, Lss, Rss, Lrs, Rrs.
+    Surround71,
+    /// A custom position for each channel.
+    Custom(Vec<Option<f32>>),
+}
+
+impl SpeakerLayout {
+    /// The position of each channel, in degrees.
+    pub fn angles(&self) -> Vec<Option<f32>> {
+        match self {
+            SpeakerLayout::Surround51 => vec![
+                Some(-30.),
+                Some(30.),
+                Some(0.),
+                None,
+                Some(-110.),
+                Some(110.),
+            ],
+            SpeakerLayout::Surround71 => vec![
+                Some(-30.),
+                Some(30.),
+                Some(0.),
+                None,
+                Some(-90.),
+                Some(90.),
+                Some(-150.),
+                Some(150.),
+            ],
+            SpeakerLayout::Custom(angles) => angles.clone(),
+        }
+    }
+}
+
+/// The smoothed energy of each channel of a multichannel signal.
+struct ChannelEnergy<const C: usize> {
+    /// The time (in ms) it takes to cover 75% of a change in energy
+    window: f32,
+    weight: f32,
+    /// Mean square of each channel
+    energy: [f32; C],
+}
+
+impl<const C: usize> ChannelEnergy<C> {
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.weight = BallisticsAccumulator::weight(self.window, sample_rate);
+    }
+}
+
+/// Plots the energy of each channel of a multichannel signal on a polygon, whose
+/// corners lie in the direction of the corresponding speakers.
+///
+/// This generalizes the idea of the [`Lissajous`](super::Lissajous) to surround
+/// formats: the polygon leans towards wherever the signal is loudest, so that an
+/// unbalanced mix is easy to spot. Additionally, a dot marks the direction of the
+/// energy-weighted average of all speaker positions, roughly where a listener
+/// would locate the sound.
+///
+/// The polygon is filled using the `background_color` and outlined using the
+/// `color`. Faint lines mark the direction of each speaker.
+///
+/// # Example
+///
+/// ```
+/// SurroundScope::new(
+///     cx,
+///     surround_bus.clone(),
+///     SpeakerLayout::Surround51,
+///     300.0,
+///     (-48.0, 0.0),
+///     ValueScaling::Decibels,
+/// )
+/// .color(Color::rgb(255, 255, 255))
+/// .background_color(Color::rgba(255, 255, 255, 60));
+/// ```
+pub struct SurroundScope<B: Bus<[f32; C]> + 'static, const C: usize> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<[f32; C]>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    energy: Arc<Mutex<ChannelEnergy<C>>>,
+    angles: Vec<Option<f32>>,
+    range: (f32, f32),
+    scaling: ValueScaling,
+}
+
+impl<B: Bus<[f32; C]> + 'static, const C: usize> SurroundScope<B, C> {
+    /// Creates a new [`SurroundScope`].
+    ///
+    /// The `window` dictates how long (in ms) it takes for the energy of each
+    /// channel to cover 75% of a change. The energy is displayed as an RMS level,
+    /// so the `range` and `scaling` work just like they do for an RMS
+    /// [`Meter`](super::Meter).
+    pub fn new(
+        cx: &mut Context,
+        bus: Arc<B>,
+        layout: SpeakerLayout,
+        window: f32,
+        range: (f32, f32),
+        scaling: ValueScaling,
+    ) -> Handle<Self> {
+        let mut energy = ChannelEnergy {
+            window,
+            weight: 0.0,
+            energy: [0.0; C],
+        };
+        energy.set_sample_rate(bus.sample_rate());
+
+        let energy = Arc::new(Mutex::new(energy));
+
+        let energy_c = energy.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut energy = lock_or_recover(&energy_c);
+            let w = energy.weight;
+            for sample in samples {
+                for (channel, value) in energy.energy.iter_mut().zip(sample.iter()) {
+                    *channel = *channel * w + value * value * (1.0 - w);
+                }
+            }
+        });
+
+        let energy_c = energy.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&energy_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            energy,
+            angles: layout.angles(),
+            range,
+            scaling,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<[f32; C]> + 'static, const C: usize> View for SurroundScope<B, C> {
+    fn element(&self) -> Option<&'static str> {
+        Some("surround-scope")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let center_x = bounds.x + bounds.w / 2.;
+        let center_y = bounds.y + bounds.h / 2.;
+        let radius = bounds.w.min(bounds.h) / 2.;
+
+        let energy = lock_or_recover(&self.energy).energy;
+
+        // Each displayed channel, as its direction and its normalized RMS level,
+        // sorted clockwise starting from the back
+        let mut corners = self
+            .angles
+            .iter()
+            .zip(energy.iter())
+            .filter_map(|(angle, energy)| {
+                angle.map(|angle| {
+                    let level =
+                        self.scaling
+                            .value_to_normalized(energy.sqrt(), self.range.0, self.range.1);
+                    (angle.to_radians(), level.clamp(0., 1.), *energy)
+                })
+            })
+            .collect::<Vec<_>>();
+        corners.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        if corners.is_empty() {
+            return;
+        }
+
+        let point = |angle: f32, distance: f32| {
+            (
+                center_x + radius * distance * angle.sin(),
+                center_y - radius * distance * angle.cos(),
+            )
+        };
+
+        let mut spokes = vg::Path::new();
+        for (angle, _, _) in &corners {
+            let (x, y) = point(*angle, 1.);
+            spokes.move_to(center_x, center_y);
+            spokes.line_to(x, y);
+        }
+
+        let mut polygon = vg::Path::new();
+        for (i, (angle, level, _)) in corners.iter().enumerate() {
+            let (x, y) = point(*angle, *level);
+            if i == 0 {
+                polygon.move_to(x, y);
+            } else {
+                polygon.line_to(x, y);
+            }
+        }
+        polygon.close();
+
+        let mut faint: vg::Color = cx.font_color().into();
+        faint.a *= 0.25;
+
+        canvas.stroke_path(
+            &spokes,
+            &vg::Paint::color(faint).with_line_width(cx.scale_factor()),
+        );
+        canvas.fill_path(&polygon, &vg::Paint::color(cx.background_color().into()));
+        canvas.stroke_path(
+            &polygon,
+            &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+        );
+
+        // The energy-weighted average of all speaker positions
+        let total: f32 = corners.iter().map(|(_, _, energy)| energy).sum();
+        if total > f32::EPSILON {
+            let (sum_x, sum_y) =
+                corners
+                    .iter()
+                    .fold((0., 0.), |(sum_x, sum_y), (angle, _, energy)| {
+                        (sum_x + angle.sin() * energy, sum_y + angle.cos() * energy)
+                    });
+
+            let dot_x = center_x + radius * sum_x / total;
+            let dot_y = center_y - radius * sum_y / total;
+
+            let mut dot = vg::Path::new();
+            dot.circle(dot_x, dot_y, 2. * cx.scale_factor());
+            canvas.fill_path(&dot, &vg::Paint::color(cx.font_color().into()));
+        }
+    }
+}
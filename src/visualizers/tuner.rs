@@ -0,0 +1,410 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::readout::TextReadout;
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, Note, RingBuffer};
+use nih_plug::util::db_to_gain;
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use realfft::num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+
+/// The lowest frequency (in Hz) the tuner can detect.
+const MIN_FREQUENCY: f32 = 40.0;
+/// The highest frequency (in Hz) the tuner can detect.
+const MAX_FREQUENCY: f32 = 2000.0;
+/// How many pitch estimates are made per second.
+const ESTIMATES_PER_SECOND: f32 = 20.0;
+/// The threshold of the cumulative mean normalized difference, below which a lag
+/// counts as a period of the signal.
+const YIN_THRESHOLD: f32 = 0.15;
+/// Signals quieter than this (in dBFS) have no pitch.
+const GATE: f32 = -60.0;
+/// How often the note display is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Correlates the start of a frame with the frame itself, at increasing lags.
+///
+/// This is done in the frequency domain, so it takes O(n log n) instead of
+/// O(n²) time.
+struct Correlator {
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    /// The zero-padded input of the forward FFT
+    input: Vec<f32>,
+    spectrum: Vec<Complex32>,
+    head_spectrum: Vec<Complex32>,
+    output: Vec<f32>,
+    scratch: Vec<Complex32>,
+}
+
+impl Correlator {
+    /// Creates a [`Correlator`] for frames of up to `len` samples.
+    fn new(len: usize) -> Self {
+        // Since only the lags within the frame are needed, the correlation may wrap
+        // around without affecting them
+        let size = len.max(2).next_power_of_two();
+
+        let mut planner = RealFftPlanner::new();
+        let forward = planner.plan_fft_forward(size);
+        let inverse = planner.plan_fft_inverse(size);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+
+        Self {
+            forward,
+            inverse,
+            input: vec![0.0; size],
+            spectrum: vec![Complex32::default(); size / 2 + 1],
+            head_spectrum: vec![Complex32::default(); size / 2 + 1],
+            output: vec![0.0; size],
+            scratch: vec![Complex32::default(); scratch_len],
+        }
+    }
+
+    /// Writes the sum of `frame[i] * frame[i + lag]` over the first `window`
+    /// samples into `result[lag]`, for each lag.
+    ///
+    /// The frame must be at least `window + result.len() - 1` samples long.
+    fn correlate(&mut self, frame: &[f32], window: usize, result: &mut [f32]) {
+        let size = self.input.len();
+
+        self.input.fill(0.0);
+        self.input[..frame.len()].copy_from_slice(frame);
+        self.forward
+            .process_with_scratch(&mut self.input, &mut self.spectrum, &mut self.scratch)
+            .unwrap();
+
+        self.input.fill(0.0);
+        self.input[..window].copy_from_slice(&frame[..window]);
+        self.forward
+            .process_with_scratch(&mut self.input, &mut self.head_spectrum, &mut self.scratch)
+            .unwrap();
+
+        for (bin, head_bin) in self.spectrum.iter_mut().zip(self.head_spectrum.iter()) {
+            *bin *= head_bin.conj();
+        }
+
+        // These are real in theory, but the inverse FFT rejects any rounding errors
+        let last = self.spectrum.len() - 1;
+        self.spectrum[0].im = 0.0;
+        self.spectrum[last].im = 0.0;
+
+        self.inverse
+            .process_with_scratch(&mut self.spectrum, &mut self.output, &mut self.scratch)
+            .unwrap();
+
+        for (value, output) in result.iter_mut().zip(self.output.iter()) {
+            *value = output / size as f32;
+        }
+    }
+}
+
+/// Estimates the fundamental frequency of a signal using the YIN algorithm.
+///
+/// The most recent samples are kept in a window long enough to contain two
+/// periods of the lowest detectable frequency. A few times per second, the window
+/// is searched for the shortest lag at which the signal repeats itself.
+struct PitchTracker {
+    buffer: RingBuffer<f32>,
+    /// The window, copied out of the buffer for the estimate
+    frame: Vec<f32>,
+    difference: Vec<f32>,
+    /// Correlates the frame, if the sample rate is known
+    correlator: Option<Correlator>,
+    /// The running sum of the squared samples of the frame
+    energy: Vec<f32>,
+    sample_rate: f32,
+    /// Samples left until the next estimate
+    countdown: usize,
+    frequency: Option<f32>,
+}
+
+impl PitchTracker {
+    fn new(sample_rate: f32) -> Self {
+        let mut tracker = Self {
+            buffer: RingBuffer::new(1),
+            frame: vec![],
+            difference: vec![],
+            correlator: None,
+            energy: vec![],
+            sample_rate: 0.0,
+            countdown: 0,
+            frequency: None,
+        };
+        tracker.set_sample_rate(sample_rate);
+        tracker
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.frequency = None;
+
+        // Until the sample rate is known, nothing can be detected
+        if !sample_rate.is_finite() || sample_rate <= 0.0 {
+            self.buffer = RingBuffer::new(1);
+            self.frame.clear();
+            self.difference.clear();
+            self.correlator = None;
+            self.energy.clear();
+            return;
+        }
+
+        let max_lag = (sample_rate / MIN_FREQUENCY).ceil() as usize;
+        self.buffer = RingBuffer::new(max_lag * 2);
+        self.frame = vec![0.0; max_lag * 2];
+        self.difference = vec![0.0; max_lag + 1];
+        self.correlator = Some(Correlator::new(max_lag * 2));
+        self.energy = vec![0.0; max_lag * 2 + 1];
+        self.countdown = self.hop();
+    }
+
+    fn hop(&self) -> usize {
+        (self.sample_rate / ESTIMATES_PER_SECOND).max(1.0) as usize
+    }
+
+    fn process(&mut self, sample: f32) {
+        if self.frame.is_empty() {
+            return;
+        }
+
+        self.buffer.enqueue(sample);
+        self.countdown -= 1;
+
+        if self.countdown == 0 {
+            self.countdown = self.hop();
+            self.frequency = self.estimate();
+        }
+    }
+
+    fn estimate(&mut self) -> Option<f32> {
+        let correlator = self.correlator.as_mut()?;
+        let len = self.frame.len();
+        for (i, sample) in self.frame.iter_mut().enumerate() {
+            *sample = self.buffer[i];
+        }
+
+        let mean_square = self.frame.iter().map(|x| x * x).sum::<f32>() / len as f32;
+        if mean_square.sqrt() < db_to_gain(GATE) {
+            return None;
+        }
+
+        let max_lag = self.difference.len() - 1;
+        let min_lag = ((self.sample_rate / MAX_FREQUENCY) as usize).max(2);
+        let window = len - max_lag;
+
+        // The difference at each lag is the energy of the two windows it compares,
+        // minus twice their correlation
+        self.energy[0] = 0.0;
+        for (i, sample) in self.frame.iter().enumerate() {
+            self.energy[i + 1] = self.energy[i] + sample * sample;
+        }
+        correlator.correlate(&self.frame, window, &mut self.difference);
+
+        // Cumulative mean normalized difference
+        self.difference[0] = 1.0;
+        let mut running_sum = 0.0;
+        for lag in 1..=max_lag {
+            let d = (self.energy[window] + self.energy[lag + window]
+                - self.energy[lag]
+                - 2.0 * self.difference[lag])
+                .max(0.0);
+            running_sum += d;
+            self.difference[lag] = if running_sum > 0.0 {
+                d * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // The first dip below the threshold, followed down to its bottom
+        let mut lag = (min_lag..max_lag).find(|&lag| self.difference[lag] < YIN_THRESHOLD)?;
+        while lag + 1 < max_lag && self.difference[lag + 1] < self.difference[lag] {
+            lag += 1;
+        }
+
+        // Parabolic interpolation between the neighbouring lags
+        let (a, b, c) = (
+            self.difference[lag - 1],
+            self.difference[lag],
+            self.difference[lag + 1],
+        );
+        let denominator = a - 2.0 * b + c;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (a - c) / denominator).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        Some(self.sample_rate / (lag as f32 + offset))
+    }
+}
+
+/// A tuner, which displays the pitch of a signal.
+///
+/// The fundamental frequency of the signal is tracked using the YIN algorithm, and
+/// shown as the nearest note, along with how far off it is in cents. Frequencies
+/// from 40 Hz to 2 kHz can be detected, and signals quieter than -60 dBFS are
+/// ignored.
+///
+/// The deviation is drawn as a needle on a horizontal scale from -50 to +50 cents,
+/// in the `color`, with a short line marking the center. The note name and cents
+/// are shown as text above it, in the same color.
+///
+/// # Example
+///
+/// ```
+/// Tuner::new(cx, bus.clone())
+///     .reference(442.0)
+///     .width(Pixels(160.0))
+///     .height(Pixels(48.0))
+///     .color(Color::rgb(255, 255, 255));
+/// ```
+pub struct Tuner<B: Bus<f32> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    tracker: Arc<Mutex<PitchTracker>>,
+    /// The frequency of A4, in Hz
+    reference: f32,
+}
+
+enum TunerEvents {
+    RefreshNote,
+}
+
+impl<B: Bus<f32> + 'static> Tuner<B> {
+    /// Creates a new [`Tuner`], tuned to A4 = 440 Hz.
+    pub fn new(cx: &mut Context, bus: Arc<B>) -> Handle<Self> {
+        let tracker = Arc::new(Mutex::new(PitchTracker::new(bus.sample_rate())));
+
+        let tracker_c = tracker.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut tracker = lock_or_recover(&tracker_c);
+            for sample in samples {
+                tracker.process(*sample);
+            }
+        });
+
+        let tracker_c = tracker.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&tracker_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            tracker,
+            reference: 440.0,
+        }
+        .build(cx, |cx| {
+            TextReadout::attach(cx, String::new(), REFRESH_INTERVAL, || {
+                TunerEvents::RefreshNote
+            })
+            .width(Stretch(1.0))
+            .text_align(TextAlign::Center);
+        })
+    }
+
+    /// The note nearest to the current pitch, if there is one.
+    fn note(&self) -> Option<Note> {
+        lock_or_recover(&self.tracker)
+            .frequency
+            .map(|frequency| Note::from_frequency(frequency, self.reference))
+    }
+}
+
+impl<B: Bus<f32> + 'static> View for Tuner<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("tuner")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let paint = vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor());
+
+        let center_x = x + w / 2.;
+        let bottom = y + h;
+
+        let mut center = vg::Path::new();
+        center.move_to(center_x, bottom - h / 4.);
+        center.line_to(center_x, bottom);
+        canvas.stroke_path(&center, &paint);
+
+        if let Some(note) = self.note() {
+            let needle_x = center_x + (note.cents / 50.).clamp(-1., 1.) * w / 2.;
+
+            let mut needle = vg::Path::new();
+            needle.move_to(needle_x, bottom - h / 2.);
+            needle.line_to(needle_x, bottom);
+            canvas.stroke_path(&needle, &paint.with_line_width(cx.scale_factor() * 2.));
+        }
+    }
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|e, _| match e {
+            TunerEvents::RefreshNote => {
+                let text = match self.note() {
+                    Some(note) => format!("{} {:+.0}", note.name(), note.cents),
+                    None => String::from("-"),
+                };
+                TextReadout::update(cx, text);
+            }
+        });
+    }
+}
+
+pub trait TunerModifiers {
+    fn reference(self, reference: f32) -> Self;
+}
+
+impl<'a, B: Bus<f32> + 'static> TunerModifiers for Handle<'a, Tuner<B>> {
+    /// Sets the frequency (in Hz) of A4 that notes are measured against.
+    ///
+    /// Defaults to 440 Hz.
+    fn reference(self, reference: f32) -> Self {
+        self.modify(|tuner| tuner.reference = reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PitchTracker;
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn pitch_of_a_sine_is_tracked() {
+        let sample_rate = 48_000.0;
+        let mut tracker = PitchTracker::new(sample_rate);
+
+        for i in 0..24_000 {
+            tracker.process(0.5 * (TAU * 220.0 * i as f32 / sample_rate).sin());
+        }
+        let frequency = tracker.frequency.unwrap();
+        assert!((frequency - 220.0).abs() < 0.5);
+
+        for _ in 0..24_000 {
+            tracker.process(0.0);
+        }
+        assert_eq!(tracker.frequency, None);
+    }
+
+    #[test]
+    fn unknown_sample_rates_stop_the_tracking() {
+        let mut tracker = PitchTracker::new(44_100.0);
+        tracker.set_sample_rate(0.0);
+
+        for i in 0..44_100 {
+            tracker.process(0.5 * (TAU * 220.0 * i as f32 / 44_100.0).sin());
+        }
+        assert_eq!(tracker.frequency, None);
+
+        tracker.set_sample_rate(44_100.0);
+        for i in 0..22_050 {
+            tracker.process(0.5 * (TAU * 330.0 * i as f32 / 44_100.0).sin());
+        }
+        assert!((tracker.frequency.unwrap() - 330.0).abs() < 0.5);
+    }
+}
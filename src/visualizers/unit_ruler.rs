@@ -1,10 +1,106 @@
-use crate::utils::ValueScaling;
+use super::{AxisLink, AxisMorph, AxisMorphModifiers};
+use crate::utils::{Note, ValueScaling};
 use nih_plug_vizia::vizia::prelude::*;
+use std::time::Duration;
+
+/// Which notes a [`UnitRuler`] created via [`UnitRuler::notes`] marks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteTicks {
+    /// Every C, i.e. the start of each octave.
+    C,
+    /// Every A - A1, A2, A3 and so on.
+    A,
+    /// Every note of the chromatic scale, for narrow ranges.
+    Chromatic,
+}
+
+/// The frequencies (in Hz) and names of all notes within the given range, with
+/// A4 tuned to 440 Hz.
+///
+/// This is what [`UnitRuler::notes`] marks, but it can also be used to place a
+/// [`Grid`](super::Grid)'s lines on the same notes.
+pub fn note_ticks(range: (f32, f32), ticks: NoteTicks) -> Vec<(f32, String)> {
+    // There are no notes below the audible range, but 0 Hz has no note at all
+    let (min, max) = (range.0.min(range.1).max(1.), range.0.max(range.1));
+
+    let first = Note::from_frequency(min, 440.).number - 1;
+    let last = Note::from_frequency(max, 440.).number + 1;
+
+    (first..=last)
+        .filter(|number| match ticks {
+            NoteTicks::C => number.rem_euclid(12) == 0,
+            NoteTicks::A => number.rem_euclid(12) == 9,
+            NoteTicks::Chromatic => true,
+        })
+        .map(|number| {
+            let frequency = 440. * 2f32.powf((number - 69) as f32 / 12.);
+            (frequency, Note { number, cents: 0. }.name())
+        })
+        .filter(|(frequency, _)| (min..=max).contains(frequency))
+        .collect()
+}
+
+/// How often the labels of a morphing [`UnitRuler`] are moved along.
+const MORPH_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The steps (in seconds) between the marks of a time axis.
+const TIME_STEPS: [f32; 19] = [
+    0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1., 2., 5., 10., 15., 30., 60., 120., 300., 600., 900., 1800.,
+    3600.,
+];
+
+/// The most steps a time axis is divided into.
+const MAX_TIME_STEPS: f32 = 5.;
+
+/// Formats a point in time (in seconds) that lies `time` seconds in the past, for
+/// a time axis divided into steps of the given size.
+fn format_time(time: f32, step: f32) -> String {
+    if time == 0. {
+        "now".to_owned()
+    } else if step >= 3600. {
+        format!("-{}h", (time / 3600.).round())
+    } else if step >= 60. {
+        format!("-{}m", (time / 60.).round())
+    } else if step >= 1. {
+        format!("-{}s", time.round())
+    } else {
+        let decimals = (-step.log10() - 1e-3).ceil().max(0.) as usize;
+        format!("-{time:.decimals$}s")
+    }
+}
+
+/// The marks of a time axis spanning the last `duration` seconds, as the (negative)
+/// time and its label - from "now" back to the start of the axis.
+///
+/// This is what [`UnitRuler::time`] marks. The axis is divided into at most five
+/// steps of a round size, such as 2 seconds or 15 minutes.
+pub fn time_ticks(duration: f32) -> Vec<(f32, String)> {
+    if duration.is_nan() || duration <= 0. {
+        return vec![];
+    }
+
+    let step = TIME_STEPS
+        .into_iter()
+        .find(|step| duration / step <= MAX_TIME_STEPS)
+        .unwrap_or(duration / MAX_TIME_STEPS);
+    let steps = (duration / step + 1e-3).floor() as usize;
+
+    (0..=steps)
+        .map(|i| {
+            let time = i as f32 * step;
+            (-time, format_time(time, step))
+        })
+        .collect()
+}
 
 /// Generic ruler that shows markers for certain values.
 ///
 /// Takes in a display range and scaling, as well as values within that range, where
-/// unit markers will be displayed.
+/// unit markers will be displayed. The range and scaling can be bound to lenses -
+/// e.g. on a zoomable display - in which case the markers move along with them, and
+/// markers outside of the current range are hidden. Using
+/// [`morph_axis`](AxisMorphModifiers::morph_axis), the markers glide to their new
+/// positions instead of snapping to them.
 ///
 /// ```
 /// UnitRuler::new(
@@ -29,46 +125,288 @@ use nih_plug_vizia::vizia::prelude::*;
 /// ```
 pub struct UnitRuler {}
 
+/// The axis that labels are placed along, and its transition to a new range or
+/// scaling, if it morphs.
+#[derive(Debug, Clone)]
+pub(crate) struct RulerAxis {
+    link: AxisLink,
+    morph: Option<AxisMorph>,
+    /// Whether the axis was gliding when the labels were last moved along
+    gliding: bool,
+    /// Counts how often the labels were moved along while gliding
+    frame: u32,
+}
+
+impl RulerAxis {
+    pub(crate) fn new(link: AxisLink) -> Self {
+        Self {
+            link,
+            morph: None,
+            gliding: false,
+            frame: 0,
+        }
+    }
+
+    /// The position of a value along the axis, following the morph if there is one.
+    fn value_to_normalized(&self, value: f32) -> f32 {
+        let AxisLink { scaling, range } = self.link;
+
+        match &self.morph {
+            Some(morph) => morph.value_to_normalized(value, range, scaling),
+            None => scaling.value_to_normalized(value, range.0, range.1),
+        }
+    }
+}
+
+impl Data for RulerAxis {
+    fn same(&self, other: &Self) -> bool {
+        self.link.same(&other.link) && self.frame == other.frame
+    }
+}
+
+/// The axis of a [`UnitRuler`], which follows the given range and scaling.
+#[derive(Lens)]
+struct UnitRulerState {
+    axis: RulerAxis,
+}
+
+enum UnitRulerEvents {
+    UpdateRange((f32, f32)),
+    UpdateScaling(ValueScaling),
+    EnableMorph,
+    MoveLabels,
+}
+
+impl Model for UnitRulerState {
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        let RulerAxis {
+            link,
+            morph,
+            gliding,
+            frame,
+        } = &mut self.axis;
+
+        event.map(|e, _| match e {
+            UnitRulerEvents::UpdateRange(range) => {
+                if let Some(morph) = morph.as_mut().filter(|_| link.range != *range) {
+                    morph.begin(link.range, link.scaling);
+                }
+                link.range = *range;
+            }
+            UnitRulerEvents::UpdateScaling(scaling) => {
+                if let Some(morph) = morph.as_mut().filter(|_| link.scaling != *scaling) {
+                    morph.begin(link.range, link.scaling);
+                }
+                link.scaling = *scaling;
+            }
+            UnitRulerEvents::EnableMorph => {
+                *morph = Some(AxisMorph::new(link.range, link.scaling));
+            }
+            UnitRulerEvents::MoveLabels => {
+                let running = morph.as_ref().is_some_and(AxisMorph::is_running);
+
+                // Once the axis has arrived, the labels are moved one last time
+                if running || *gliding {
+                    *frame = frame.wrapping_add(1);
+                }
+                *gliding = running;
+            }
+        });
+    }
+}
+
 impl UnitRuler {
     pub fn new<'a>(
         cx: &'a mut Context,
-        range: (f32, f32),
-        scaling: ValueScaling,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+        values: Vec<(f32, &'static str)>,
+        orientation: Orientation,
+    ) -> Handle<'a, Self> {
+        let values = values
+            .into_iter()
+            .map(|(value, text)| (value, text.to_string()))
+            .collect();
+
+        Self::with_axis(cx, range, scaling, move |cx| {
+            Self::labels(cx, values, orientation);
+        })
+    }
+
+    /// Creates a [`UnitRuler`] for a frequency axis, marking musical notes instead
+    /// of numeric values.
+    ///
+    /// The notes are generated using [`note_ticks`], for the given range. If the
+    /// range changes, the notes are generated anew.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// UnitRuler::notes(
+    ///     cx,
+    ///     (20., 20_000.),
+    ///     ValueScaling::Frequency,
+    ///     NoteTicks::A,
+    ///     Orientation::Horizontal,
+    /// )
+    /// .font_size(12.)
+    /// .color(Color::rgb(160, 160, 160))
+    /// .height(Pixels(16.));
+    /// ```
+    pub fn notes<'a>(
+        cx: &'a mut Context,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+        ticks: NoteTicks,
+        orientation: Orientation,
+    ) -> Handle<'a, Self> {
+        Self::with_axis(cx, range, scaling, move |cx| {
+            Binding::new(
+                cx,
+                UnitRulerState::axis.map(|axis| axis.link.range),
+                move |cx, range| {
+                    let values = note_ticks(range.get(cx), ticks);
+                    Self::labels(cx, values, orientation);
+                },
+            );
+        })
+    }
+
+    /// Creates a [`UnitRuler`] for the time axis of a [`Graph`](super::Graph) or
+    /// [`Oscilloscope`](super::Oscilloscope), marking how many seconds ago each
+    /// point was - from "now" at the right edge back to the start of the axis.
+    ///
+    /// The marks are generated using [`time_ticks`], and follow the given duration
+    /// (in seconds). Pass the same lens as the graph's duration, so both stay in
+    /// sync.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// Graph::peak(cx, bus.clone(), Data::duration, 50.0, (-32.0, 8.0), ValueScaling::Decibels);
+    /// UnitRuler::time(cx, Data::duration)
+    ///     .font_size(12.)
+    ///     .color(Color::rgb(160, 160, 160))
+    ///     .height(Pixels(16.));
+    /// ```
+    pub fn time<'a>(cx: &'a mut Context, duration: impl Lens<Target = f32>) -> Handle<'a, Self> {
+        let range = duration.clone().map(|duration| (-*duration, 0.));
+
+        Self::with_axis(cx, range, ValueScaling::Linear, move |cx| {
+            Binding::new(cx, duration, |cx, duration| {
+                let values = time_ticks(duration.get(cx));
+                Self::labels(cx, values, Orientation::Horizontal);
+            });
+        })
+    }
+
+    /// Creates a [`UnitRuler`] whose range and scaling follow an [`AxisLink`].
+    ///
+    /// Markers for values outside of the link's current range are hidden.
+    pub fn linked<'a>(
+        cx: &'a mut Context,
+        link: impl Lens<Target = AxisLink>,
         values: Vec<(f32, &'static str)>,
         orientation: Orientation,
     ) -> Handle<'a, Self> {
-        Self {}.build(cx, |cx| {
-            let normalized_values = values
-                .into_iter()
-                .filter_map(|v| {
-                    scaling
-                        .value_to_normalized_optional(v.0, range.0, range.1)
-                        .map(|value| (value, v.1))
-                })
-                .collect::<Vec<(f32, &'static str)>>();
-            ZStack::new(cx, |cx| {
-                for value in normalized_values {
-                    match orientation {
-                        Orientation::Vertical => {
-                            Label::new(cx, value.1)
-                                .top(Percentage(100. - value.0 * 100.))
-                                .width(Stretch(1.0))
-                                .text_align(TextAlign::Right)
-                                .transform(Transform::TranslateY(LengthOrPercentage::Percentage(
-                                    -50.,
-                                )));
-                        }
-                        Orientation::Horizontal => {
-                            Label::new(cx, value.1)
-                                .left(Percentage(value.0 * 100.))
-                                .transform(Transform::TranslateX(LengthOrPercentage::Percentage(
-                                    -50.,
-                                )));
-                        }
+        let values = values
+            .into_iter()
+            .map(|(value, text)| (value, text.to_string()))
+            .collect();
+
+        Self::with_axis(
+            cx,
+            link.clone().then(AxisLink::range),
+            link.then(AxisLink::scaling),
+            move |cx| Self::labels(cx, values, orientation),
+        )
+    }
+
+    /// Builds a [`UnitRuler`] that keeps its own range and scaling, which follow
+    /// the given ones if they are bound to a lens.
+    fn with_axis<'a>(
+        cx: &'a mut Context,
+        range: impl Res<(f32, f32)>,
+        scaling: impl Res<ValueScaling>,
+        content: impl FnOnce(&mut Context),
+    ) -> Handle<'a, Self> {
+        let axis = AxisLink::new(scaling.get_val(cx), range.get_val(cx));
+
+        let mut handle = Self {}.build(cx, |cx| {
+            UnitRulerState { axis }.build(cx);
+
+            content(cx);
+        });
+
+        let e = handle.entity();
+        range.set_or_bind(handle.context(), e, move |cx, r| {
+            (*cx).emit_to(e, UnitRulerEvents::UpdateRange(r));
+        });
+        scaling.set_or_bind(handle.context(), e, move |cx, s| {
+            (*cx).emit_to(e, UnitRulerEvents::UpdateScaling(s));
+        });
+
+        handle
+    }
+
+    /// Builds a label for each of the values, positioned along the ruler's axis.
+    ///
+    /// Labels for values outside of the axis' current range are hidden.
+    fn labels(cx: &mut Context, values: Vec<(f32, String)>, orientation: Orientation) {
+        ZStack::new(cx, |cx| {
+            Self::place_labels(
+                cx,
+                UnitRulerState::axis,
+                values,
+                orientation,
+                move |label| {
+                    if let Orientation::Vertical = orientation {
+                        label.width(Stretch(1.0)).text_align(TextAlign::Right);
                     }
+                },
+            );
+        });
+    }
+
+    /// Builds a label for each of the values, centered on its position along the
+    /// given axis, and finished off by the `style` function.
+    ///
+    /// Labels for values outside of the axis' current range are hidden. This is
+    /// also used by a [`LabeledGrid`](super::LabeledGrid) to label its lines.
+    pub(crate) fn place_labels(
+        cx: &mut Context,
+        axis: impl Lens<Target = RulerAxis>,
+        values: Vec<(f32, String)>,
+        orientation: Orientation,
+        style: impl Fn(Handle<'_, Label>),
+    ) {
+        for (value, text) in values {
+            let position = axis.clone().map(move |axis| {
+                let normalized = axis.value_to_normalized(value);
+                match orientation {
+                    Orientation::Vertical => Percentage(100. - normalized * 100.),
+                    Orientation::Horizontal => Percentage(normalized * 100.),
                 }
             });
-        })
+            let display = axis.clone().map(move |axis| {
+                let AxisLink { scaling, range } = axis.link;
+                match scaling.value_to_normalized_optional(value, range.0, range.1) {
+                    Some(_) => Display::Flex,
+                    None => Display::None,
+                }
+            });
+
+            let label = Label::new(cx, &text).display(display);
+
+            style(match orientation {
+                Orientation::Vertical => label
+                    .top(position)
+                    .transform(Transform::TranslateY(LengthOrPercentage::Percentage(-50.))),
+                Orientation::Horizontal => label
+                    .left(position)
+                    .transform(Transform::TranslateX(LengthOrPercentage::Percentage(-50.))),
+            });
+        }
     }
 }
 
@@ -77,3 +415,58 @@ impl View for UnitRuler {
         Some("unit-ruler")
     }
 }
+
+impl<'a> AxisMorphModifiers for Handle<'a, UnitRuler> {
+    /// Lets the labels glide to a new range or scaling over 150 ms, instead of
+    /// snapping to it.
+    ///
+    /// Let the views the ruler belongs to morph as well, so that they keep lining
+    /// up while they glide.
+    fn morph_axis(mut self) -> Self {
+        let e = self.entity();
+        self.context().emit_to(e, UnitRulerEvents::EnableMorph);
+
+        let timer = self
+            .context()
+            .add_timer(MORPH_INTERVAL, None, move |cx, action| {
+                if let TimerAction::Tick(_) = action {
+                    cx.emit_to(e, UnitRulerEvents::MoveLabels);
+                }
+            });
+        self.context().start_timer(timer);
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{note_ticks, time_ticks, NoteTicks};
+
+    #[test]
+    fn notes_within_the_range_are_marked() {
+        let a = note_ticks((20., 20_000.), NoteTicks::A);
+        assert_eq!(a.len(), 10);
+        assert_eq!(a[0].1, "A0");
+        assert!((a[0].0 - 27.5).abs() < 1e-3);
+        assert_eq!(a[9].1, "A9");
+
+        let c = note_ticks((250., 500.), NoteTicks::C);
+        assert_eq!(c.len(), 1);
+        assert_eq!(c[0].1, "C4");
+
+        assert_eq!(note_ticks((430., 470.), NoteTicks::Chromatic).len(), 2);
+    }
+
+    #[test]
+    fn time_axes_are_divided_into_round_steps() {
+        let ticks = time_ticks(10.);
+        assert_eq!(ticks.len(), 6);
+        assert_eq!(ticks[0], (0., "now".to_owned()));
+        assert_eq!(ticks[5], (-10., "-10s".to_owned()));
+
+        assert_eq!(time_ticks(0.5)[5].1, "-0.5s");
+        assert_eq!(time_ticks(3600.)[4].1, "-60m");
+        assert_eq!(time_ticks(7.)[3].1, "-6s");
+    }
+}
@@ -0,0 +1,222 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+
+use crate::accumulators::BallisticsAccumulator;
+use crate::bus::Bus;
+use crate::utils::lock_or_recover;
+
+type Sample = [f32; 2];
+
+/// The running share of side energy in a stereo signal.
+///
+/// Mid and side energy are averaged with an exponential moving average, so that
+/// the width can be updated sample by sample.
+struct Width {
+    /// The time (in ms) it takes to cover 75% of a change in width.
+    window: f32,
+    weight: f32,
+    /// Average of mid²
+    mid: f32,
+    /// Average of side²
+    side: f32,
+}
+
+impl Width {
+    fn new(window: f32, sample_rate: f32) -> Self {
+        let mut width = Self {
+            window,
+            weight: 0.0,
+            mid: 0.0,
+            side: 0.0,
+        };
+        width.set_sample_rate(sample_rate);
+        width
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.weight = BallisticsAccumulator::weight(self.window, sample_rate);
+    }
+
+    fn process(&mut self, [left, right]: Sample) {
+        let w = self.weight;
+
+        let mid = (left + right) / 2.0;
+        let side = (left - right) / 2.0;
+
+        self.mid = self.mid * w + mid * mid * (1.0 - w);
+        self.side = self.side * w + side * side * (1.0 - w);
+    }
+
+    /// The side energy's share of the total energy, from 0 (mono) to 1 (fully out
+    /// of phase).
+    ///
+    /// Silence has no width, so 0 is returned for it.
+    fn value(&self) -> f32 {
+        let energy = self.mid + self.side;
+
+        if energy > f32::EPSILON {
+            (self.side / energy).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// How a [`WidthMeter`] displays the width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidthMeterStyle {
+    /// A horizontal bar, growing from the left edge.
+    Bar,
+    /// A semicircular gauge with a needle, from mono on the left to fully out of
+    /// phase on the right.
+    Arc,
+}
+
+/// Displays the stereo width of a signal, as the share of side energy in the total
+/// energy of its mid and side channels.
+///
+/// A width of 0 means the signal is mono. Two unrelated channels of equal level
+/// have a width of 0.5, and anything above that means the channels are partially
+/// out of phase, up to 1 for a signal that only consists of side.
+///
+/// The bar or the gauge's track is drawn using the `background_color`, and the
+/// current width using the `color`.
+///
+/// # Example
+///
+/// Width meter averaging over 300 ms, shown as a gauge.
+///
+/// ```
+/// WidthMeter::new(cx, bus.clone(), 300.0, WidthMeterStyle::Arc)
+///     .size(Pixels(64.0))
+///     .color(Color::rgb(255, 255, 255))
+///     .background_color(Color::rgba(255, 255, 255, 60));
+/// ```
+pub struct WidthMeter<B: Bus<Sample> + 'static> {
+    dispatcher_handle: Arc<dyn Fn(<B as Bus<Sample>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    width: Arc<Mutex<Width>>,
+    style: WidthMeterStyle,
+}
+
+impl<B: Bus<Sample> + 'static> WidthMeter<B> {
+    /// Creates a new [`WidthMeter`].
+    ///
+    /// The `window` dictates how long (in ms) it takes for the meter to cover 75%
+    /// of a change in width.
+    pub fn new(cx: &mut Context, bus: Arc<B>, window: f32, style: WidthMeterStyle) -> Handle<Self> {
+        let width = Arc::new(Mutex::new(Width::new(window, bus.sample_rate())));
+
+        let width_c = width.clone();
+        let dispatcher_handle = bus.register_dispatcher(move |samples| {
+            let mut width = lock_or_recover(&width_c);
+            for sample in samples {
+                width.process(*sample);
+            }
+        });
+
+        let width_c = width.clone();
+        let sample_rate_handle = bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&width_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            dispatcher_handle,
+            sample_rate_handle,
+            width,
+            style,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+impl<B: Bus<Sample> + 'static> View for WidthMeter<B> {
+    fn element(&self) -> Option<&'static str> {
+        Some("width-meter")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let width = lock_or_recover(&self.width).value();
+
+        match self.style {
+            WidthMeterStyle::Bar => {
+                let mut bar = vg::Path::new();
+                bar.rect(x, y, w * width, h);
+                canvas.fill_path(&bar, &vg::Paint::color(cx.background_color().into()));
+
+                let mut indicator = vg::Path::new();
+                indicator.move_to(x + w * width, y);
+                indicator.line_to(x + w * width, y + h);
+                canvas.stroke_path(
+                    &indicator,
+                    &vg::Paint::color(cx.font_color().into()).with_line_width(cx.scale_factor()),
+                );
+            }
+            WidthMeterStyle::Arc => {
+                let thickness = w.min(h * 2.) * 0.08;
+
+                // The gauge is a half circle, standing on the bottom edge
+                let center_x = x + w / 2.;
+                let center_y = y + h;
+                let radius = (w / 2.).min(h) - thickness / 2.;
+                if radius <= 0. {
+                    return;
+                }
+
+                let mut track = vg::Path::new();
+                track.arc(center_x, center_y, radius, PI, 2. * PI, vg::Solidity::Hole);
+                canvas.stroke_path(
+                    &track,
+                    &vg::Paint::color(cx.background_color().into()).with_line_width(thickness),
+                );
+
+                let angle = PI * (1. + width);
+
+                let mut needle = vg::Path::new();
+                needle.move_to(center_x, center_y);
+                needle.line_to(
+                    center_x + radius * angle.cos(),
+                    center_y + radius * angle.sin(),
+                );
+                canvas.stroke_path(
+                    &needle,
+                    &vg::Paint::color(cx.font_color().into())
+                        .with_line_width(cx.scale_factor() * 1.5),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Width;
+
+    fn width(samples: impl Iterator<Item = [f32; 2]>) -> f32 {
+        let mut width = Width::new(500.0, 1000.0);
+        samples.for_each(|sample| width.process(sample));
+        width.value()
+    }
+
+    #[test]
+    fn width_of_typical_signals() {
+        let sine = |i: usize| (i as f32 * 0.1).sin();
+        let cosine = |i: usize| (i as f32 * 0.1).cos();
+
+        let mono = width((0..5000).map(|i| [sine(i), sine(i)]));
+        let inverted = width((0..5000).map(|i| [sine(i), -sine(i)]));
+        let quadrature = width((0..5000).map(|i| [sine(i), cosine(i)]));
+
+        assert!(mono.abs() < 1e-3);
+        assert!((inverted - 1.0).abs() < 1e-3);
+        assert!((quadrature - 0.5).abs() < 0.1);
+        assert_eq!(width((0..5000).map(|_| [0.0, 0.0])), 0.0);
+    }
+}
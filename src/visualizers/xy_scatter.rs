@@ -0,0 +1,347 @@
+use nih_plug_vizia::vizia::{prelude::*, vg};
+use std::sync::{Arc, Mutex};
+
+use crate::bus::Bus;
+use crate::utils::{lock_or_recover, RingBuffer, SamplePairs, ValueScaling};
+
+/// How often (in ms) a new point is plotted.
+const POINT_INTERVAL: f32 = 5.0;
+
+/// The number of opacity steps points fade out in.
+const FADE_STEPS: usize = 16;
+
+/// Folds a pair of samples into the point that is being collected, if there is
+/// one yet.
+type Reduce = fn(Option<[f32; 2]>, [f32; 2]) -> [f32; 2];
+
+/// The plotted points of an [`XYScatter`], and the pairs of samples they are made
+/// of.
+struct ScatterPoints {
+    pairs: SamplePairs,
+    reduce: Reduce,
+    /// The point that is being collected
+    current: Option<[f32; 2]>,
+    /// The number of pairs collected into the current point
+    collected: usize,
+    /// The number of pairs each point is made of
+    hop: usize,
+    /// The most recent points, as x and y values
+    points: RingBuffer<Option<[f32; 2]>>,
+}
+
+impl ScatterPoints {
+    fn new(duration: f32, reduce: Reduce) -> Self {
+        let size = (duration * 1000. / POINT_INTERVAL).ceil().max(1.) as usize;
+
+        Self {
+            pairs: SamplePairs::new(1),
+            reduce,
+            current: None,
+            collected: 0,
+            hop: 1,
+            points: RingBuffer::new(size),
+        }
+    }
+
+    fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.hop = (sample_rate * POINT_INTERVAL / 1000.).round().max(1.) as usize;
+        // The buses may lag behind each other by a buffer or two
+        self.pairs = SamplePairs::new((sample_rate / 10.).ceil().max(1.) as usize);
+    }
+
+    /// Queues the samples of one bus - 0 for x, 1 for y - plotting a point for
+    /// every `hop` pairs.
+    fn push(&mut self, side: usize, samples: impl IntoIterator<Item = f32>) {
+        let Self {
+            pairs,
+            reduce,
+            current,
+            collected,
+            hop,
+            points,
+        } = self;
+
+        pairs.push(side, samples, |pair| {
+            *current = Some(reduce(*current, pair));
+
+            *collected += 1;
+            if *collected >= *hop {
+                points.enqueue(current.take());
+                *collected = 0;
+            }
+        });
+    }
+}
+
+/// Plots the values of two buses against each other, as a scatter of recent
+/// points that fade out over time.
+///
+/// This is a building block for your own correlational displays - e.g. the level
+/// of a signal against an estimate of its frequency, or the value of one
+/// modulation source against another. Each axis has its own range and scaling,
+/// with `x` growing to the right and `y` growing upwards. Points outside of either
+/// range are left out.
+///
+/// Both buses are paired up sample by sample, so they should run at the same
+/// sample rate, and be updated together. Every 5 ms, a point is plotted - by
+/// default, the latest pair. Points are kept for the given `duration` (in
+/// seconds), and drawn in the `color`, getting more transparent as they age.
+///
+/// For the activity display of a compressor, use [`XYScatter::dynamics`].
+///
+/// # Example
+///
+/// ```
+/// XYScatter::new(
+///     cx,
+///     level_bus.clone(),
+///     pitch_bus.clone(),
+///     1.0,
+///     ((-60.0, 0.0), ValueScaling::Decibels),
+///     ((20.0, 20_000.0), ValueScaling::Frequency),
+/// )
+/// .point_size(2.0)
+/// .color(Color::rgb(64, 200, 255));
+/// ```
+pub struct XYScatter<BX: Bus<f32> + 'static, BY: Bus<f32> + 'static> {
+    x_dispatcher_handle: Arc<dyn Fn(<BX as Bus<f32>>::O<'_>) + Send + Sync>,
+    y_dispatcher_handle: Arc<dyn Fn(<BY as Bus<f32>>::O<'_>) + Send + Sync>,
+    sample_rate_handle: Arc<dyn Fn(f32) + Send + Sync>,
+    points: Arc<Mutex<ScatterPoints>>,
+    x_axis: ((f32, f32), ValueScaling),
+    y_axis: ((f32, f32), ValueScaling),
+    point_size: f32,
+}
+
+impl<BX: Bus<f32> + 'static, BY: Bus<f32> + 'static> XYScatter<BX, BY> {
+    /// Creates a new [`XYScatter`].
+    ///
+    /// Each axis is given as its range and scaling.
+    pub fn new(
+        cx: &mut Context,
+        x_bus: Arc<BX>,
+        y_bus: Arc<BY>,
+        duration: f32,
+        x_axis: ((f32, f32), ValueScaling),
+        y_axis: ((f32, f32), ValueScaling),
+    ) -> Handle<Self> {
+        Self::with_reduce(cx, x_bus, y_bus, duration, x_axis, y_axis, |_, pair| pair)
+    }
+
+    /// Creates an [`XYScatter`] that plots the input level of a compressor against
+    /// its gain reduction.
+    ///
+    /// This is the "activity" display of many modern compressors: each point shows
+    /// how much gain reduction was applied at a certain level, so the points trace
+    /// out the compressor's curve, including its knee and how it behaves during
+    /// attack and release. The level is plotted from left to right, and the gain
+    /// reduction from top to bottom.
+    ///
+    /// The `level_bus` carries the input signal, or its level, and the `gain_bus`
+    /// carries the gain your compressor applies as a linear factor - just like for
+    /// a [`Graph::gain_reduction`](super::Graph::gain_reduction). Each point shows
+    /// the peak level and the lowest gain within its 5 ms. The `level_range` and
+    /// `gain_range` are given in dB.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let gain_reduction_bus: Arc<ValueBus> = Default::default();
+    ///
+    /// // Inside your plug-in's process() function
+    /// gain_reduction_bus.send_held(self.compressor.gain(), buffer.samples());
+    ///
+    /// // Inside your editor
+    /// XYScatter::dynamics(
+    ///     cx,
+    ///     input_bus.clone(),
+    ///     gain_reduction_bus.clone(),
+    ///     2.0,
+    ///     (-60.0, 0.0),
+    ///     (-24.0, 0.0),
+    /// )
+    /// .color(Color::rgb(255, 160, 64));
+    /// ```
+    pub fn dynamics(
+        cx: &mut Context,
+        level_bus: Arc<BX>,
+        gain_bus: Arc<BY>,
+        duration: f32,
+        level_range: (f32, f32),
+        gain_range: (f32, f32),
+    ) -> Handle<Self> {
+        Self::with_reduce(
+            cx,
+            level_bus,
+            gain_bus,
+            duration,
+            (level_range, ValueScaling::Decibels),
+            (gain_range, ValueScaling::Decibels),
+            reduce_dynamics,
+        )
+    }
+
+    fn with_reduce(
+        cx: &mut Context,
+        x_bus: Arc<BX>,
+        y_bus: Arc<BY>,
+        duration: f32,
+        x_axis: ((f32, f32), ValueScaling),
+        y_axis: ((f32, f32), ValueScaling),
+        reduce: Reduce,
+    ) -> Handle<Self> {
+        let mut points = ScatterPoints::new(duration, reduce);
+        points.set_sample_rate(x_bus.sample_rate());
+
+        let points = Arc::new(Mutex::new(points));
+
+        let points_c = points.clone();
+        let x_dispatcher_handle = x_bus.register_dispatcher(move |samples| {
+            lock_or_recover(&points_c).push(0, samples.copied());
+        });
+
+        let points_c = points.clone();
+        let y_dispatcher_handle = y_bus.register_dispatcher(move |samples| {
+            lock_or_recover(&points_c).push(1, samples.copied());
+        });
+
+        let points_c = points.clone();
+        let sample_rate_handle = x_bus.register_sample_rate_listener(move |sample_rate| {
+            lock_or_recover(&points_c).set_sample_rate(sample_rate);
+        });
+
+        Self {
+            x_dispatcher_handle,
+            y_dispatcher_handle,
+            sample_rate_handle,
+            points,
+            x_axis,
+            y_axis,
+            point_size: 3.0,
+        }
+        .build(cx, |_| {})
+    }
+}
+
+/// Keeps the peak level and the lowest gain of the pairs within a point.
+fn reduce_dynamics(current: Option<[f32; 2]>, [level, gain]: [f32; 2]) -> [f32; 2] {
+    let level = level.abs();
+
+    match current {
+        Some([peak, lowest]) => [peak.max(level), lowest.min(gain)],
+        None => [level, gain],
+    }
+}
+
+impl<BX: Bus<f32> + 'static, BY: Bus<f32> + 'static> View for XYScatter<BX, BY> {
+    fn element(&self) -> Option<&'static str> {
+        Some("xy-scatter")
+    }
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+
+        let x = bounds.x;
+        let y = bounds.y;
+        let w = bounds.w;
+        let h = bounds.h;
+
+        let point_size = self.point_size * cx.scale_factor();
+
+        let ((x_range, x_scaling), (y_range, y_scaling)) = (self.x_axis, self.y_axis);
+
+        // Older points are collected into more transparent paths
+        let mut paths = vec![vg::Path::new(); FADE_STEPS];
+
+        {
+            let points = &lock_or_recover(&self.points).points;
+            let len = points.len();
+
+            for i in 0..len {
+                let Some([value_x, value_y]) = points[i] else {
+                    continue;
+                };
+
+                // Points outside of either range are left out
+                let (Some(normalized_x), Some(normalized_y)) = (
+                    x_scaling.value_to_normalized_optional(value_x, x_range.0, x_range.1),
+                    y_scaling.value_to_normalized_optional(value_y, y_range.0, y_range.1),
+                ) else {
+                    continue;
+                };
+
+                let point_x = x + normalized_x * w;
+                let point_y = y + (1. - normalized_y) * h;
+
+                paths[i * FADE_STEPS / len].rect(
+                    point_x - point_size / 2.,
+                    point_y - point_size / 2.,
+                    point_size,
+                    point_size,
+                );
+            }
+        }
+
+        let color: vg::Color = cx.font_color().into();
+        for (step, path) in paths.iter().enumerate() {
+            let mut color = color;
+            color.a *= (step + 1) as f32 / FADE_STEPS as f32;
+            canvas.fill_path(path, &vg::Paint::color(color));
+        }
+    }
+}
+
+pub trait XYScatterModifiers {
+    fn point_size(self, size: f32) -> Self;
+}
+
+impl<BX: Bus<f32> + 'static, BY: Bus<f32> + 'static> XYScatterModifiers
+    for Handle<'_, XYScatter<BX, BY>>
+{
+    /// Sets the size of each plotted point, in logical pixels.
+    ///
+    /// The size is multiplied by the window's scale factor. Defaults to 3.
+    fn point_size(self, size: f32) -> Self {
+        self.modify(|scatter| scatter.point_size = size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reduce_dynamics, ScatterPoints};
+
+    #[test]
+    fn points_take_the_latest_pair() {
+        let mut points = ScatterPoints::new(1.0, |_, pair| pair);
+        points.set_sample_rate(1_000.);
+
+        points.push(0, [1., 2., 3., 4., 5., 6.]);
+        points.push(1, [10., 20., 30.]);
+        let latest = points.points.len() - 1;
+        assert_eq!(points.points[latest], None);
+
+        points.push(1, [40., 50., 60.]);
+        assert_eq!(points.points[latest], Some([5., 50.]));
+        assert_eq!(points.points[latest - 1], None);
+    }
+
+    #[test]
+    fn dynamics_points_hold_the_peak_level_and_the_lowest_gain() {
+        let mut points = ScatterPoints::new(1.0, reduce_dynamics);
+        points.set_sample_rate(1_000.);
+
+        points.push(0, [0.1, -0.5, 0.2, 0.3, 0.1, 0.4]);
+        points.push(1, [1.0, 0.8, 0.9, 1.0, 0.7]);
+
+        let latest = points.points.len() - 1;
+        assert_eq!(points.points[latest], Some([0.5, 0.7]));
+        assert_eq!(points.points[latest - 1], None);
+
+        // The sixth level was still waiting for its gain
+        points.push(1, [0.6, 1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(points.points[latest], Some([0.5, 0.7]));
+
+        // Another 5 pairs make up the next point
+        points.push(0, [0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(points.points[latest], Some([0.4, 0.6]));
+    }
+}